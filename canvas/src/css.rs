@@ -0,0 +1,797 @@
+// pathfinder/canvas/src/css.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing of the CSS values that the canvas API accepts as strings.
+
+#[cfg(any(feature = "pf-text", test))]
+use crate::{FontStretch, FontVariantCaps};
+use pathfinder_color::ColorU;
+use pathfinder_content::effects::ColorMatrix;
+use pathfinder_geometry::util;
+use pathfinder_geometry::vector::{Vector2F, vec2f};
+use std::f32::consts::PI;
+
+/// One function of a CSS `filter` value. Lengths are in pixels and angles are in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FilterFunction {
+    Blur(f32),
+    Brightness(f32),
+    Contrast(f32),
+    DropShadow { offset: Vector2F, blur: f32, color: ColorU },
+    Grayscale(f32),
+    HueRotate(f32),
+    Invert(f32),
+    Opacity(f32),
+    Saturate(f32),
+    Sepia(f32),
+}
+
+impl FilterFunction {
+    /// Returns the color matrix this function applies, if it's a pure color transform.
+    ///
+    /// `Opacity` isn't one, because it affects alpha.
+    pub(crate) fn color_matrix(&self) -> Option<ColorMatrix> {
+        match *self {
+            FilterFunction::Brightness(amount) => Some(ColorMatrix::brightness(amount)),
+            FilterFunction::Contrast(amount) => Some(ColorMatrix::contrast(amount)),
+            FilterFunction::Grayscale(amount) => Some(ColorMatrix::grayscale(amount)),
+            FilterFunction::HueRotate(angle) => Some(ColorMatrix::hue_rotate(angle)),
+            FilterFunction::Invert(amount) => Some(ColorMatrix::invert(amount)),
+            FilterFunction::Saturate(amount) => Some(ColorMatrix::saturate(amount)),
+            FilterFunction::Sepia(amount) => Some(ColorMatrix::sepia(amount)),
+            FilterFunction::Blur(_) |
+            FilterFunction::DropShadow { .. } |
+            FilterFunction::Opacity(_) => None,
+        }
+    }
+}
+
+/// Parses a CSS `filter` value, returning `None` if it's invalid.
+///
+/// `none` parses to an empty list. Relative lengths like `em` aren't supported.
+pub(crate) fn parse_filter(string: &str) -> Option<Vec<FilterFunction>> {
+    let mut parser = Parser::new(string);
+    parser.skip_whitespace();
+    if parser.eat_keyword("none") {
+        parser.skip_whitespace();
+        return if parser.is_at_end() { Some(vec![]) } else { None };
+    }
+
+    let mut functions = vec![];
+    while !parser.is_at_end() {
+        let name = parser.function_name()?.to_ascii_lowercase();
+        parser.skip_whitespace();
+        let function = match &*name {
+            "blur" => FilterFunction::Blur(parser.optional(|parser| parser.length(), 0.0)?),
+            "brightness" => FilterFunction::Brightness(parser.amount(None)?),
+            "contrast" => FilterFunction::Contrast(parser.amount(None)?),
+            "grayscale" => FilterFunction::Grayscale(parser.amount(Some(1.0))?),
+            "hue-rotate" => {
+                FilterFunction::HueRotate(parser.optional(|parser| parser.angle(), 0.0)?)
+            }
+            "invert" => FilterFunction::Invert(parser.amount(Some(1.0))?),
+            "opacity" => FilterFunction::Opacity(parser.amount(Some(1.0))?),
+            "saturate" => FilterFunction::Saturate(parser.amount(None)?),
+            "sepia" => FilterFunction::Sepia(parser.amount(Some(1.0))?),
+            "drop-shadow" => parser.drop_shadow()?,
+            _ => return None,
+        };
+        parser.skip_whitespace();
+        if !parser.eat_char(')') {
+            return None;
+        }
+        functions.push(function);
+        parser.skip_whitespace();
+    }
+
+    if functions.is_empty() {
+        None
+    } else {
+        Some(functions)
+    }
+}
+
+/// The size of the `medium` font size keyword, in pixels. `rem` lengths are relative to it too,
+/// since a canvas has no root element.
+#[cfg(any(feature = "pf-text", test))]
+const MEDIUM_FONT_SIZE: f32 = 16.0;
+
+/// A CSS `font` shorthand value. The size is in pixels. The line height is validated but not kept,
+/// since canvas text ignores it.
+#[cfg(any(feature = "pf-text", test))]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FontShorthand {
+    pub(crate) style: FontStyle,
+    pub(crate) weight: f32,
+    pub(crate) stretch: FontStretch,
+    pub(crate) variant_caps: FontVariantCaps,
+    pub(crate) size: f32,
+    pub(crate) families: Vec<FontFamilyName>,
+}
+
+#[cfg(any(feature = "pf-text", test))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+/// One entry of a CSS font family list. The generic families are the ones font-kit knows.
+#[cfg(any(feature = "pf-text", test))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum FontFamilyName {
+    Title(String),
+    Serif,
+    SansSerif,
+    Monospace,
+    Cursive,
+    Fantasy,
+}
+
+/// Parses a CSS `font` shorthand value, such as `italic bold 14px/1.2 'Helvetica Neue', serif`,
+/// returning `None` if it's invalid.
+///
+/// `em`, `%`, `larger` and `smaller` sizes are relative to `base_size`. System fonts like
+/// `caption` aren't supported.
+#[cfg(any(feature = "pf-text", test))]
+pub(crate) fn parse_font(string: &str, base_size: f32) -> Option<FontShorthand> {
+    let mut parser = Parser::new(string);
+    let (mut style, mut variant_caps, mut weight, mut stretch) = (None, None, None, None);
+
+    // Up to four style, variant, weight and stretch values can come before the size, in any
+    // order. Each can appear once, but `normal` is valid for all of them.
+    for _ in 0..4 {
+        parser.skip_whitespace();
+        if parser.eat_keyword("normal") {
+            continue;
+        }
+        if style.is_none() {
+            style = parser.font_style();
+            if style.is_some() {
+                continue;
+            }
+        }
+        if variant_caps.is_none() && parser.eat_keyword("small-caps") {
+            variant_caps = Some(FontVariantCaps::SmallCaps);
+            continue;
+        }
+        if weight.is_none() {
+            weight = parser.font_weight();
+            if weight.is_some() {
+                continue;
+            }
+        }
+        if stretch.is_none() {
+            stretch = parser.font_stretch();
+            if stretch.is_some() {
+                continue;
+            }
+        }
+        break;
+    }
+
+    parser.skip_whitespace();
+    let size = parser.font_size(base_size)?;
+    parser.skip_whitespace();
+    if parser.eat_char('/') {
+        parser.skip_whitespace();
+        parser.line_height(base_size)?;
+        parser.skip_whitespace();
+    }
+
+    let mut families = vec![parser.font_family_name()?];
+    while parser.eat_comma() {
+        families.push(parser.font_family_name()?);
+    }
+    parser.skip_whitespace();
+    if !parser.is_at_end() {
+        return None;
+    }
+
+    Some(FontShorthand {
+        style: style.unwrap_or(FontStyle::Normal),
+        weight: weight.unwrap_or(400.0),
+        stretch: stretch.unwrap_or(FontStretch::Normal),
+        variant_caps: variant_caps.unwrap_or(FontVariantCaps::Normal),
+        size,
+        families,
+    })
+}
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn new(string: &'a str) -> Parser<'a> {
+        Parser { rest: string }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.rest.is_empty()
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    }
+
+    fn eat_char(&mut self, c: char) -> bool {
+        if self.peek_char() == Some(c) {
+            self.rest = &self.rest[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    // Consumes a comma, along with any whitespace around it.
+    fn eat_comma(&mut self) -> bool {
+        let start = self.rest;
+        self.skip_whitespace();
+        if self.eat_char(',') {
+            self.skip_whitespace();
+            true
+        } else {
+            self.rest = start;
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let start = self.rest;
+        match self.ident() {
+            Some(ident) if ident.eq_ignore_ascii_case(keyword) => true,
+            _ => {
+                self.rest = start;
+                false
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        let end = self.rest
+                      .find(|c: char| {
+                          !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii())
+                      })
+                      .unwrap_or(self.rest.len());
+        let ident = &self.rest[0..end];
+        match ident.chars().next() {
+            Some(c) if c.is_ascii_alphabetic() || c == '-' || c == '_' || !c.is_ascii() => {
+                self.rest = &self.rest[end..];
+                Some(ident)
+            }
+            _ => None,
+        }
+    }
+
+    // Consumes an identifier immediately followed by an opening parenthesis.
+    fn function_name(&mut self) -> Option<&'a str> {
+        let start = self.rest;
+        match self.ident() {
+            Some(name) if self.eat_char('(') => Some(name),
+            _ => {
+                self.rest = start;
+                None
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        let bytes = self.rest.as_bytes();
+        let mut end = 0;
+        if end < bytes.len() && (bytes[end] == b'+' || bytes[end] == b'-') {
+            end += 1;
+        }
+        let digits_start = end;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+        if end < bytes.len() && bytes[end] == b'.' {
+            end += 1;
+            while end < bytes.len() && bytes[end].is_ascii_digit() {
+                end += 1;
+            }
+        }
+        if end == digits_start || &self.rest[digits_start..end] == "." {
+            return None;
+        }
+        if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+            let mut exponent_end = end + 1;
+            if exponent_end < bytes.len() &&
+                    (bytes[exponent_end] == b'+' || bytes[exponent_end] == b'-') {
+                exponent_end += 1;
+            }
+            if exponent_end < bytes.len() && bytes[exponent_end].is_ascii_digit() {
+                while exponent_end < bytes.len() && bytes[exponent_end].is_ascii_digit() {
+                    exponent_end += 1;
+                }
+                end = exponent_end;
+            }
+        }
+
+        let number = self.rest[0..end].parse().ok()?;
+        self.rest = &self.rest[end..];
+        Some(number)
+    }
+
+    // Parses a number along with its unit, which is empty for a plain number.
+    fn dimension(&mut self) -> Option<(f32, &'a str)> {
+        let number = self.number()?;
+        if self.eat_char('%') {
+            return Some((number, "%"));
+        }
+        Some((number, self.ident().unwrap_or("")))
+    }
+
+    fn length(&mut self) -> Option<f32> {
+        let (number, unit) = self.dimension()?;
+        let scale = match &*unit.to_ascii_lowercase() {
+            "" if number == 0.0 => 0.0,
+            "px" => 1.0,
+            "in" => 96.0,
+            "cm" => 96.0 / 2.54,
+            "mm" => 96.0 / 25.4,
+            "q" => 96.0 / 101.6,
+            "pt" => 96.0 / 72.0,
+            "pc" => 16.0,
+            _ => return None,
+        };
+        Some(number * scale)
+    }
+
+    fn angle(&mut self) -> Option<f32> {
+        let (number, unit) = self.dimension()?;
+        angle_to_radians(number, unit)
+    }
+
+    // Parses a nonnegative number or percentage, returning it as a number. Amounts above `max`,
+    // if present, are clamped to it.
+    fn amount(&mut self, max: Option<f32>) -> Option<f32> {
+        let amount = self.optional(|parser| {
+            match parser.dimension()? {
+                (number, "") => Some(number),
+                (percentage, "%") => Some(percentage / 100.0),
+                _ => None,
+            }
+        }, 1.0)?;
+        if amount < 0.0 {
+            return None;
+        }
+        Some(match max {
+            Some(max) => f32::min(amount, max),
+            None => amount,
+        })
+    }
+
+    // Runs `parse` unless the function's closing parenthesis is next, in which case the argument
+    // was omitted and `default` applies. Negative values are invalid.
+    fn optional<F>(&mut self, parse: F, default: f32) -> Option<f32>
+                   where F: FnOnce(&mut Parser<'a>) -> Option<f32> {
+        if self.peek_char() == Some(')') {
+            return Some(default);
+        }
+        match parse(self)? {
+            value if value < 0.0 => None,
+            value => Some(value),
+        }
+    }
+
+    fn drop_shadow(&mut self) -> Option<FilterFunction> {
+        // The color can come before or after the lengths.
+        let mut color = self.color();
+        self.skip_whitespace();
+
+        let mut lengths = vec![];
+        while lengths.len() < 3 {
+            let start = self.rest;
+            match self.length() {
+                Some(length) => lengths.push(length),
+                None => {
+                    self.rest = start;
+                    break;
+                }
+            }
+            self.skip_whitespace();
+        }
+        if lengths.len() < 2 || (lengths.len() == 3 && lengths[2] < 0.0) {
+            return None;
+        }
+
+        if color.is_none() {
+            color = self.color();
+        }
+
+        Some(FilterFunction::DropShadow {
+            offset: vec2f(lengths[0], lengths[1]),
+            blur: lengths.get(2).cloned().unwrap_or(0.0),
+            color: color.unwrap_or(ColorU::black()),
+        })
+    }
+
+    // Parses a CSS color. `currentcolor` is opaque black, as it is for a canvas that isn't in a
+    // document.
+    fn color(&mut self) -> Option<ColorU> {
+        let start = self.rest;
+        let color = self.color_without_backtracking();
+        if color.is_none() {
+            self.rest = start;
+        }
+        color
+    }
+
+    fn color_without_backtracking(&mut self) -> Option<ColorU> {
+        if self.eat_char('#') {
+            return self.hex_color();
+        }
+
+        if let Some(name) = self.function_name() {
+            self.skip_whitespace();
+            let color = match &*name.to_ascii_lowercase() {
+                "rgb" | "rgba" => self.rgb_arguments()?,
+                "hsl" | "hsla" => self.hsl_arguments()?,
+                _ => return None,
+            };
+            self.skip_whitespace();
+            return if self.eat_char(')') { Some(color) } else { None };
+        }
+
+        let name = self.ident()?.to_ascii_lowercase();
+        match &*name {
+            "transparent" => Some(ColorU::transparent_black()),
+            "currentcolor" => Some(ColorU::black()),
+            _ => {
+                let index = NAMED_COLORS.binary_search_by(|&(other, _)| other.cmp(&name)).ok()?;
+                let rgb = NAMED_COLORS[index].1;
+                Some(ColorU::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255))
+            }
+        }
+    }
+
+    fn hex_color(&mut self) -> Option<ColorU> {
+        let end = self.rest.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(self.rest.len());
+        let digits = &self.rest[0..end];
+        let digit = |index: usize| u8::from_str_radix(&digits[index..(index + 1)], 16).unwrap();
+        let byte = |index: usize| u8::from_str_radix(&digits[index..(index + 2)], 16).unwrap();
+        let color = match digits.len() {
+            3 => ColorU::new(digit(0) * 17, digit(1) * 17, digit(2) * 17, 255),
+            4 => ColorU::new(digit(0) * 17, digit(1) * 17, digit(2) * 17, digit(3) * 17),
+            6 => ColorU::new(byte(0), byte(2), byte(4), 255),
+            8 => ColorU::new(byte(0), byte(2), byte(4), byte(6)),
+            _ => return None,
+        };
+        self.rest = &self.rest[end..];
+        Some(color)
+    }
+
+    // Parses the components of `rgb()`, in either the legacy comma-separated syntax or the
+    // space-separated one.
+    fn rgb_arguments(&mut self) -> Option<ColorU> {
+        let mut components = [0.0; 3];
+        let mut commas = false;
+        for (index, component) in components.iter_mut().enumerate() {
+            if index > 0 {
+                if index == 1 {
+                    commas = self.eat_comma();
+                } else if commas != self.eat_comma() {
+                    return None;
+                }
+                self.skip_whitespace();
+            }
+            let value = match self.dimension()? {
+                (number, "") => number / 255.0,
+                (percentage, "%") => percentage / 100.0,
+                _ => return None,
+            };
+            *component = util::clamp(value, 0.0, 1.0);
+        }
+        let alpha = self.alpha_argument(commas)?;
+        Some(color_from_unit_components(components[0], components[1], components[2], alpha))
+    }
+
+    // Parses the components of `hsl()`, converting to RGB per CSS Color 4 § 7.1.
+    fn hsl_arguments(&mut self) -> Option<ColorU> {
+        let hue = match self.dimension()? {
+            (degrees, "") => degrees,
+            (angle, unit) => angle_to_radians(angle, unit)? * 180.0 / PI,
+        };
+        let commas = self.eat_comma();
+        self.skip_whitespace();
+        let saturation = self.percentage()?;
+        if commas != self.eat_comma() {
+            return None;
+        }
+        self.skip_whitespace();
+        let lightness = self.percentage()?;
+        let alpha = self.alpha_argument(commas)?;
+
+        let hue = hue.rem_euclid(360.0) / 30.0;
+        let saturation = util::clamp(saturation, 0.0, 1.0);
+        let lightness = util::clamp(lightness, 0.0, 1.0);
+        let channel = |n: f32| {
+            let k = (n + hue) % 12.0;
+            let a = saturation * f32::min(lightness, 1.0 - lightness);
+            lightness - a * util::clamp(f32::min(k - 3.0, 9.0 - k), -1.0, 1.0)
+        };
+        Some(color_from_unit_components(channel(0.0), channel(8.0), channel(4.0), alpha))
+    }
+
+    fn percentage(&mut self) -> Option<f32> {
+        match self.dimension()? {
+            (percentage, "%") => Some(percentage / 100.0),
+            _ => None,
+        }
+    }
+
+    // Parses the optional alpha that ends `rgb()` and `hsl()`.
+    fn alpha_argument(&mut self, commas: bool) -> Option<f32> {
+        self.skip_whitespace();
+        let has_alpha = if commas { self.eat_char(',') } else { self.eat_char('/') };
+        if !has_alpha {
+            return Some(1.0);
+        }
+        self.skip_whitespace();
+        let alpha = match self.dimension()? {
+            (number, "") => number,
+            (percentage, "%") => percentage / 100.0,
+            _ => return None,
+        };
+        Some(util::clamp(alpha, 0.0, 1.0))
+    }
+}
+
+#[cfg(any(feature = "pf-text", test))]
+impl<'a> Parser<'a> {
+    fn font_style(&mut self) -> Option<FontStyle> {
+        if self.eat_keyword("italic") {
+            Some(FontStyle::Italic)
+        } else if self.eat_keyword("oblique") {
+            Some(FontStyle::Oblique)
+        } else {
+            None
+        }
+    }
+
+    // Parses a `font-weight` other than `normal`. The relative weights are relative to `normal`,
+    // since a canvas has no parent element to inherit from.
+    fn font_weight(&mut self) -> Option<f32> {
+        if self.eat_keyword("bold") || self.eat_keyword("bolder") {
+            return Some(700.0);
+        }
+        if self.eat_keyword("lighter") {
+            return Some(100.0);
+        }
+        let start = self.rest;
+        match self.dimension() {
+            Some((weight, "")) if (1.0..=1000.0).contains(&weight) => Some(weight),
+            _ => {
+                self.rest = start;
+                None
+            }
+        }
+    }
+
+    // Parses a `font-stretch` keyword other than `normal`.
+    fn font_stretch(&mut self) -> Option<FontStretch> {
+        let start = self.rest;
+        let stretch = match &*self.ident()?.to_ascii_lowercase() {
+            "ultra-condensed" => FontStretch::UltraCondensed,
+            "extra-condensed" => FontStretch::ExtraCondensed,
+            "condensed" => FontStretch::Condensed,
+            "semi-condensed" => FontStretch::SemiCondensed,
+            "semi-expanded" => FontStretch::SemiExpanded,
+            "expanded" => FontStretch::Expanded,
+            "extra-expanded" => FontStretch::ExtraExpanded,
+            "ultra-expanded" => FontStretch::UltraExpanded,
+            _ => {
+                self.rest = start;
+                return None;
+            }
+        };
+        Some(stretch)
+    }
+
+    fn font_size(&mut self, base_size: f32) -> Option<f32> {
+        if let Some(keyword) = self.ident() {
+            // The absolute sizes use the CSS scaling factors from `medium`.
+            let scale = match &*keyword.to_ascii_lowercase() {
+                "xx-small" => 3.0 / 5.0,
+                "x-small" => 3.0 / 4.0,
+                "small" => 8.0 / 9.0,
+                "medium" => 1.0,
+                "large" => 6.0 / 5.0,
+                "x-large" => 3.0 / 2.0,
+                "xx-large" => 2.0,
+                "xxx-large" => 3.0,
+                "larger" => return Some(base_size * 1.2),
+                "smaller" => return Some(base_size / 1.2),
+                _ => return None,
+            };
+            return Some(MEDIUM_FONT_SIZE * scale);
+        }
+        match self.relative_length(base_size)? {
+            size if size < 0.0 => None,
+            size => Some(size),
+        }
+    }
+
+    // Parses a `line-height`, which canvas text ignores, so it's only validated.
+    fn line_height(&mut self, base_size: f32) -> Option<()> {
+        if self.eat_keyword("normal") {
+            return Some(());
+        }
+        let start = self.rest;
+        let line_height = match self.dimension()? {
+            (number, "") => number,
+            _ => {
+                self.rest = start;
+                self.relative_length(base_size)?
+            }
+        };
+        if line_height < 0.0 {
+            None
+        } else {
+            Some(())
+        }
+    }
+
+    // Parses a length or percentage, where percentages and `em` are relative to `base_size`.
+    fn relative_length(&mut self, base_size: f32) -> Option<f32> {
+        let start = self.rest;
+        match self.dimension()? {
+            (percentage, "%") => Some(percentage / 100.0 * base_size),
+            (number, unit) if unit.eq_ignore_ascii_case("em") => Some(number * base_size),
+            (number, unit) if unit.eq_ignore_ascii_case("rem") => Some(number * MEDIUM_FONT_SIZE),
+            _ => {
+                self.rest = start;
+                self.length()
+            }
+        }
+    }
+
+    // Parses a family name: a quoted string, a generic family, or a sequence of identifiers,
+    // which are joined with single spaces.
+    fn font_family_name(&mut self) -> Option<FontFamilyName> {
+        if let Some(name) = self.string() {
+            return Some(FontFamilyName::Title(name));
+        }
+
+        let mut words = vec![self.ident()?];
+        loop {
+            let start = self.rest;
+            self.skip_whitespace();
+            match self.ident() {
+                Some(word) => words.push(word),
+                None => {
+                    self.rest = start;
+                    break;
+                }
+            }
+        }
+
+        if words.len() == 1 {
+            match &*words[0].to_ascii_lowercase() {
+                "serif" => return Some(FontFamilyName::Serif),
+                "sans-serif" => return Some(FontFamilyName::SansSerif),
+                "monospace" => return Some(FontFamilyName::Monospace),
+                "cursive" => return Some(FontFamilyName::Cursive),
+                "fantasy" => return Some(FontFamilyName::Fantasy),
+                "inherit" | "initial" | "unset" | "default" => return None,
+                _ => {}
+            }
+        }
+        Some(FontFamilyName::Title(words.join(" ")))
+    }
+
+    // Parses a quoted string. A backslash escapes the character after it; hexadecimal escapes
+    // aren't supported.
+    fn string(&mut self) -> Option<String> {
+        let quote = match self.peek_char() {
+            Some(quote) if quote == '"' || quote == '\'' => quote,
+            _ => return None,
+        };
+        let mut string = String::new();
+        let mut chars = self.rest.char_indices().skip(1);
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    match chars.next() {
+                        Some((_, '\n')) => {}
+                        Some((_, escaped)) => string.push(escaped),
+                        None => return None,
+                    }
+                }
+                '\n' => return None,
+                c if c == quote => {
+                    self.rest = &self.rest[(index + 1)..];
+                    return Some(string);
+                }
+                c => string.push(c),
+            }
+        }
+        None
+    }
+}
+
+// Unlike `ColorF::to_u8()`, this rounds, so that `rgb(128 0 0)` stays 128.
+fn color_from_unit_components(r: f32, g: f32, b: f32, a: f32) -> ColorU {
+    let to_u8 = |value: f32| (value * 255.0).round() as u8;
+    ColorU::new(to_u8(r), to_u8(g), to_u8(b), to_u8(a))
+}
+
+fn angle_to_radians(number: f32, unit: &str) -> Option<f32> {
+    let scale = match &*unit.to_ascii_lowercase() {
+        "" if number == 0.0 => 0.0,
+        "deg" => PI / 180.0,
+        "grad" => PI / 200.0,
+        "rad" => 1.0,
+        "turn" => PI * 2.0,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+// The CSS named colors, sorted by name.
+static NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4), ("azure", 0xf0ffff), ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0), ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c), ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b), ("darkcyan", 0x008b8b), ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00), ("darkorchid", 0x9932cc), ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f), ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3), ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969), ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0), ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700), ("goldenrod", 0xdaa520), ("gray", 0x808080),
+    ("green", 0x008000), ("greenyellow", 0xadff2f), ("grey", 0x808080),
+    ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082), ("ivory", 0xfffff0), ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5), ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6), ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90), ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa), ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899), ("lightslategrey", 0x778899), ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa), ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371), ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1), ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead),
+    ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500),
+    ("orchid", 0xda70d6), ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093), ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd), ("powderblue", 0xb0e0e6), ("purple", 0x800080),
+    ("rebeccapurple", 0x663399), ("red", 0xff0000), ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d), ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd), ("slategray", 0x708090), ("slategrey", 0x708090),
+    ("snow", 0xfffafa), ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c), ("teal", 0x008080), ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3), ("white", 0xffffff), ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
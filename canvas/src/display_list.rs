@@ -0,0 +1,766 @@
+// pathfinder/canvas/src/display_list.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Recorded sequences of canvas calls that can be replayed and serialized.
+
+use crate::{CanvasRenderingContext2D, CompositeOperation, FillRule, FillStyle, ImageData};
+use crate::{ImageSmoothingQuality, LayerOptions, LineCap, LineJoin, Path2D};
+use pathfinder_color::ColorU;
+use pathfinder_content::outline::{ArcDirection, Contour, Outline};
+use pathfinder_geometry::rect::{RectF, RectI};
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{Vector2F, Vector2I};
+use pathfinder_content::pattern::Pattern;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde::{Deserialize as DeriveDeserialize, Serialize as DeriveSerialize};
+use std::iter;
+
+#[cfg(feature = "pf-text")]
+use crate::{FontKerning, FontStretch, FontVariantCaps, TextAlign, TextBaseline, TextDirection};
+#[cfg(feature = "pf-text")]
+use serde::ser::Error as SerializeError;
+#[cfg(feature = "pf-text")]
+use skribo::FontCollection;
+#[cfg(feature = "pf-text")]
+use std::fmt::{Debug, Error as FmtError, Formatter};
+#[cfg(feature = "pf-text")]
+use std::sync::Arc;
+
+/// A sequence of calls to a `CanvasRenderingContext2D`, recorded with `begin_recording()` and
+/// `end_recording()`.
+///
+/// Display lists serialize with serde; a binary format such as `bincode` keeps them compact.
+/// Patterns of render targets, such as those made from other canvases, and fonts that weren't
+/// set by PostScript name or CSS shorthand only exist in the recording process, so serializing a
+/// display list that uses them fails.
+#[derive(Clone, Debug, Default, DeriveDeserialize, DeriveSerialize)]
+pub struct DisplayList {
+    commands: Vec<Command>,
+}
+
+impl DisplayList {
+    #[inline]
+    pub fn new() -> DisplayList {
+        DisplayList::default()
+    }
+
+    #[inline]
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    #[inline]
+    pub fn push(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    /// Replays the calls onto `context`, with `transform` applied after its current transform.
+    ///
+    /// The state of `context` is saved beforehand and restored afterward, ending any layers the
+    /// calls leave open, so only the current default path outlives the replay. Text is laid
+    /// out with the font context of `context`. As in HTML, `put_image_data()` ignores the
+    /// transform.
+    pub fn replay(&self, context: &mut CanvasRenderingContext2D, transform: Transform2F) {
+        let (state_depth, layer_count) = (context.saved_states.len(), context.layers.len());
+        context.save();
+        let base_transform = context.transform() * transform;
+        context.set_transform(&base_transform);
+
+        for command in &self.commands {
+            match *command {
+                Command::Save => context.save(),
+                Command::Restore => {
+                    // Don't restore the state saved above, or any state saved before it.
+                    if context.saved_states.len() > state_depth + 1 {
+                        context.restore();
+                    }
+                }
+                Command::BeginLayer(ref options) => context.begin_layer(options.clone()),
+                Command::EndLayer => {
+                    if context.layers.len() > layer_count {
+                        context.end_layer();
+                    }
+                }
+                Command::SetLineWidth(width) => context.set_line_width(width),
+                Command::SetLineCap(cap) => context.set_line_cap(cap),
+                Command::SetLineJoin(join) => context.set_line_join(join),
+                Command::SetMiterLimit(limit) => context.set_miter_limit(limit),
+                Command::SetLineDash(ref dash) => context.set_line_dash(dash.clone()),
+                Command::SetLineDashOffset(offset) => context.set_line_dash_offset(offset),
+                Command::SetFillStyle(ref style) => context.set_fill_style(style.clone()),
+                Command::SetStrokeStyle(ref style) => context.set_stroke_style(style.clone()),
+                Command::SetShadowBlur(blur) => context.set_shadow_blur(blur),
+                Command::SetShadowColor(color) => context.set_shadow_color(color),
+                Command::SetShadowOffset(offset) => context.set_shadow_offset(offset),
+                Command::SetFilter(ref filter) => context.set_filter(filter),
+                Command::BeginPath => context.begin_path(),
+                Command::ClosePath => context.close_path(),
+                Command::MoveTo(to) => context.move_to(to),
+                Command::LineTo(to) => context.line_to(to),
+                Command::QuadraticCurveTo { ctrl, to } => context.quadratic_curve_to(ctrl, to),
+                Command::BezierCurveTo { ctrl0, ctrl1, to } => {
+                    context.bezier_curve_to(ctrl0, ctrl1, to)
+                }
+                Command::Arc { center, radius, start_angle, end_angle, direction } => {
+                    context.arc(center, radius, start_angle, end_angle, direction)
+                }
+                Command::ArcTo { ctrl, to, radius } => context.arc_to(ctrl, to, radius),
+                Command::Rect(rect) => context.rect(rect),
+                Command::Ellipse { center, axes, rotation, start_angle, end_angle } => {
+                    context.ellipse(center, axes, rotation, start_angle, end_angle)
+                }
+                Command::FillRect(rect) => context.fill_rect(rect),
+                Command::StrokeRect(rect) => context.stroke_rect(rect),
+                Command::ClearRect(rect) => context.clear_rect(rect),
+                Command::FillPath(ref path, fill_rule) => {
+                    context.fill_path(path.clone(), fill_rule)
+                }
+                Command::StrokePath(ref path) => context.stroke_path(path.clone()),
+                Command::Fill(fill_rule) => context.fill(fill_rule),
+                Command::Stroke => context.stroke(),
+                Command::Clip(fill_rule) => context.clip(fill_rule),
+                Command::ClipPath(ref path, fill_rule) => {
+                    context.clip_path(path.clone(), fill_rule)
+                }
+                Command::Rotate(angle) => context.rotate(angle),
+                Command::Scale(scale) => context.scale(scale),
+                Command::Translate(offset) => context.translate(offset),
+                Command::SetTransform(ref new_transform) => {
+                    context.set_transform(&(base_transform * *new_transform))
+                }
+                Command::ResetTransform => context.set_transform(&base_transform),
+                Command::SetGlobalAlpha(alpha) => context.set_global_alpha(alpha),
+                Command::SetGlobalCompositeOperation(operation) => {
+                    context.set_global_composite_operation(operation)
+                }
+                Command::DrawImage { ref pattern, src_rect, dest_rect } => {
+                    context.draw_subimage(pattern.clone(), src_rect, dest_rect)
+                }
+                Command::SetImageSmoothingEnabled(enabled) => {
+                    context.set_image_smoothing_enabled(enabled)
+                }
+                Command::SetImageSmoothingQuality(quality) => {
+                    context.set_image_smoothing_quality(quality)
+                }
+                Command::PutImageData { ref image_data, origin, dirty_rect } => {
+                    context.put_image_data(image_data, origin, dirty_rect)
+                }
+                #[cfg(feature = "pf-text")]
+                Command::FillText { ref text, position } => context.fill_text(text, position),
+                #[cfg(feature = "pf-text")]
+                Command::StrokeText { ref text, position } => context.stroke_text(text, position),
+                #[cfg(feature = "pf-text")]
+                Command::SetFont(RecordedFont::PostScriptNames(ref names)) => {
+                    let names: Vec<&str> = names.iter().map(|name| &**name).collect();
+                    context.set_font(&names[..])
+                }
+                #[cfg(feature = "pf-text")]
+                Command::SetFont(RecordedFont::Collection(ref collection)) => {
+                    context.set_font(collection.clone())
+                }
+                #[cfg(feature = "pf-text")]
+                Command::SetFontShorthand(ref font) => context.set_font_shorthand(font),
+                #[cfg(feature = "pf-text")]
+                Command::SetFontSize(size) => context.set_font_size(size),
+                #[cfg(feature = "pf-text")]
+                Command::SetTextAlign(align) => context.set_text_align(align),
+                #[cfg(feature = "pf-text")]
+                Command::SetTextBaseline(baseline) => context.set_text_baseline(baseline),
+                #[cfg(feature = "pf-text")]
+                Command::SetDirection(direction) => context.set_direction(direction),
+                #[cfg(feature = "pf-text")]
+                Command::SetLetterSpacing(spacing) => context.set_letter_spacing(spacing),
+                #[cfg(feature = "pf-text")]
+                Command::SetWordSpacing(spacing) => context.set_word_spacing(spacing),
+                #[cfg(feature = "pf-text")]
+                Command::SetFontKerning(kerning) => context.set_font_kerning(kerning),
+                #[cfg(feature = "pf-text")]
+                Command::SetFontStretch(stretch) => context.set_font_stretch(stretch),
+                #[cfg(feature = "pf-text")]
+                Command::SetFontVariantCaps(variant_caps) => {
+                    context.set_font_variant_caps(variant_caps)
+                }
+            }
+        }
+
+        while context.layers.len() > layer_count {
+            context.end_layer();
+        }
+        while context.saved_states.len() > state_depth {
+            context.restore();
+        }
+    }
+}
+
+/// One recorded call. Each variant is named after the method that records it, and
+/// `draw_image()` records the `DrawImage` that `draw_subimage()` would.
+#[derive(Clone, Debug, DeriveDeserialize, DeriveSerialize)]
+pub enum Command {
+    Save,
+    Restore,
+    BeginLayer(LayerOptions),
+    EndLayer,
+    SetLineWidth(f32),
+    SetLineCap(#[serde(with = "LineCapDef")] LineCap),
+    SetLineJoin(LineJoin),
+    SetMiterLimit(f32),
+    SetLineDash(Vec<f32>),
+    SetLineDashOffset(f32),
+    SetFillStyle(FillStyle),
+    SetStrokeStyle(FillStyle),
+    SetShadowBlur(f32),
+    SetShadowColor(#[serde(with = "color_u")] ColorU),
+    SetShadowOffset(#[serde(with = "vector_2f")] Vector2F),
+    SetFilter(String),
+    BeginPath,
+    ClosePath,
+    MoveTo(#[serde(with = "vector_2f")] Vector2F),
+    LineTo(#[serde(with = "vector_2f")] Vector2F),
+    QuadraticCurveTo {
+        #[serde(with = "vector_2f")]
+        ctrl: Vector2F,
+        #[serde(with = "vector_2f")]
+        to: Vector2F,
+    },
+    BezierCurveTo {
+        #[serde(with = "vector_2f")]
+        ctrl0: Vector2F,
+        #[serde(with = "vector_2f")]
+        ctrl1: Vector2F,
+        #[serde(with = "vector_2f")]
+        to: Vector2F,
+    },
+    Arc {
+        #[serde(with = "vector_2f")]
+        center: Vector2F,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        #[serde(with = "ArcDirectionDef")]
+        direction: ArcDirection,
+    },
+    ArcTo {
+        #[serde(with = "vector_2f")]
+        ctrl: Vector2F,
+        #[serde(with = "vector_2f")]
+        to: Vector2F,
+        radius: f32,
+    },
+    Rect(#[serde(with = "rect_f")] RectF),
+    Ellipse {
+        #[serde(with = "vector_2f")]
+        center: Vector2F,
+        #[serde(with = "vector_2f")]
+        axes: Vector2F,
+        rotation: f32,
+        start_angle: f32,
+        end_angle: f32,
+    },
+    FillRect(#[serde(with = "rect_f")] RectF),
+    StrokeRect(#[serde(with = "rect_f")] RectF),
+    ClearRect(#[serde(with = "rect_f")] RectF),
+    FillPath(Path2D, #[serde(with = "FillRuleDef")] FillRule),
+    StrokePath(Path2D),
+    Fill(#[serde(with = "FillRuleDef")] FillRule),
+    Stroke,
+    Clip(#[serde(with = "FillRuleDef")] FillRule),
+    ClipPath(Path2D, #[serde(with = "FillRuleDef")] FillRule),
+    Rotate(f32),
+    Scale(#[serde(with = "vector_2f")] Vector2F),
+    Translate(#[serde(with = "vector_2f")] Vector2F),
+    SetTransform(#[serde(with = "transform_2f")] Transform2F),
+    ResetTransform,
+    SetGlobalAlpha(f32),
+    SetGlobalCompositeOperation(CompositeOperation),
+    /// The pattern is the image before `src_rect` is mapped onto `dest_rect`.
+    DrawImage {
+        #[serde(with = "pattern")]
+        pattern: Pattern,
+        #[serde(with = "rect_f")]
+        src_rect: RectF,
+        #[serde(with = "rect_f")]
+        dest_rect: RectF,
+    },
+    SetImageSmoothingEnabled(bool),
+    SetImageSmoothingQuality(ImageSmoothingQuality),
+    PutImageData {
+        image_data: ImageData,
+        #[serde(with = "vector_2i")]
+        origin: Vector2I,
+        #[serde(with = "rect_i")]
+        dirty_rect: RectI,
+    },
+    #[cfg(feature = "pf-text")]
+    FillText {
+        text: String,
+        #[serde(with = "vector_2f")]
+        position: Vector2F,
+    },
+    #[cfg(feature = "pf-text")]
+    StrokeText {
+        text: String,
+        #[serde(with = "vector_2f")]
+        position: Vector2F,
+    },
+    #[cfg(feature = "pf-text")]
+    SetFont(RecordedFont),
+    #[cfg(feature = "pf-text")]
+    SetFontShorthand(String),
+    #[cfg(feature = "pf-text")]
+    SetFontSize(f32),
+    #[cfg(feature = "pf-text")]
+    SetTextAlign(TextAlign),
+    #[cfg(feature = "pf-text")]
+    SetTextBaseline(TextBaseline),
+    #[cfg(feature = "pf-text")]
+    SetDirection(TextDirection),
+    #[cfg(feature = "pf-text")]
+    SetLetterSpacing(f32),
+    #[cfg(feature = "pf-text")]
+    SetWordSpacing(f32),
+    #[cfg(feature = "pf-text")]
+    SetFontKerning(FontKerning),
+    #[cfg(feature = "pf-text")]
+    SetFontStretch(FontStretch),
+    #[cfg(feature = "pf-text")]
+    SetFontVariantCaps(FontVariantCaps),
+}
+
+/// The font collection a `set_font()` call set.
+///
+/// Collections set by PostScript name are looked up again by the font context of the context
+/// they're replayed onto. Others can only be replayed in the recording process.
+#[cfg(feature = "pf-text")]
+#[derive(Clone)]
+pub enum RecordedFont {
+    PostScriptNames(Vec<String>),
+    Collection(Arc<FontCollection>),
+}
+
+#[cfg(feature = "pf-text")]
+impl Debug for RecordedFont {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), FmtError> {
+        match *self {
+            RecordedFont::PostScriptNames(ref names) => names.fmt(formatter),
+            RecordedFont::Collection(_) => write!(formatter, "FontCollection"),
+        }
+    }
+}
+
+#[cfg(feature = "pf-text")]
+impl Serialize for RecordedFont {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        match *self {
+            RecordedFont::PostScriptNames(ref names) => names.serialize(serializer),
+            RecordedFont::Collection(_) => {
+                Err(S::Error::custom("fonts not set by PostScript name can't be serialized"))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pf-text")]
+impl<'de> Deserialize<'de> for RecordedFont {
+    fn deserialize<D>(deserializer: D) -> Result<RecordedFont, D::Error>
+                      where D: Deserializer<'de> {
+        Ok(RecordedFont::PostScriptNames(Vec::deserialize(deserializer)?))
+    }
+}
+
+// Paths are serialized as their contours, the last of which is the one being built.
+
+#[derive(DeriveDeserialize, DeriveSerialize)]
+struct ContourData {
+    // Each point and whether it's an endpoint rather than a control point.
+    points: Vec<(f32, f32, bool)>,
+    closed: bool,
+}
+
+impl ContourData {
+    fn from_contour(contour: &Contour) -> ContourData {
+        let points = (0..contour.len()).map(|point_index| {
+            let position = contour.position_of(point_index);
+            (position.x(), position.y(), contour.point_is_endpoint(point_index))
+        }).collect();
+        ContourData { points, closed: contour.is_closed() }
+    }
+
+    fn to_contour(&self) -> Contour {
+        let mut contour = Contour::with_capacity(self.points.len());
+        let mut ctrls = vec![];
+        for &(x, y, is_endpoint) in &self.points {
+            let point = Vector2F::new(x, y);
+            if !is_endpoint {
+                ctrls.push(point);
+                continue;
+            }
+            match ctrls[..] {
+                [] => contour.push_endpoint(point),
+                [ctrl] => contour.push_quadratic(ctrl, point),
+                [ctrl0, ctrl1] => contour.push_cubic(ctrl0, ctrl1, point),
+                _ => contour.push_endpoint(point),
+            }
+            ctrls.clear();
+        }
+        if self.closed {
+            contour.close();
+        }
+        contour
+    }
+}
+
+impl Serialize for Path2D {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let contours = self.outline.contours().iter().chain(iter::once(&self.current_contour));
+        serializer.collect_seq(contours.map(ContourData::from_contour))
+    }
+}
+
+impl<'de> Deserialize<'de> for Path2D {
+    fn deserialize<D>(deserializer: D) -> Result<Path2D, D::Error> where D: Deserializer<'de> {
+        let mut contours = Vec::<ContourData>::deserialize(deserializer)?;
+        let current_contour = match contours.pop() {
+            Some(contour) => contour.to_contour(),
+            None => Contour::new(),
+        };
+        let mut outline = Outline::new();
+        for contour in &contours {
+            outline.push_contour(contour.to_contour());
+        }
+        Ok(Path2D { outline, current_contour })
+    }
+}
+
+impl Serialize for ImageData {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        pixels::serialize(self.size, &self.data, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImageData {
+    fn deserialize<D>(deserializer: D) -> Result<ImageData, D::Error>
+                      where D: Deserializer<'de> {
+        let (size, data) = pixels::deserialize(deserializer)?;
+        Ok(ImageData { size, data })
+    }
+}
+
+#[derive(DeriveDeserialize, DeriveSerialize)]
+#[serde(remote = "FillRule")]
+enum FillRuleDef {
+    Winding,
+    EvenOdd,
+}
+
+#[derive(DeriveDeserialize, DeriveSerialize)]
+#[serde(remote = "LineCap")]
+enum LineCapDef {
+    Butt,
+    Square,
+    Round,
+}
+
+#[derive(DeriveDeserialize, DeriveSerialize)]
+#[serde(remote = "ArcDirection")]
+#[allow(clippy::upper_case_acronyms)]
+enum ArcDirectionDef {
+    CW,
+    CCW,
+}
+
+// Serialization of geometry and content types, for use with `#[serde(with)]`.
+
+pub(crate) mod vector_2f {
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S>(vector: &Vector2F, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        (vector.x(), vector.y()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vector2F, D::Error>
+                                      where D: Deserializer<'de> {
+        let (x, y) = Deserialize::deserialize(deserializer)?;
+        Ok(vec2f(x, y))
+    }
+}
+
+pub(crate) mod vector_2i {
+    use pathfinder_geometry::vector::{Vector2I, vec2i};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S>(vector: &Vector2I, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        (vector.x(), vector.y()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vector2I, D::Error>
+                                      where D: Deserializer<'de> {
+        let (x, y) = Deserialize::deserialize(deserializer)?;
+        Ok(vec2i(x, y))
+    }
+}
+
+pub(crate) mod rect_f {
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_geometry::vector::vec2f;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S>(rect: &RectF, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        (rect.origin_x(), rect.origin_y(), rect.width(), rect.height()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<RectF, D::Error>
+                                      where D: Deserializer<'de> {
+        let (x, y, width, height) = Deserialize::deserialize(deserializer)?;
+        Ok(RectF::new(vec2f(x, y), vec2f(width, height)))
+    }
+}
+
+pub(crate) mod rect_i {
+    use pathfinder_geometry::rect::RectI;
+    use pathfinder_geometry::vector::vec2i;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S>(rect: &RectI, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        (rect.origin_x(), rect.origin_y(), rect.width(), rect.height()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<RectI, D::Error>
+                                      where D: Deserializer<'de> {
+        let (x, y, width, height) = Deserialize::deserialize(deserializer)?;
+        Ok(RectI::new(vec2i(x, y), vec2i(width, height)))
+    }
+}
+
+pub(crate) mod transform_2f {
+    use pathfinder_geometry::transform2d::Transform2F;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S>(transform: &Transform2F, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        let t = transform;
+        (t.m11(), t.m12(), t.m13(), t.m21(), t.m22(), t.m23()).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Transform2F, D::Error>
+                                      where D: Deserializer<'de> {
+        let (m11, m12, m13, m21, m22, m23) = Deserialize::deserialize(deserializer)?;
+        Ok(Transform2F::row_major(m11, m12, m13, m21, m22, m23))
+    }
+}
+
+pub(crate) mod color_u {
+    use pathfinder_color::ColorU;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S>(color: &ColorU, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        [color.r, color.g, color.b, color.a].serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<ColorU, D::Error>
+                                      where D: Deserializer<'de> {
+        let [r, g, b, a]: [u8; 4] = Deserialize::deserialize(deserializer)?;
+        Ok(ColorU::new(r, g, b, a))
+    }
+}
+
+// Images are serialized as their size and their RGBA bytes.
+mod pixels {
+    use pathfinder_color::ColorU;
+    use pathfinder_geometry::vector::Vector2I;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(super) fn serialize<S>(size: Vector2I, pixels: &[ColorU], serializer: S)
+                               -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        let bytes: Vec<u8> = pixels.iter().flat_map(|pixel| {
+            vec![pixel.r, pixel.g, pixel.b, pixel.a]
+        }).collect();
+        (size.x(), size.y(), bytes).serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<(Vector2I, Vec<ColorU>), D::Error>
+                                      where D: Deserializer<'de> {
+        let (width, height, bytes): (i32, i32, Vec<u8>) = Deserialize::deserialize(deserializer)?;
+        if width < 0 || height < 0 || bytes.len() != width as usize * height as usize * 4 {
+            return Err(D::Error::custom("image size doesn't match its pixels"));
+        }
+        let pixels = bytes.chunks(4).map(|bytes| {
+            ColorU::new(bytes[0], bytes[1], bytes[2], bytes[3])
+        }).collect();
+        Ok((Vector2I::new(width, height), pixels))
+    }
+}
+
+pub(crate) mod gradient {
+    use pathfinder_color::ColorU;
+    use pathfinder_content::gradient::{Gradient, GradientGeometry};
+    use pathfinder_geometry::line_segment::LineSegment2F;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::Vector2F;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize, Serialize)]
+    enum GeometryData {
+        Linear {
+            #[serde(with = "super::vector_2f")]
+            from: Vector2F,
+            #[serde(with = "super::vector_2f")]
+            to: Vector2F,
+        },
+        Radial {
+            #[serde(with = "super::vector_2f")]
+            from: Vector2F,
+            #[serde(with = "super::vector_2f")]
+            to: Vector2F,
+            #[serde(with = "super::vector_2f")]
+            radii: Vector2F,
+            #[serde(with = "super::transform_2f")]
+            transform: Transform2F,
+        },
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct StopData {
+        offset: f32,
+        #[serde(with = "super::color_u")]
+        color: ColorU,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct GradientData {
+        geometry: GeometryData,
+        stops: Vec<StopData>,
+    }
+
+    pub(crate) fn serialize<S>(gradient: &Gradient, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        let geometry = match gradient.geometry {
+            GradientGeometry::Linear(line) => {
+                GeometryData::Linear { from: line.from(), to: line.to() }
+            }
+            GradientGeometry::Radial { line, radii, transform } => {
+                GeometryData::Radial {
+                    from: line.from(),
+                    to: line.to(),
+                    radii: Vector2F(radii),
+                    transform,
+                }
+            }
+        };
+        let stops = gradient.stops().iter().map(|stop| {
+            StopData { offset: stop.offset, color: stop.color }
+        }).collect();
+        GradientData { geometry, stops }.serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Gradient, D::Error>
+                                      where D: Deserializer<'de> {
+        let data = GradientData::deserialize(deserializer)?;
+        let mut gradient = match data.geometry {
+            GeometryData::Linear { from, to } => Gradient::linear_from_points(from, to),
+            GeometryData::Radial { from, to, radii, transform } => {
+                let mut gradient = Gradient::radial(LineSegment2F::new(from, to), radii.0);
+                gradient.apply_transform(transform);
+                gradient
+            }
+        };
+        for stop in data.stops {
+            gradient.add_color_stop(stop.color, stop.offset);
+        }
+        Ok(gradient)
+    }
+}
+
+pub(crate) mod pattern {
+    use pathfinder_content::pattern::{Image, Pattern, PatternSampling, PatternSource};
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::Vector2I;
+    use pathfinder_color::ColorU;
+    use serde::de::Deserializer;
+    use serde::ser::{Error, SerializeTuple, Serializer};
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(remote = "PatternSampling")]
+    enum PatternSamplingDef {
+        Nearest,
+        Bilinear,
+        Trilinear,
+        Bicubic,
+    }
+
+    // The pattern's image, kept apart so that its pixels serialize without being copied into
+    // a `PatternData`.
+    struct ImageRef<'a>(&'a Image);
+
+    impl<'a> Serialize for ImageRef<'a> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+            super::pixels::serialize(self.0.size(), self.0.pixels(), serializer)
+        }
+    }
+
+    #[derive(Deserialize, Serialize)]
+    struct PatternData {
+        #[serde(with = "super::transform_2f")]
+        transform: Transform2F,
+        #[serde(with = "PatternSamplingDef")]
+        sampling: PatternSampling,
+        repeat_x: bool,
+        repeat_y: bool,
+    }
+
+    pub(crate) fn serialize<S>(pattern: &Pattern, serializer: S) -> Result<S::Ok, S::Error>
+                               where S: Serializer {
+        let image = match *pattern.source() {
+            PatternSource::Image(ref image) => image,
+            PatternSource::RenderTarget { .. } => {
+                return Err(S::Error::custom("patterns of render targets can't be serialized"));
+            }
+        };
+        if pattern.filter().is_some() {
+            return Err(S::Error::custom("pattern filters can't be serialized"));
+        }
+
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&ImageRef(image))?;
+        tuple.serialize_element(&PatternData {
+            transform: pattern.transform(),
+            sampling: pattern.sampling(),
+            repeat_x: pattern.repeat_x(),
+            repeat_y: pattern.repeat_y(),
+        })?;
+        tuple.end()
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Pattern, D::Error>
+                                      where D: Deserializer<'de> {
+        #[derive(Deserialize)]
+        struct ImageOwned(#[serde(deserialize_with = "super::pixels::deserialize")]
+                          (Vector2I, Vec<ColorU>));
+
+        let (ImageOwned((size, pixels)), data) =
+            <(ImageOwned, PatternData)>::deserialize(deserializer)?;
+        let mut pattern = Pattern::from_image(Image::new(size, Arc::new(pixels)));
+        pattern.apply_transform(data.transform);
+        pattern.set_sampling(data.sampling);
+        pattern.set_repeat_x(data.repeat_x);
+        pattern.set_repeat_y(data.repeat_y);
+        Ok(pattern)
+    }
+}
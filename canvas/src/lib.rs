@@ -19,17 +19,24 @@ pub use pathfinder_geometry::rect::{RectF, RectI};
 pub use pathfinder_geometry::transform2d::Transform2F;
 pub use pathfinder_geometry::vector::{IntoVector2F, Vector2F, Vector2I, vec2f, vec2i};
 
+use crate::css::FilterFunction;
+use crate::display_list::Command;
 use pathfinder_content::dash::OutlineDash;
-use pathfinder_content::effects::{BlendMode, BlurDirection, PatternFilter};
+use pathfinder_content::effects::{BlendMode, BlurDirection, ColorMatrix, PatternFilter};
 use pathfinder_content::gradient::Gradient;
 use pathfinder_content::outline::{Contour, Outline};
-use pathfinder_content::pattern::Pattern;
+use pathfinder_content::pattern::{Image, Pattern, PatternSampling};
 use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_content::stroke::{LineJoin as StrokeLineJoin};
 use pathfinder_content::stroke::{OutlineStrokeToFill, StrokeStyle};
 use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_gpu::Device;
+use pathfinder_renderer::concurrent::executor::SequentialExecutor;
+use pathfinder_renderer::gpu::renderer::Renderer;
+use pathfinder_renderer::options::BuildOptions;
 use pathfinder_renderer::paint::{Paint, PaintCompositeOp};
 use pathfinder_renderer::scene::{ClipPath, ClipPathId, DrawPath, RenderTarget, Scene};
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::default::Default;
 use std::f32::consts::PI;
@@ -38,8 +45,11 @@ use std::fmt::{Debug, Error as FmtError, Formatter};
 use std::mem;
 use std::sync::Arc;
 
+pub use crate::display_list::DisplayList;
 pub use text::CanvasFontContext;
 
+#[cfg(feature = "pf-text")]
+use crate::css::FontShorthand;
 #[cfg(feature = "pf-text")]
 use skribo::FontCollection;
 #[cfg(not(feature = "pf-text"))]
@@ -51,6 +61,10 @@ pub use text::TextMetrics;
 const HAIRLINE_STROKE_WIDTH: f32 = 0.0333;
 const DEFAULT_FONT_SIZE: f32 = 10.0;
 
+mod css;
+
+pub mod display_list;
+
 #[cfg(feature = "pf-text")]
 mod text;
 
@@ -105,6 +119,9 @@ impl Canvas {
             canvas: self,
             current_state: State::default(default_font_collection),
             saved_states: vec![],
+            layers: vec![],
+            current_path: Path2D::new(),
+            display_list: None,
             canvas_font_context,
         }
     }
@@ -119,6 +136,9 @@ pub struct CanvasRenderingContext2D {
     canvas: Canvas,
     current_state: State,
     saved_states: Vec<State>,
+    layers: Vec<Layer>,
+    current_path: Path2D,
+    display_list: Option<DisplayList>,
     #[allow(dead_code)]
     canvas_font_context: CanvasFontContext,
 }
@@ -131,8 +151,11 @@ impl CanvasRenderingContext2D {
         &self.canvas
     }
 
-    #[inline]
-    pub fn into_canvas(self) -> Canvas {
+    /// Returns the canvas, ending any layers that are still open first.
+    pub fn into_canvas(mut self) -> Canvas {
+        while !self.layers.is_empty() {
+            self.end_layer();
+        }
         self.canvas
     }
 
@@ -140,19 +163,22 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn fill_rect(&mut self, rect: RectF) {
+        self.record(|| Command::FillRect(rect));
         let mut path = Path2D::new();
         path.rect(rect);
-        self.fill_path(path, FillRule::Winding);
+        self.push_fill_path(path, FillRule::Winding);
     }
 
     #[inline]
     pub fn stroke_rect(&mut self, rect: RectF) {
+        self.record(|| Command::StrokeRect(rect));
         let mut path = Path2D::new();
         path.rect(rect);
-        self.stroke_path(path);
+        self.stroke_outline(path.into_outline());
     }
 
     pub fn clear_rect(&mut self, rect: RectF) {
+        self.record(|| Command::ClearRect(rect));
         let mut path = Path2D::new();
         path.rect(rect);
 
@@ -172,26 +198,31 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_line_width(&mut self, new_line_width: f32) {
+        self.record(|| Command::SetLineWidth(new_line_width));
         self.current_state.line_width = new_line_width
     }
 
     #[inline]
     pub fn set_line_cap(&mut self, new_line_cap: LineCap) {
+        self.record(|| Command::SetLineCap(new_line_cap));
         self.current_state.line_cap = new_line_cap
     }
 
     #[inline]
     pub fn set_line_join(&mut self, new_line_join: LineJoin) {
+        self.record(|| Command::SetLineJoin(new_line_join));
         self.current_state.line_join = new_line_join
     }
 
     #[inline]
     pub fn set_miter_limit(&mut self, new_miter_limit: f32) {
+        self.record(|| Command::SetMiterLimit(new_miter_limit));
         self.current_state.miter_limit = new_miter_limit
     }
 
     #[inline]
     pub fn set_line_dash(&mut self, mut new_line_dash: Vec<f32>) {
+        self.record(|| Command::SetLineDash(new_line_dash.clone()));
         // Duplicate and concatenate if an odd number of dashes are present.
         if new_line_dash.len() % 2 == 1 {
             let mut real_line_dash = new_line_dash.clone();
@@ -204,6 +235,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_line_dash_offset(&mut self, new_line_dash_offset: f32) {
+        self.record(|| Command::SetLineDashOffset(new_line_dash_offset));
         self.current_state.line_dash_offset = new_line_dash_offset
     }
 
@@ -211,12 +243,16 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_fill_style<FS>(&mut self, new_fill_style: FS) where FS: Into<FillStyle> {
-        self.current_state.fill_paint = new_fill_style.into().into_paint();
+        let new_fill_style = new_fill_style.into();
+        self.record(|| Command::SetFillStyle(new_fill_style.clone()));
+        self.current_state.fill_paint = new_fill_style.into_paint();
     }
 
     #[inline]
     pub fn set_stroke_style<FS>(&mut self, new_stroke_style: FS) where FS: Into<FillStyle> {
-        self.current_state.stroke_paint = new_stroke_style.into().into_paint();
+        let new_stroke_style = new_stroke_style.into();
+        self.record(|| Command::SetStrokeStyle(new_stroke_style.clone()));
+        self.current_state.stroke_paint = new_stroke_style.into_paint();
     }
 
     // Shadows
@@ -228,6 +264,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_shadow_blur(&mut self, new_shadow_blur: f32) {
+        self.record(|| Command::SetShadowBlur(new_shadow_blur));
         self.current_state.shadow_blur = new_shadow_blur;
     }
 
@@ -238,6 +275,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_shadow_color(&mut self, new_shadow_color: ColorU) {
+        self.record(|| Command::SetShadowColor(new_shadow_color));
         self.current_state.shadow_color = new_shadow_color;
     }
 
@@ -248,50 +286,180 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_shadow_offset(&mut self, new_shadow_offset: Vector2F) {
+        self.record(|| Command::SetShadowOffset(new_shadow_offset));
         self.current_state.shadow_offset = new_shadow_offset;
     }
 
+    // Filters
+
+    #[inline]
+    pub fn filter(&self) -> &str {
+        &self.current_state.filter_string
+    }
+
+    /// Sets the filter to a CSS filter value, such as `blur(2px) grayscale(50%)` or `none`.
+    ///
+    /// As in HTML, invalid values are ignored.
+    pub fn set_filter(&mut self, new_filter: &str) {
+        self.record(|| Command::SetFilter(new_filter.to_owned()));
+        if let Some(filter) = css::parse_filter(new_filter) {
+            self.current_state.filter = filter;
+            self.current_state.filter_string = new_filter.to_owned();
+        }
+    }
+
+    // The current default path
+    //
+    // As in HTML, points are transformed by the current transform as they're added, so changing
+    // the transform afterward doesn't move them.
+
+    #[inline]
+    pub fn begin_path(&mut self) {
+        self.record(|| Command::BeginPath);
+        self.current_path = Path2D::new();
+    }
+
+    #[inline]
+    pub fn close_path(&mut self) {
+        self.record(|| Command::ClosePath);
+        self.current_path.close_path();
+    }
+
+    #[inline]
+    pub fn move_to(&mut self, to: Vector2F) {
+        self.record(|| Command::MoveTo(to));
+        self.current_path.move_to(self.current_state.transform * to);
+    }
+
+    #[inline]
+    pub fn line_to(&mut self, to: Vector2F) {
+        self.record(|| Command::LineTo(to));
+        self.current_path.line_to(self.current_state.transform * to);
+    }
+
+    #[inline]
+    pub fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
+        self.record(|| Command::QuadraticCurveTo { ctrl, to });
+        let transform = self.current_state.transform;
+        self.current_path.quadratic_curve_to(transform * ctrl, transform * to);
+    }
+
+    #[inline]
+    pub fn bezier_curve_to(&mut self, ctrl0: Vector2F, ctrl1: Vector2F, to: Vector2F) {
+        self.record(|| Command::BezierCurveTo { ctrl0, ctrl1, to });
+        let transform = self.current_state.transform;
+        self.current_path.bezier_curve_to(transform * ctrl0, transform * ctrl1, transform * to);
+    }
+
+    #[inline]
+    pub fn arc(&mut self,
+               center: Vector2F,
+               radius: f32,
+               start_angle: f32,
+               end_angle: f32,
+               direction: ArcDirection) {
+        self.record(|| Command::Arc { center, radius, start_angle, end_angle, direction });
+        let transform = self.current_state.transform;
+        self.current_path.push_arc(center, radius, start_angle, end_angle, direction, &transform);
+    }
+
+    #[inline]
+    pub fn arc_to(&mut self, ctrl: Vector2F, to: Vector2F, radius: f32) {
+        self.record(|| Command::ArcTo { ctrl, to, radius });
+        let transform = self.current_state.transform;
+        self.current_path.push_arc_to(ctrl, to, radius, &transform);
+    }
+
+    #[inline]
+    pub fn rect(&mut self, rect: RectF) {
+        self.record(|| Command::Rect(rect));
+        let transform = self.current_state.transform;
+        self.current_path.push_rect(rect, &transform);
+    }
+
+    #[inline]
+    pub fn ellipse<A>(&mut self,
+                      center: Vector2F,
+                      axes: A,
+                      rotation: f32,
+                      start_angle: f32,
+                      end_angle: f32)
+                      where A: IntoVector2F {
+        let axes = axes.into_vector_2f();
+        self.record(|| Command::Ellipse { center, axes, rotation, start_angle, end_angle });
+        let transform = self.current_state.transform;
+        self.current_path.push_ellipse(center,
+                                       axes,
+                                       rotation,
+                                       start_angle,
+                                       end_angle,
+                                       &transform);
+    }
+
     // Drawing paths
 
     #[inline]
     pub fn fill_path(&mut self, path: Path2D, fill_rule: FillRule) {
-        self.push_path(path.into_outline(), PathOp::Fill, fill_rule);
+        self.record(|| Command::FillPath(path.clone(), fill_rule));
+        self.push_fill_path(path, fill_rule);
     }
 
     #[inline]
     pub fn stroke_path(&mut self, path: Path2D) {
-        let mut stroke_style = self.current_state.resolve_stroke_style();
-
-        // The smaller scale is relevant here, as we multiply by it and want to ensure it is always
-        // bigger than `HAIRLINE_STROKE_WIDTH`.
-        let transform_scales = self.current_state.transform.extract_scale();
-        let transform_scale = f32::min(transform_scales.x(), transform_scales.y());
+        self.record(|| Command::StrokePath(path.clone()));
+        self.stroke_outline(path.into_outline());
+    }
 
-        // Avoid the division in the normal case of sufficient thickness.
-        if stroke_style.line_width * transform_scale < HAIRLINE_STROKE_WIDTH {
-            stroke_style.line_width = HAIRLINE_STROKE_WIDTH / transform_scale;
+    /// Fills the current default path.
+    #[inline]
+    pub fn fill(&mut self, fill_rule: FillRule) {
+        self.record(|| Command::Fill(fill_rule));
+        let outline = self.current_path.clone().into_outline();
+        self.push_path(outline, PathOp::Fill, fill_rule);
+    }
+
+    /// Strokes the current default path.
+    ///
+    /// The line width, dashes and so forth are in the space of the current transform, not the
+    /// ones the points were added with. If the current transform is singular, nothing is drawn.
+    pub fn stroke(&mut self) {
+        self.record(|| Command::Stroke);
+        let transform = self.current_state.transform;
+        if transform.matrix.det() == 0.0 {
+            return;
         }
+        let mut outline = self.current_path.clone().into_outline();
+        outline.transform(&transform.inverse());
+        self.stroke_outline(outline);
+    }
 
-        let mut outline = path.into_outline();
-        if !self.current_state.line_dash.is_empty() {
-            let mut dash = OutlineDash::new(&outline,
-                                            &self.current_state.line_dash,
-                                            self.current_state.line_dash_offset);
-            dash.dash();
-            outline = dash.into_outline();
-        }
+    /// Intersects the clip region with the current default path.
+    #[inline]
+    pub fn clip(&mut self, fill_rule: FillRule) {
+        self.record(|| Command::Clip(fill_rule));
+        let outline = self.current_path.clone().into_outline();
+        self.push_clip_path(outline, fill_rule);
+    }
 
-        let mut stroke_to_fill = OutlineStrokeToFill::new(&outline, stroke_style);
-        stroke_to_fill.offset();
-        outline = stroke_to_fill.into_outline();
+    fn push_fill_path(&mut self, path: Path2D, fill_rule: FillRule) {
+        let mut outline = path.into_outline();
+        outline.transform(&self.current_state.transform);
+        self.push_path(outline, PathOp::Fill, fill_rule);
+    }
 
+    fn stroke_outline(&mut self, outline: Outline) {
+        let outline = self.current_state.stroke_to_fill(outline);
         self.push_path(outline, PathOp::Stroke, FillRule::Winding);
     }
 
     pub fn clip_path(&mut self, path: Path2D, fill_rule: FillRule) {
+        self.record(|| Command::ClipPath(path.clone(), fill_rule));
         let mut outline = path.into_outline();
         outline.transform(&self.current_state.transform);
+        self.push_clip_path(outline, fill_rule);
+    }
 
+    fn push_clip_path(&mut self, outline: Outline, fill_rule: FillRule) {
         let mut clip_path = ClipPath::new(outline);
         clip_path.set_fill_rule(fill_rule);
         let clip_path_id = self.canvas.scene.push_clip_path(clip_path);
@@ -299,19 +467,22 @@ impl CanvasRenderingContext2D {
         self.current_state.clip_path = Some(clip_path_id);
     }
 
-    fn push_path(&mut self, mut outline: Outline, path_op: PathOp, fill_rule: FillRule) {
+    // The outline must already be transformed by the current transform.
+    fn push_path(&mut self, outline: Outline, path_op: PathOp, fill_rule: FillRule) {
         let paint = self.current_state.resolve_paint(match path_op {
             PathOp::Fill => &self.current_state.fill_paint,
             PathOp::Stroke => &self.current_state.stroke_paint,
         });
-        let paint_id = self.canvas.scene.push_paint(&paint);
+        if !self.current_state.filter.is_empty() {
+            let paint = paint.into_owned();
+            self.push_filtered_paths(vec![outline], paint, fill_rule);
+            return;
+        }
 
-        let transform = self.current_state.transform;
+        let paint_id = self.canvas.scene.push_paint(&paint);
         let clip_path = self.current_state.clip_path;
         let blend_mode = self.current_state.global_composite_operation.to_blend_mode();
 
-        outline.transform(&transform);
-
         if !self.current_state.shadow_color.is_fully_transparent() {
             let mut outline = outline.clone();
             outline.transform(&Transform2F::from_translation(self.current_state.shadow_offset));
@@ -417,20 +588,105 @@ impl CanvasRenderingContext2D {
 
     }
 
+    // Draws the paths into a layer, runs the filter functions over that, and composites the
+    // result and its shadow into the canvas. The outlines must already be transformed.
+    fn push_filtered_paths(&mut self,
+                           mut outlines: Vec<Outline>,
+                           mut paint: Paint,
+                           fill_rule: FillRule) {
+        let state = &self.current_state;
+        let scene = &mut self.canvas.scene;
+
+        let has_shadow = !state.shadow_color.is_fully_transparent();
+        let shadow_sigma = state.shadow_blur * 0.5;
+
+        // Find the area the filters can draw to. Content more than `reach` outside the canvas
+        // can't be moved or blurred into it, so it's left out.
+        outlines.retain(|outline| !outline.contours().is_empty());
+        let mut bounds = match outlines.first() {
+            Some(outline) => outline.bounds(),
+            None => return,
+        };
+        for outline in &outlines[1..] {
+            bounds = bounds.union_rect(outline.bounds());
+        }
+        let (mut bounds, mut reach) = filter_reach(bounds, &state.filter);
+        if has_shadow {
+            // The shadow is offset when it's composited, but it needs room to blur.
+            bounds = bounds.dilate(shadow_sigma * 3.0);
+            reach += state.shadow_offset.length() + shadow_sigma * 3.0;
+        }
+        let canvas_rect = scene.view_box().dilate(reach);
+        let bounds = match bounds.intersection(canvas_rect) {
+            Some(bounds) => bounds.round_out().to_i32(),
+            None => return,
+        };
+        if bounds.width() == 0 || bounds.height() == 0 {
+            return;
+        }
+
+        // Draw the paths into a layer.
+        let to_layer = Transform2F::from_translation(-bounds.origin().to_f32());
+        paint.apply_transform(&to_layer);
+        let layer = FilterLayer::push(scene, bounds.size());
+        let paint_id = scene.push_paint(&paint);
+        for mut outline in outlines {
+            outline.transform(&to_layer);
+            let mut path = DrawPath::new(outline, paint_id);
+            path.set_fill_rule(fill_rule);
+            scene.push_path(path);
+        }
+        scene.pop_render_target();
+
+        // Run the filter functions.
+        let layer = layer.filter(scene, &state.filter);
+
+        // Composite the shadow, then the layer itself.
+        let origin = bounds.origin().to_f32();
+        let clip_path = state.clip_path;
+        let blend_mode = state.global_composite_operation.to_blend_mode();
+        if has_shadow {
+            let shadow = layer.shadow(scene, Vector2F::zero(), shadow_sigma, state.shadow_color);
+            shadow.composite(scene, origin + state.shadow_offset, 1.0, clip_path, blend_mode);
+        }
+        layer.composite(scene, origin, 1.0, clip_path, blend_mode);
+    }
+
+    // Hit testing
+
+    /// Returns true if filling `path` with the current transform would cover `point`, which is
+    /// in canvas coordinates.
+    pub fn is_point_in_path(&self, path: &Path2D, point: Vector2F, fill_rule: FillRule) -> bool {
+        let mut outline = path.clone().into_outline();
+        outline.transform(&self.current_state.transform);
+        outline.contains_point(point, fill_rule)
+    }
+
+    /// Returns true if stroking `path` with the current transform and line styles would cover
+    /// `point`, which is in canvas coordinates.
+    pub fn is_point_in_stroke(&self, path: &Path2D, point: Vector2F) -> bool {
+        let outline = self.current_state.stroke_to_fill(path.clone().into_outline());
+        outline.contains_point(point, FillRule::Winding)
+    }
+
     // Transformations
 
     #[inline]
     pub fn rotate(&mut self, angle: f32) {
+        self.record(|| Command::Rotate(angle));
         self.current_state.transform *= Transform2F::from_rotation(angle)
     }
 
     #[inline]
     pub fn scale<S>(&mut self, scale: S) where S: IntoVector2F {
+        let scale = scale.into_vector_2f();
+        self.record(|| Command::Scale(scale));
         self.current_state.transform *= Transform2F::from_scale(scale)
     }
 
     #[inline]
     pub fn translate(&mut self, offset: Vector2F) {
+        self.record(|| Command::Translate(offset));
         self.current_state.transform *= Transform2F::from_translation(offset)
     }
 
@@ -441,11 +697,13 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_transform(&mut self, new_transform: &Transform2F) {
+        self.record(|| Command::SetTransform(*new_transform));
         self.current_state.transform = *new_transform;
     }
 
     #[inline]
     pub fn reset_transform(&mut self) {
+        self.record(|| Command::ResetTransform);
         self.current_state.transform = Transform2F::default();
     }
 
@@ -458,6 +716,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_global_alpha(&mut self, new_global_alpha: f32) {
+        self.record(|| Command::SetGlobalAlpha(new_global_alpha));
         self.current_state.global_alpha = new_global_alpha;
     }
 
@@ -468,6 +727,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_global_composite_operation(&mut self, new_composite_operation: CompositeOperation) {
+        self.record(|| Command::SetGlobalCompositeOperation(new_composite_operation));
         self.current_state.global_composite_operation = new_composite_operation;
     }
 
@@ -484,14 +744,22 @@ impl CanvasRenderingContext2D {
     pub fn draw_subimage<I, L>(&mut self, image: I, src_location: RectF, dest_location: L)
                                where I: CanvasImageSource, L: CanvasImageDestLocation {
         let dest_size = dest_location.size().unwrap_or(src_location.size());
+        let dest_rect = RectF::new(dest_location.origin(), dest_size);
         let scale = dest_size / src_location.size();
         let offset = dest_location.origin() - src_location.origin();
         let transform = Transform2F::from_scale(scale).translate(offset);
 
-        let pattern = image.to_pattern(self, transform);
-        let old_fill_paint = self.current_state.fill_paint.clone();
-        self.set_fill_style(pattern);
-        self.fill_rect(RectF::new(dest_location.origin(), dest_size));
+        let mut pattern = image.to_pattern(self, Transform2F::default());
+        self.record(|| {
+            Command::DrawImage { pattern: pattern.clone(), src_rect: src_location, dest_rect }
+        });
+        pattern.apply_transform(transform);
+
+        let fill_paint = Paint::from_pattern(pattern);
+        let old_fill_paint = mem::replace(&mut self.current_state.fill_paint, fill_paint);
+        let mut path = Path2D::new();
+        path.rect(dest_rect);
+        self.push_fill_path(path, FillRule::Winding);
         self.current_state.fill_paint = old_fill_paint;
     }
 
@@ -504,6 +772,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_image_smoothing_enabled(&mut self, enabled: bool) {
+        self.record(|| Command::SetImageSmoothingEnabled(enabled));
         self.current_state.image_smoothing_enabled = enabled
     }
 
@@ -514,23 +783,183 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_image_smoothing_quality(&mut self, new_quality: ImageSmoothingQuality) {
+        self.record(|| Command::SetImageSmoothingQuality(new_quality));
         self.current_state.image_smoothing_quality = new_quality
     }
 
+    // Pixel manipulation
+
+    /// Renders the canvas with `renderer` and returns the non-premultiplied pixels in `rect`.
+    ///
+    /// The renderer's destination framebuffer should be the size of the canvas, and the
+    /// renderer's background color, if any, shows through. Pixels of `rect` outside the canvas
+    /// are transparent black. This waits for the GPU, so it's slow.
+    pub fn get_image_data<D>(&self, renderer: &mut Renderer<D>, rect: RectI) -> ImageData
+                             where D: Device {
+        let mut image_data = ImageData::new(rect.size());
+        let canvas_rect = RectI::new(Vector2I::zero(), self.canvas.size());
+        let read_rect = match rect.intersection(canvas_rect) {
+            Some(read_rect) if read_rect.width() > 0 && read_rect.height() > 0 => read_rect,
+            _ => return image_data,
+        };
+
+        let mut scene = self.canvas.scene.clone();
+        scene.build_and_render(renderer, BuildOptions::default(), &SequentialExecutor);
+        let image = renderer.read_back(read_rect);
+        image_data.put_image(&image, read_rect.origin() - rect.origin());
+        image_data
+    }
+
+    /// Replaces the pixels at `origin` with the part of `image_data` inside `dirty_rect`.
+    ///
+    /// As in HTML, the current transform, clip, global alpha, shadow and compositing operation
+    /// don't apply.
+    pub fn put_image_data(&mut self, image_data: &ImageData, origin: Vector2I, dirty_rect: RectI) {
+        self.record(|| {
+            Command::PutImageData { image_data: image_data.clone(), origin, dirty_rect }
+        });
+        let image_rect = RectI::new(Vector2I::zero(), image_data.size);
+        let dirty_rect = match dirty_rect.intersection(image_rect) {
+            Some(dirty_rect) if dirty_rect.width() > 0 && dirty_rect.height() > 0 => dirty_rect,
+            _ => return,
+        };
+
+        // `Copy` would be simpler, but it replaces whole tiles, so erase just the destination
+        // pixels and draw the image over them instead.
+        let dest_outline = Outline::from_rect(RectI::new(origin + dirty_rect.origin(),
+                                                         dirty_rect.size()).to_f32());
+        let erase_paint_id = self.canvas.scene.push_paint(&Paint::black());
+        let mut erase_path = DrawPath::new(dest_outline.clone(), erase_paint_id);
+        erase_path.set_blend_mode(BlendMode::DestOut);
+        self.canvas.scene.push_path(erase_path);
+
+        let mut pattern = Pattern::from_image(image_data.to_image());
+        pattern.apply_transform(Transform2F::from_translation(origin.to_f32()));
+        pattern.set_smoothing_enabled(false);
+        let paint_id = self.canvas.scene.push_paint(&Paint::from_pattern(pattern));
+        self.canvas.scene.push_path(DrawPath::new(dest_outline, paint_id));
+    }
+
     // The canvas state
 
     #[inline]
     pub fn save(&mut self) {
+        self.record(|| Command::Save);
         self.saved_states.push(self.current_state.clone());
     }
 
+    /// Restores the most recently saved state. As in HTML, this does nothing if the state was
+    /// saved by `begin_layer()`; call `end_layer()` instead.
     #[inline]
     pub fn restore(&mut self) {
+        self.record(|| Command::Restore);
+        if let Some(layer) = self.layers.last() {
+            if self.saved_states.len() <= layer.state_depth {
+                return;
+            }
+        }
         if let Some(state) = self.saved_states.pop() {
             self.current_state = state;
         }
     }
 
+    // Layers
+
+    /// Starts capturing drawing into a layer, which `end_layer()` composites onto the canvas with
+    /// the opacity, compositing operation and filter in `options`.
+    ///
+    /// The layer is sized to what's drawn into it, plus however far the filter can spread that,
+    /// so drawing keeps the current transform. Like `save()`, this saves the state, which
+    /// `end_layer()` restores; the clip in effect now clips the layer when it's composited rather
+    /// than the drawing inside it. Layers may be nested.
+    pub fn begin_layer(&mut self, options: LayerOptions) {
+        self.record(|| Command::BeginLayer(options.clone()));
+        let filter = css::parse_filter(&options.filter).unwrap_or_default();
+        self.saved_states.push(self.current_state.clone());
+        self.current_state.clip_path = None;
+
+        // Draw into a scene of its own until the layer ends and its bounds are known.
+        let mut layer_scene = Scene::new();
+        layer_scene.set_view_box(self.canvas.scene.view_box());
+        let parent_scene = mem::replace(&mut self.canvas.scene, layer_scene);
+        self.layers.push(Layer {
+            parent_scene,
+            state_depth: self.saved_states.len(),
+            alpha: options.alpha,
+            composite_operation: options.composite_operation,
+            filter,
+        });
+    }
+
+    /// Ends the innermost layer, compositing it onto the canvas (or the enclosing layer) and
+    /// restoring the state saved by `begin_layer()`. This does nothing if no layer is open.
+    pub fn end_layer(&mut self) {
+        self.record(|| Command::EndLayer);
+        let layer = match self.layers.pop() {
+            None => return,
+            Some(layer) => layer,
+        };
+        self.saved_states.truncate(layer.state_depth);
+        self.current_state = self.saved_states.pop().unwrap();
+        let layer_scene = mem::replace(&mut self.canvas.scene, layer.parent_scene);
+        let scene = &mut self.canvas.scene;
+
+        // As with filtered paths, content more than the filter's reach outside the canvas is left
+        // out.
+        let bounds = match layer_scene.bounds_outside_render_targets() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let (bounds, reach) = filter_reach(bounds, &layer.filter);
+        let bounds = match bounds.intersection(scene.view_box().dilate(reach)) {
+            Some(bounds) => bounds.round_out().to_i32(),
+            None => return,
+        };
+        if bounds.width() == 0 || bounds.height() == 0 {
+            return;
+        }
+
+        let origin = bounds.origin().to_f32();
+        let filter_layer = FilterLayer::push(scene, bounds.size());
+        scene.append_scene_with_transform(layer_scene, Transform2F::from_translation(-origin));
+        scene.pop_render_target();
+        let filter_layer = filter_layer.filter(scene, &layer.filter);
+        filter_layer.composite(scene,
+                               origin,
+                               layer.alpha,
+                               self.current_state.clip_path,
+                               layer.composite_operation.to_blend_mode());
+    }
+
+    // Recording
+
+    /// Starts recording calls into a display list, discarding any recording in progress.
+    ///
+    /// Getters, `get_image_data()`, `create_pattern_from_canvas()` and `fill_layout()` aren't
+    /// recorded.
+    #[inline]
+    pub fn begin_recording(&mut self) {
+        self.display_list = Some(DisplayList::new());
+    }
+
+    /// Stops recording and returns the calls recorded since `begin_recording()`.
+    #[inline]
+    pub fn end_recording(&mut self) -> DisplayList {
+        self.display_list.take().unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.display_list.is_some()
+    }
+
+    #[inline]
+    fn record<F>(&mut self, command: F) where F: FnOnce() -> Command {
+        if let Some(ref mut display_list) = self.display_list {
+            display_list.push(command());
+        }
+    }
+
     // Extensions
 
     pub fn create_pattern_from_canvas(&mut self, canvas: Canvas, transform: Transform2F)
@@ -564,8 +993,18 @@ struct State {
     shadow_color: ColorU,
     shadow_blur: f32,
     shadow_offset: Vector2F,
+    filter: Vec<FilterFunction>,
+    filter_string: String,
     text_align: TextAlign,
     text_baseline: TextBaseline,
+    direction: TextDirection,
+    letter_spacing: f32,
+    word_spacing: f32,
+    font_kerning: FontKerning,
+    font_stretch: FontStretch,
+    font_variant_caps: FontVariantCaps,
+    #[cfg(feature = "pf-text")]
+    font_shorthand: Option<FontShorthand>,
     image_smoothing_enabled: bool,
     image_smoothing_quality: ImageSmoothingQuality,
     global_alpha: f32,
@@ -590,8 +1029,18 @@ impl State {
             shadow_color: ColorU::transparent_black(),
             shadow_blur: 0.0,
             shadow_offset: Vector2F::zero(),
-            text_align: TextAlign::Left,
+            filter: vec![],
+            filter_string: "none".to_owned(),
+            text_align: TextAlign::Start,
             text_baseline: TextBaseline::Alphabetic,
+            direction: TextDirection::Inherit,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            font_kerning: FontKerning::Auto,
+            font_stretch: FontStretch::Normal,
+            font_variant_caps: FontVariantCaps::Normal,
+            #[cfg(feature = "pf-text")]
+            font_shorthand: None,
             image_smoothing_enabled: true,
             image_smoothing_quality: ImageSmoothingQuality::Low,
             global_alpha: 1.0,
@@ -604,7 +1053,7 @@ impl State {
         let mut must_copy = !self.transform.is_identity() || self.global_alpha < 1.0;
         if !must_copy {
             if let Some(ref pattern) = paint.pattern() {
-                must_copy = self.image_smoothing_enabled != pattern.smoothing_enabled()
+                must_copy = self.pattern_sampling() != pattern.sampling()
             }
         }
 
@@ -620,11 +1069,49 @@ impl State {
         paint.set_base_color(base_color.to_u8());
 
         if let Some(ref mut pattern) = paint.pattern_mut() {
-            pattern.set_smoothing_enabled(self.image_smoothing_enabled);
+            pattern.set_sampling(self.pattern_sampling());
         }
         Cow::Owned(paint)
     }
 
+    fn pattern_sampling(&self) -> PatternSampling {
+        if !self.image_smoothing_enabled {
+            return PatternSampling::Nearest;
+        }
+        match self.image_smoothing_quality {
+            ImageSmoothingQuality::Low => PatternSampling::Bilinear,
+            ImageSmoothingQuality::Medium => PatternSampling::Trilinear,
+            ImageSmoothingQuality::High => PatternSampling::Bicubic,
+        }
+    }
+
+    // Strokes an untransformed outline, returning the result transformed.
+    fn stroke_to_fill(&self, mut outline: Outline) -> Outline {
+        let mut stroke_style = self.resolve_stroke_style();
+
+        // The smaller scale is relevant here, as we multiply by it and want to ensure it is always
+        // bigger than `HAIRLINE_STROKE_WIDTH`.
+        let transform_scales = self.transform.extract_scale();
+        let transform_scale = f32::min(transform_scales.x(), transform_scales.y());
+
+        // Avoid the division in the normal case of sufficient thickness.
+        if stroke_style.line_width * transform_scale < HAIRLINE_STROKE_WIDTH {
+            stroke_style.line_width = HAIRLINE_STROKE_WIDTH / transform_scale;
+        }
+
+        if !self.line_dash.is_empty() {
+            let mut dash = OutlineDash::new(&outline, &self.line_dash, self.line_dash_offset);
+            dash.dash();
+            outline = dash.into_outline();
+        }
+
+        let mut stroke_to_fill = OutlineStrokeToFill::new(&outline, stroke_style);
+        stroke_to_fill.offset();
+        outline = stroke_to_fill.into_outline();
+        outline.transform(&self.transform);
+        outline
+    }
+
     fn resolve_stroke_style(&self) -> StrokeStyle {
         StrokeStyle {
             line_width: self.line_width,
@@ -650,6 +1137,18 @@ impl Path2D {
         Path2D { outline: Outline::new(), current_contour: Contour::new() }
     }
 
+    /// Creates a path from SVG path data, like the `new Path2D(d)` constructor in HTML.
+    ///
+    /// As in HTML, invalid path data is used up to the first error.
+    pub fn from_svg_path_data(data: &str) -> Path2D {
+        let mut outline = Outline::from_svg_path_data_lenient(data);
+        let current_contour = match outline.contours().last() {
+            Some(contour) if !contour.is_closed() => outline.pop_contour().unwrap(),
+            _ => Contour::new(),
+        };
+        Path2D { outline, current_contour }
+    }
+
     #[inline]
     pub fn close_path(&mut self) {
         self.current_contour.close();
@@ -684,36 +1183,34 @@ impl Path2D {
                start_angle: f32,
                end_angle: f32,
                direction: ArcDirection) {
-        let transform = Transform2F::from_scale(radius).translate(center);
-        self.current_contour.push_arc(&transform, start_angle, end_angle, direction);
+        let transform = Transform2F::default();
+        self.push_arc(center, radius, start_angle, end_angle, direction, &transform);
     }
 
     #[inline]
     pub fn arc_to(&mut self, ctrl: Vector2F, to: Vector2F, radius: f32) {
-        // FIXME(pcwalton): What should we do if there's no initial point?
-        let from = self.current_contour.last_position().unwrap_or_default();
-        let (v0, v1) = (from - ctrl, to - ctrl);
-        let (vu0, vu1) = (v0.normalize(), v1.normalize());
-        let hypot = radius / f32::sqrt(0.5 * (1.0 - vu0.dot(vu1)));
-        let bisector = vu0 + vu1;
-        let center = ctrl + bisector * (hypot / bisector.length());
-
-        let transform = Transform2F::from_scale(radius).translate(center);
-        let chord = LineSegment2F::new(vu0.yx() * vec2f(-1.0,  1.0), vu1.yx() * vec2f( 1.0, -1.0));
+        self.push_arc_to(ctrl, to, radius, &Transform2F::default());
+    }
 
-        // FIXME(pcwalton): Is clockwise direction correct?
-        self.current_contour.push_arc_from_unit_chord(&transform, chord, ArcDirection::CW);
+    /// Adds an elliptical arc from the current point to `to`, parameterized like the SVG `A`
+    /// path command. `x_axis_rotation` is in radians, and `direction` plays the role of the sweep
+    /// flag.
+    #[inline]
+    pub fn svg_arc_to(&mut self,
+                      radii: Vector2F,
+                      x_axis_rotation: f32,
+                      large_arc: bool,
+                      direction: ArcDirection,
+                      to: Vector2F) {
+        self.current_contour.push_svg_arc(radii, x_axis_rotation, large_arc, direction, to);
     }
 
+    #[inline]
     pub fn rect(&mut self, rect: RectF) {
-        self.flush_current_contour();
-        self.current_contour.push_endpoint(rect.origin());
-        self.current_contour.push_endpoint(rect.upper_right());
-        self.current_contour.push_endpoint(rect.lower_right());
-        self.current_contour.push_endpoint(rect.lower_left());
-        self.current_contour.close();
+        self.push_rect(rect, &Transform2F::default());
     }
 
+    #[inline]
     pub fn ellipse<A>(&mut self,
                       center: Vector2F,
                       axes: A,
@@ -721,14 +1218,8 @@ impl Path2D {
                       start_angle: f32,
                       end_angle: f32)
                       where A: IntoVector2F {
-        self.flush_current_contour();
-
-        let transform = Transform2F::from_scale(axes).rotate(rotation).translate(center);
-        self.current_contour.push_arc(&transform, start_angle, end_angle, ArcDirection::CW);
-
-        if end_angle - start_angle >= 2.0 * PI {
-            self.current_contour.close();
-        }
+        let (axes, transform) = (axes.into_vector_2f(), Transform2F::default());
+        self.push_ellipse(center, axes, rotation, start_angle, end_angle, &transform);
     }
 
     // https://html.spec.whatwg.org/multipage/canvas.html#dom-path2d-addpath
@@ -748,6 +1239,64 @@ impl Path2D {
         self.outline
     }
 
+    // The `push_*` methods add shapes given in the space of `transform`, for the current default
+    // path of `CanvasRenderingContext2D`.
+
+    fn push_arc(&mut self,
+                center: Vector2F,
+                radius: f32,
+                start_angle: f32,
+                end_angle: f32,
+                direction: ArcDirection,
+                transform: &Transform2F) {
+        let transform = *transform * Transform2F::from_scale(radius).translate(center);
+        self.current_contour.push_arc(&transform, start_angle, end_angle, direction);
+    }
+
+    fn push_arc_to(&mut self, ctrl: Vector2F, to: Vector2F, radius: f32, transform: &Transform2F) {
+        // FIXME(pcwalton): What should we do if there's no initial point?
+        let from = self.current_contour.last_position().unwrap_or_default();
+        let from = transform.inverse() * from;
+        let (v0, v1) = (from - ctrl, to - ctrl);
+        let (vu0, vu1) = (v0.normalize(), v1.normalize());
+        let hypot = radius / f32::sqrt(0.5 * (1.0 - vu0.dot(vu1)));
+        let bisector = vu0 + vu1;
+        let center = ctrl + bisector * (hypot / bisector.length());
+
+        let transform = *transform * Transform2F::from_scale(radius).translate(center);
+        let chord = LineSegment2F::new(vu0.yx() * vec2f(-1.0,  1.0), vu1.yx() * vec2f( 1.0, -1.0));
+
+        // FIXME(pcwalton): Is clockwise direction correct?
+        self.current_contour.push_arc_from_unit_chord(&transform, chord, ArcDirection::CW);
+    }
+
+    fn push_rect(&mut self, rect: RectF, transform: &Transform2F) {
+        self.flush_current_contour();
+        self.current_contour.push_endpoint(*transform * rect.origin());
+        self.current_contour.push_endpoint(*transform * rect.upper_right());
+        self.current_contour.push_endpoint(*transform * rect.lower_right());
+        self.current_contour.push_endpoint(*transform * rect.lower_left());
+        self.current_contour.close();
+    }
+
+    fn push_ellipse(&mut self,
+                    center: Vector2F,
+                    axes: Vector2F,
+                    rotation: f32,
+                    start_angle: f32,
+                    end_angle: f32,
+                    transform: &Transform2F) {
+        self.flush_current_contour();
+
+        let transform =
+            *transform * Transform2F::from_scale(axes).rotate(rotation).translate(center);
+        self.current_contour.push_arc(&transform, start_angle, end_angle, ArcDirection::CW);
+
+        if end_angle - start_angle >= 2.0 * PI {
+            self.current_contour.close();
+        }
+    }
+
     fn flush_current_contour(&mut self) {
         if !self.current_contour.is_empty() {
             self.outline.push_contour(mem::replace(&mut self.current_contour, Contour::new()));
@@ -755,11 +1304,47 @@ impl Path2D {
     }
 }
 
-#[derive(Clone)]
+/// A rectangle of non-premultiplied RGBA pixels, like `ImageData` in HTML.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageData {
+    pub size: Vector2I,
+    /// The pixels, in row-major order from the top left.
+    pub data: Vec<ColorU>,
+}
+
+impl ImageData {
+    /// Creates transparent black image data.
+    #[inline]
+    pub fn new(size: Vector2I) -> ImageData {
+        let pixel_count = size.x() as usize * size.y() as usize;
+        ImageData { size, data: vec![ColorU::transparent_black(); pixel_count] }
+    }
+
+    #[inline]
+    pub fn from_image(image: &Image) -> ImageData {
+        ImageData { size: image.size(), data: (**image.pixels()).clone() }
+    }
+
+    #[inline]
+    pub fn to_image(&self) -> Image {
+        Image::new(self.size, Arc::new(self.data.clone()))
+    }
+
+    // Copies `image` in at `origin`, which must keep it within bounds.
+    fn put_image(&mut self, image: &Image, origin: Vector2I) {
+        let (width, stride) = (image.size().x() as usize, self.size.x() as usize);
+        for (y, row) in image.pixels().chunks(width).enumerate() {
+            let start = (origin.y() as usize + y) * stride + origin.x() as usize;
+            self.data[start..(start + width)].copy_from_slice(row);
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum FillStyle {
-    Color(ColorU),
-    Gradient(Gradient),
-    Pattern(Pattern),
+    Color(#[serde(with = "display_list::color_u")] ColorU),
+    Gradient(#[serde(with = "display_list::gradient")] Gradient),
+    Pattern(#[serde(with = "display_list::pattern")] Pattern),
 }
 
 impl FillStyle {
@@ -772,14 +1357,18 @@ impl FillStyle {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum TextAlign {
     Left,
     Right,
     Center,
+    /// The left side for left-to-right text and the right side for right-to-left text.
+    Start,
+    /// The right side for left-to-right text and the left side for right-to-left text.
+    End,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum TextBaseline {
     Alphabetic,
     Top,
@@ -789,19 +1378,65 @@ pub enum TextBaseline {
     Bottom,
 }
 
+/// The direction of text, which decides which side `TextAlign::Start` and `TextAlign::End` refer
+/// to. Glyphs are laid out left to right in either direction.
+///
+/// `Inherit` is left to right, since a canvas here has no element to inherit a direction from.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+    Inherit,
+}
+
+/// Whether text is kerned, with the font's GPOS `kern` feature or its `kern` table. `Auto` kerns,
+/// like `Normal`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum FontKerning {
+    Auto,
+    Normal,
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum FontStretch {
+    UltraCondensed,
+    ExtraCondensed,
+    Condensed,
+    SemiCondensed,
+    Normal,
+    SemiExpanded,
+    Expanded,
+    ExtraExpanded,
+    UltraExpanded,
+}
+
+/// Which capital letter forms text uses. Small and petite capitals are synthesized by drawing
+/// uppercased letters at a smaller size, and titling capitals are drawn as ordinary capitals.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum FontVariantCaps {
+    Normal,
+    SmallCaps,
+    AllSmallCaps,
+    PetiteCaps,
+    AllPetiteCaps,
+    Unicase,
+    TitlingCaps,
+}
+
 // We duplicate `pathfinder_content::stroke::LineJoin` here because the HTML canvas API treats the
 // miter limit as part of the canvas state, while the native Pathfinder API treats the miter limit
 // as part of the line join. Pathfinder's choice is more logical, because the miter limit is
 // specific to miter joins. In this API, however, for compatibility we go with the HTML canvas
 // semantics.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum LineJoin {
     Miter,
     Bevel,
     Round,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum CompositeOperation {
     SourceOver,
     SourceIn,
@@ -864,10 +1499,35 @@ impl CompositeOperation {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// How `end_layer()` composites a layer onto the canvas.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LayerOptions {
+    /// The opacity of the layer, from 0 to 1.
+    pub alpha: f32,
+    pub composite_operation: CompositeOperation,
+    /// A CSS filter value, as in `set_filter()`. Invalid values are ignored.
+    pub filter: String,
+}
+
+impl Default for LayerOptions {
+    #[inline]
+    fn default() -> LayerOptions {
+        LayerOptions {
+            alpha: 1.0,
+            composite_operation: CompositeOperation::SourceOver,
+            filter: "none".to_owned(),
+        }
+    }
+}
+
+/// How smoothly images are resampled when image smoothing is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum ImageSmoothingQuality {
+    /// Bilinear sampling.
     Low,
+    /// Trilinear sampling between mipmap levels.
     Medium,
+    /// Anisotropic trilinear sampling when downscaling and bicubic sampling when upscaling.
     High,
 }
 
@@ -897,6 +1557,14 @@ impl CanvasImageSource for Canvas {
     }
 }
 
+impl CanvasImageSource for Image {
+    #[inline]
+    fn to_pattern(self, dest_context: &mut CanvasRenderingContext2D, transform: Transform2F)
+                  -> Pattern {
+        Pattern::from_image(self).to_pattern(dest_context, transform)
+    }
+}
+
 impl CanvasImageDestLocation for RectF {
     #[inline]
     fn origin(&self) -> Vector2F {
@@ -946,6 +1614,154 @@ impl From<Pattern> for FillStyle {
     }
 }
 
+// An intermediate image that filters draw into: a render target the size of the filtered area.
+#[derive(Clone, Copy)]
+struct FilterLayer {
+    id: RenderTargetId,
+    size: Vector2I,
+}
+
+impl FilterLayer {
+    // Starts drawing into a new layer. The caller must pop the render target.
+    fn push(scene: &mut Scene, size: Vector2I) -> FilterLayer {
+        let id = scene.push_render_target(RenderTarget::new(size, String::new()));
+        FilterLayer { id, size }
+    }
+
+    // Returns a paint that draws this layer with its top left corner at `origin`. Layers are
+    // premultiplied, so with no other filter the identity color matrix unpremultiplies them.
+    fn paint(&self, origin: Vector2F, filter: Option<PatternFilter>) -> Paint {
+        let mut pattern = Pattern::from_render_target(self.id, self.size);
+        pattern.apply_transform(Transform2F::from_translation(origin));
+        let identity = PatternFilter::ColorMatrix(ColorMatrix::identity());
+        pattern.set_filter(Some(filter.unwrap_or(identity)));
+        Paint::from_pattern(pattern)
+    }
+
+    // Fills a rect the size of this layer at `origin` in the current render target.
+    fn push_rect(&self,
+                 scene: &mut Scene,
+                 origin: Vector2F,
+                 paint: &Paint,
+                 clip_path: Option<ClipPathId>,
+                 blend_mode: BlendMode) {
+        let outline = Outline::from_rect(RectF::new(origin, self.size.to_f32()));
+        let mut path = DrawPath::new(outline, scene.push_paint(paint));
+        path.set_clip_path(clip_path);
+        path.set_blend_mode(blend_mode);
+        scene.push_path(path);
+    }
+
+    // Returns a new layer that this one has been drawn into with `paint`.
+    fn map(&self, scene: &mut Scene, paint: &Paint) -> FilterLayer {
+        let layer = FilterLayer::push(scene, self.size);
+        layer.push_rect(scene, Vector2F::zero(), paint, None, BlendMode::SrcOver);
+        scene.pop_render_target();
+        layer
+    }
+
+    fn color_matrix(&self, scene: &mut Scene, matrix: ColorMatrix, alpha: f32) -> FilterLayer {
+        let mut paint = self.paint(Vector2F::zero(), Some(PatternFilter::ColorMatrix(matrix)));
+        paint.set_base_color(ColorU::new(255, 255, 255, (alpha * 255.0).round() as u8));
+        self.map(scene, &paint)
+    }
+
+    fn blur(&self, scene: &mut Scene, sigma: f32) -> FilterLayer {
+        if sigma == 0.0 {
+            return *self;
+        }
+        let filter = PatternFilter::Blur { direction: BlurDirection::X, sigma };
+        let layer_x = self.map(scene, &self.paint(Vector2F::zero(), Some(filter)));
+        let filter = PatternFilter::Blur { direction: BlurDirection::Y, sigma };
+        layer_x.map(scene, &layer_x.paint(Vector2F::zero(), Some(filter)))
+    }
+
+    // Returns a layer holding the shadow of this one, moved by `offset`.
+    fn shadow(&self, scene: &mut Scene, offset: Vector2F, sigma: f32, color: ColorU)
+              -> FilterLayer {
+        // As with shadows of paths, the shadow has this layer's alpha and the shadow color.
+        let mut paint = self.paint(offset, None);
+        paint.set_base_color(color);
+        if let Some(ref mut overlay) = *paint.overlay_mut() {
+            overlay.set_composite_op(PaintCompositeOp::DestIn);
+        }
+        self.map(scene, &paint).blur(scene, sigma)
+    }
+
+    fn drop_shadow(&self, scene: &mut Scene, offset: Vector2F, sigma: f32, color: ColorU)
+                   -> FilterLayer {
+        let shadow = self.shadow(scene, offset, sigma, color);
+        let layer = FilterLayer::push(scene, self.size);
+        let (origin, blend_mode) = (Vector2F::zero(), BlendMode::SrcOver);
+        shadow.push_rect(scene, origin, &shadow.paint(origin, None), None, blend_mode);
+        self.push_rect(scene, origin, &self.paint(origin, None), None, blend_mode);
+        scene.pop_render_target();
+        layer
+    }
+
+    // Returns a layer with the filter functions run over this one in order.
+    fn filter(&self, scene: &mut Scene, functions: &[FilterFunction]) -> FilterLayer {
+        let mut layer = *self;
+        for function in functions {
+            layer = match *function {
+                FilterFunction::Blur(sigma) => layer.blur(scene, sigma),
+                FilterFunction::DropShadow { offset, blur, color } => {
+                    layer.drop_shadow(scene, offset, blur, color)
+                }
+                FilterFunction::Opacity(amount) => {
+                    layer.color_matrix(scene, ColorMatrix::identity(), amount)
+                }
+                _ => layer.color_matrix(scene, function.color_matrix().unwrap(), 1.0),
+            };
+        }
+        layer
+    }
+
+    fn composite(&self,
+                 scene: &mut Scene,
+                 origin: Vector2F,
+                 alpha: f32,
+                 clip_path: Option<ClipPathId>,
+                 blend_mode: BlendMode) {
+        let mut paint = self.paint(origin, None);
+        if alpha < 1.0 {
+            paint.set_base_color(ColorU::new(255, 255, 255, (alpha * 255.0).round() as u8));
+        }
+        self.push_rect(scene, origin, &paint, clip_path, blend_mode);
+    }
+}
+
+// Returns the area the filter functions can draw to when run over content within `bounds`, and
+// how far they can move content, so that content that far outside the canvas can still show.
+fn filter_reach(mut bounds: RectF, functions: &[FilterFunction]) -> (RectF, f32) {
+    let mut reach = 0.0;
+    for function in functions {
+        match *function {
+            FilterFunction::Blur(sigma) => {
+                bounds = bounds.dilate(sigma * 3.0);
+                reach += sigma * 3.0;
+            }
+            FilterFunction::DropShadow { offset, blur, .. } => {
+                bounds = bounds.union_rect((bounds + offset).dilate(blur * 3.0));
+                reach += offset.length() + blur * 3.0;
+            }
+            _ => {}
+        }
+    }
+    (bounds, reach)
+}
+
+// A layer opened by `begin_layer()`.
+struct Layer {
+    // The scene that was being drawn into, which the layer is composited onto when it ends.
+    parent_scene: Scene,
+    // The number of saved states, including the one `begin_layer()` saved.
+    state_depth: usize,
+    alpha: f32,
+    composite_operation: CompositeOperation,
+    filter: Vec<FilterFunction>,
+}
+
 struct ShadowBlurRenderTargetInfo {
     id_x: RenderTargetId,
     id_y: RenderTargetId,
@@ -3,8 +3,21 @@
 // For this file only, any copyright is dedicated to the Public Domain.
 // https://creativecommons.org/publicdomain/zero/1.0/
 
-use pathfinder_geometry::vector::{Vector2F, vec2f};
-use super::Path2D;
+use pathfinder_content::effects::{ColorMatrix, PatternFilter};
+use pathfinder_content::gradient::Gradient;
+use pathfinder_content::outline::{ArcDirection, ContourIterFlags, Outline};
+use pathfinder_content::pattern::{Pattern, PatternSampling, PatternSource};
+use pathfinder_color::ColorU;
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::rect::{RectF, RectI};
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{vec2f, vec2i};
+use std::f32::consts::PI;
+use super::{Canvas, CanvasFontContext, CanvasRenderingContext2D, FillRule, ImageData};
+use super::{CompositeOperation, FontStretch, FontVariantCaps, ImageSmoothingQuality};
+use super::{DisplayList, LayerOptions, LineCap, Path2D};
+use super::css::{self, FilterFunction, FontFamilyName, FontShorthand, FontStyle};
+use super::display_list::Command;
 
 #[test]
 pub fn test_path2d_formatting() {
@@ -17,3 +30,756 @@ pub fn test_path2d_formatting() {
     path.close_path();
     assert_eq!(format!("{:?}", path), "M 0 1 L 2 3 L 4 5 z");
 }
+
+#[test]
+pub fn test_path2d_from_svg_path_data() {
+    let mut path = Path2D::from_svg_path_data("M0 1 l2 2 z m1 1 h5");
+    path.line_to(vec2f(6.0, 7.0));
+    assert_eq!(format!("{:?}", path), "M 0 1 L 2 3 z M 1 2 L 6 2 L 6 7");
+
+    // Invalid path data is used up to the error.
+    let path = Path2D::from_svg_path_data("M 0 1 L 2 3 L 4");
+    assert_eq!(format!("{:?}", path), "M 0 1 L 2 3");
+}
+
+#[test]
+pub fn test_path2d_svg_arc_to() {
+    let mut path = Path2D::new();
+    path.move_to(vec2f(0.0, 0.0));
+    path.svg_arc_to(vec2f(10.0, 10.0), 0.0, false, ArcDirection::CCW, vec2f(20.0, 0.0));
+    let outline = path.into_outline();
+    assert_eq!(outline.contours()[0].last_position(), Some(vec2f(20.0, 0.0)));
+    assert!((outline.bounds().max_y() - 10.0).abs() < 0.01);
+}
+
+fn new_context() -> CanvasRenderingContext2D {
+    let font_context = CanvasFontContext::from_system_source();
+    Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context)
+}
+
+// The paint and outline of each path drawn, for comparing scenes.
+fn drawn_paths(context: CanvasRenderingContext2D) -> Vec<String> {
+    let scene = context.into_canvas().into_scene();
+    scene.paths().map(|(paint, outline, _)| {
+        match paint.pattern().map(Pattern::source) {
+            // Render target IDs differ between scenes.
+            Some(&PatternSource::RenderTarget { size, .. }) => {
+                format!("{:?} {:?} {:?}", paint.base_color(), size, outline)
+            }
+            _ => format!("{:?} {:?}", paint, outline),
+        }
+    }).collect()
+}
+
+fn drawn_outlines(context: CanvasRenderingContext2D) -> Vec<Outline> {
+    let scene = context.into_canvas().into_scene();
+    scene.paths().map(|(_, outline, _)| outline.clone()).collect()
+}
+
+#[test]
+pub fn test_current_path_is_transformed_when_points_are_added() {
+    let mut context = new_context();
+    context.move_to(vec2f(0.0, 0.0));
+    context.line_to(vec2f(10.0, 0.0));
+    context.translate(vec2f(100.0, 0.0));
+    context.line_to(vec2f(10.0, 10.0));
+    context.close_path();
+    context.fill(FillRule::Winding);
+
+    // Filling doesn't reset the path, and the transform at fill time doesn't apply to it.
+    context.scale(2.0);
+    context.fill(FillRule::Winding);
+    context.begin_path();
+    context.rect(RectF::new(vec2f(1.0, 2.0), vec2f(3.0, 4.0)));
+    context.fill(FillRule::Winding);
+
+    let outlines: Vec<_> = drawn_outlines(context).iter().map(|outline| {
+        format!("{:?}", outline)
+    }).collect();
+    assert_eq!(outlines, vec![
+        "M 0 0 L 10 0 L 110 10 z",
+        "M 0 0 L 10 0 L 110 10 z",
+        "M 102 4 L 108 4 L 108 12 L 102 12 z",
+    ]);
+}
+
+#[test]
+pub fn test_current_path_survives_save_and_restore() {
+    let mut context = new_context();
+    context.save();
+    context.move_to(vec2f(1.0, 2.0));
+    context.restore();
+    context.line_to(vec2f(3.0, 4.0));
+    context.fill(FillRule::Winding);
+    assert_eq!(format!("{:?}", drawn_outlines(context)[0]), "M 1 2 L 3 4");
+}
+
+#[test]
+pub fn test_stroke_uses_transform_at_stroke_time() {
+    let mut context = new_context();
+    context.rect(RectF::new(vec2f(10.0, 10.0), vec2f(20.0, 20.0)));
+    context.scale(2.0);
+    context.set_line_width(3.0);
+    context.stroke();
+
+    // The rectangle stays put, but the line is 3 units wide in the scaled space.
+    let bounds = drawn_outlines(context)[0].bounds();
+    assert!((bounds.origin() - vec2f(7.0, 7.0)).length() < 0.01);
+    assert!((bounds.lower_right() - vec2f(33.0, 33.0)).length() < 0.01);
+}
+
+#[test]
+pub fn test_arc_follows_current_transform() {
+    let mut context = new_context();
+    context.translate(vec2f(50.0, 50.0));
+    context.scale(vec2f(2.0, 1.0));
+    context.arc(vec2f(0.0, 0.0), 10.0, 0.0, PI * 2.0, ArcDirection::CW);
+    context.fill(FillRule::Winding);
+
+    // Every on-curve point lies on the transformed circle, an ellipse with radii 20 and 10.
+    let outline = &drawn_outlines(context)[0];
+    for segment in outline.contours()[0].iter(ContourIterFlags::empty()) {
+        let point = (segment.baseline.to() - vec2f(50.0, 50.0)) / vec2f(20.0, 10.0);
+        assert!((point.length() - 1.0).abs() < 0.001);
+    }
+}
+
+#[test]
+pub fn test_is_point_in_path() {
+    let mut context = new_context();
+    let mut path = Path2D::new();
+    path.arc(vec2f(0.0, 0.0), 10.0, 0.0, PI * 2.0, ArcDirection::CW);
+    path.rect(RectF::new(vec2f(-5.0, -5.0), vec2f(10.0, 10.0)));
+    assert!(context.is_point_in_path(&path, vec2f(9.0, 0.0), FillRule::Winding));
+    assert!(!context.is_point_in_path(&path, vec2f(7.5, 7.5), FillRule::Winding));
+    assert!(context.is_point_in_path(&path, vec2f(0.0, 0.0), FillRule::Winding));
+    assert!(!context.is_point_in_path(&path, vec2f(0.0, 0.0), FillRule::EvenOdd));
+
+    // The path is transformed; the point isn't.
+    context.translate(vec2f(50.0, 50.0));
+    context.scale(2.0);
+    assert!(!context.is_point_in_path(&path, vec2f(9.0, 0.0), FillRule::Winding));
+    assert!(context.is_point_in_path(&path, vec2f(68.0, 50.0), FillRule::Winding));
+}
+
+#[test]
+pub fn test_is_point_in_stroke() {
+    let mut context = new_context();
+    let mut path = Path2D::new();
+    path.move_to(vec2f(10.0, 10.0));
+    path.line_to(vec2f(90.0, 10.0));
+    assert!(context.is_point_in_stroke(&path, vec2f(50.0, 10.4)));
+    assert!(!context.is_point_in_stroke(&path, vec2f(50.0, 11.0)));
+    assert!(!context.is_point_in_stroke(&path, vec2f(9.0, 10.0)));
+
+    context.set_line_width(4.0);
+    context.set_line_cap(LineCap::Square);
+    assert!(context.is_point_in_stroke(&path, vec2f(50.0, 11.0)));
+    assert!(context.is_point_in_stroke(&path, vec2f(9.0, 10.0)));
+
+    context.set_line_dash(vec![10.0, 10.0]);
+    context.set_line_cap(LineCap::Butt);
+    assert!(context.is_point_in_stroke(&path, vec2f(15.0, 10.0)));
+    assert!(!context.is_point_in_stroke(&path, vec2f(25.0, 10.0)));
+    context.set_line_dash_offset(5.0);
+    assert!(context.is_point_in_stroke(&path, vec2f(12.0, 10.0)));
+    assert!(!context.is_point_in_stroke(&path, vec2f(17.0, 10.0)));
+}
+
+#[test]
+pub fn test_put_image_data_ignores_state() {
+    let mut context = new_context();
+    context.translate(vec2f(30.0, 30.0));
+    context.set_global_alpha(0.5);
+    context.clip_path(Path2D::new(), FillRule::Winding);
+
+    let mut image_data = ImageData::new(vec2i(4, 4));
+    image_data.data[5] = ColorU::white();
+    context.put_image_data(&image_data, vec2i(10, 20), RectI::new(vec2i(1, 1), vec2i(8, 2)));
+
+    // The destination is erased first, then the image is drawn over it.
+    let scene = context.into_canvas().into_scene();
+    let mut paths = scene.paths();
+    let (_, erase_outline, _) = paths.next().unwrap();
+    assert_eq!(format!("{:?}", erase_outline), "M 11 21 L 14 21 L 14 23 L 11 23 z");
+    let (paint, outline, _) = paths.next().unwrap();
+    assert_eq!(format!("{:?}", outline), "M 11 21 L 14 21 L 14 23 L 11 23 z");
+    assert_eq!(paint.base_color(), ColorU::white());
+    let pattern = paint.pattern().unwrap();
+    assert_eq!(pattern.transform(), Transform2F::from_translation(vec2f(10.0, 20.0)));
+    assert!(!pattern.smoothing_enabled());
+}
+
+#[cfg(feature = "pf-render-tests")]
+#[test]
+pub fn test_put_image_data_keeps_pixels_outside_dirty_rect() {
+    use pathfinder_gl::GLVersion;
+    use pathfinder_gl::headless::HeadlessContext;
+    use pathfinder_renderer::gpu::options::{DestFramebuffer, RendererOptions};
+    use pathfinder_renderer::gpu::renderer::Renderer;
+    use pathfinder_resources::embedded::EmbeddedResourceLoader;
+
+    let size = vec2i(48, 48);
+    let gl_context = HeadlessContext::new(GLVersion::GL3, size).unwrap();
+    let mut renderer = Renderer::new(gl_context.create_device(),
+                                     &EmbeddedResourceLoader::new(),
+                                     DestFramebuffer::full_window(size),
+                                     RendererOptions { background_color: None });
+
+    let font_context = CanvasFontContext::from_system_source();
+    let mut context = Canvas::new(size.to_f32()).get_context_2d(font_context);
+    context.set_fill_style(ColorU::new(255, 0, 0, 255));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), size.to_f32()));
+
+    // The destination, (6, 4) to (12, 9), lies inside the first tile but isn't aligned to it.
+    let mut image_data = ImageData::new(vec2i(8, 8));
+    for pixel in &mut image_data.data {
+        *pixel = ColorU::new(0, 0, 255, 255);
+    }
+    image_data.data[2 * 8 + 2] = ColorU::transparent_black();
+    context.put_image_data(&image_data, vec2i(5, 3), RectI::new(vec2i(1, 1), vec2i(6, 5)));
+
+    let result = context.get_image_data(&mut renderer, RectI::new(vec2i(0, 0), size));
+    let dest_rect = RectI::new(vec2i(6, 4), vec2i(6, 5));
+    for y in 0..size.y() {
+        for x in 0..size.x() {
+            let expected = if (x, y) == (7, 5) {
+                ColorU::transparent_black()
+            } else if dest_rect.contains_point(vec2i(x, y)) {
+                ColorU::new(0, 0, 255, 255)
+            } else {
+                ColorU::new(255, 0, 0, 255)
+            };
+            assert_eq!(result.data[(y * size.x() + x) as usize], expected, "at ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+pub fn test_image_smoothing_quality_selects_pattern_sampling() {
+    let mut context = new_context();
+    let image = Pattern::from_image(ImageData::new(vec2i(64, 64)).to_image());
+    let dest_rect = RectF::new(vec2f(0.0, 0.0), vec2f(8.0, 8.0));
+    for &quality in &[ImageSmoothingQuality::Low,
+                      ImageSmoothingQuality::Medium,
+                      ImageSmoothingQuality::High] {
+        context.set_image_smoothing_quality(quality);
+        context.draw_image(image.clone(), dest_rect);
+    }
+    context.set_image_smoothing_enabled(false);
+    context.draw_image(image, dest_rect);
+
+    let scene = context.into_canvas().into_scene();
+    let samplings: Vec<_> = scene.paths().map(|(paint, _, _)| {
+        paint.pattern().unwrap().sampling()
+    }).collect();
+    assert_eq!(samplings, vec![
+        PatternSampling::Bilinear,
+        PatternSampling::Trilinear,
+        PatternSampling::Bicubic,
+        PatternSampling::Nearest,
+    ]);
+}
+
+#[test]
+pub fn test_draw_image_from_image() {
+    let mut context = new_context();
+    let image = ImageData::new(vec2i(4, 2)).to_image();
+    context.draw_image(image.clone(), vec2f(10.0, 20.0));
+    context.draw_image(Pattern::from_image(image), vec2f(10.0, 20.0));
+    let paths = drawn_paths(context);
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0], paths[1]);
+}
+
+#[test]
+pub fn test_image_data_put_image() {
+    let mut image_data = ImageData::new(vec2i(3, 2));
+    let source = ImageData { size: vec2i(2, 1), data: vec![ColorU::black(), ColorU::white()] };
+    image_data.put_image(&source.to_image(), vec2i(1, 1));
+    let transparent = ColorU::transparent_black();
+    assert_eq!(image_data.data, vec![
+        transparent, transparent, transparent,
+        transparent, ColorU::black(), ColorU::white(),
+    ]);
+    assert_eq!(ImageData::from_image(&image_data.to_image()), image_data);
+}
+
+#[test]
+pub fn test_parse_filter() {
+    assert_eq!(css::parse_filter("none"), Some(vec![]));
+    assert_eq!(css::parse_filter(" blur(2px)  GRAYSCALE(50%) opacity() "), Some(vec![
+        FilterFunction::Blur(2.0),
+        FilterFunction::Grayscale(0.5),
+        FilterFunction::Opacity(1.0),
+    ]));
+    assert_eq!(css::parse_filter("invert(3) hue-rotate(0.5turn) blur(1in)"), Some(vec![
+        FilterFunction::Invert(1.0),
+        FilterFunction::HueRotate(PI),
+        FilterFunction::Blur(96.0),
+    ]));
+    assert_eq!(css::parse_filter("drop-shadow(1px -2px 3px #f008)"), Some(vec![
+        FilterFunction::DropShadow {
+            offset: vec2f(1.0, -2.0),
+            blur: 3.0,
+            color: ColorU::new(255, 0, 0, 136),
+        },
+    ]));
+    assert_eq!(css::parse_filter("drop-shadow(rgb(0 128 0 / 50%) 0 4px)"), Some(vec![
+        FilterFunction::DropShadow {
+            offset: vec2f(0.0, 4.0),
+            blur: 0.0,
+            color: ColorU::new(0, 128, 0, 128),
+        },
+    ]));
+
+    for invalid in &["", "blur(2)", "blur(-1px)", "brightness(-1)", "sepia(1px)",
+                     "hue-rotate(90)", "drop-shadow(1px)", "blur(1px", "blur(1em)",
+                     "none blur(1px)", "unknown(1)"] {
+        assert_eq!(css::parse_filter(invalid), None, "{:?} should be invalid", invalid);
+    }
+}
+
+#[test]
+pub fn test_parse_filter_colors() {
+    let parse_color = |color: &str| {
+        match css::parse_filter(&format!("drop-shadow(0 0 {})", color)) {
+            Some(functions) => match functions[..] {
+                [FilterFunction::DropShadow { color, .. }] => Some(color),
+                _ => unreachable!(),
+            },
+            None => None,
+        }
+    };
+
+    assert_eq!(parse_color("#abc"), Some(ColorU::new(0xaa, 0xbb, 0xcc, 255)));
+    assert_eq!(parse_color("#01020304"), Some(ColorU::new(1, 2, 3, 4)));
+    assert_eq!(parse_color("RebeccaPurple"), Some(ColorU::new(0x66, 0x33, 0x99, 255)));
+    assert_eq!(parse_color("transparent"), Some(ColorU::transparent_black()));
+    assert_eq!(parse_color("currentColor"), Some(ColorU::black()));
+    assert_eq!(parse_color("rgba(255, 0, 300, 0.5)"), Some(ColorU::new(255, 0, 255, 128)));
+    assert_eq!(parse_color("rgb(100% 50% 0%)"), Some(ColorU::new(255, 128, 0, 255)));
+    assert_eq!(parse_color("hsl(120deg 100% 25%)"), Some(ColorU::new(0, 128, 0, 255)));
+    assert_eq!(parse_color("hsla(0, 100%, 50%, 0)"), Some(ColorU::new(255, 0, 0, 0)));
+
+    for invalid in &["#ab", "notacolor", "rgb(1, 2 3)", "hsl(0 100 50)", "rgb(1 2 3) x"] {
+        assert_eq!(parse_color(invalid), None, "{:?} should be invalid", invalid);
+    }
+}
+
+#[test]
+pub fn test_parse_font() {
+    assert_eq!(css::parse_font("italic bold 14px/1.2 'Helvetica Neue', sans-serif", 10.0),
+               Some(FontShorthand {
+                   style: FontStyle::Italic,
+                   weight: 700.0,
+                   stretch: FontStretch::Normal,
+                   variant_caps: FontVariantCaps::Normal,
+                   size: 14.0,
+                   families: vec![
+                       FontFamilyName::Title("Helvetica Neue".to_owned()),
+                       FontFamilyName::SansSerif,
+                   ],
+               }));
+    assert_eq!(css::parse_font("10px sans-serif", 10.0), Some(FontShorthand {
+        style: FontStyle::Normal,
+        weight: 400.0,
+        stretch: FontStretch::Normal,
+        variant_caps: FontVariantCaps::Normal,
+        size: 10.0,
+        families: vec![FontFamilyName::SansSerif],
+    }));
+    assert_eq!(css::parse_font("normal small-caps 300 condensed 2em  Times   New Roman,serif",
+                               10.0),
+               Some(FontShorthand {
+                   style: FontStyle::Normal,
+                   weight: 300.0,
+                   stretch: FontStretch::Condensed,
+                   variant_caps: FontVariantCaps::SmallCaps,
+                   size: 20.0,
+                   families: vec![
+                       FontFamilyName::Title("Times New Roman".to_owned()),
+                       FontFamilyName::Serif,
+                   ],
+               }));
+
+    let parse_size = |font: &str| css::parse_font(font, 10.0).map(|font| font.size);
+    assert_eq!(parse_size("12pt serif"), Some(16.0));
+    assert_eq!(parse_size("150% serif"), Some(15.0));
+    assert_eq!(parse_size("2rem serif"), Some(32.0));
+    assert_eq!(parse_size("x-large serif"), Some(24.0));
+    assert_eq!(parse_size("oblique 900 0 \"serif\""), Some(0.0));
+
+    let parse_families = |font: &str| css::parse_font(font, 10.0).map(|font| font.families);
+    assert_eq!(parse_families("1px \"sans-serif\", 'It\\'s', Noto Sans 日本語"), Some(vec![
+        FontFamilyName::Title("sans-serif".to_owned()),
+        FontFamilyName::Title("It's".to_owned()),
+        FontFamilyName::Title("Noto Sans 日本語".to_owned()),
+    ]));
+
+    for invalid in &["", "serif", "12px", "bold", "bold bold 12px serif", "12 serif",
+                     "-1px serif", "12px/ serif", "12px serif,", "12px 'serif", "12px inherit",
+                     "normal normal normal normal normal 12px serif", "caption"] {
+        assert_eq!(css::parse_font(invalid, 10.0), None, "{:?} should be invalid", invalid);
+    }
+}
+
+#[test]
+pub fn test_set_filter() {
+    let mut context = new_context();
+    assert_eq!(context.filter(), "none");
+    context.set_filter("sepia(1)");
+    context.save();
+    context.set_filter("blur(nope)");
+    assert_eq!(context.filter(), "sepia(1)");
+    context.set_filter("none");
+    context.restore();
+    assert_eq!(context.filter(), "sepia(1)");
+}
+
+#[test]
+pub fn test_filter_draws_through_layers() {
+    let mut context = new_context();
+    context.set_filter("grayscale(100%)");
+    context.fill_rect(RectF::new(vec2f(10.0, 20.0), vec2f(30.0, 40.0)));
+
+    // The path is drawn into a layer at its bounds, filtered into another, and composited back.
+    let scene = context.into_canvas().into_scene();
+    let paths: Vec<_> = scene.paths().collect();
+    assert_eq!(paths.len(), 3);
+    assert_eq!(format!("{:?}", paths[0].1), "M 0 0 L 30 0 L 30 40 L 0 40 z");
+    assert_eq!(format!("{:?}", paths[1].1), "M 0 0 L 30 0 L 30 40 L 0 40 z");
+    let filter = paths[1].0.pattern().unwrap().filter();
+    assert_eq!(filter, Some(PatternFilter::ColorMatrix(ColorMatrix::grayscale(1.0))));
+    assert_eq!(format!("{:?}", paths[2].1), "M 10 20 L 40 20 L 40 60 L 10 60 z");
+    let pattern = paths[2].0.pattern().unwrap();
+    assert_eq!(pattern.transform(), Transform2F::from_translation(vec2f(10.0, 20.0)));
+}
+
+#[cfg(feature = "pf-text")]
+#[test]
+pub fn test_filter_draws_text_through_layers() {
+    let draw_text = |filter: &str| {
+        let mut context = new_context();
+        context.set_font_size(20.0);
+        context.set_filter(filter);
+        context.fill_text("Hi", vec2f(10.0, 40.0));
+        context.into_canvas().into_scene()
+    };
+    let scene = draw_text("none");
+    let glyph_outlines: Vec<_> = scene.paths().map(|(_, outline, _)| outline.clone()).collect();
+    let mut glyph_bounds = glyph_outlines[0].bounds();
+    for outline in &glyph_outlines[1..] {
+        glyph_bounds = glyph_bounds.union_rect(outline.bounds());
+    }
+
+    // The glyphs are drawn into a layer at their bounds, filtered into another, and composited
+    // back in one path.
+    let scene = draw_text("grayscale(100%)");
+    let paths: Vec<_> = scene.paths().collect();
+    let glyph_count = glyph_outlines.len();
+    assert_eq!(paths.len(), glyph_count + 2);
+    let to_layer = Transform2F::from_translation(-glyph_bounds.round_out().origin());
+    for (path, mut outline) in paths.iter().zip(glyph_outlines) {
+        outline.transform(&to_layer);
+        assert_eq!(format!("{:?}", path.1), format!("{:?}", outline));
+    }
+    let filter = paths[glyph_count].0.pattern().unwrap().filter();
+    assert_eq!(filter, Some(PatternFilter::ColorMatrix(ColorMatrix::grayscale(1.0))));
+    assert_eq!(paths[glyph_count + 1].1.bounds(), glyph_bounds.round_out());
+}
+
+// Returns Roboto with its GPOS table swapped for a `kern` table with a single pair, so that
+// HarfBuzz kerns with the `kern` table.
+#[cfg(feature = "pf-text")]
+fn font_with_kern_pair(left: char, right: char, value: i16) -> Vec<u8> {
+    use font_kit::loaders::default::Font;
+    use std::sync::Arc;
+
+    let data = include_bytes!("../../resources/fonts/Roboto-Regular.ttf").to_vec();
+    let font = Font::from_bytes(Arc::new(data.clone()), 0).unwrap();
+    let (left, right) = (font.glyph_for_char(left).unwrap(), font.glyph_for_char(right).unwrap());
+
+    let mut kern_table = vec![];
+    // Version and subtable count, then a horizontal format 0 subtable with one pair.
+    for &field in &[0u16, 1, 0, 14 + 6, 0x0001, 1, 6, 0, 0, left as u16, right as u16] {
+        kern_table.extend_from_slice(&field.to_be_bytes());
+    }
+    kern_table.extend_from_slice(&value.to_be_bytes());
+
+    let read_u32 = |offset: usize| {
+        u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+    };
+    let table_count = u16::from_be_bytes([data[4], data[5]]) as usize;
+    let mut tables: Vec<([u8; 4], &[u8])> = (0..table_count).map(|index| {
+        let record = 12 + index * 16;
+        let (offset, length) = (read_u32(record + 8) as usize, read_u32(record + 12) as usize);
+        ([data[record], data[record + 1], data[record + 2], data[record + 3]],
+         &data[offset..(offset + length)])
+    }).filter(|&(tag, _)| &tag != b"GPOS").collect();
+    tables.push((*b"kern", &kern_table));
+    tables.sort_by_key(|&(tag, _)| tag);
+
+    // The table directory. Checksums aren't checked, so they're left as zero.
+    let mut new_data = data[0..4].to_vec();
+    let search_range = 16 * (tables.len() as u16).next_power_of_two() / 2;
+    let entry_selector = (search_range / 16).trailing_zeros() as u16;
+    let range_shift = tables.len() as u16 * 16 - search_range;
+    for &field in &[tables.len() as u16, search_range, entry_selector, range_shift] {
+        new_data.extend_from_slice(&field.to_be_bytes());
+    }
+    let mut offset = 12 + tables.len() * 16;
+    for &(tag, table) in &tables {
+        new_data.extend_from_slice(&tag);
+        new_data.extend_from_slice(&0u32.to_be_bytes());
+        new_data.extend_from_slice(&(offset as u32).to_be_bytes());
+        new_data.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+    for &(_, table) in &tables {
+        new_data.extend_from_slice(table);
+        new_data.resize((new_data.len() + 3) & !3, 0);
+    }
+    new_data
+}
+
+#[cfg(feature = "pf-text")]
+#[test]
+pub fn test_kerning_from_kern_table() {
+    use font_kit::handle::Handle;
+    use std::iter;
+    use std::sync::Arc;
+    use super::FontKerning;
+
+    let data = Arc::new(font_with_kern_pair('A', 'V', -200));
+    let font_context = CanvasFontContext::from_fonts(iter::once(Handle::from_memory(data, 0)));
+    let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+    context.set_font("Roboto-Regular");
+    // Roboto has 2048 units per em, so this size scales font units exactly.
+    context.set_font_size(16.0);
+
+    let kerned_width = context.measure_text("AV").width;
+    let unkerned_pair_width = context.measure_text("VA").width;
+    context.set_font_kerning(FontKerning::None);
+    let unkerned_width = context.measure_text("AV").width;
+
+    // The pair is kerned once, and only when kerning is on.
+    assert_eq!(kerned_width - unkerned_width, -200.0 * 16.0 / 2048.0);
+    assert_eq!(context.measure_text("VA").width, unkerned_pair_width);
+}
+
+#[test]
+pub fn test_layer_composites_with_options() {
+    let mut context = new_context();
+    context.translate(vec2f(5.0, 5.0));
+    context.begin_layer(LayerOptions {
+        alpha: 0.5,
+        composite_operation: CompositeOperation::Multiply,
+        filter: "grayscale(100%)".to_owned(),
+    });
+    context.translate(vec2f(5.0, 5.0));
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+    context.end_layer();
+    assert_eq!(context.transform(), Transform2F::from_translation(vec2f(5.0, 5.0)));
+
+    // The rect keeps the transform from outside the layer, which is sized to it. The layer is
+    // filtered into another, which is composited with the layer's opacity.
+    let scene = context.into_canvas().into_scene();
+    let paths: Vec<_> = scene.paths().collect();
+    assert_eq!(paths.len(), 3);
+    assert_eq!(format!("{:?}", paths[0].1), "M 0 0 L 10 0 L 10 10 L 0 10 z");
+    let filter = paths[1].0.pattern().unwrap().filter();
+    assert_eq!(filter, Some(PatternFilter::ColorMatrix(ColorMatrix::grayscale(1.0))));
+    assert_eq!(format!("{:?}", paths[2].1), "M 10 10 L 20 10 L 20 20 L 10 20 z");
+    assert_eq!(paths[2].0.base_color().a, 128);
+}
+
+#[test]
+pub fn test_layer_includes_filter_reach() {
+    let mut context = new_context();
+    context.begin_layer(LayerOptions {
+        filter: "drop-shadow(-20px 0 2px black)".to_owned(),
+        ..LayerOptions::default()
+    });
+    context.fill_rect(RectF::new(vec2f(110.0, 10.0), vec2f(10.0, 10.0)));
+    context.end_layer();
+
+    // The rect is past the right edge of the canvas, but its shadow isn't. The layer reaches
+    // that far and leaves room for the shadow to blur.
+    let scene = context.into_canvas().into_scene();
+    let paths: Vec<_> = scene.paths().collect();
+    assert_eq!(format!("{:?}", paths[0].1), "M 26 6 L 36 6 L 36 16 L 26 16 z");
+    let composite_path = paths.last().unwrap();
+    assert_eq!(format!("{:?}", composite_path.1), "M 84 4 L 120 4 L 120 26 L 84 26 z");
+    let pattern = composite_path.0.pattern().unwrap();
+    assert_eq!(pattern.transform(), Transform2F::from_translation(vec2f(84.0, 4.0)));
+}
+
+#[test]
+pub fn test_restore_stops_at_layer() {
+    let mut context = new_context();
+    context.set_global_alpha(0.25);
+    context.begin_layer(LayerOptions::default());
+    context.set_global_alpha(0.5);
+    context.save();
+    context.set_global_alpha(0.75);
+    context.restore();
+    context.restore();
+    assert_eq!(context.global_alpha(), 0.5);
+    context.save();
+    context.end_layer();
+    assert_eq!(context.global_alpha(), 0.25);
+    context.end_layer();
+    context.restore();
+    assert_eq!(context.global_alpha(), 0.25);
+}
+
+#[test]
+pub fn test_into_canvas_ends_open_layers() {
+    let mut context = new_context();
+    context.begin_layer(LayerOptions::default());
+    context.begin_layer(LayerOptions::default());
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0)));
+
+    // Each layer is composited into the one around it.
+    let scene = context.into_canvas().into_scene();
+    assert_eq!(scene.paths().count(), 3);
+}
+
+#[test]
+pub fn test_recording_captures_calls() {
+    let mut context = new_context();
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0)));
+    context.begin_recording();
+    context.set_fill_style(ColorU::white());
+    context.scale(2.0);
+    let image = Pattern::from_image(ImageData::new(vec2i(4, 2)).to_image());
+    context.draw_image(image, vec2f(10.0, 20.0));
+    let display_list = context.end_recording();
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0)));
+    assert!(!context.is_recording());
+
+    // Drawing the image is one call, even though it sets the fill style and fills a rect.
+    let commands = display_list.commands();
+    assert_eq!(commands.len(), 3);
+    match commands[1] {
+        Command::Scale(scale) => assert_eq!(scale, vec2f(2.0, 2.0)),
+        ref command => panic!("unexpected command {:?}", command),
+    }
+    match commands[2] {
+        Command::DrawImage { ref pattern, src_rect, dest_rect } => {
+            assert_eq!(pattern.transform(), Transform2F::default());
+            assert_eq!(src_rect, RectF::new(vec2f(0.0, 0.0), vec2f(4.0, 2.0)));
+            assert_eq!(dest_rect, RectF::new(vec2f(10.0, 20.0), vec2f(4.0, 2.0)));
+        }
+        ref command => panic!("unexpected command {:?}", command),
+    }
+}
+
+#[test]
+pub fn test_display_list_replay() {
+    let mut context = new_context();
+    context.begin_recording();
+    context.save();
+    context.translate(vec2f(10.0, 0.0));
+    context.set_global_alpha(0.5);
+    context.fill_rect(RectF::new(vec2f(0.0, 0.0), vec2f(5.0, 5.0)));
+    context.restore();
+    context.set_transform(&Transform2F::from_scale(2.0));
+    context.stroke_rect(RectF::new(vec2f(0.0, 0.0), vec2f(1.0, 1.0)));
+    context.restore();
+    let display_list = context.end_recording();
+
+    // Replaying without a transform draws the same thing.
+    let mut replay_context = new_context();
+    display_list.replay(&mut replay_context, Transform2F::default());
+    assert_eq!(drawn_paths(replay_context), drawn_paths(context));
+
+    // Replaying applies the transform, and the recorded `restore()`s don't reach the state
+    // saved before replaying, which the replay leaves alone.
+    let mut replay_context = new_context();
+    replay_context.set_global_alpha(0.25);
+    replay_context.save();
+    replay_context.translate(vec2f(100.0, 0.0));
+    display_list.replay(&mut replay_context, Transform2F::from_scale(0.5));
+    assert_eq!(replay_context.global_alpha(), 0.25);
+    assert_eq!(replay_context.transform(), Transform2F::from_translation(vec2f(100.0, 0.0)));
+    replay_context.restore();
+    assert_eq!(replay_context.transform(), Transform2F::default());
+    let outlines = drawn_outlines(replay_context);
+    assert_eq!(format!("{:?}", outlines[0]), "M 105 0 L 107.5 0 L 107.5 2.5 L 105 2.5 z");
+    assert!((outlines[1].bounds().max_x() - 101.5).abs() < 0.01);
+}
+
+// Records a display list that uses every serde helper, and returns it with the context it drew
+// into.
+fn record_display_list_for_serialization() -> (CanvasRenderingContext2D, DisplayList) {
+    let mut context = new_context();
+    context.begin_recording();
+    let mut gradient = Gradient::linear_from_points(vec2f(0.0, 0.0), vec2f(10.0, 0.0));
+    gradient.add_color_stop(ColorU::black(), 0.0);
+    gradient.add_color_stop(ColorU::white(), 1.0);
+    context.set_fill_style(gradient);
+    let mut gradient = Gradient::radial(LineSegment2F::new(vec2f(5.0, 5.0), vec2f(6.0, 6.0)),
+                                        vec2f(1.0, 8.0).0);
+    gradient.apply_transform(Transform2F::from_rotation(0.5));
+    gradient.add_color_stop(ColorU::new(255, 0, 0, 128), 0.25);
+    context.set_stroke_style(gradient);
+    context.set_line_cap(LineCap::Round);
+    let mut path = Path2D::new();
+    path.move_to(vec2f(1.0, 2.0));
+    path.bezier_curve_to(vec2f(3.0, 4.0), vec2f(5.0, 6.0), vec2f(7.0, 8.0));
+    path.close_path();
+    path.move_to(vec2f(9.0, 9.0));
+    path.quadratic_curve_to(vec2f(20.0, 9.0), vec2f(20.0, 20.0));
+    context.fill_path(path.clone(), FillRule::EvenOdd);
+    context.begin_layer(LayerOptions { alpha: 0.5, ..LayerOptions::default() });
+    context.stroke_path(path);
+    let mut image_data = ImageData::new(vec2i(2, 2));
+    image_data.data[1] = ColorU::new(1, 2, 3, 4);
+    let mut pattern = Pattern::from_image(image_data.to_image());
+    pattern.set_repeat_x(true);
+    pattern.set_smoothing_enabled(false);
+    pattern.apply_transform(Transform2F::from_scale(2.0));
+    context.draw_image(pattern, RectF::new(vec2f(30.0, 30.0), vec2f(8.0, 8.0)));
+    context.end_layer();
+    context.put_image_data(&image_data, vec2i(50, 50), RectI::new(vec2i(0, 0), vec2i(2, 2)));
+    let display_list = context.end_recording();
+    (context, display_list)
+}
+
+#[test]
+pub fn test_display_list_serialization() {
+    let (context, display_list) = record_display_list_for_serialization();
+    let json = serde_json::to_string(&display_list).unwrap();
+    let deserialized: DisplayList = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", deserialized), format!("{:?}", display_list));
+    let mut replay_context = new_context();
+    deserialized.replay(&mut replay_context, Transform2F::default());
+    assert_eq!(drawn_paths(replay_context), drawn_paths(context));
+}
+
+#[test]
+pub fn test_display_list_binary_serialization() {
+    // Binary formats aren't self-describing, so this catches serde helpers that only work with
+    // formats like JSON.
+    let (context, display_list) = record_display_list_for_serialization();
+    let bytes = bincode::serialize(&display_list).unwrap();
+    assert!(bytes.len() < serde_json::to_string(&display_list).unwrap().len());
+    let deserialized: DisplayList = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(format!("{:?}", deserialized), format!("{:?}", display_list));
+    let mut replay_context = new_context();
+    deserialized.replay(&mut replay_context, Transform2F::default());
+    assert_eq!(drawn_paths(replay_context), drawn_paths(context));
+}
+
+#[test]
+pub fn test_render_target_patterns_dont_serialize() {
+    let mut context = new_context();
+    context.begin_recording();
+    let pattern = context.create_pattern_from_canvas(Canvas::new(vec2f(4.0, 4.0)),
+                                                     Transform2F::default());
+    context.set_fill_style(pattern);
+    let display_list = context.end_recording();
+    assert!(serde_json::to_string(&display_list).is_err());
+}
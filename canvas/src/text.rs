@@ -8,41 +8,52 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::{CanvasRenderingContext2D, State, TextAlign, TextBaseline};
+use crate::css::{self, FontFamilyName, FontShorthand, FontStyle};
+use crate::display_list::{Command, RecordedFont};
+use crate::{CanvasRenderingContext2D, DEFAULT_FONT_SIZE, FontKerning, FontStretch};
+use crate::{FontVariantCaps, State, TextAlign, TextBaseline, TextDirection};
 use font_kit::canvas::RasterizationOptions;
 use font_kit::family_name::FamilyName;
 use font_kit::handle::Handle;
 use font_kit::hinting::HintingOptions;
 use font_kit::loaders::default::Font;
-use font_kit::properties::Properties;
+use font_kit::properties::{Properties, Stretch, Style, Weight};
 use font_kit::source::{Source, SystemSource};
 use font_kit::sources::mem::MemSource;
+use harfbuzz_rs::{Direction, Face, Feature, Font as ShapingFont, Shared, Tag, UnicodeBuffer};
+use pathfinder_content::fill::FillRule;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::util;
-use pathfinder_geometry::vector::{Vector2F, vec2f};
-use pathfinder_renderer::paint::PaintId;
+use pathfinder_geometry::vector::{Vector2F, vec2f, vec2i};
+use pathfinder_renderer::paint::Paint;
+use pathfinder_renderer::scene::Scene;
 use pathfinder_text::{FontContext, FontRenderOptions, TextRenderMode};
-use skribo::{FontCollection, FontFamily, FontRef, Layout, TextStyle};
+use skribo::{FontCollection, FontFamily, FontRef, Glyph, Layout, TextStyle};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::Arc;
 
+// Synthesized small capitals are drawn at this fraction of the font size, as browsers draw them.
+const SMALL_CAPS_SCALE: f32 = 0.7;
+
 impl CanvasRenderingContext2D {
     pub fn fill_text(&mut self, string: &str, position: Vector2F) {
-        let paint = self.current_state.resolve_paint(&self.current_state.fill_paint);
-        let paint_id = self.canvas.scene.push_paint(&paint);
-        self.fill_or_stroke_text(string, position, paint_id, TextRenderMode::Fill);
+        self.record(|| Command::FillText { text: string.to_owned(), position });
+        let paint = self.current_state.resolve_paint(&self.current_state.fill_paint).into_owned();
+        self.fill_or_stroke_text(string, position, paint, TextRenderMode::Fill);
     }
 
     pub fn stroke_text(&mut self, string: &str, position: Vector2F) {
-        let paint = self.current_state.resolve_paint(&self.current_state.stroke_paint);
-        let paint_id = self.canvas.scene.push_paint(&paint);
+        self.record(|| Command::StrokeText { text: string.to_owned(), position });
+        let paint = self.current_state.resolve_paint(&self.current_state.stroke_paint).into_owned();
         let render_mode = TextRenderMode::Stroke(self.current_state.resolve_stroke_style());
-        self.fill_or_stroke_text(string, position, paint_id, render_mode);
+        self.fill_or_stroke_text(string, position, paint, render_mode);
     }
 
     pub fn measure_text(&self, string: &str) -> TextMetrics {
-        let mut metrics = self.layout_text(string).metrics();
+        let (runs, trailing_spacing) = self.layout_text(string);
+        let mut metrics = text_metrics(&runs, trailing_spacing);
         metrics.make_origin_relative(&self.current_state);
         metrics
     }
@@ -74,38 +85,88 @@ impl CanvasRenderingContext2D {
     fn fill_or_stroke_text(&mut self,
                            string: &str,
                            mut position: Vector2F,
-                           paint_id: PaintId,
+                           paint: Paint,
                            render_mode: TextRenderMode) {
-        let layout = self.layout_text(string);
+        let (runs, trailing_spacing) = self.layout_text(string);
 
         let clip_path = self.current_state.clip_path;
         let blend_mode = self.current_state.global_composite_operation.to_blend_mode();
 
-        position += layout.metrics().text_origin(&self.current_state);
+        position += text_metrics(&runs, trailing_spacing).text_origin(&self.current_state);
         let transform = self.current_state.transform * Transform2F::from_translation(position);
 
-        // TODO(pcwalton): Report errors.
-        drop(self.canvas_font_context
-                 .0
-                 .borrow_mut()
-                 .font_context
-                 .push_layout(&mut self.canvas.scene,
-                              &layout,
-                              &TextStyle { size: self.current_state.font_size },
-                              &FontRenderOptions {
-                                  transform,
-                                  render_mode,
-                                  hinting_options: HintingOptions::None,
-                                  clip_path,
-                                  blend_mode,
-                                  paint_id,
-                              }));
+        // Filtered text is laid out into a scene of its own to get the glyph outlines, which are
+        // then filtered together like a path.
+        let mut glyph_scene = if self.current_state.filter.is_empty() {
+            None
+        } else {
+            Some(Scene::new())
+        };
+        let scene = match glyph_scene {
+            Some(ref mut glyph_scene) => glyph_scene,
+            None => &mut self.canvas.scene,
+        };
+        let paint_id = scene.push_paint(&paint);
+
+        let mut canvas_font_context = self.canvas_font_context.0.borrow_mut();
+        for run in &runs {
+            // TODO(pcwalton): Report errors.
+            drop(canvas_font_context.font_context
+                                    .push_layout(scene,
+                                                 run,
+                                                 &TextStyle { size: run.size },
+                                                 &FontRenderOptions {
+                                                     transform,
+                                                     render_mode,
+                                                     hinting_options: HintingOptions::None,
+                                                     clip_path,
+                                                     blend_mode,
+                                                     paint_id,
+                                                 }));
+        }
+        drop(canvas_font_context);
+
+        if let Some(glyph_scene) = glyph_scene {
+            let outlines = glyph_scene.paths().map(|(_, outline, _)| outline.clone()).collect();
+            self.push_filtered_paths(outlines, paint, FillRule::Winding);
+        }
     }
 
-    fn layout_text(&self, string: &str) -> Layout {
-        skribo::layout(&TextStyle { size: self.current_state.font_size },
-                       &self.current_state.font_collection,
-                       string)
+    // Lays the text out in runs, which only differ in size where small capitals are synthesized.
+    // Glyph offsets are relative to the start of the text and include kerning, letter spacing
+    // and word spacing. Also returns the spacing after the last glyph, which counts toward the
+    // width of the text.
+    fn layout_text(&self, string: &str) -> (Vec<Layout>, f32) {
+        let state = &self.current_state;
+        let kerning = state.font_kerning != FontKerning::None;
+        let mut canvas_font_context = self.canvas_font_context.0.borrow_mut();
+
+        let mut runs = vec![];
+        let (mut pen, mut spacing) = (0.0, 0.0);
+        for (text, size) in variant_caps_runs(string, state.font_variant_caps, state.font_size) {
+            let mut run = canvas_font_context.shape_text(&state.font_collection,
+                                                         &text,
+                                                         size,
+                                                         kerning);
+
+            // `shift` is how far glyphs move from where they were shaped, starting with the end
+            // of the previous run.
+            let mut shift = pen;
+            for glyph in &mut run.glyphs {
+                glyph.offset += vec2f(shift, 0.0);
+
+                spacing = state.letter_spacing;
+                if state.word_spacing != 0.0 && is_word_separator(&glyph.font.font,
+                                                                  glyph.glyph_id) {
+                    spacing += state.word_spacing;
+                }
+                shift += spacing;
+            }
+
+            pen = run.advance.x() + shift;
+            runs.push(run);
+        }
+        (runs, spacing)
     }
 
     // Text styles
@@ -117,8 +178,37 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_font<FC>(&mut self, font_collection: FC) where FC: IntoFontCollection {
+        let postscript_names = match self.display_list {
+            Some(_) => font_collection.postscript_names(),
+            None => None,
+        };
         let font_collection = font_collection.into_font_collection(&self.canvas_font_context);
+        self.record(|| {
+            Command::SetFont(match postscript_names {
+                Some(names) => RecordedFont::PostScriptNames(names),
+                None => RecordedFont::Collection(font_collection.clone()),
+            })
+        });
         self.current_state.font_collection = font_collection; 
+        self.current_state.font_shorthand = None;
+    }
+
+    /// Sets the font and font size from a CSS `font` shorthand value, such as
+    /// `italic bold 14px/1.2 'Helvetica Neue', sans-serif`, as the HTML `font` attribute does.
+    ///
+    /// Each family is matched against the font source of the font context, and the matches fall
+    /// back to one another in order, then to the best sans-serif match. As in HTML, invalid values
+    /// are ignored, and relative sizes are relative to the default size of 10px.
+    pub fn set_font_shorthand(&mut self, new_font: &str) {
+        self.record(|| Command::SetFontShorthand(new_font.to_owned()));
+        if let Some(font) = css::parse_font(new_font, DEFAULT_FONT_SIZE) {
+            self.current_state.font_collection =
+                self.canvas_font_context.select_font_collection(&font);
+            self.current_state.font_size = font.size;
+            self.current_state.font_stretch = font.stretch;
+            self.current_state.font_variant_caps = font.variant_caps;
+            self.current_state.font_shorthand = Some(font);
+        }
     }
 
     #[inline]
@@ -128,6 +218,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_font_size(&mut self, new_font_size: f32) {
+        self.record(|| Command::SetFontSize(new_font_size));
         self.current_state.font_size = new_font_size;
     }
 
@@ -138,6 +229,7 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_text_align(&mut self, new_text_align: TextAlign) {
+        self.record(|| Command::SetTextAlign(new_text_align));
         self.current_state.text_align = new_text_align;
     }
 
@@ -148,8 +240,83 @@ impl CanvasRenderingContext2D {
 
     #[inline]
     pub fn set_text_baseline(&mut self, new_text_baseline: TextBaseline) {
+        self.record(|| Command::SetTextBaseline(new_text_baseline));
         self.current_state.text_baseline = new_text_baseline;
     }
+
+    #[inline]
+    pub fn direction(&self) -> TextDirection {
+        self.current_state.direction
+    }
+
+    #[inline]
+    pub fn set_direction(&mut self, new_direction: TextDirection) {
+        self.record(|| Command::SetDirection(new_direction));
+        self.current_state.direction = new_direction;
+    }
+
+    /// The extra space after each character, in pixels. It can be negative.
+    #[inline]
+    pub fn letter_spacing(&self) -> f32 {
+        self.current_state.letter_spacing
+    }
+
+    #[inline]
+    pub fn set_letter_spacing(&mut self, new_letter_spacing: f32) {
+        self.record(|| Command::SetLetterSpacing(new_letter_spacing));
+        self.current_state.letter_spacing = new_letter_spacing;
+    }
+
+    /// The extra space after each space character, in addition to the letter spacing, in pixels.
+    #[inline]
+    pub fn word_spacing(&self) -> f32 {
+        self.current_state.word_spacing
+    }
+
+    #[inline]
+    pub fn set_word_spacing(&mut self, new_word_spacing: f32) {
+        self.record(|| Command::SetWordSpacing(new_word_spacing));
+        self.current_state.word_spacing = new_word_spacing;
+    }
+
+    #[inline]
+    pub fn font_kerning(&self) -> FontKerning {
+        self.current_state.font_kerning
+    }
+
+    #[inline]
+    pub fn set_font_kerning(&mut self, new_font_kerning: FontKerning) {
+        self.record(|| Command::SetFontKerning(new_font_kerning));
+        self.current_state.font_kerning = new_font_kerning;
+    }
+
+    #[inline]
+    pub fn font_stretch(&self) -> FontStretch {
+        self.current_state.font_stretch
+    }
+
+    /// Sets the font stretch. A font set with `set_font_shorthand()` is matched again with the
+    /// new stretch; fonts set with `set_font()` are kept as they are.
+    pub fn set_font_stretch(&mut self, new_font_stretch: FontStretch) {
+        self.record(|| Command::SetFontStretch(new_font_stretch));
+        self.current_state.font_stretch = new_font_stretch;
+        if let Some(ref mut font) = self.current_state.font_shorthand {
+            font.stretch = new_font_stretch;
+            self.current_state.font_collection =
+                self.canvas_font_context.select_font_collection(font);
+        }
+    }
+
+    #[inline]
+    pub fn font_variant_caps(&self) -> FontVariantCaps {
+        self.current_state.font_variant_caps
+    }
+
+    #[inline]
+    pub fn set_font_variant_caps(&mut self, new_font_variant_caps: FontVariantCaps) {
+        self.record(|| Command::SetFontVariantCaps(new_font_variant_caps));
+        self.current_state.font_variant_caps = new_font_variant_caps;
+    }
 }
 
 /// Represents the dimensions of a piece of text in the canvas.
@@ -204,8 +371,17 @@ pub(super) struct CanvasFontContextData {
     pub(super) font_source: Arc<dyn Source>,
     #[allow(dead_code)]
     pub(super) default_font_collection: Arc<FontCollection>,
+    font_collection_cache: HashMap<FontCollectionKey, Arc<FontCollection>>,
+    // HarfBuzz fonts for the fonts shaped so far, which are slow to create.
+    shaping_fonts: Vec<(Arc<Font>, Shared<ShapingFont<'static>>)>,
 }
 
+// Font data shared with HarfBuzz.
+struct ShapingFontData(Arc<Vec<u8>>);
+
+// What `set_font_shorthand()` matches fonts by, with the weight as bits so that it can be hashed.
+type FontCollectionKey = (Vec<FontFamilyName>, FontStyle, u32, FontStretch);
+
 impl CanvasFontContext {
     pub fn new(font_source: Arc<dyn Source>) -> CanvasFontContext {
         let mut default_font_collection = FontCollection::new();
@@ -220,6 +396,8 @@ impl CanvasFontContext {
             font_source,
             default_font_collection: Arc::new(default_font_collection),
             font_context: FontContext::new(),
+            font_collection_cache: HashMap::new(),
+            shaping_fonts: vec![],
         })))
     }
 
@@ -245,16 +423,141 @@ impl CanvasFontContext {
             .load()
             .expect("Failed to load the font!")
     }
+
+    // Returns a collection of the best match in each family of `font` that has one, in order,
+    // followed by the best sans-serif match. Matching and loading fonts is slow, so collections
+    // are cached.
+    fn select_font_collection(&self, font: &FontShorthand) -> Arc<FontCollection> {
+        let key = (font.families.clone(), font.style, font.weight.to_bits(), font.stretch);
+        if let Some(font_collection) = self.0.borrow().font_collection_cache.get(&key) {
+            return font_collection.clone();
+        }
+
+        let properties = Properties {
+            style: match font.style {
+                FontStyle::Normal => Style::Normal,
+                FontStyle::Italic => Style::Italic,
+                FontStyle::Oblique => Style::Oblique,
+            },
+            weight: Weight(font.weight),
+            stretch: match font.stretch {
+                FontStretch::UltraCondensed => Stretch::ULTRA_CONDENSED,
+                FontStretch::ExtraCondensed => Stretch::EXTRA_CONDENSED,
+                FontStretch::Condensed => Stretch::CONDENSED,
+                FontStretch::SemiCondensed => Stretch::SEMI_CONDENSED,
+                FontStretch::Normal => Stretch::NORMAL,
+                FontStretch::SemiExpanded => Stretch::SEMI_EXPANDED,
+                FontStretch::Expanded => Stretch::EXPANDED,
+                FontStretch::ExtraExpanded => Stretch::EXTRA_EXPANDED,
+                FontStretch::UltraExpanded => Stretch::ULTRA_EXPANDED,
+            },
+        };
+
+        let mut families = font.families.clone();
+        if !families.contains(&FontFamilyName::SansSerif) {
+            families.push(FontFamilyName::SansSerif);
+        }
+
+        let mut this = self.0.borrow_mut();
+        let mut font_collection = FontCollection::new();
+        let mut matched_any = false;
+        for family in families {
+            let family_name = match family {
+                FontFamilyName::Title(title) => FamilyName::Title(title),
+                FontFamilyName::Serif => FamilyName::Serif,
+                FontFamilyName::SansSerif => FamilyName::SansSerif,
+                FontFamilyName::Monospace => FamilyName::Monospace,
+                FontFamilyName::Cursive => FamilyName::Cursive,
+                FontFamilyName::Fantasy => FamilyName::Fantasy,
+            };
+            let handle = match this.font_source.select_best_match(&[family_name], &properties) {
+                Ok(handle) => handle,
+                Err(_) => continue,
+            };
+            if let Ok(font) = handle.load() {
+                font_collection.add_family(FontFamily::new_from_font(font));
+                matched_any = true;
+            }
+        }
+
+        // If nothing matched, even sans-serif, use the default font rather than no font at all.
+        let font_collection = if matched_any {
+            Arc::new(font_collection)
+        } else {
+            this.default_font_collection.clone()
+        };
+        this.font_collection_cache.insert(key, font_collection.clone());
+        font_collection
+    }
+}
+
+impl CanvasFontContextData {
+    // Shapes `text` with the fonts of `font_collection` that cover it. This is what
+    // `skribo::layout()` does, except that kerning can be turned off: skribo always shapes with
+    // HarfBuzz's default features, which include `kern`.
+    fn shape_text(&mut self,
+                  font_collection: &FontCollection,
+                  text: &str,
+                  size: f32,
+                  kerning: bool)
+                  -> Layout {
+        let features = [Feature::new(Tag::new('k', 'e', 'r', 'n'), kerning as u32, ..)];
+
+        let mut layout = Layout { size, glyphs: vec![], advance: Vector2F::zero() };
+        for (range, font) in font_collection.itemize(text) {
+            let shaping_font = self.shaping_font(&font.font);
+            let buffer = UnicodeBuffer::new().add_str_item(text, &text[range])
+                                             .set_direction(Direction::Ltr);
+            let glyph_buffer = harfbuzz_rs::shape(&shaping_font, buffer, &features);
+
+            let scale = size / font.font.metrics().units_per_em as f32;
+            let positions = glyph_buffer.get_glyph_positions();
+            for (info, position) in glyph_buffer.get_glyph_infos().iter().zip(positions) {
+                let offset = vec2i(position.x_offset, position.y_offset).to_f32() * scale;
+                layout.glyphs.push(Glyph {
+                    font: font.clone(),
+                    glyph_id: info.codepoint,
+                    offset: layout.advance + offset,
+                });
+                layout.advance += vec2i(position.x_advance, position.y_advance).to_f32() * scale;
+            }
+        }
+        layout
+    }
+
+    fn shaping_font(&mut self, font: &Arc<Font>) -> Shared<ShapingFont<'static>> {
+        if let Some((_, shaping_font)) = self.shaping_fonts
+                                             .iter()
+                                             .find(|(other, _)| Arc::ptr_eq(other, font)) {
+            return shaping_font.clone();
+        }
+
+        let data = font.copy_font_data().unwrap_or_default();
+        let shaping_font = ShapingFont::new(Face::new(ShapingFontData(data), 0)).to_shared();
+        self.shaping_fonts.push((font.clone(), shaping_font.clone()));
+        shaping_font
+    }
+}
+
+impl AsRef<[u8]> for ShapingFontData {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
 }
 
 // Text layout utilities
 
 impl TextMetrics {
     fn text_origin(&self, state: &State) -> Vector2F {
-        let x = match state.text_align {
-            TextAlign::Left => 0.0,
-            TextAlign::Right => -self.width,
-            TextAlign::Center => -0.5 * self.width,
+        let x = match (state.text_align, state.direction) {
+            (TextAlign::Left, _) |
+            (TextAlign::Start, TextDirection::Ltr) |
+            (TextAlign::Start, TextDirection::Inherit) |
+            (TextAlign::End, TextDirection::Rtl) => 0.0,
+            (TextAlign::Right, _) | (TextAlign::Start, TextDirection::Rtl) | (TextAlign::End, _) => {
+                -self.width
+            }
+            (TextAlign::Center, _) => -0.5 * self.width,
         };
 
         let y = match state.text_baseline {
@@ -285,6 +588,71 @@ impl TextMetrics {
     }
 }
 
+// Combines the metrics of runs laid out one after another by `layout_text()`. As in browsers, the
+// spacing after the last glyph is part of the width.
+fn text_metrics(runs: &[Layout], trailing_spacing: f32) -> TextMetrics {
+    let mut metrics = runs[0].metrics();
+    for run in runs[1..].iter().filter(|run| !run.glyphs.is_empty()) {
+        let run_metrics = run.metrics();
+        metrics.width = run_metrics.width;
+        metrics.actual_bounding_box_right = run_metrics.actual_bounding_box_right;
+        metrics.font_bounding_box_ascent =
+            metrics.font_bounding_box_ascent.max(run_metrics.font_bounding_box_ascent);
+        metrics.font_bounding_box_descent =
+            metrics.font_bounding_box_descent.min(run_metrics.font_bounding_box_descent);
+        metrics.actual_bounding_box_ascent =
+            metrics.actual_bounding_box_ascent.max(run_metrics.actual_bounding_box_ascent);
+        metrics.actual_bounding_box_descent =
+            metrics.actual_bounding_box_descent.min(run_metrics.actual_bounding_box_descent);
+        metrics.em_height_ascent = metrics.em_height_ascent.max(run_metrics.em_height_ascent);
+        metrics.em_height_descent = metrics.em_height_descent.min(run_metrics.em_height_descent);
+    }
+    metrics.width += trailing_spacing;
+    metrics.actual_bounding_box_right += trailing_spacing;
+    metrics
+}
+
+// Splits text into the runs `layout_text()` lays out. Letters drawn as small or petite capitals
+// are uppercased and go in runs of their own at `SMALL_CAPS_SCALE` times the size. There's
+// always at least one run.
+fn variant_caps_runs(string: &str, variant_caps: FontVariantCaps, size: f32)
+                     -> Vec<(String, f32)> {
+    let (small_lowercase, small_uppercase) = match variant_caps {
+        FontVariantCaps::Normal | FontVariantCaps::TitlingCaps => {
+            return vec![(string.to_owned(), size)]
+        }
+        FontVariantCaps::SmallCaps | FontVariantCaps::PetiteCaps => (true, false),
+        FontVariantCaps::AllSmallCaps | FontVariantCaps::AllPetiteCaps => (true, true),
+        FontVariantCaps::Unicase => (false, true),
+    };
+
+    let mut runs: Vec<(String, f32)> = vec![];
+    for character in string.chars() {
+        let small = (small_lowercase && character.is_lowercase()) ||
+            (small_uppercase && character.is_uppercase());
+        let run_size = if small { size * SMALL_CAPS_SCALE } else { size };
+        match runs.last_mut() {
+            Some((_, last_size)) if *last_size == run_size => {}
+            _ => runs.push((String::new(), run_size)),
+        }
+        let run = &mut runs.last_mut().unwrap().0;
+        if small {
+            run.extend(character.to_uppercase());
+        } else {
+            run.push(character);
+        }
+    }
+
+    if runs.is_empty() {
+        runs.push((String::new(), size));
+    }
+    runs
+}
+
+fn is_word_separator(font: &Font, glyph_id: u32) -> bool {
+    [' ', '\u{a0}'].iter().any(|&character| font.glyph_for_char(character) == Some(glyph_id))
+}
+
 pub trait LayoutExt {
     fn metrics(&self) -> TextMetrics;
     fn width(&self) -> f32;
@@ -417,6 +785,13 @@ impl LayoutExt for Layout {
 /// `CanvasRenderingContext2D::set_font()`.
 pub trait IntoFontCollection {
     fn into_font_collection(self, font_context: &CanvasFontContext) -> Arc<FontCollection>;
+
+    /// The PostScript names of the fonts, if they were given that way, so that display lists
+    /// can look them up again when they're replayed.
+    #[inline]
+    fn postscript_names(&self) -> Option<Vec<String>> {
+        None
+    }
 }
 
 impl IntoFontCollection for Arc<FontCollection> {
@@ -474,6 +849,11 @@ impl IntoFontCollection for Font {
     fn into_font_collection(self, context: &CanvasFontContext) -> Arc<FontCollection> {
         FontFamily::new_from_font(self).into_font_collection(context)
     }
+
+    #[inline]
+    fn postscript_names(&self) -> Option<Vec<String>> {
+        self.postscript_name().map(|postscript_name| vec![postscript_name])
+    }
 }
 
 impl<'a> IntoFontCollection for &'a [Font] {
@@ -492,6 +872,11 @@ impl<'a> IntoFontCollection for &'a str {
     fn into_font_collection(self, context: &CanvasFontContext) -> Arc<FontCollection> {
         context.get_font_by_postscript_name(self).into_font_collection(context)
     }
+
+    #[inline]
+    fn postscript_names(&self) -> Option<Vec<String>> {
+        Some(vec![(*self).to_owned()])
+    }
 }
 
 impl<'a, 'b> IntoFontCollection for &'a [&'b str] {
@@ -504,4 +889,88 @@ impl<'a, 'b> IntoFontCollection for &'a [&'b str] {
         }
         Arc::new(font_collection)
     }
+
+    #[inline]
+    fn postscript_names(&self) -> Option<Vec<String>> {
+        Some(self.iter().map(|&postscript_name| postscript_name.to_owned()).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Canvas, CanvasFontContext, FontVariantCaps, TextAlign, TextDirection};
+    use pathfinder_geometry::vector::vec2f;
+    use std::iter;
+    use super::{SMALL_CAPS_SCALE, TextMetrics, variant_caps_runs};
+
+    const SMALL_SIZE: f32 = 10.0 * SMALL_CAPS_SCALE;
+
+    fn runs(string: &str, variant_caps: FontVariantCaps) -> Vec<(String, f32)> {
+        variant_caps_runs(string, variant_caps, 10.0)
+    }
+
+    fn run(text: &str, size: f32) -> (String, f32) {
+        (text.to_owned(), size)
+    }
+
+    #[test]
+    fn test_variant_caps_runs() {
+        assert_eq!(runs("Hello", FontVariantCaps::Normal), vec![run("Hello", 10.0)]);
+        assert_eq!(runs("Hello", FontVariantCaps::TitlingCaps), vec![run("Hello", 10.0)]);
+        assert_eq!(runs("Hello", FontVariantCaps::SmallCaps),
+                   vec![run("H", 10.0), run("ELLO", SMALL_SIZE)]);
+        assert_eq!(runs("Hello", FontVariantCaps::PetiteCaps),
+                   vec![run("H", 10.0), run("ELLO", SMALL_SIZE)]);
+        assert_eq!(runs("Hello", FontVariantCaps::AllSmallCaps), vec![run("HELLO", SMALL_SIZE)]);
+        assert_eq!(runs("Hello", FontVariantCaps::AllPetiteCaps), vec![run("HELLO", SMALL_SIZE)]);
+        assert_eq!(runs("Hello", FontVariantCaps::Unicase),
+                   vec![run("H", SMALL_SIZE), run("ello", 10.0)]);
+
+        // Characters without case stay at full size, and uppercasing may change the length.
+        assert_eq!(runs("a1 straße", FontVariantCaps::SmallCaps),
+                   vec![run("A", SMALL_SIZE),
+                        run("1 ", 10.0),
+                        run("STRASSE", SMALL_SIZE)]);
+        assert_eq!(runs("", FontVariantCaps::SmallCaps), vec![run("", 10.0)]);
+    }
+
+    #[test]
+    fn test_text_origin_alignment() {
+        let metrics = TextMetrics {
+            width: 100.0,
+            actual_bounding_box_left: 0.0,
+            actual_bounding_box_right: 100.0,
+            font_bounding_box_ascent: 0.0,
+            font_bounding_box_descent: 0.0,
+            actual_bounding_box_ascent: 0.0,
+            actual_bounding_box_descent: 0.0,
+            em_height_ascent: 0.0,
+            em_height_descent: 0.0,
+            hanging_baseline: 0.0,
+            alphabetic_baseline: 0.0,
+            ideographic_baseline: 0.0,
+        };
+
+        let font_context = CanvasFontContext::from_fonts(iter::empty());
+        let mut context = Canvas::new(vec2f(100.0, 100.0)).get_context_2d(font_context);
+        for &(text_align, direction, x) in &[
+            (TextAlign::Start, TextDirection::Ltr, 0.0),
+            (TextAlign::Start, TextDirection::Inherit, 0.0),
+            (TextAlign::Start, TextDirection::Rtl, -100.0),
+            (TextAlign::End, TextDirection::Ltr, -100.0),
+            (TextAlign::End, TextDirection::Inherit, -100.0),
+            (TextAlign::End, TextDirection::Rtl, 0.0),
+            (TextAlign::Left, TextDirection::Rtl, 0.0),
+            (TextAlign::Right, TextDirection::Ltr, -100.0),
+            (TextAlign::Center, TextDirection::Rtl, -50.0),
+        ] {
+            context.set_text_align(text_align);
+            context.set_direction(direction);
+            assert_eq!(metrics.text_origin(&context.current_state).x(),
+                       x,
+                       "{:?} {:?}",
+                       text_align,
+                       direction);
+        }
+    }
 }
@@ -119,11 +119,13 @@ impl<'a> DashState<'a> {
             current_dash_index += 1;
         }
 
+        // The offset is now how far into the current dash we start.
+        let current_dash_index = current_dash_index.min(dashes.len() - 1);
         DashState {
             output: Contour::new(),
             dashes,
             current_dash_index,
-            distance_left: offset,
+            distance_left: dashes[current_dash_index] - offset,
         }
     }
 
@@ -132,3 +134,37 @@ impl<'a> DashState<'a> {
         self.current_dash_index % 2 == 0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dash::OutlineDash;
+    use crate::outline::{Contour, Outline};
+    use pathfinder_geometry::vector::vec2f;
+
+    fn dash_line(length: f32, dashes: &[f32], offset: f32) -> String {
+        let mut contour = Contour::new();
+        contour.push_endpoint(vec2f(0.0, 0.0));
+        contour.push_endpoint(vec2f(length, 0.0));
+        let mut outline = Outline::new();
+        outline.push_contour(contour);
+
+        let mut dash = OutlineDash::new(&outline, dashes, offset);
+        dash.dash();
+        format!("{:?}", dash.into_outline())
+    }
+
+    #[test]
+    fn test_dash_offset() {
+        // With no offset, the line starts with a whole dash.
+        assert_eq!(dash_line(20.0, &[4.0, 2.0], 0.0),
+                   "M 0 0 L 4 0 M 6 0 L 10 0 M 12 0 L 16 0 M 18 0 L 20 0");
+        // An offset inside a dash shortens the first dash.
+        assert_eq!(dash_line(20.0, &[4.0, 2.0], 1.0),
+                   "M 0 0 L 3 0 M 5 0 L 9 0 M 11 0 L 15 0 M 17 0 L 20 0");
+        // An offset inside a gap starts with the rest of the gap.
+        assert_eq!(dash_line(20.0, &[4.0, 2.0], 5.0),
+                   "M 1 0 L 5 0 M 7 0 L 11 0 M 13 0 L 17 0 M 19 0 L 20 0");
+        // An offset past the pattern length wraps around.
+        assert_eq!(dash_line(20.0, &[4.0, 2.0], 13.0), dash_line(20.0, &[4.0, 2.0], 1.0));
+    }
+}
@@ -13,7 +13,7 @@
 use pathfinder_color::ColorF;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::vector::Vector2F;
-use pathfinder_simd::default::F32x2;
+use pathfinder_simd::default::{F32x2, F32x4};
 
 /// This intentionally does not precisely match what Core Graphics does (a
 /// Lanczos function), because we don't want any ringing artefacts.
@@ -71,12 +71,27 @@ pub enum PatternFilter {
     ///
     /// To produce a full Gaussian blur, perform two successive blur operations, one in each
     /// direction.
+    ///
+    /// The source is assumed to be premultiplied, as render targets are.
     Blur {
         direction: BlurDirection,
         sigma: f32,
     },
+
+    /// Transforms the color of each pixel with a color matrix, leaving alpha alone.
+    ///
+    /// The source is assumed to be premultiplied, as render targets are.
+    ColorMatrix(ColorMatrix),
 }
 
+/// An affine transform of RGB colors, like the ones CSS filter functions such as `saturate()`
+/// and `sepia()` perform.
+///
+/// Each row computes one output channel, red, green, and blue in that order, as
+/// `r * row[0] + g * row[1] + b * row[2] + row[3]`, where the components are in 0 to 1.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColorMatrix(pub [F32x4; 3]);
+
 /// Blend modes that can be applied to individual paths.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum BlendMode {
@@ -128,6 +143,99 @@ impl Default for BlendMode {
     }
 }
 
+impl ColorMatrix {
+    #[inline]
+    pub fn identity() -> ColorMatrix {
+        ColorMatrix::scale_and_offset(1.0, 0.0)
+    }
+
+    /// Multiplies each channel by `amount`.
+    #[inline]
+    pub fn brightness(amount: f32) -> ColorMatrix {
+        ColorMatrix::scale_and_offset(amount, 0.0)
+    }
+
+    /// Scales each channel's distance from 0.5 by `amount`.
+    #[inline]
+    pub fn contrast(amount: f32) -> ColorMatrix {
+        ColorMatrix::scale_and_offset(amount, 0.5 - 0.5 * amount)
+    }
+
+    /// Moves each channel `amount` of the way toward its inverse.
+    #[inline]
+    pub fn invert(amount: f32) -> ColorMatrix {
+        ColorMatrix::scale_and_offset(1.0 - 2.0 * amount, amount)
+    }
+
+    /// Desaturates by `amount`, where 1 is fully gray.
+    pub fn grayscale(amount: f32) -> ColorMatrix {
+        let a = 1.0 - amount;
+        ColorMatrix([
+            F32x4::new(0.2126 + 0.7874 * a, 0.7152 - 0.7152 * a, 0.0722 - 0.0722 * a, 0.0),
+            F32x4::new(0.2126 - 0.2126 * a, 0.7152 + 0.2848 * a, 0.0722 - 0.0722 * a, 0.0),
+            F32x4::new(0.2126 - 0.2126 * a, 0.7152 - 0.7152 * a, 0.0722 + 0.9278 * a, 0.0),
+        ])
+    }
+
+    /// Tints toward sepia by `amount`, where 1 is fully sepia.
+    pub fn sepia(amount: f32) -> ColorMatrix {
+        let a = 1.0 - amount;
+        ColorMatrix([
+            F32x4::new(0.393 + 0.607 * a, 0.769 - 0.769 * a, 0.189 - 0.189 * a, 0.0),
+            F32x4::new(0.349 - 0.349 * a, 0.686 + 0.314 * a, 0.168 - 0.168 * a, 0.0),
+            F32x4::new(0.272 - 0.272 * a, 0.534 - 0.534 * a, 0.131 + 0.869 * a, 0.0),
+        ])
+    }
+
+    /// Scales saturation by `amount`, where 0 is gray and values above 1 oversaturate.
+    pub fn saturate(amount: f32) -> ColorMatrix {
+        let s = amount;
+        ColorMatrix([
+            F32x4::new(0.213 + 0.787 * s, 0.715 - 0.715 * s, 0.072 - 0.072 * s, 0.0),
+            F32x4::new(0.213 - 0.213 * s, 0.715 + 0.285 * s, 0.072 - 0.072 * s, 0.0),
+            F32x4::new(0.213 - 0.213 * s, 0.715 - 0.715 * s, 0.072 + 0.928 * s, 0.0),
+        ])
+    }
+
+    /// Rotates hues by `angle` radians.
+    pub fn hue_rotate(angle: f32) -> ColorMatrix {
+        let (sin, cos) = angle.sin_cos();
+        ColorMatrix([
+            F32x4::new(0.213 + cos * 0.787 - sin * 0.213,
+                       0.715 - cos * 0.715 - sin * 0.715,
+                       0.072 - cos * 0.072 + sin * 0.928,
+                       0.0),
+            F32x4::new(0.213 - cos * 0.213 + sin * 0.143,
+                       0.715 + cos * 0.285 + sin * 0.140,
+                       0.072 - cos * 0.072 - sin * 0.283,
+                       0.0),
+            F32x4::new(0.213 - cos * 0.213 - sin * 0.787,
+                       0.715 - cos * 0.715 + sin * 0.715,
+                       0.072 + cos * 0.928 + sin * 0.072,
+                       0.0),
+        ])
+    }
+
+    fn scale_and_offset(scale: f32, offset: f32) -> ColorMatrix {
+        ColorMatrix([
+            F32x4::new(scale, 0.0, 0.0, offset),
+            F32x4::new(0.0, scale, 0.0, offset),
+            F32x4::new(0.0, 0.0, scale, offset),
+        ])
+    }
+
+    /// Applies this matrix to a color, clamping the result.
+    pub fn apply(&self, color: ColorF) -> ColorF {
+        let rgb1 = F32x4::new(color.r(), color.g(), color.b(), 1.0);
+        let dot = |row: F32x4| {
+            let product = row * rgb1;
+            product[0] + product[1] + product[2] + product[3]
+        };
+        let rgb = F32x4::new(dot(self.0[0]), dot(self.0[1]), dot(self.0[2]), color.a());
+        ColorF(rgb.clamp(F32x4::default(), F32x4::splat(1.0)))
+    }
+}
+
 impl Default for Filter {
     #[inline]
     fn default() -> Filter {
@@ -203,3 +311,33 @@ impl BlendMode {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::effects::ColorMatrix;
+    use pathfinder_color::ColorF;
+
+    fn approx_eq(a: ColorF, b: ColorF) -> bool {
+        (0..4).all(|index| (a.0[index] - b.0[index]).abs() < 0.001)
+    }
+
+    #[test]
+    fn test_color_matrix() {
+        let color = ColorF::new(0.2, 0.4, 0.8, 0.5);
+        assert_eq!(ColorMatrix::identity().apply(color), color);
+        let inverted = ColorMatrix::invert(1.0).apply(color);
+        assert!(approx_eq(inverted, ColorF::new(0.8, 0.6, 0.2, 0.5)));
+        let flat = ColorMatrix::contrast(0.0).apply(color);
+        assert!(approx_eq(flat, ColorF::new(0.5, 0.5, 0.5, 0.5)));
+        assert!(approx_eq(ColorMatrix::hue_rotate(0.0).apply(color), color));
+        assert!(approx_eq(ColorMatrix::saturate(1.0).apply(color), color));
+
+        // Grayscale output has equal channels.
+        let gray = ColorMatrix::grayscale(1.0).apply(color);
+        assert!((gray.r() - gray.g()).abs() < 0.001 && (gray.g() - gray.b()).abs() < 0.001);
+
+        // Brightness doubles and then clamps.
+        let bright = ColorMatrix::brightness(2.0).apply(color);
+        assert!(approx_eq(bright, ColorF::new(0.4, 0.8, 1.0, 0.5)));
+    }
+}
@@ -22,13 +22,17 @@ pub mod dash;
 pub mod effects;
 pub mod fill;
 pub mod gradient;
+pub mod offset;
 pub mod orientation;
 pub mod outline;
 pub mod pattern;
+pub mod polyline;
+pub mod rebase;
 pub mod render_target;
 pub mod segment;
 pub mod sorted_vector;
 pub mod stroke;
+pub mod svg_path;
 pub mod transform;
 
 mod dilation;
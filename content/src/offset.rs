@@ -0,0 +1,454 @@
+// pathfinder/content/src/offset.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Curve-preserving offsetting of outlines.
+//!
+//! Unlike dilation, which moves points along their normals, this approximates the true offset of
+//! each curve with new Bézier curves, so it remains accurate for arbitrary distances.
+
+use crate::orientation::Orientation;
+use crate::outline::{ArcDirection, Contour, ContourIterFlags, Outline};
+use crate::segment::Segment;
+use crate::stroke::LineJoin;
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{Vector2F, vec2f};
+
+const TOLERANCE: f32 = 0.01;
+const EPSILON: f32 = 0.0001;
+
+const MAX_SUBDIVISION_DEPTH: u32 = 8;
+const ERROR_SAMPLE_COUNT: u32 = 8;
+const CUSP_SAMPLE_COUNT: u32 = 16;
+const CUSP_BISECTION_ITERATIONS: u32 = 24;
+
+/// Offsets every contour of an outline by a fixed distance.
+///
+/// Positive distances grow the outline and negative distances shrink it. Corners on the outside
+/// of the offset are filled in with the given join. Inside corners between straight edges are
+/// trimmed; other self-intersections are left for the fill rule to resolve.
+pub struct OutlineOffset<'a> {
+    input: &'a Outline,
+    output: Outline,
+    distance: f32,
+    join: LineJoin,
+}
+
+impl<'a> OutlineOffset<'a> {
+    #[inline]
+    pub fn new(input: &'a Outline, distance: f32, join: LineJoin) -> OutlineOffset<'a> {
+        OutlineOffset { input, output: Outline::new(), distance, join }
+    }
+
+    pub fn offset(&mut self) {
+        // We always offset to the left of the direction of travel, so flip the sign as necessary
+        // to make positive distances point outward.
+        let distance = match Orientation::from_outline(self.input) {
+            Orientation::Ccw => self.distance,
+            Orientation::Cw => -self.distance,
+        };
+
+        let mut output = Outline::new();
+        for input in &self.input.contours {
+            let mut contour_offset = ContourOffset::new(input, distance, self.join);
+            contour_offset.offset();
+            output.push_contour(contour_offset.output);
+        }
+        self.output = output;
+    }
+
+    #[inline]
+    pub fn into_outline(self) -> Outline {
+        self.output
+    }
+}
+
+struct ContourOffset<'a> {
+    input: &'a Contour,
+    output: Contour,
+    distance: f32,
+    join: LineJoin,
+}
+
+impl<'a> ContourOffset<'a> {
+    #[inline]
+    fn new(input: &'a Contour, distance: f32, join: LineJoin) -> ContourOffset<'a> {
+        ContourOffset { input, output: Contour::new(), distance, join }
+    }
+
+    fn offset(&mut self) {
+        let segments: Vec<Segment> = self.input
+                                         .iter(ContourIterFlags::empty())
+                                         .filter(|segment| !segment_is_degenerate(segment))
+                                         .map(|segment| {
+            if segment.is_quadratic() { segment.to_cubic() } else { segment }
+        }).collect();
+        if segments.is_empty() {
+            return;
+        }
+
+        // Offset each segment separately first, so that inside corners can be trimmed.
+        let mut pieces: Vec<Vec<Segment>> = segments.iter().map(|segment| {
+            let mut pieces = vec![];
+            if segment.is_line() {
+                pieces.push(Segment::line(segment.baseline.offset(self.distance)));
+            } else {
+                self.offset_cubic(segment, &mut pieces);
+            }
+            pieces
+        }).collect();
+
+        let closed = self.input.is_closed();
+        let mut joins = vec![vec![]; segments.len()];
+        for next_index in 0..segments.len() {
+            let prev_index = if next_index > 0 {
+                next_index - 1
+            } else if closed {
+                segments.len() - 1
+            } else {
+                continue;
+            };
+
+            let (_, prev_tangent) = segment_tangents(&segments[prev_index]);
+            let (next_tangent, _) = segment_tangents(&segments[next_index]);
+            let join_point = segments[next_index].baseline.from();
+            if self.corner_is_outside(prev_tangent, next_tangent) {
+                joins[next_index] = self.join_pieces(join_point, prev_tangent, next_tangent);
+            } else {
+                let prev_piece = *pieces[prev_index].last().unwrap();
+                let next_piece = pieces[next_index][0];
+                if let Some((prev_piece, next_piece)) = trim_inside_corner(prev_piece,
+                                                                          next_piece) {
+                    *pieces[prev_index].last_mut().unwrap() = prev_piece;
+                    pieces[next_index][0] = next_piece;
+                }
+            }
+        }
+
+        for (segment_index, segment_pieces) in pieces.iter().enumerate() {
+            if segment_index > 0 {
+                self.push_pieces(&joins[segment_index]);
+            }
+            self.push_pieces(segment_pieces);
+        }
+
+        if closed {
+            self.push_pieces(&joins[0]);
+            self.output.close();
+        }
+    }
+
+    fn offset_cubic(&self, segment: &Segment, pieces: &mut Vec<Segment>) {
+        // Split at the cusps of the offset curve, where the radius of curvature equals the
+        // distance. Each piece between cusps has a smooth offset that we can fit.
+        let mut remaining = *segment;
+        let mut t_start = 0.0;
+        for t in find_cusps(segment, self.distance) {
+            let local_t = (t - t_start) / (1.0 - t_start);
+            let (before, after) = remaining.as_cubic_segment().split(local_t);
+            self.offset_cubic_piece(&before, 0, pieces);
+            remaining = after;
+            t_start = t;
+        }
+        self.offset_cubic_piece(&remaining, 0, pieces);
+    }
+
+    fn offset_cubic_piece(&self, segment: &Segment, depth: u32, pieces: &mut Vec<Segment>) {
+        let candidate = offset_cubic_once(segment, self.distance);
+        if depth < MAX_SUBDIVISION_DEPTH &&
+                !error_is_within_tolerance(segment, &candidate, self.distance) {
+            let (before, after) = segment.as_cubic_segment().split(0.5);
+            self.offset_cubic_piece(&before, depth + 1, pieces);
+            self.offset_cubic_piece(&after, depth + 1, pieces);
+            return;
+        }
+        pieces.push(candidate);
+    }
+
+    fn corner_is_outside(&self, prev_tangent: Vector2F, next_tangent: Vector2F) -> bool {
+        let det = prev_tangent.det(next_tangent);
+        if f32::abs(det) < EPSILON {
+            // Either no corner at all or a complete reversal, which we treat as outside.
+            return prev_tangent.dot(next_tangent) < 0.0;
+        }
+        det * self.distance < 0.0
+    }
+
+    fn join_pieces(&self, join_point: Vector2F, prev_tangent: Vector2F, next_tangent: Vector2F)
+                   -> Vec<Segment> {
+        let distance = self.distance;
+        let from = join_point + normal(prev_tangent) * distance;
+        let to = join_point + normal(next_tangent) * distance;
+        if (to - from).square_length() < EPSILON * EPSILON {
+            return vec![];
+        }
+
+        match self.join {
+            LineJoin::Bevel => vec![],
+            LineJoin::Miter(miter_limit) => {
+                let prev_line = LineSegment2F::new(from, from + prev_tangent);
+                let next_line = LineSegment2F::new(to, to + next_tangent);
+                if let Some(t) = prev_line.intersection_t(next_line) {
+                    let miter_endpoint = prev_line.sample(t);
+                    let threshold = miter_limit * f32::abs(distance);
+                    if t >= 0.0 &&
+                            (miter_endpoint - join_point).square_length() <=
+                            threshold * threshold {
+                        return vec![Segment::line(LineSegment2F::new(from, miter_endpoint)),
+                                    Segment::line(LineSegment2F::new(miter_endpoint, to))];
+                    }
+                }
+                vec![]
+            }
+            LineJoin::Round => {
+                let scale = f32::abs(distance);
+                let chord = LineSegment2F::new((from - join_point) * (1.0 / scale),
+                                               (to - join_point) * (1.0 / scale));
+                // The arc always bulges in the direction we were traveling.
+                let direction = if chord.from().det(prev_tangent) >= 0.0 {
+                    ArcDirection::CW
+                } else {
+                    ArcDirection::CCW
+                };
+                let transform = Transform2F::from_scale(scale).translate(join_point);
+                let mut arc = Contour::new();
+                arc.push_arc_from_unit_chord(&transform, chord, direction);
+                arc.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT).collect()
+            }
+        }
+    }
+
+    fn push_pieces(&mut self, pieces: &[Segment]) {
+        for piece in pieces {
+            let from = piece.baseline.from();
+            match self.output.last_position() {
+                Some(last_position) if (last_position - from).square_length() <
+                    EPSILON * EPSILON => {}
+                _ => self.output.push_endpoint(from),
+            }
+
+            let to = piece.baseline.to();
+            if piece.is_line() {
+                self.output.push_endpoint(to);
+            } else if piece.is_quadratic() {
+                self.output.push_quadratic(piece.ctrl.from(), to);
+            } else {
+                self.output.push_cubic(piece.ctrl.from(), piece.ctrl.to(), to);
+            }
+        }
+    }
+}
+
+// If two lines meeting at an inside corner cross, cuts both of them off at the crossing point.
+fn trim_inside_corner(prev: Segment, next: Segment) -> Option<(Segment, Segment)> {
+    if !prev.is_line() || !next.is_line() {
+        return None;
+    }
+
+    let prev_t = prev.baseline.intersection_t(next.baseline)?;
+    let next_t = next.baseline.intersection_t(prev.baseline)?;
+    if !(0.0..=1.0).contains(&prev_t) || !(0.0..=1.0).contains(&next_t) {
+        return None;
+    }
+
+    let crossing = prev.baseline.sample(prev_t);
+    Some((Segment::line(LineSegment2F::new(prev.baseline.from(), crossing)),
+          Segment::line(LineSegment2F::new(crossing, next.baseline.to()))))
+}
+
+// Approximates the offset of a cubic by offsetting its endpoints and scaling its control
+// handles by `1 - dκ`, which matches the derivatives of the true offset at both ends.
+fn offset_cubic_once(segment: &Segment, distance: f32) -> Segment {
+    let (p0, p1) = (segment.baseline.from(), segment.ctrl.from());
+    let (p2, p3) = (segment.ctrl.to(), segment.baseline.to());
+    let (start_tangent, end_tangent) = segment_tangents(segment);
+
+    let from = p0 + normal(start_tangent) * distance;
+    let to = p3 + normal(end_tangent) * distance;
+
+    let start_scale = 1.0 - distance * curvature(p1 - p0, p2 - p1 - p1 + p0);
+    let end_scale = 1.0 - distance * curvature(p3 - p2, p3 - p2 - p2 + p1);
+
+    Segment::cubic(LineSegment2F::new(from, to),
+                   LineSegment2F::new(from + (p1 - p0) * start_scale,
+                                      to + (p2 - p3) * end_scale))
+}
+
+fn error_is_within_tolerance(segment: &Segment, candidate: &Segment, distance: f32) -> bool {
+    for sample_index in 1..ERROR_SAMPLE_COUNT {
+        let t = sample_index as f32 / ERROR_SAMPLE_COUNT as f32;
+        let derivative = segment.as_cubic_segment().derivative(t);
+        if derivative.square_length() < EPSILON * EPSILON {
+            continue;
+        }
+
+        let expected = segment.sample(t) + normal(derivative.normalize()) * distance;
+        if (candidate.sample(t) - expected).square_length() > TOLERANCE * TOLERANCE {
+            return false;
+        }
+    }
+    true
+}
+
+// Returns the times at which `1 - dκ` changes sign, in increasing order.
+fn find_cusps(segment: &Segment, distance: f32) -> Vec<f32> {
+    let cubic = segment.as_cubic_segment();
+    let cusp_function = |t: f32| {
+        let (derivative, second_derivative) = (cubic.derivative(t),
+                                               cubic.second_derivative(t));
+        let speed = derivative.length();
+        speed * speed * speed - distance * derivative.det(second_derivative)
+    };
+
+    let mut cusps = vec![];
+    let mut prev_t = 0.0;
+    let mut prev_value = cusp_function(prev_t);
+    for sample_index in 1..(CUSP_SAMPLE_COUNT + 1) {
+        let next_t = sample_index as f32 / CUSP_SAMPLE_COUNT as f32;
+        let next_value = cusp_function(next_t);
+        if (prev_value < 0.0) != (next_value < 0.0) {
+            let (mut min_t, mut max_t) = (prev_t, next_t);
+            for _ in 0..CUSP_BISECTION_ITERATIONS {
+                let mid_t = (min_t + max_t) * 0.5;
+                if (cusp_function(mid_t) < 0.0) == (prev_value < 0.0) {
+                    min_t = mid_t;
+                } else {
+                    max_t = mid_t;
+                }
+            }
+            let t = (min_t + max_t) * 0.5;
+            if t > EPSILON && t < 1.0 - EPSILON {
+                cusps.push(t);
+            }
+        }
+        prev_t = next_t;
+        prev_value = next_value;
+    }
+    cusps
+}
+
+fn segment_is_degenerate(segment: &Segment) -> bool {
+    let from = segment.baseline.from();
+    let is_near = |point: Vector2F| (point - from).square_length() < EPSILON * EPSILON;
+    is_near(segment.baseline.to()) &&
+        (segment.is_line() || (is_near(segment.ctrl.from()) && is_near(segment.ctrl.to())))
+}
+
+// Returns the unit tangents at the start and end of a non-degenerate line or cubic, skipping
+// over control points that coincide with endpoints.
+fn segment_tangents(segment: &Segment) -> (Vector2F, Vector2F) {
+    let (p0, p3) = (segment.baseline.from(), segment.baseline.to());
+    if segment.is_line() {
+        let tangent = (p3 - p0).normalize();
+        return (tangent, tangent);
+    }
+
+    let (p1, p2) = (segment.ctrl.from(), segment.ctrl.to());
+    let start = first_nonzero_vector(&[p1 - p0, p2 - p0, p3 - p0]);
+    let end = first_nonzero_vector(&[p3 - p2, p3 - p1, p3 - p0]);
+    return (start.normalize(), end.normalize());
+
+    fn first_nonzero_vector(vectors: &[Vector2F]) -> Vector2F {
+        for &vector in vectors {
+            if vector.square_length() >= EPSILON * EPSILON {
+                return vector;
+            }
+        }
+        vectors[vectors.len() - 1]
+    }
+}
+
+// The left normal, which `LineSegment2F::offset()` also uses.
+#[inline]
+fn normal(tangent: Vector2F) -> Vector2F {
+    vec2f(-tangent.y(), tangent.x())
+}
+
+// Signed curvature, given vectors proportional to the first and second derivatives. The
+// constant factors of 3 and 6 from the Bézier derivatives are folded in here.
+fn curvature(derivative: Vector2F, second_derivative: Vector2F) -> f32 {
+    let speed = derivative.length();
+    if speed < EPSILON {
+        return 0.0;
+    }
+    (6.0 * derivative.det(second_derivative)) / (9.0 * speed * speed * speed)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::offset::OutlineOffset;
+    use crate::outline::{Contour, ContourIterFlags, Outline};
+    use crate::stroke::LineJoin;
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+
+    fn offset(outline: &Outline, distance: f32, join: LineJoin) -> Outline {
+        let mut offset = OutlineOffset::new(outline, distance, join);
+        offset.offset();
+        offset.into_outline()
+    }
+
+    fn assert_rect_approx_eq(a: RectF, b: RectF) {
+        assert!((a.origin() - b.origin()).length() < 0.001, "{:?} != {:?}", a, b);
+        assert!((a.lower_right() - b.lower_right()).length() < 0.001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_offset_rect_miter() {
+        let rect = RectF::new(vec2f(10.0, 20.0), vec2f(30.0, 40.0));
+        let outline = Outline::from_rect(rect);
+        assert_rect_approx_eq(offset(&outline, 5.0, LineJoin::Miter(10.0)).bounds(),
+                              rect.dilate(5.0));
+        assert_rect_approx_eq(offset(&outline, -5.0, LineJoin::Miter(10.0)).bounds(),
+                              rect.contract(5.0));
+    }
+
+    #[test]
+    fn test_offset_rect_round() {
+        let rect = RectF::new(vec2f(0.0, 0.0), vec2f(10.0, 10.0));
+        let outline = offset(&Outline::from_rect(rect), 3.0, LineJoin::Round);
+        assert_rect_approx_eq(outline.bounds(), rect.dilate(3.0));
+
+        // The corners must be rounded, not mitered.
+        for contour in outline.contours() {
+            for point_index in 0..contour.len() {
+                if contour.point_is_endpoint(point_index) {
+                    let point = contour.position_of(point_index);
+                    assert!((point - vec2f(-3.0, -3.0)).length() > 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_offset_circle() {
+        let (center, radius) = (vec2f(50.0, 50.0), 20.0);
+        let mut contour = Contour::new();
+        contour.push_ellipse(&Transform2F::from_scale(radius).translate(center));
+        contour.close();
+        let mut outline = Outline::new();
+        outline.push_contour(contour);
+
+        for &distance in &[-15.0, -5.0, 5.0, 40.0] {
+            let result = offset(&outline, distance, LineJoin::Bevel);
+            let expected_radius = radius + distance;
+            for contour in result.contours() {
+                for segment in contour.iter(ContourIterFlags::empty()) {
+                    for t_index in 0..9 {
+                        let point: Vector2F = segment.sample(t_index as f32 / 8.0);
+                        let error = (point - center).length() - expected_radius;
+                        assert!(error.abs() < 0.05, "distance {}: error {}", distance, error);
+                    }
+                }
+            }
+        }
+    }
+}
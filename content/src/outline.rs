@@ -12,7 +12,9 @@
 
 use crate::clip::{self, ContourPolygonClipper, ContourRectClipper};
 use crate::dilation::ContourDilator;
+use crate::fill::FillRule;
 use crate::orientation::Orientation;
+use crate::polyline::Polyline;
 use crate::segment::{Segment, SegmentFlags, SegmentKind};
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::RectF;
@@ -20,7 +22,7 @@ use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::transform3d::Perspective;
 use pathfinder_geometry::unit_vector::UnitVector;
 use pathfinder_geometry::vector::{Vector2F, vec2f};
-use std::f32::consts::PI;
+use std::f32::consts::{FRAC_PI_2, PI};
 use std::fmt::{self, Debug, Formatter};
 use std::mem;
 
@@ -132,6 +134,24 @@ impl Outline {
         self.contours
     }
 
+    /// Returns the winding number of `point` with respect to this outline, treating every
+    /// contour as closed, as filling does.
+    pub fn winding_number(&self, point: Vector2F) -> i32 {
+        if !self.bounds.contains_point(point) {
+            return 0;
+        }
+        self.contours.iter().map(|contour| contour.winding_number(point)).sum()
+    }
+
+    /// Returns true if filling this outline with `fill_rule` would cover `point`.
+    pub fn contains_point(&self, point: Vector2F, fill_rule: FillRule) -> bool {
+        let winding_number = self.winding_number(point);
+        match fill_rule {
+            FillRule::Winding => winding_number != 0,
+            FillRule::EvenOdd => winding_number % 2 != 0,
+        }
+    }
+
     /// Removes all contours from this outline.
     #[inline]
     pub fn clear(&mut self) {
@@ -238,6 +258,22 @@ impl Outline {
     pub fn close_all_contours(&mut self) {
         self.contours.iter_mut().for_each(|contour| contour.close());
     }
+
+    /// Converts each contour to a polyline, approximating curves to within `tolerance`.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Polyline> {
+        self.contours.iter().map(|contour| contour.flatten(tolerance)).collect()
+    }
+
+    /// Replaces overly dense point data with a smaller number of curves, deviating from the
+    /// original by at most roughly `tolerance`.
+    pub fn simplify(&self, tolerance: f32) -> Outline {
+        let mut outline = Outline::new();
+        for contour in &self.contours {
+            let polyline = contour.flatten(tolerance * 0.25).simplify(tolerance * 0.25);
+            outline.push_contour(polyline.fit_curves(tolerance * 0.5));
+        }
+        outline
+    }
 }
 
 impl Debug for Outline {
@@ -338,6 +374,19 @@ impl Contour {
         self.closed
     }
 
+    /// Returns the winding number of `point` with respect to this contour, treating it as closed
+    /// even if it isn't.
+    pub fn winding_number(&self, point: Vector2F) -> i32 {
+        let (first_position, last_position) = match (self.points.first(), self.points.last()) {
+            (Some(&first_position), Some(&last_position)) => (first_position, last_position),
+            _ => return 0,
+        };
+        let close_segment = Segment::line(LineSegment2F::new(last_position, first_position));
+        self.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT)
+            .map(|segment| segment.winding_number(point))
+            .sum::<i32>() + close_segment.winding_number(point)
+    }
+
     #[inline]
     pub fn position_of(&self, index: u32) -> Vector2F {
         self.points[index as usize]
@@ -499,6 +548,96 @@ impl Contour {
                           PushSegmentFlags::UPDATE_BOUNDS);
     }
 
+    /// Appends an elliptical arc from the last point to `to`, using the endpoint
+    /// parameterization of the SVG `A` path command.
+    ///
+    /// `x_axis_rotation` is in radians, and `direction` corresponds to the sweep flag: `CW` is
+    /// the direction of increasing angle. As SVG specifies, negative radii are made positive,
+    /// zero radii produce a straight line, and radii too small to reach `to` are scaled up.
+    ///
+    /// See: https://www.w3.org/TR/SVG11/implnote.html#ArcImplementationNotes
+    pub fn push_svg_arc(&mut self,
+                        radii: Vector2F,
+                        x_axis_rotation: f32,
+                        large_arc: bool,
+                        direction: ArcDirection,
+                        to: Vector2F) {
+        let from = match self.last_position() {
+            None => {
+                self.push_endpoint(to);
+                return;
+            }
+            Some(from) => from,
+        };
+        if from == to {
+            return;
+        }
+
+        let mut radii = vec2f(radii.x().abs(), radii.y().abs());
+        if radii.x() == 0.0 || radii.y() == 0.0 {
+            self.push_endpoint(to);
+            return;
+        }
+
+        // Convert to center parameterization.
+        let rotation = Transform2F::from_rotation(x_axis_rotation);
+        let p1 = rotation.inverse() * ((from - to) * 0.5);
+
+        let lambda = p1.x() * p1.x() / (radii.x() * radii.x()) +
+            p1.y() * p1.y() / (radii.y() * radii.y());
+        if lambda > 1.0 {
+            radii *= lambda.sqrt();
+        }
+
+        let sweep = direction == ArcDirection::CW;
+        let (rx2, ry2) = (radii.x() * radii.x(), radii.y() * radii.y());
+        let (x2, y2) = (p1.x() * p1.x(), p1.y() * p1.y());
+        let numerator = (rx2 * ry2 - rx2 * y2 - ry2 * x2).max(0.0);
+        let mut coefficient = (numerator / (rx2 * y2 + ry2 * x2)).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let center_prime = vec2f(radii.x() * p1.y() / radii.y(),
+                                 -radii.y() * p1.x() / radii.x()) * coefficient;
+        let center = rotation * center_prime + (from + to) * 0.5;
+
+        let start_vector = (p1 - center_prime) / radii;
+        let end_vector = (-p1 - center_prime) / radii;
+        let start_angle = start_vector.y().atan2(start_vector.x());
+        let mut sweep_angle = end_vector.y().atan2(end_vector.x()) - start_angle;
+        if sweep && sweep_angle < 0.0 {
+            sweep_angle += 2.0 * PI;
+        } else if !sweep && sweep_angle > 0.0 {
+            sweep_angle -= 2.0 * PI;
+        }
+
+        // Approximate with at most quarter-ellipse cubics.
+        let transform = Transform2F::from_scale(radii).rotate(x_axis_rotation).translate(center);
+        let segment_count = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.0) as u32;
+        let segment_sweep_angle = sweep_angle / segment_count as f32;
+        let unit_arc = Segment::arc(segment_sweep_angle.abs());
+        for segment_index in 0..segment_count {
+            let mid_angle = start_angle + segment_sweep_angle * (segment_index as f32 + 0.5);
+            let rotation = Transform2F::from_rotation(mid_angle);
+            let mut segment = unit_arc.transform(&(transform * rotation));
+            if segment_sweep_angle < 0.0 {
+                segment = segment.reversed();
+            }
+
+            // Land exactly on the requested endpoint.
+            let point = if segment_index + 1 == segment_count {
+                to
+            } else {
+                segment.baseline.to()
+            };
+            if segment.is_line() {
+                self.push_endpoint(point);
+            } else {
+                self.push_cubic(segment.ctrl.from(), segment.ctrl.to(), point);
+            }
+        }
+    }
+
     #[inline]
     pub fn segment_after(&self, point_index: u32) -> Segment {
         debug_assert!(self.point_is_endpoint(point_index));
@@ -616,6 +755,44 @@ impl Contour {
         }
     }
 
+    /// Converts this contour to a polyline, approximating curves to within `tolerance`.
+    pub fn flatten(&self, tolerance: f32) -> Polyline {
+        let mut points = Vec::with_capacity(self.points.len());
+        for (segment_index, segment) in self.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT)
+                                            .enumerate() {
+            if segment_index == 0 {
+                points.push(segment.baseline.from());
+            }
+
+            match segment.kind {
+                SegmentKind::None => {}
+                SegmentKind::Line => points.push(segment.baseline.to()),
+                SegmentKind::Quadratic | SegmentKind::Cubic => {
+                    flatten_cubic(&segment.to_cubic(), tolerance, 0, &mut points);
+                }
+            }
+        }
+
+        // A single point still counts.
+        if points.is_empty() && !self.points.is_empty() {
+            points.push(self.points[0]);
+        }
+
+        return Polyline::new(points, self.closed);
+
+        fn flatten_cubic(segment: &Segment, tolerance: f32, depth: u32, points: &mut Vec<Vector2F>) {
+            if depth >= MAX_FLATTENING_DEPTH || segment.as_cubic_segment().is_flat(tolerance) {
+                points.push(segment.baseline.to());
+                return;
+            }
+            let (before, after) = segment.as_cubic_segment().split(0.5);
+            flatten_cubic(&before, tolerance, depth + 1, points);
+            flatten_cubic(&after, tolerance, depth + 1, points);
+        }
+
+        const MAX_FLATTENING_DEPTH: u32 = 16;
+    }
+
     pub fn dilate(&mut self, amount: Vector2F, orientation: Orientation) {
         ContourDilator::new(self, amount, orientation).dilate();
         self.bounds = self.bounds.dilate(amount);
@@ -925,3 +1102,27 @@ pub(crate) fn union_rect(bounds: &mut RectF, new_point: Vector2F, first: bool) {
         *bounds = bounds.union_point(new_point)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::fill::FillRule;
+    use crate::outline::Outline;
+    use pathfinder_geometry::vector::vec2f;
+
+    #[test]
+    fn test_contains_point() {
+        // Two nested squares wound the same way, plus an open circle that's filled as if closed.
+        let outline = Outline::from_svg_path_data("M 0 0 H 10 V 10 H 0 z M 2 2 H 8 V 8 H 2 z \
+                                                   M 20 5 A 5 5 0 1 1 30 5").unwrap();
+        assert_eq!(outline.winding_number(vec2f(1.0, 1.0)), 1);
+        assert_eq!(outline.winding_number(vec2f(5.0, 5.0)), 2);
+        assert!(outline.contains_point(vec2f(5.0, 5.0), FillRule::Winding));
+        assert!(!outline.contains_point(vec2f(5.0, 5.0), FillRule::EvenOdd));
+        assert!(outline.contains_point(vec2f(1.0, 1.0), FillRule::EvenOdd));
+        assert!(!outline.contains_point(vec2f(15.0, 5.0), FillRule::Winding));
+
+        assert!(outline.contains_point(vec2f(25.0, 2.0), FillRule::Winding));
+        assert!(!outline.contains_point(vec2f(25.0, 6.0), FillRule::Winding));
+        assert!(!outline.contains_point(vec2f(20.5, 1.0), FillRule::Winding));
+    }
+}
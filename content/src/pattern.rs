@@ -22,7 +22,9 @@ use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 #[cfg(feature = "pf-image")]
-use image::RgbaImage;
+use image::imageops;
+#[cfg(feature = "pf-image")]
+use image::{ImageError, ImageFormat, RgbaImage};
 
 /// A raster image pattern.
 #[derive(Clone, PartialEq, Debug)]
@@ -30,6 +32,7 @@ pub struct Pattern {
     source: PatternSource,
     transform: Transform2F,
     filter: Option<PatternFilter>,
+    sampling: PatternSampling,
     flags: PatternFlags,
 }
 
@@ -57,10 +60,27 @@ bitflags! {
     pub struct PatternFlags: u8 {
         const REPEAT_X      = 0x01;
         const REPEAT_Y      = 0x02;
-        const NO_SMOOTHING  = 0x04;
     }
 }
 
+/// How a pattern is sampled when it's drawn at a size other than its natural one.
+///
+/// Mipmaps are only generated for image patterns. Render target patterns that request
+/// `Trilinear` or `Bicubic` sampling are sampled bilinearly when downscaled.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PatternSampling {
+    /// Nearest-neighbor sampling, which keeps pixel edges sharp.
+    Nearest,
+    /// Bilinear sampling. This is the cheapest smooth mode, but downscaling by more than half
+    /// aliases.
+    Bilinear,
+    /// Trilinear sampling between mipmap levels, which keeps downscaled images smooth.
+    Trilinear,
+    /// Anisotropic trilinear sampling when downscaling and bicubic (Catmull-Rom) sampling when
+    /// upscaling. This is the slowest mode.
+    Bicubic,
+}
+
 impl Pattern {
     #[inline]
     fn from_source(source: PatternSource) -> Pattern {
@@ -68,6 +88,7 @@ impl Pattern {
             source,
             transform: Transform2F::default(),
             filter: None,
+            sampling: PatternSampling::Bilinear,
             flags: PatternFlags::empty(),
         }
     }
@@ -130,14 +151,31 @@ impl Pattern {
         self.flags.set(PatternFlags::REPEAT_Y, repeat_y);
     }
 
+    #[inline]
+    pub fn sampling(&self) -> PatternSampling {
+        self.sampling
+    }
+
+    #[inline]
+    pub fn set_sampling(&mut self, sampling: PatternSampling) {
+        self.sampling = sampling;
+    }
+
     #[inline]
     pub fn smoothing_enabled(&self) -> bool {
-        !self.flags.contains(PatternFlags::NO_SMOOTHING)
+        self.sampling != PatternSampling::Nearest
     }
 
+    /// Switches between nearest-neighbor and smooth sampling.
+    ///
+    /// Enabling smoothing on a pattern that's already smooth keeps its current sampling mode.
     #[inline]
     pub fn set_smoothing_enabled(&mut self, enable: bool) {
-        self.flags.set(PatternFlags::NO_SMOOTHING, !enable);
+        if !enable {
+            self.sampling = PatternSampling::Nearest;
+        } else if self.sampling == PatternSampling::Nearest {
+            self.sampling = PatternSampling::Bilinear;
+        }
     }
 
     #[inline]
@@ -171,6 +209,22 @@ impl Image {
         Image::new(vec2i(width as i32, height as i32), Arc::new(pixels))
     }
 
+    /// Decodes a PNG, JPEG, GIF or WebP image, rotating and flipping it as its EXIF orientation
+    /// says. Animated images decode to their first frame.
+    ///
+    /// All color types are converted to 8-bit RGBA. None of these formats store premultiplied
+    /// alpha, so the pixels are non-premultiplied, as `Image` expects.
+    #[cfg(feature = "pf-image")]
+    pub fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+        let format = image::guess_format(bytes)?;
+        let image_buffer = image::load_from_memory_with_format(bytes, format)?.to_rgba();
+        let image_buffer = match exif_orientation(bytes, format) {
+            Some(orientation) => apply_exif_orientation(image_buffer, orientation),
+            None => image_buffer,
+        };
+        Ok(Image::from_image_buffer(image_buffer))
+    }
+
     #[inline]
     pub fn size(&self) -> Vector2I {
         self.size
@@ -221,6 +275,229 @@ impl Hash for Pattern {
     fn hash<H>(&self, state: &mut H) where H: Hasher {
         self.source.hash(state);
         util::hash_transform2f(self.transform, state);
+        self.sampling.hash(state);
         self.flags.hash(state);
     }
 }
+
+// Image decoding
+
+// Rotates and flips an image into the orientation an EXIF orientation value from 1 to 8
+// describes.
+#[cfg(feature = "pf-image")]
+fn apply_exif_orientation(image_buffer: RgbaImage, orientation: u16) -> RgbaImage {
+    match orientation {
+        2 => imageops::flip_horizontal(&image_buffer),
+        3 => imageops::rotate180(&image_buffer),
+        4 => imageops::flip_vertical(&image_buffer),
+        5 => imageops::flip_horizontal(&imageops::rotate90(&image_buffer)),
+        6 => imageops::rotate90(&image_buffer),
+        7 => imageops::flip_vertical(&imageops::rotate90(&image_buffer)),
+        8 => imageops::rotate270(&image_buffer),
+        _ => image_buffer,
+    }
+}
+
+// Finds the orientation in an image's EXIF data, if it has any.
+#[cfg(feature = "pf-image")]
+fn exif_orientation(bytes: &[u8], format: ImageFormat) -> Option<u16> {
+    let tiff = match format {
+        ImageFormat::Jpeg => jpeg_exif(bytes)?,
+        ImageFormat::Png => png_exif(bytes)?,
+        ImageFormat::WebP => webp_exif(bytes)?,
+        _ => return None,
+    };
+    tiff_orientation(tiff)
+}
+
+// Returns the TIFF structure in a JPEG's `APP1` segment, looking through the segments before the
+// image data.
+#[cfg(feature = "pf-image")]
+fn jpeg_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut offset = 2;
+    loop {
+        if *bytes.get(offset)? != 0xff {
+            return None;
+        }
+        let marker = *bytes.get(offset + 1)?;
+        // The start of scan or end of image.
+        if marker == 0xda || marker == 0xd9 {
+            return None;
+        }
+        let length = read_u16(bytes, offset + 2, true)? as usize;
+        let data = bytes.get((offset + 4)..(offset + 2 + length))?;
+        if marker == 0xe1 && data.starts_with(b"Exif\0\0") {
+            return Some(&data[6..]);
+        }
+        offset += 2 + length;
+    }
+}
+
+// Returns the TIFF structure in a PNG's `eXIf` chunk.
+#[cfg(feature = "pf-image")]
+fn png_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut offset = 8;
+    loop {
+        let length = read_u32(bytes, offset, true)? as usize;
+        let data = bytes.get((offset + 8)..(offset + 8 + length))?;
+        match bytes.get((offset + 4)..(offset + 8))? {
+            b"eXIf" => return Some(data),
+            b"IEND" => return None,
+            _ => offset += 12 + length,
+        }
+    }
+}
+
+// Returns the TIFF structure in a WebP's `EXIF` chunk. Some encoders put the JPEG `Exif` header
+// before it.
+#[cfg(feature = "pf-image")]
+fn webp_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut offset = 12;
+    loop {
+        let size = read_u32(bytes, offset + 4, false)? as usize;
+        let data = bytes.get((offset + 8)..(offset + 8 + size))?;
+        if bytes.get(offset..(offset + 4))? == b"EXIF" {
+            return Some(data.strip_prefix(b"Exif\0\0").unwrap_or(data));
+        }
+        // Chunks are padded to an even size.
+        offset += 8 + size + size % 2;
+    }
+}
+
+// Reads the orientation tag from the first IFD of EXIF data, returning `None` if it's missing or
+// out of range.
+#[cfg(feature = "pf-image")]
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(0..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+    let ifd_offset = read_u32(tiff, 4, big_endian)? as usize;
+    let entry_count = read_u16(tiff, ifd_offset, big_endian)? as usize;
+    for index in 0..entry_count {
+        let entry_offset = ifd_offset + 2 + index * 12;
+        if read_u16(tiff, entry_offset, big_endian)? == 0x0112 {
+            // The orientation is a short, which is stored at the start of the value field.
+            return match read_u16(tiff, entry_offset + 8, big_endian)? {
+                orientation @ 1..=8 => Some(orientation),
+                _ => None,
+            };
+        }
+    }
+    None
+}
+
+#[cfg(feature = "pf-image")]
+fn read_u16(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u16> {
+    let bytes = [*bytes.get(offset)?, *bytes.get(offset + 1)?];
+    Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+}
+
+#[cfg(feature = "pf-image")]
+fn read_u32(bytes: &[u8], offset: usize, big_endian: bool) -> Option<u32> {
+    let high = read_u16(bytes, offset, big_endian)? as u32;
+    let low = read_u16(bytes, offset + 2, big_endian)? as u32;
+    Some(if big_endian { (high << 16) | low } else { (low << 16) | high })
+}
+
+#[cfg(all(test, feature = "pf-image"))]
+mod test {
+    use crate::pattern::Image;
+    use image::ColorType;
+    use image::jpeg::JPEGEncoder;
+    use image::png::PNGEncoder;
+    use pathfinder_color::ColorU;
+    use pathfinder_geometry::vector::vec2i;
+
+    // Encodes RGBA pixels as a PNG, with an `eXIf` chunk holding `tiff` if it's present.
+    fn encode_png(width: u32, height: u32, pixels: &[u8], tiff: Option<&[u8]>) -> Vec<u8> {
+        let mut png = vec![];
+        PNGEncoder::new(&mut png).encode(pixels, width, height, ColorType::Rgba8).unwrap();
+        if let Some(tiff) = tiff {
+            // Put the chunk after the 8-byte signature and the 25-byte `IHDR` chunk.
+            let mut chunk = (tiff.len() as u32).to_be_bytes().to_vec();
+            chunk.extend_from_slice(b"eXIf");
+            chunk.extend_from_slice(tiff);
+            let crc = crc32(&chunk[4..]);
+            chunk.extend_from_slice(&crc.to_be_bytes());
+            png.splice(33..33, chunk);
+        }
+        png
+    }
+
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &byte in bytes {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+
+    // EXIF data with nothing but an orientation tag.
+    fn orientation_tiff(orientation: u16, big_endian: bool) -> Vec<u8> {
+        if big_endian {
+            let mut tiff = b"MM\0*\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+            tiff.extend_from_slice(&orientation.to_be_bytes());
+            tiff.extend_from_slice(&[0; 6]);
+            tiff
+        } else {
+            let mut tiff = b"II*\0\x08\0\0\0\x01\0\x12\x01\x03\0\x01\0\0\0".to_vec();
+            tiff.extend_from_slice(&orientation.to_le_bytes());
+            tiff.extend_from_slice(&[0; 6]);
+            tiff
+        }
+    }
+
+    const RED: ColorU = ColorU { r: 255, g: 0, b: 0, a: 255 };
+    const GREEN: ColorU = ColorU { r: 0, g: 255, b: 0, a: 128 };
+
+    #[test]
+    fn test_decode_png() {
+        let pixels = [255, 0, 0, 255, 0, 255, 0, 128];
+        let image = Image::decode(&encode_png(2, 1, &pixels, None)).unwrap();
+        assert_eq!(image.size(), vec2i(2, 1));
+        assert_eq!(**image.pixels(), vec![RED, GREEN]);
+        assert!(!image.is_opaque());
+
+        let mut gray_png = vec![];
+        PNGEncoder::new(&mut gray_png).encode(&[64, 200], 1, 1, ColorType::La8).unwrap();
+        let image = Image::decode(&gray_png).unwrap();
+        assert_eq!(**image.pixels(), vec![ColorU::new(64, 64, 64, 200)]);
+
+        assert!(Image::decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_decode_applies_exif_orientation() {
+        let pixels = [255, 0, 0, 255, 0, 255, 0, 128];
+        let decode = |orientation: u16, big_endian: bool| {
+            let tiff = orientation_tiff(orientation, big_endian);
+            let image = Image::decode(&encode_png(2, 1, &pixels, Some(&tiff))).unwrap();
+            (image.size(), (**image.pixels()).clone())
+        };
+
+        assert_eq!(decode(1, true), (vec2i(2, 1), vec![RED, GREEN]));
+        assert_eq!(decode(2, false), (vec2i(2, 1), vec![GREEN, RED]));
+        assert_eq!(decode(3, true), (vec2i(2, 1), vec![GREEN, RED]));
+        assert_eq!(decode(5, false), (vec2i(1, 2), vec![RED, GREEN]));
+        assert_eq!(decode(6, true), (vec2i(1, 2), vec![RED, GREEN]));
+        assert_eq!(decode(7, false), (vec2i(1, 2), vec![GREEN, RED]));
+        assert_eq!(decode(8, true), (vec2i(1, 2), vec![GREEN, RED]));
+        assert_eq!(decode(9, false), (vec2i(2, 1), vec![RED, GREEN]));
+
+        // JPEGs keep EXIF data in an `APP1` segment, which goes after the start of image marker.
+        let mut jpeg = vec![];
+        JPEGEncoder::new(&mut jpeg).encode(&[0; 6], 2, 1, ColorType::Rgb8).unwrap();
+        let tiff = orientation_tiff(8, false);
+        let mut segment = vec![0xff, 0xe1];
+        segment.extend_from_slice(&(tiff.len() as u16 + 8).to_be_bytes());
+        segment.extend_from_slice(b"Exif\0\0");
+        segment.extend_from_slice(&tiff);
+        jpeg.splice(2..2, segment);
+        assert_eq!(Image::decode(&jpeg).unwrap().size(), vec2i(1, 2));
+    }
+}
@@ -0,0 +1,442 @@
+// pathfinder/content/src/polyline.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Polylines, simplification, and fitting of curves to point data.
+
+use crate::outline::Contour;
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::util;
+use pathfinder_geometry::vector::Vector2F;
+
+const EPSILON: f32 = 0.000001;
+
+const MAX_REPARAMETERIZE_ITERATIONS: u32 = 4;
+
+/// A sequence of points connected by straight lines.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<Vector2F>,
+    pub closed: bool,
+}
+
+impl Polyline {
+    #[inline]
+    pub fn new(points: Vec<Vector2F>, closed: bool) -> Polyline {
+        Polyline { points, closed }
+    }
+
+    /// Converts this polyline to a contour made up of straight lines.
+    pub fn to_contour(&self) -> Contour {
+        let mut contour = Contour::with_capacity(self.points.len());
+        for &point in &self.points {
+            contour.push_endpoint(point);
+        }
+        if self.closed {
+            contour.close();
+        }
+        contour
+    }
+
+    /// Removes points that deviate from the resulting polyline by less than `tolerance`, using
+    /// the Ramer–Douglas–Peucker algorithm.
+    pub fn simplify(&self, tolerance: f32) -> Polyline {
+        let mut points = dedup_points(&self.points, self.closed);
+        if self.closed && points.len() > 1 {
+            points.push(points[0]);
+        }
+        if points.len() < 3 {
+            return Polyline::new(dedup_points(&self.points, self.closed), self.closed);
+        }
+
+        let mut keep = vec![false; points.len()];
+        keep[0] = true;
+        keep[points.len() - 1] = true;
+
+        // Use an explicit stack, since polylines can have hundreds of thousands of points.
+        let mut ranges = vec![(0, points.len() - 1)];
+        while let Some((first, last)) = ranges.pop() {
+            let chord = LineSegment2F::new(points[first], points[last]);
+            let mut max_square_distance = 0.0;
+            let mut max_index = first;
+            for (index, &point) in points.iter().enumerate().take(last).skip(first + 1) {
+                let square_distance = square_distance_to_line_segment(point, chord);
+                if square_distance > max_square_distance {
+                    max_square_distance = square_distance;
+                    max_index = index;
+                }
+            }
+
+            if max_square_distance > tolerance * tolerance {
+                keep[max_index] = true;
+                ranges.push((first, max_index));
+                ranges.push((max_index, last));
+            }
+        }
+
+        if self.closed {
+            points.pop();
+            keep.pop();
+        }
+
+        let points = points.into_iter()
+                           .zip(keep)
+                           .filter_map(|(point, keep)| if keep { Some(point) } else { None })
+                           .collect();
+        Polyline::new(points, self.closed)
+    }
+
+    /// Fits a sequence of cubic Bézier curves through the points of this polyline, so that no
+    /// point is farther than `tolerance` from the result.
+    ///
+    /// This follows Philip J. Schneider, "An Algorithm for Automatically Fitting Digitized
+    /// Curves", Graphics Gems, 1990.
+    pub fn fit_curves(&self, tolerance: f32) -> Contour {
+        let mut points = dedup_points(&self.points, self.closed);
+        if self.closed && points.len() > 1 {
+            points.push(points[0]);
+        }
+
+        let mut contour = Contour::new();
+        match points.len() {
+            0 => return contour,
+            1 => {
+                contour.push_endpoint(points[0]);
+                return contour;
+            }
+            _ => {}
+        }
+
+        let last = points.len() - 1;
+        let (start_tangent, end_tangent) = if self.closed && points.len() > 3 {
+            // Keep the curve smooth where it wraps around.
+            let (end_tangent, start_tangent) =
+                split_tangents(points[last - 1], points[0], points[1]);
+            (start_tangent, end_tangent)
+        } else {
+            ((points[1] - points[0]).normalize(), (points[last - 1] - points[last]).normalize())
+        };
+
+        contour.push_endpoint(points[0]);
+        let square_tolerance = tolerance * tolerance;
+
+        // Work through the ranges in order, splitting as necessary, with an explicit stack.
+        let mut ranges = vec![(0, last, start_tangent, end_tangent)];
+        while let Some((first, last, start_tangent, end_tangent)) = ranges.pop() {
+            let range = &points[first..(last + 1)];
+            let (curve, split_index) = fit_cubic(range, start_tangent, end_tangent,
+                                                 square_tolerance);
+            match split_index {
+                None => contour.push_cubic(curve[1], curve[2], curve[3]),
+                Some(split_index) => {
+                    let split_index = first + split_index;
+                    let (center_end_tangent, center_start_tangent) =
+                        split_tangents(points[split_index - 1],
+                                       points[split_index],
+                                       points[split_index + 1]);
+                    ranges.push((split_index, last, center_start_tangent, end_tangent));
+                    ranges.push((first, split_index, start_tangent, center_end_tangent));
+                }
+            }
+        }
+
+        if self.closed {
+            contour.close();
+        }
+        contour
+    }
+}
+
+// Returns the tangents at `point` of the curves that end and start there, pointing toward the
+// previous and next points respectively. The curves join smoothly unless those points coincide,
+// as at a spike, in which case they meet at a corner.
+fn split_tangents(previous: Vector2F, point: Vector2F, next: Vector2F) -> (Vector2F, Vector2F) {
+    let (to_previous, to_next) = (previous - point, next - point);
+    let tangent = previous - next;
+    if tangent.square_length() > EPSILON * (to_previous.square_length() +
+                                            to_next.square_length()) {
+        let tangent = tangent.normalize();
+        (tangent, -tangent)
+    } else {
+        (to_previous.normalize(), to_next.normalize())
+    }
+}
+
+// Fits a single cubic to the points. Returns the curve and, if it isn't good enough, the index
+// at which the points should be split.
+fn fit_cubic(points: &[Vector2F],
+             start_tangent: Vector2F,
+             end_tangent: Vector2F,
+             square_tolerance: f32)
+             -> ([Vector2F; 4], Option<usize>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if points.len() == 2 {
+        let distance = (last - first).length() / 3.0;
+        let curve = [first, first + start_tangent * distance, last + end_tangent * distance, last];
+        return (curve, None);
+    }
+
+    let mut parameters = chord_length_parameterize(points);
+    let mut curve = generate_bezier(points, &parameters, start_tangent, end_tangent);
+    let (mut max_error, mut split_index) = compute_max_error(points, &curve, &parameters);
+    if max_error <= square_tolerance {
+        return (curve, None);
+    }
+
+    // If we're close, try to improve the fit by reparameterizing before giving up and splitting.
+    if max_error <= square_tolerance * 4.0 {
+        for _ in 0..MAX_REPARAMETERIZE_ITERATIONS {
+            reparameterize(points, &mut parameters, &curve);
+            curve = generate_bezier(points, &parameters, start_tangent, end_tangent);
+            let (error, index) = compute_max_error(points, &curve, &parameters);
+            if error <= square_tolerance {
+                return (curve, None);
+            }
+            max_error = error;
+            split_index = index;
+        }
+    }
+
+    debug!("fit_cubic(): splitting at {} with error {}", split_index, max_error);
+    (curve, Some(split_index))
+}
+
+// Finds the control points by least squares, holding the tangent directions fixed. The sums are
+// accumulated in double precision, since there may be many points.
+fn generate_bezier(points: &[Vector2F],
+                   parameters: &[f32],
+                   start_tangent: Vector2F,
+                   end_tangent: Vector2F)
+                   -> [Vector2F; 4] {
+    let (first, last) = (points[0], points[points.len() - 1]);
+
+    let (mut c00, mut c01, mut c11, mut x0, mut x1) = (0.0f64, 0.0f64, 0.0f64, 0.0f64, 0.0f64);
+    for (&point, &t) in points.iter().zip(parameters.iter()) {
+        let [b0, b1, b2, b3] = bernstein(t);
+        let (a0, a1) = (start_tangent * b1, end_tangent * b2);
+        let rest = point - (first * (b0 + b1) + last * (b2 + b3));
+        c00 += a0.dot(a0) as f64;
+        c01 += a0.dot(a1) as f64;
+        c11 += a1.dot(a1) as f64;
+        x0 += a0.dot(rest) as f64;
+        x1 += a1.dot(rest) as f64;
+    }
+
+    let det_c0_c1 = c00 * c11 - c01 * c01;
+    let (mut start_alpha, mut end_alpha) = (0.0, 0.0);
+    if det_c0_c1 != 0.0 {
+        start_alpha = ((x0 * c11 - x1 * c01) / det_c0_c1) as f32;
+        end_alpha = ((c00 * x1 - c01 * x0) / det_c0_c1) as f32;
+    }
+
+    // If the least squares solution is degenerate, fall back to the Wu/Barsky heuristic.
+    let chord_length = (last - first).length();
+    let epsilon = EPSILON * chord_length;
+    if start_alpha < epsilon || end_alpha < epsilon {
+        start_alpha = chord_length / 3.0;
+        end_alpha = start_alpha;
+    }
+
+    [first, first + start_tangent * start_alpha, last + end_tangent * end_alpha, last]
+}
+
+// Improves the parameters with one step of Newton-Raphson iteration.
+fn reparameterize(points: &[Vector2F], parameters: &mut [f32], curve: &[Vector2F; 4]) {
+    let derivative = [(curve[1] - curve[0]) * 3.0,
+                      (curve[2] - curve[1]) * 3.0,
+                      (curve[3] - curve[2]) * 3.0];
+    let second_derivative = [(derivative[1] - derivative[0]) * 2.0,
+                             (derivative[2] - derivative[1]) * 2.0];
+
+    for (&point, t) in points.iter().zip(parameters.iter_mut()) {
+        let vector = evaluate_cubic(curve, *t) - point;
+        let d1 = evaluate_quadratic(&derivative, *t);
+        let d2 = second_derivative[0].lerp(second_derivative[1], *t);
+        let denominator = d1.dot(d1) + vector.dot(d2);
+        if f32::abs(denominator) > EPSILON {
+            *t -= vector.dot(d1) / denominator;
+        }
+    }
+}
+
+fn compute_max_error(points: &[Vector2F], curve: &[Vector2F; 4], parameters: &[f32])
+                     -> (f32, usize) {
+    let mut max_error = 0.0;
+    let mut split_index = points.len() / 2;
+    for index in 1..(points.len() - 1) {
+        let error = (evaluate_cubic(curve, parameters[index]) - points[index]).square_length();
+        if error >= max_error {
+            max_error = error;
+            split_index = index;
+        }
+    }
+    (max_error, split_index)
+}
+
+fn chord_length_parameterize(points: &[Vector2F]) -> Vec<f32> {
+    let mut parameters = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    parameters.push(0.0);
+    for window in points.windows(2) {
+        total += (window[1] - window[0]).length();
+        parameters.push(total);
+    }
+    for parameter in &mut parameters {
+        *parameter /= total;
+    }
+    parameters
+}
+
+#[inline]
+fn bernstein(t: f32) -> [f32; 4] {
+    let u = 1.0 - t;
+    [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t]
+}
+
+#[inline]
+fn evaluate_cubic(curve: &[Vector2F; 4], t: f32) -> Vector2F {
+    let [b0, b1, b2, b3] = bernstein(t);
+    curve[0] * b0 + curve[1] * b1 + curve[2] * b2 + curve[3] * b3
+}
+
+#[inline]
+fn evaluate_quadratic(curve: &[Vector2F; 3], t: f32) -> Vector2F {
+    let u = 1.0 - t;
+    curve[0] * (u * u) + curve[1] * (2.0 * u * t) + curve[2] * (t * t)
+}
+
+fn square_distance_to_line_segment(point: Vector2F, line_segment: LineSegment2F) -> f32 {
+    let vector = line_segment.vector();
+    if vector.is_zero() {
+        return (point - line_segment.from()).square_length();
+    }
+    let t = util::clamp(vector.projection_coefficient(point - line_segment.from()), 0.0, 1.0);
+    (point - line_segment.sample(t)).square_length()
+}
+
+fn dedup_points(points: &[Vector2F], closed: bool) -> Vec<Vector2F> {
+    let mut result: Vec<Vector2F> = Vec::with_capacity(points.len());
+    for &point in points {
+        if result.last() != Some(&point) {
+            result.push(point);
+        }
+    }
+    if closed && result.len() > 1 && result[0] == result[result.len() - 1] {
+        result.pop();
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::outline::{ContourIterFlags, Outline};
+    use crate::polyline::Polyline;
+    use pathfinder_geometry::transform2d::Transform2F;
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_simplify_collinear() {
+        let points = (0..100).map(|i| vec2f(i as f32, 2.0 * i as f32)).collect();
+        let polyline = Polyline::new(points, false).simplify(0.01);
+        assert_eq!(polyline.points, vec![vec2f(0.0, 0.0), vec2f(99.0, 198.0)]);
+    }
+
+    #[test]
+    fn test_flatten_and_fit_circle() {
+        let (center, radius) = (vec2f(100.0, 100.0), 50.0);
+        let mut outline = Outline::new();
+        let mut contour = crate::outline::Contour::new();
+        contour.push_ellipse(&Transform2F::from_scale(radius).translate(center));
+        contour.close();
+        outline.push_contour(contour);
+
+        let polylines = outline.flatten(0.05);
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].closed);
+        for &point in &polylines[0].points {
+            assert!(((point - center).length() - radius).abs() < 0.1);
+        }
+
+        let contour = polylines[0].fit_curves(0.1);
+        assert!(contour.len() < polylines[0].points.len() as u32);
+        for segment in contour.iter(ContourIterFlags::empty()) {
+            for t_index in 0..9 {
+                let point: Vector2F = segment.sample(t_index as f32 / 8.0);
+                assert!(((point - center).length() - radius).abs() < 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_dense_trace() {
+        let points: Vec<_> = (0..100_000).map(|i| {
+            let t = i as f32 / 100_000.0 * 4.0 * PI;
+            vec2f(t * 100.0, f32::sin(t) * 100.0)
+        }).collect();
+        let polyline = Polyline::new(points, false);
+        let contour = polyline.simplify(0.01).fit_curves(0.5);
+        assert!(contour.len() < 100);
+        assert_eq!(contour.position_of(0), polyline.points[0]);
+        assert_eq!(contour.last_position(), polyline.points.last().cloned());
+
+        // Every traced point is within the combined tolerances of the curves.
+        let tolerance = 0.01 + 0.5;
+        let segments: Vec<_> = contour.iter(ContourIterFlags::empty()).collect();
+        let bounds: Vec<_> =
+            segments.iter().map(|segment| segment.bounds().dilate(tolerance)).collect();
+        for &point in &polyline.points {
+            let deviation = segments.iter()
+                                    .zip(&bounds)
+                                    .filter(|&(_, bounds)| bounds.contains_point(point))
+                                    .map(|(segment, _)| {
+                                        let t = segment.nearest_point(point);
+                                        (segment.sample(t) - point).length()
+                                    })
+                                    .fold(f32::INFINITY, f32::min);
+            assert!(deviation <= tolerance, "{:?} is {} from the curves", point, deviation);
+        }
+
+        // Nor do the curves stray from the trace between those points. The trace is the graph of
+        // a function, so the vertical distance to it bounds the distance.
+        for segment in &segments {
+            for t_index in 0..65 {
+                let point = segment.sample(t_index as f32 / 64.0);
+                let deviation = (point.y() - f32::sin(point.x() / 100.0) * 100.0).abs();
+                assert!(deviation <= tolerance, "{:?} is {} from the trace", point, deviation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_back_and_forth() {
+        // The turnaround points have neighbors in the same place, so there's no tangent through
+        // them, and closing the polyline puts the start at one.
+        let points = vec![vec2f(0.0, 0.0), vec2f(10.0, 5.0), vec2f(20.0, 0.0), vec2f(10.0, 5.0)];
+        for &closed in &[false, true] {
+            let polyline = Polyline::new(points.clone(), closed);
+            let contour = polyline.fit_curves(0.1);
+            for point_index in 0..contour.len() {
+                let point = contour.position_of(point_index);
+                assert!(point.x().is_finite() && point.y().is_finite(), "{:?}", contour);
+            }
+
+            let segments: Vec<_> = contour.iter(ContourIterFlags::empty()).collect();
+            for &point in &points {
+                let deviation = segments.iter()
+                                        .map(|segment| {
+                                            let t = segment.nearest_point(point);
+                                            (segment.sample(t) - point).length()
+                                        })
+                                        .fold(f32::INFINITY, f32::min);
+                assert!(deviation <= 0.1, "{:?} is {} from the curves", point, deviation);
+            }
+        }
+    }
+}
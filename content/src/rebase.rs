@@ -0,0 +1,158 @@
+// pathfinder/content/src/rebase.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Building outlines from double-precision coordinates.
+//!
+//! Outlines store `f32` points, which resolve only about 1 unit at coordinates around 10⁷, so
+//! world-scale data (GIS, CAD) must be brought near the origin before it is converted. The
+//! builder here does that in `f64`: each point is mapped into a local frame around a chosen
+//! origin and only then rounded. To draw the result, compose the view transform with
+//! `Transform2D::from_translation(origin)` in `f64` and convert that product with
+//! `Transform2D::to_f32()`; the large translations cancel before any precision is lost.
+
+use crate::outline::{Contour, Outline};
+use pathfinder_geometry::transform2d::Transform2D;
+use pathfinder_geometry::vector::{Vector2D, Vector2F};
+use std::mem;
+
+/// Builds an `Outline` from points in `f64` world coordinates, rebased to a local frame.
+#[derive(Clone, Debug)]
+pub struct RebasedOutlineBuilder {
+    transform: Transform2D,
+    outline: Outline,
+    contour: Contour,
+}
+
+impl RebasedOutlineBuilder {
+    /// Creates a builder whose output coordinates are relative to `origin`.
+    #[inline]
+    pub fn new(origin: Vector2D) -> RebasedOutlineBuilder {
+        RebasedOutlineBuilder::with_transform(Transform2D::from_translation(-origin))
+    }
+
+    /// Creates a builder that maps world coordinates to output coordinates with `transform`.
+    ///
+    /// This is useful to apply a zoom in `f64` as well, so that the output is in device pixels.
+    #[inline]
+    pub fn with_transform(transform: Transform2D) -> RebasedOutlineBuilder {
+        RebasedOutlineBuilder { transform, outline: Outline::new(), contour: Contour::new() }
+    }
+
+    /// Returns the transform from world coordinates to output coordinates.
+    #[inline]
+    pub fn transform(&self) -> &Transform2D {
+        &self.transform
+    }
+
+    #[inline]
+    pub fn move_to(&mut self, to: Vector2D) {
+        self.flush_current_contour();
+        let to = self.rebase(to);
+        self.contour.push_endpoint(to);
+    }
+
+    #[inline]
+    pub fn line_to(&mut self, to: Vector2D) {
+        let to = self.rebase(to);
+        self.contour.push_endpoint(to);
+    }
+
+    #[inline]
+    pub fn quadratic_curve_to(&mut self, ctrl: Vector2D, to: Vector2D) {
+        let (ctrl, to) = (self.rebase(ctrl), self.rebase(to));
+        self.contour.push_quadratic(ctrl, to);
+    }
+
+    #[inline]
+    pub fn bezier_curve_to(&mut self, ctrl0: Vector2D, ctrl1: Vector2D, to: Vector2D) {
+        let (ctrl0, ctrl1, to) = (self.rebase(ctrl0), self.rebase(ctrl1), self.rebase(to));
+        self.contour.push_cubic(ctrl0, ctrl1, to);
+    }
+
+    #[inline]
+    pub fn close_path(&mut self) {
+        self.contour.close();
+    }
+
+    /// Appends a polygon or polyline given by its vertices.
+    pub fn push_polygon(&mut self, points: &[Vector2D], closed: bool) {
+        let mut points = points.iter();
+        if let Some(&first) = points.next() {
+            self.move_to(first);
+            for &point in points {
+                self.line_to(point);
+            }
+            if closed {
+                self.close_path();
+            }
+        }
+    }
+
+    pub fn into_outline(mut self) -> Outline {
+        self.flush_current_contour();
+        self.outline
+    }
+
+    #[inline]
+    fn rebase(&self, point: Vector2D) -> Vector2F {
+        (self.transform * point).to_f32()
+    }
+
+    fn flush_current_contour(&mut self) {
+        if !self.contour.is_empty() {
+            self.outline.push_contour(mem::replace(&mut self.contour, Contour::new()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rebase::RebasedOutlineBuilder;
+    use pathfinder_geometry::transform2d::Transform2D;
+    use pathfinder_geometry::vector::{vec2d, vec2f};
+
+    #[test]
+    fn test_rebase_keeps_precision() {
+        let origin = vec2d(40_075_016.0, -20_037_508.0);
+        let mut builder = RebasedOutlineBuilder::new(origin);
+        builder.push_polygon(&[origin + vec2d(0.125, 0.25),
+                               origin + vec2d(1.5, 0.25),
+                               origin + vec2d(1.5, 2.75)],
+                             true);
+        let outline = builder.into_outline();
+        assert_eq!(outline.contours().len(), 1);
+
+        let contour = &outline.contours()[0];
+        assert!(contour.is_closed());
+        assert_eq!(contour.position_of(0), vec2f(0.125, 0.25));
+        assert_eq!(contour.position_of(1), vec2f(1.5, 0.25));
+        assert_eq!(contour.position_of(2), vec2f(1.5, 2.75));
+
+        // The same points converted directly collapse together.
+        assert_eq!((origin + vec2d(0.125, 0.25)).to_f32(), (origin + vec2d(1.5, 0.25)).to_f32());
+    }
+
+    #[test]
+    fn test_rebase_with_transform() {
+        let origin = vec2d(1.0e9, 1.0e9);
+        let transform = Transform2D::from_translation(-origin).scale(vec2d(2.0, 2.0));
+        let mut builder = RebasedOutlineBuilder::with_transform(transform);
+        builder.move_to(origin + vec2d(0.5, 0.5));
+        builder.bezier_curve_to(origin + vec2d(1.0, 0.5),
+                                origin + vec2d(1.0, 1.0),
+                                origin + vec2d(0.5, 1.0));
+        builder.move_to(origin);
+        builder.line_to(origin + vec2d(0.25, 0.0));
+        let outline = builder.into_outline();
+        assert_eq!(outline.contours().len(), 2);
+        assert_eq!(outline.contours()[0].position_of(3), vec2f(1.0, 2.0));
+        assert_eq!(outline.contours()[1].position_of(1), vec2f(0.5, 0.0));
+    }
+}
@@ -10,7 +10,9 @@
 
 //! Line or curve segments, optimized with SIMD.
 
+use arrayvec::ArrayVec;
 use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::util::{self, EPSILON};
 use pathfinder_geometry::vector::{Vector2F, vec2f};
@@ -18,6 +20,20 @@ use pathfinder_simd::default::F32x4;
 use std::f32::consts::SQRT_2;
 
 const MAX_NEWTON_ITERATIONS: u32 = 32;
+const MAX_BISECTION_ITERATIONS: u32 = 32;
+
+// Polynomials are normalized before root finding, so this is relative to their largest
+// coefficient.
+const ROOT_EPSILON: f32 = 0.000001;
+
+const NEAREST_POINT_SAMPLE_COUNT: u32 = 16;
+
+// Curve-curve intersection subdivides until both pieces are smaller than this fraction of the
+// extent of the two curves.
+const INTERSECTION_TOLERANCE: f32 = 0.0001;
+const MAX_INTERSECTION_DEPTH: u32 = 32;
+// If more piece pairs than this survive a subdivision level, the curves overlap or are tangent.
+const MAX_INTERSECTION_PAIRS: usize = 1024;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Segment {
@@ -236,6 +252,243 @@ impl Segment {
         // FIXME(pcwalton)
         distance / self.arc_length()
     }
+
+    /// Returns the tight bounding box of this segment.
+    ///
+    /// Unlike the bounds of the control points, this only includes the points the curve actually
+    /// passes through.
+    pub fn bounds(&self) -> RectF {
+        if self.is_line() {
+            return RectF::from_points(self.baseline.from().min(self.baseline.to()),
+                                      self.baseline.from().max(self.baseline.to()));
+        }
+        self.to_cubic().as_cubic_segment().bounds()
+    }
+
+    /// Returns the time of the point on this segment closest to `point`.
+    pub fn nearest_point(&self, point: Vector2F) -> f32 {
+        if self.is_line() {
+            let vector = self.baseline.vector();
+            if vector.is_zero() {
+                return 0.0;
+            }
+            return util::clamp(vector.projection_coefficient(point - self.baseline.from()),
+                               0.0,
+                               1.0);
+        }
+
+        let segment = self.to_cubic();
+        let cubic = segment.as_cubic_segment();
+        let square_distance = |t: f32| (cubic.sample(t) - point).square_length();
+
+        // Sample the curve coarsely, then polish each local minimum with Newton's method applied
+        // to `(B(t) - P) ⋅ B'(t) = 0`.
+        let samples: Vec<f32> = (0..(NEAREST_POINT_SAMPLE_COUNT + 1)).map(|index| {
+            square_distance(index as f32 / NEAREST_POINT_SAMPLE_COUNT as f32)
+        }).collect();
+
+        let (mut best_t, mut best_square_distance) = (0.0, samples[0]);
+        for (index, &sample) in samples.iter().enumerate() {
+            let is_local_minimum = (index == 0 || sample <= samples[index - 1]) &&
+                (index + 1 == samples.len() || sample <= samples[index + 1]);
+            if !is_local_minimum {
+                continue;
+            }
+
+            let mut t = index as f32 / NEAREST_POINT_SAMPLE_COUNT as f32;
+            for _ in 0..MAX_NEWTON_ITERATIONS {
+                let (vector, derivative) = (cubic.sample(t) - point, cubic.derivative(t));
+                let slope = derivative.square_length() +
+                    vector.dot(cubic.second_derivative(t));
+                if slope <= 0.0 {
+                    break;
+                }
+                let next_t = util::clamp(t - vector.dot(derivative) / slope, 0.0, 1.0);
+                let converged = f32::abs(next_t - t) <= ROOT_EPSILON;
+                t = next_t;
+                if converged {
+                    break;
+                }
+            }
+
+            for &candidate_t in &[index as f32 / NEAREST_POINT_SAMPLE_COUNT as f32, t] {
+                let candidate_square_distance = square_distance(candidate_t);
+                if candidate_square_distance < best_square_distance {
+                    best_t = candidate_t;
+                    best_square_distance = candidate_square_distance;
+                }
+            }
+        }
+        best_t
+    }
+
+    /// Returns this segment's contribution to the winding number of `point`.
+    ///
+    /// This is the signed number of times the segment crosses the ray from `point` toward +x,
+    /// counting downward crossings (toward +y) as +1 and upward ones as -1. Each crossing is
+    /// found on the curve itself rather than a flattened approximation. Of the endpoints of each
+    /// piece that is monotonic in y, the one with the smaller y is included and the other
+    /// excluded, so that the contributions of connected segments add up to the winding number of
+    /// the contour.
+    pub fn winding_number(&self, point: Vector2F) -> i32 {
+        if self.is_none() {
+            return 0;
+        }
+        if self.is_line() {
+            return monotonic_winding_number(self, point);
+        }
+
+        let segment = self.to_cubic();
+        match segment.as_cubic_segment().y_extrema() {
+            (None, _) => monotonic_winding_number(&segment, point),
+            (Some(t0), None) => {
+                let (before, after) = segment.split(t0);
+                monotonic_winding_number(&before, point) + monotonic_winding_number(&after, point)
+            }
+            (Some(t0), Some(t1)) => {
+                let (before, rest) = segment.split(t0);
+                let (middle, after) = rest.split((t1 - t0) / (1.0 - t0));
+                monotonic_winding_number(&before, point) +
+                    monotonic_winding_number(&middle, point) +
+                    monotonic_winding_number(&after, point)
+            }
+        }
+    }
+
+    /// Returns the intersections of this segment with a line segment, as pairs of times
+    /// `(t on this segment, t on the line segment)`, sorted by time on this segment.
+    ///
+    /// Collinear overlapping stretches are not reported.
+    pub fn line_segment_intersections(&self, line: LineSegment2F) -> ArrayVec<[(f32, f32); 3]> {
+        let mut results = ArrayVec::new();
+        let line_vector = line.vector();
+        if line_vector.is_zero() {
+            return results;
+        }
+
+        if self.is_line() {
+            if let (Some(t), Some(line_t)) = (self.baseline.intersection_t(line),
+                                              line.intersection_t(self.baseline)) {
+                if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&line_t) {
+                    results.push((t, line_t));
+                }
+            }
+            return results;
+        }
+
+        // Project the curve onto the line's normal and find where it crosses zero.
+        let segment = self.to_cubic();
+        let cubic = segment.as_cubic_segment();
+        let (a, b, c, d) = cubic.polynomial_coefficients();
+        let d = d - line.from();
+        for t in solve_cubic_in_unit_interval(line_vector.det(a),
+                                              line_vector.det(b),
+                                              line_vector.det(c),
+                                              line_vector.det(d)) {
+            let line_t = line_vector.projection_coefficient(cubic.sample(t) - line.from());
+            if (-ROOT_EPSILON..=(1.0 + ROOT_EPSILON)).contains(&line_t) {
+                results.push((t, util::clamp(line_t, 0.0, 1.0)));
+            }
+        }
+        results
+    }
+
+    /// Returns the intersections of this segment with another, as pairs of times
+    /// `(t on this segment, t on the other segment)`, sorted by time on this segment.
+    ///
+    /// If the segments overlap along a stretch, only the endpoints of the overlap that are also
+    /// endpoints of one of the segments are reported.
+    pub fn intersections(&self, other: &Segment) -> Vec<(f32, f32)> {
+        if self.is_none() || other.is_none() {
+            return vec![];
+        }
+        if other.is_line() {
+            return self.line_segment_intersections(other.baseline).to_vec();
+        }
+        if self.is_line() {
+            return other.line_segment_intersections(self.baseline)
+                        .iter()
+                        .map(|&(other_t, t)| (t, other_t))
+                        .collect();
+        }
+
+        let (this, other) = (self.to_cubic(), other.to_cubic());
+        let extent = control_point_bounds(&this).union_rect(control_point_bounds(&other)).size();
+        let tolerance = f32::max(f32::max(extent.x(), extent.y()) * INTERSECTION_TOLERANCE,
+                                 f32::MIN_POSITIVE);
+
+        // Subdivide both curves, discarding pairs of pieces whose control point bounds are
+        // disjoint, until the pieces are small enough to be treated as lines.
+        let mut candidates = vec![];
+        let mut pairs = vec![((this, 0.0, 1.0), (other, 0.0, 1.0))];
+        for _ in 0..MAX_INTERSECTION_DEPTH {
+            let mut next_pairs = vec![];
+            for (piece_a, piece_b) in pairs.drain(..) {
+                let (bounds_a, bounds_b) = (control_point_bounds(&piece_a.0),
+                                            control_point_bounds(&piece_b.0));
+                if !bounds_a.dilate(tolerance).intersects(bounds_b) {
+                    continue;
+                }
+
+                let is_small = |bounds: RectF| {
+                    bounds.width() <= tolerance && bounds.height() <= tolerance
+                };
+                let (a_is_small, b_is_small) = (is_small(bounds_a), is_small(bounds_b));
+                if a_is_small && b_is_small {
+                    candidates.push(intersect_pieces(&this, &other, &piece_a, &piece_b));
+                    continue;
+                }
+
+                let pieces_a = if a_is_small { vec![piece_a] } else { split_piece(&piece_a) };
+                let pieces_b = if b_is_small { vec![piece_b] } else { split_piece(&piece_b) };
+                for piece_a in &pieces_a {
+                    for piece_b in &pieces_b {
+                        next_pairs.push((*piece_a, *piece_b));
+                    }
+                }
+            }
+
+            pairs = next_pairs;
+            if pairs.len() > MAX_INTERSECTION_PAIRS {
+                // Either the curves overlap, in which case the ends of the overlap touch the
+                // other curve, or they graze each other and the surviving pairs are already
+                // clustered around the contact.
+                let contacts = endpoint_contacts(&this, &other, tolerance);
+                if contacts.len() >= 2 {
+                    return contacts;
+                }
+                break;
+            }
+            if pairs.is_empty() {
+                break;
+            }
+        }
+        for (piece_a, piece_b) in &pairs {
+            candidates.push(intersect_pieces(&this, &other, piece_a, piece_b));
+        }
+
+        // Near tangential contacts many neighboring pairs survive. Merge runs of candidates
+        // between which the curves never separate, keeping the closest one.
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let square_distance = |(t, other_t): (f32, f32)| {
+            (this.as_cubic_segment().sample(t) -
+             other.as_cubic_segment().sample(other_t)).square_length()
+        };
+        let mut results: Vec<(f32, f32)> = vec![];
+        for candidate in candidates {
+            if let Some(last) = results.last_mut() {
+                let midpoint = ((last.0 + candidate.0) * 0.5, (last.1 + candidate.1) * 0.5);
+                if square_distance(midpoint) <= 4.0 * tolerance * tolerance {
+                    if square_distance(candidate) < square_distance(*last) {
+                        *last = candidate;
+                    }
+                    continue;
+                }
+            }
+            results.push(candidate);
+        }
+        results
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -423,4 +676,400 @@ impl<'s> CubicSegment<'s> {
     pub fn max_y(&self) -> f32 {
         f32::max(self.0.baseline.max_y(), self.0.ctrl.max_y())
     }
+
+    /// Returns the times at which the x coordinate of this curve reaches a local extremum, in
+    /// increasing order.
+    #[inline]
+    pub fn x_extrema(self) -> (Option<f32>, Option<f32>) {
+        let transposed = Segment::cubic(LineSegment2F(self.0.baseline.0.yxwz()),
+                                        LineSegment2F(self.0.ctrl.0.yxwz()));
+        transposed.as_cubic_segment().y_extrema()
+    }
+
+    /// Returns the tight bounding box of this curve, computed from its endpoints and extrema.
+    pub fn bounds(self) -> RectF {
+        let (from, to) = (self.0.baseline.from(), self.0.baseline.to());
+        let mut bounds = RectF::from_points(from.min(to), from.max(to));
+        let (x_extrema, y_extrema) = (self.x_extrema(), self.y_extrema());
+        for t in [x_extrema.0, x_extrema.1, y_extrema.0, y_extrema.1].iter().filter_map(|&t| t) {
+            bounds = bounds.union_point(self.sample(t));
+        }
+        bounds
+    }
+
+    /// Returns the times strictly between the endpoints at which this curve changes the
+    /// direction it turns in, in increasing order.
+    pub fn inflection_points(self) -> (Option<f32>, Option<f32>) {
+        // The curvature changes sign where B'(t) × B''(t) = 0. In terms of the power basis
+        // coefficients, this is the quadratic 3(a × b)t² + 3(a × c)t + (b × c) = 0.
+        let (a, b, c, _) = self.polynomial_coefficients();
+        let mut inflections = solve_quadratic(3.0 * a.det(b), 3.0 * a.det(c), b.det(c))
+            .into_iter()
+            .filter(|&t| t > EPSILON && t < 1.0 - EPSILON);
+        (inflections.next(), inflections.next())
+    }
+
+    /// Returns the first derivative of this curve at time `t`.
+    #[inline]
+    pub fn derivative(self, t: f32) -> Vector2F {
+        let (p0, p1) = (self.0.baseline.from(), self.0.ctrl.from());
+        let (p2, p3) = (self.0.ctrl.to(), self.0.baseline.to());
+        let u = 1.0 - t;
+        ((p1 - p0) * (u * u) + (p2 - p1) * (2.0 * u * t) + (p3 - p2) * (t * t)) * 3.0
+    }
+
+    /// Returns the second derivative of this curve at time `t`.
+    #[inline]
+    pub fn second_derivative(self, t: f32) -> Vector2F {
+        let (p0, p1) = (self.0.baseline.from(), self.0.ctrl.from());
+        let (p2, p3) = (self.0.ctrl.to(), self.0.baseline.to());
+        ((p2 - p1 - p1 + p0) * (1.0 - t) + (p3 - p2 - p2 + p1) * t) * 6.0
+    }
+
+    // Returns `(a, b, c, d)` such that the curve is `at³ + bt² + ct + d`.
+    fn polynomial_coefficients(self) -> (Vector2F, Vector2F, Vector2F, Vector2F) {
+        let (p0, p1) = (self.0.baseline.from(), self.0.ctrl.from());
+        let (p2, p3) = (self.0.ctrl.to(), self.0.baseline.to());
+        (p3 - p0 + (p1 - p2) * 3.0, (p0 - p1 - p1 + p2) * 3.0, (p1 - p0) * 3.0, p0)
+    }
+}
+
+// A piece of a cubic curve together with the range of times it covers on the original.
+type CurvePiece = (Segment, f32, f32);
+
+fn control_point_bounds(segment: &Segment) -> RectF {
+    let cubic = segment.as_cubic_segment();
+    RectF::from_points(vec2f(cubic.min_x(), cubic.min_y()), vec2f(cubic.max_x(), cubic.max_y()))
+}
+
+fn split_piece(piece: &CurvePiece) -> Vec<CurvePiece> {
+    let (before, after) = piece.0.as_cubic_segment().split(0.5);
+    let mid_t = (piece.1 + piece.2) * 0.5;
+    vec![(before, piece.1, mid_t), (after, mid_t, piece.2)]
+}
+
+// Intersects the chords of two small pieces and polishes the result against the original
+// curves with Newton's method.
+fn intersect_pieces(a: &Segment, b: &Segment, piece_a: &CurvePiece, piece_b: &CurvePiece)
+                    -> (f32, f32) {
+    let local_t = |chord: LineSegment2F, other: LineSegment2F| {
+        util::clamp(chord.intersection_t(other).unwrap_or(0.5), 0.0, 1.0)
+    };
+    let (chord_a, chord_b) = (piece_a.0.baseline, piece_b.0.baseline);
+    let mut t = util::lerp(piece_a.1, piece_a.2, local_t(chord_a, chord_b));
+    let mut other_t = util::lerp(piece_b.1, piece_b.2, local_t(chord_b, chord_a));
+
+    let (a, b) = (a.as_cubic_segment(), b.as_cubic_segment());
+    let mut delta = a.sample(t) - b.sample(other_t);
+    for _ in 0..MAX_NEWTON_ITERATIONS {
+        let (derivative_a, derivative_b) = (a.derivative(t), b.derivative(other_t));
+        let det = derivative_a.det(derivative_b);
+        if f32::abs(det) <= ROOT_EPSILON * derivative_a.length() * derivative_b.length() {
+            break;
+        }
+
+        let next_t = t - delta.det(derivative_b) / det;
+        let next_other_t = other_t + derivative_a.det(delta) / det;
+        if !(0.0..=1.0).contains(&next_t) || !(0.0..=1.0).contains(&next_other_t) {
+            break;
+        }
+        let next_delta = a.sample(next_t) - b.sample(next_other_t);
+        if next_delta.square_length() >= delta.square_length() {
+            break;
+        }
+        t = next_t;
+        other_t = next_other_t;
+        delta = next_delta;
+    }
+    (t, other_t)
+}
+
+// Reports where the endpoints of each curve lie on the other. Used for overlapping curves.
+fn endpoint_contacts(a: &Segment, b: &Segment, tolerance: f32) -> Vec<(f32, f32)> {
+    let mut contacts = vec![];
+    for &t in &[0.0, 1.0] {
+        contacts.push((t, b.nearest_point(a.sample(t))));
+    }
+    for &other_t in &[0.0, 1.0] {
+        contacts.push((a.nearest_point(b.sample(other_t)), other_t));
+    }
+
+    let mut results: Vec<(f32, f32)> = vec![];
+    for (t, other_t) in contacts {
+        if (a.sample(t) - b.sample(other_t)).square_length() > tolerance * tolerance {
+            continue;
+        }
+        if results.iter().all(|&(result_t, result_other_t)| {
+            f32::abs(result_t - t) > EPSILON || f32::abs(result_other_t - other_t) > EPSILON
+        }) {
+            results.push((t, other_t));
+        }
+    }
+    results.sort_by(|a, b| a.0.total_cmp(&b.0));
+    results
+}
+
+// Returns the real roots of `at² + bt + c`, in increasing order.
+fn solve_quadratic(a: f32, b: f32, c: f32) -> ArrayVec<[f32; 2]> {
+    let mut roots = ArrayVec::new();
+    let scale = f32::max(f32::abs(a), f32::max(f32::abs(b), f32::abs(c)));
+    if scale == 0.0 {
+        return roots;
+    }
+    let (a, b, c) = (a / scale, b / scale, c / scale);
+
+    if f32::abs(a) <= ROOT_EPSILON {
+        if f32::abs(b) > ROOT_EPSILON {
+            roots.push(-c / b);
+        }
+        return roots;
+    }
+
+    let discrim = b * b - 4.0 * a * c;
+    if discrim < 0.0 {
+        return roots;
+    }
+
+    // Avoid catastrophic cancellation; see Numerical Recipes § 5.6.
+    let q = -0.5 * (b + f32::sqrt(discrim).copysign(b));
+    if q == 0.0 {
+        roots.push(0.0);
+        return roots;
+    }
+    let (t0, t1) = (q / a, c / q);
+    roots.push(f32::min(t0, t1));
+    roots.push(f32::max(t0, t1));
+    roots
+}
+
+// Computes `Segment::winding_number()` for a line, or a cubic that is monotonic in y.
+fn monotonic_winding_number(segment: &Segment, point: Vector2F) -> i32 {
+    let (from_y, to_y) = (segment.baseline.from_y(), segment.baseline.to_y());
+    let direction = if from_y < to_y {
+        1
+    } else if from_y > to_y {
+        -1
+    } else {
+        return 0;
+    };
+    if point.y() < f32::min(from_y, to_y) || point.y() >= f32::max(from_y, to_y) {
+        return 0;
+    }
+
+    // Skip finding the crossing if the control polygon is entirely to one side of the point.
+    let (min_x, max_x) = if segment.is_line() {
+        (segment.baseline.min_x(), segment.baseline.max_x())
+    } else {
+        let cubic = segment.as_cubic_segment();
+        (cubic.min_x(), cubic.max_x())
+    };
+    if point.x() >= max_x {
+        return 0;
+    }
+    if point.x() < min_x {
+        return direction;
+    }
+
+    // y is monotonic in t, so bisect for the time at which the curve reaches the ray.
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..MAX_BISECTION_ITERATIONS {
+        let mid = (lo + hi) * 0.5;
+        if (segment.sample(mid).y() < point.y()) == (direction > 0) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    if segment.sample((lo + hi) * 0.5).x() > point.x() {
+        direction
+    } else {
+        0
+    }
+}
+
+// Returns the roots of `at³ + bt² + ct + d` in [0, 1], in increasing order.
+//
+// The interval is split into monotonic pieces at the critical points, and each piece that
+// changes sign is bisected. Unlike the closed-form solution, this is well-behaved for any
+// combination of degenerate coefficients.
+fn solve_cubic_in_unit_interval(a: f32, b: f32, c: f32, d: f32) -> ArrayVec<[f32; 3]> {
+    let mut roots: ArrayVec<[f32; 3]> = ArrayVec::new();
+    let scale = f32::max(f32::max(f32::abs(a), f32::abs(b)), f32::max(f32::abs(c), f32::abs(d)));
+    if scale == 0.0 {
+        return roots;
+    }
+    let (a, b, c, d) = (a / scale, b / scale, c / scale, d / scale);
+    let evaluate = |t: f32| ((a * t + b) * t + c) * t + d;
+
+    let mut knots: ArrayVec<[f32; 4]> = ArrayVec::new();
+    knots.push(0.0);
+    knots.extend(solve_quadratic(3.0 * a, 2.0 * b, c).into_iter().filter(|&t| t > 0.0 && t < 1.0));
+    knots.push(1.0);
+
+    for window in knots.windows(2) {
+        let (mut lo, mut hi) = (window[0], window[1]);
+        let (mut lo_value, hi_value) = (evaluate(lo), evaluate(hi));
+        let root = if f32::abs(lo_value) <= ROOT_EPSILON {
+            lo
+        } else if f32::abs(hi_value) <= ROOT_EPSILON {
+            hi
+        } else if (lo_value < 0.0) == (hi_value < 0.0) {
+            continue;
+        } else {
+            for _ in 0..MAX_BISECTION_ITERATIONS {
+                let mid = (lo + hi) * 0.5;
+                let mid_value = evaluate(mid);
+                if (mid_value < 0.0) == (lo_value < 0.0) {
+                    lo = mid;
+                    lo_value = mid_value;
+                } else {
+                    hi = mid;
+                }
+            }
+            (lo + hi) * 0.5
+        };
+
+        let is_new = match roots.last() {
+            None => true,
+            Some(&last) => root - last > ROOT_EPSILON,
+        };
+        if is_new && !roots.is_full() {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod test {
+    use crate::segment::Segment;
+    use pathfinder_geometry::line_segment::LineSegment2F;
+    use pathfinder_geometry::vector::{Vector2F, vec2f};
+
+    fn arch(from: Vector2F, ctrl0: Vector2F, ctrl1: Vector2F, to: Vector2F) -> Segment {
+        Segment::cubic(LineSegment2F::new(from, to), LineSegment2F::new(ctrl0, ctrl1))
+    }
+
+    fn assert_close(a: Vector2F, b: Vector2F) {
+        assert!((a - b).length() < 0.001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_tight_bounds() {
+        let segment = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        let bounds = segment.bounds();
+        assert_close(bounds.origin(), vec2f(0.0, 0.0));
+        assert_close(bounds.lower_right(), vec2f(10.0, 7.5));
+
+        let segment = arch(vec2f(0.0, 0.0), vec2f(10.0, 0.0), vec2f(10.0, 10.0), vec2f(0.0, 10.0));
+        assert_close(segment.bounds().lower_right(), vec2f(7.5, 10.0));
+        assert_eq!(segment.as_cubic_segment().x_extrema(), (Some(0.5), None));
+    }
+
+    #[test]
+    fn test_inflection_points() {
+        let segment = arch(vec2f(0.0, 0.0), vec2f(1.0, 1.0), vec2f(2.0, -1.0), vec2f(3.0, 0.0));
+        let (t0, t1) = segment.as_cubic_segment().inflection_points();
+        assert!((t0.unwrap() - 0.5).abs() < 0.0001);
+        assert_eq!(t1, None);
+
+        let segment = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        assert_eq!(segment.as_cubic_segment().inflection_points(), (None, None));
+    }
+
+    #[test]
+    fn test_nearest_point() {
+        let segment = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        assert!((segment.nearest_point(vec2f(5.0, 20.0)) - 0.5).abs() < 0.0001);
+        assert_eq!(segment.nearest_point(vec2f(-5.0, -5.0)), 0.0);
+        assert_eq!(segment.nearest_point(vec2f(15.0, -5.0)), 1.0);
+
+        let line = Segment::line(LineSegment2F::new(vec2f(0.0, 0.0), vec2f(10.0, 0.0)));
+        assert_eq!(line.nearest_point(vec2f(2.5, 3.0)), 0.25);
+    }
+
+    #[test]
+    fn test_winding_number() {
+        // The arch rises to y = 7.5 and comes back down, so a ray crosses it zero or two times,
+        // unless it starts between the two sides.
+        let segment = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        assert_eq!(segment.winding_number(vec2f(-1.0, 5.0)), 0);
+        assert_eq!(segment.winding_number(vec2f(5.0, 5.0)), -1);
+        assert_eq!(segment.winding_number(vec2f(5.0, 7.4)), -1);
+        assert_eq!(segment.winding_number(vec2f(5.0, 7.6)), 0);
+        assert_eq!(segment.winding_number(vec2f(11.0, 5.0)), 0);
+
+        // Near the curve, the answer comes from the curve, not its control polygon.
+        let inside = segment.sample(0.25) + vec2f(0.01, 0.0);
+        let outside = segment.sample(0.25) - vec2f(0.01, 0.0);
+        assert_eq!(segment.winding_number(inside), -1);
+        assert_eq!(segment.winding_number(outside), 0);
+
+        let line = Segment::line(LineSegment2F::new(vec2f(0.0, 10.0), vec2f(10.0, 0.0)));
+        assert_eq!(line.winding_number(vec2f(2.0, 2.0)), -1);
+        assert_eq!(line.winding_number(vec2f(8.0, 8.0)), 0);
+        assert_eq!(line.winding_number(vec2f(-1.0, 0.0)), -1);
+        assert_eq!(line.winding_number(vec2f(-1.0, 10.0)), 0);
+    }
+
+    #[test]
+    fn test_line_segment_intersections() {
+        let segment = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        let line = LineSegment2F::new(vec2f(-1.0, 5.0), vec2f(11.0, 5.0));
+        let intersections = segment.line_segment_intersections(line);
+        assert_eq!(intersections.len(), 2);
+        assert!(intersections[0].0 < intersections[1].0);
+        for &(t, line_t) in &intersections {
+            assert_close(segment.sample(t), line.sample(line_t));
+            assert!((segment.sample(t).y() - 5.0).abs() < 0.001);
+        }
+
+        // Tangent to the top of the arch.
+        let line = LineSegment2F::new(vec2f(0.0, 7.5), vec2f(10.0, 7.5));
+        let intersections = segment.line_segment_intersections(line);
+        assert_eq!(intersections.len(), 1);
+        assert_close(segment.sample(intersections[0].0), vec2f(5.0, 7.5));
+
+        let line = LineSegment2F::new(vec2f(0.0, 8.0), vec2f(10.0, 8.0));
+        assert!(segment.line_segment_intersections(line).is_empty());
+    }
+
+    #[test]
+    fn test_curve_intersections() {
+        let a = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        let b = arch(vec2f(0.0, 8.0), vec2f(0.0, -2.0), vec2f(10.0, -2.0), vec2f(10.0, 8.0));
+        let intersections = a.intersections(&b);
+        assert_eq!(intersections.len(), 2);
+        for &(t, other_t) in &intersections {
+            assert_close(a.sample(t), b.sample(other_t));
+        }
+        assert!((a.sample(intersections[0].0).x() + a.sample(intersections[1].0).x() - 10.0)
+                    .abs() < 0.001);
+
+        // Tangential contact at the top of the arch.
+        let c = arch(vec2f(0.0, 15.0), vec2f(0.0, 5.0), vec2f(10.0, 5.0), vec2f(10.0, 15.0));
+        let intersections = a.intersections(&c);
+        assert_eq!(intersections.len(), 1);
+        assert!((a.sample(intersections[0].0) - vec2f(5.0, 7.5)).length() < 0.01);
+
+        // A curve overlapping part of itself reports the ends of the overlap.
+        let half = a.as_cubic_segment().split_after(0.5);
+        let intersections = a.intersections(&half);
+        assert_eq!(intersections.len(), 2);
+        assert_close(a.sample(intersections[0].0), vec2f(5.0, 7.5));
+        assert_close(a.sample(intersections[1].0), vec2f(10.0, 0.0));
+    }
+
+    #[test]
+    fn test_intersections_with_nan() {
+        // Paths built from user data can have NaN coordinates, which mustn't panic.
+        let a = arch(vec2f(0.0, 0.0), vec2f(0.0, 10.0), vec2f(10.0, 10.0), vec2f(10.0, 0.0));
+        let nan = vec2f(f32::NAN, f32::NAN);
+        let b = arch(nan, nan, nan, nan);
+        a.intersections(&b);
+        b.intersections(&a);
+        let c = arch(vec2f(0.0, 8.0), vec2f(0.0, f32::NAN), vec2f(10.0, -2.0), vec2f(10.0, 8.0));
+        a.intersections(&c);
+        c.intersections(&a);
+    }
 }
@@ -0,0 +1,577 @@
+// pathfinder/content/src/svg_path.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing and serialization of SVG path data (the `d` attribute).
+//!
+//! See: https://www.w3.org/TR/SVG11/paths.html#PathData
+
+use crate::outline::{ArcDirection, Contour, ContourIterFlags, Outline};
+use crate::segment::SegmentKind;
+use pathfinder_geometry::vector::{Vector2F, vec2f};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write};
+use std::mem;
+
+/// An error encountered while parsing SVG path data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the path data at which the error occurred.
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    /// The path data didn't begin with a move-to command.
+    MissingMoveTo,
+    /// A number was expected but not found.
+    ExpectedNumber,
+    /// An elliptical arc flag (`0` or `1`) was expected but not found.
+    ExpectedFlag,
+    /// A character that is neither a command nor part of a number was found.
+    UnexpectedCharacter(char),
+}
+
+impl Outline {
+    /// Parses SVG path data, as found in the `d` attribute of an SVG `<path>` element.
+    pub fn from_svg_path_data(data: &str) -> Result<Outline, ParseError> {
+        let mut parser = PathDataParser::new(data);
+        match parser.parse() {
+            Ok(()) => Ok(parser.into_outline()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Parses SVG path data, keeping everything up to the first error, if any.
+    ///
+    /// This is the error handling that SVG rendering and the HTML `Path2D` constructor use.
+    pub fn from_svg_path_data_lenient(data: &str) -> Outline {
+        let mut parser = PathDataParser::new(data);
+        if let Err(error) = parser.parse() {
+            debug!("from_svg_path_data_lenient(): {}", error);
+        }
+        parser.into_outline()
+    }
+
+    /// Serializes this outline as SVG path data, with as many digits as needed to round-trip
+    /// every coordinate exactly.
+    pub fn to_svg_path_data(&self) -> String {
+        let mut data = String::new();
+        self.write_svg_path_data(&mut data, None).unwrap();
+        data
+    }
+
+    /// Serializes this outline as SVG path data, with at most `precision` digits after the
+    /// decimal point.
+    pub fn to_svg_path_data_with_precision(&self, precision: usize) -> String {
+        let mut data = String::new();
+        self.write_svg_path_data(&mut data, Some(precision)).unwrap();
+        data
+    }
+
+    fn write_svg_path_data<W>(&self, writer: &mut W, precision: Option<usize>) -> fmt::Result
+                              where W: Write {
+        let mut first = true;
+        for contour in &self.contours {
+            if contour.is_empty() {
+                continue;
+            }
+            if !first {
+                writer.write_char(' ')?;
+            }
+            first = false;
+
+            writer.write_char('M')?;
+            write_point(writer, contour.position_of(0), precision)?;
+
+            for segment in contour.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT) {
+                match segment.kind {
+                    SegmentKind::None => {}
+                    SegmentKind::Line => {
+                        writer.write_str(" L")?;
+                        write_point(writer, segment.baseline.to(), precision)?;
+                    }
+                    SegmentKind::Quadratic => {
+                        writer.write_str(" Q")?;
+                        write_point(writer, segment.ctrl.from(), precision)?;
+                        write_point(writer, segment.baseline.to(), precision)?;
+                    }
+                    SegmentKind::Cubic => {
+                        writer.write_str(" C")?;
+                        write_point(writer, segment.ctrl.from(), precision)?;
+                        write_point(writer, segment.ctrl.to(), precision)?;
+                        write_point(writer, segment.baseline.to(), precision)?;
+                    }
+                }
+            }
+
+            if contour.is_closed() {
+                writer.write_str(" z")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_point<W>(writer: &mut W, point: Vector2F, precision: Option<usize>) -> fmt::Result
+                  where W: Write {
+    writer.write_char(' ')?;
+    write_number(writer, point.x(), precision)?;
+    writer.write_char(' ')?;
+    write_number(writer, point.y(), precision)
+}
+
+fn write_number<W>(writer: &mut W, value: f32, precision: Option<usize>) -> fmt::Result
+                   where W: Write {
+    let mut string = match precision {
+        None => format!("{}", value),
+        Some(precision) => format!("{:.*}", precision, value),
+    };
+    if string.contains('.') {
+        while string.ends_with('0') {
+            string.pop();
+        }
+        if string.ends_with('.') {
+            string.pop();
+        }
+    }
+    if string == "-0" {
+        string = "0".to_owned();
+    }
+    writer.write_str(&string)
+}
+
+struct PathDataParser<'a> {
+    data: &'a [u8],
+    position: usize,
+
+    outline: Outline,
+    contour: Contour,
+    current_point: Vector2F,
+    subpath_start: Vector2F,
+    // The second control point of the last cubic or the control point of the last quadratic, for
+    // the smooth curve commands.
+    last_cubic_ctrl: Option<Vector2F>,
+    last_quadratic_ctrl: Option<Vector2F>,
+}
+
+impl<'a> PathDataParser<'a> {
+    fn new(data: &'a str) -> PathDataParser<'a> {
+        PathDataParser {
+            data: data.as_bytes(),
+            position: 0,
+            outline: Outline::new(),
+            contour: Contour::new(),
+            current_point: Vector2F::zero(),
+            subpath_start: Vector2F::zero(),
+            last_cubic_ctrl: None,
+            last_quadratic_ctrl: None,
+        }
+    }
+
+    fn into_outline(mut self) -> Outline {
+        self.flush_contour();
+        self.outline
+    }
+
+    fn parse(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.position == self.data.len() {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(b'M') | Some(b'm') => {}
+            _ => return Err(self.error(ParseErrorKind::MissingMoveTo)),
+        }
+
+        let mut command = b'M';
+        loop {
+            self.skip_whitespace();
+            let byte = match self.peek() {
+                None => return Ok(()),
+                Some(byte) => byte,
+            };
+
+            if is_command(byte) {
+                self.position += 1;
+                command = byte;
+
+                // Separators only go between arguments, not before the first one.
+                self.skip_whitespace();
+                if self.peek() == Some(b',') {
+                    return Err(self.error(ParseErrorKind::UnexpectedCharacter(',')));
+                }
+            } else if !self.at_number() || command == b'Z' || command == b'z' {
+                self.skip_comma_whitespace();
+                return Err(self.error(ParseErrorKind::UnexpectedCharacter(self.peek_char())));
+            } else if command == b'M' {
+                // Extra coordinate pairs after a move-to are implicit line-tos.
+                command = b'L';
+            } else if command == b'm' {
+                command = b'l';
+            }
+
+            self.parse_command(command)?;
+        }
+    }
+
+    fn parse_command(&mut self, command: u8) -> Result<(), ParseError> {
+        let relative = command.is_ascii_lowercase();
+        let origin = if relative { self.current_point } else { Vector2F::zero() };
+
+        let (mut cubic_ctrl, mut quadratic_ctrl) = (None, None);
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let to = origin + self.parse_point()?;
+                self.flush_contour();
+                self.contour.push_endpoint(to);
+                self.current_point = to;
+                self.subpath_start = to;
+            }
+            b'Z' => {
+                self.start_contour_if_necessary();
+                self.contour.close();
+                self.flush_contour();
+                self.current_point = self.subpath_start;
+            }
+            b'L' => {
+                let to = origin + self.parse_point()?;
+                self.start_contour_if_necessary();
+                self.contour.push_endpoint(to);
+                self.current_point = to;
+            }
+            b'H' => {
+                let to = vec2f(origin.x() + self.parse_number()?, self.current_point.y());
+                self.start_contour_if_necessary();
+                self.contour.push_endpoint(to);
+                self.current_point = to;
+            }
+            b'V' => {
+                let to = vec2f(self.current_point.x(), origin.y() + self.parse_number()?);
+                self.start_contour_if_necessary();
+                self.contour.push_endpoint(to);
+                self.current_point = to;
+            }
+            b'C' => {
+                let ctrl0 = origin + self.parse_point()?;
+                let ctrl1 = origin + self.parse_point()?;
+                let to = origin + self.parse_point()?;
+                self.push_cubic(ctrl0, ctrl1, to);
+                cubic_ctrl = Some(ctrl1);
+            }
+            b'S' => {
+                let ctrl0 = self.reflect(self.last_cubic_ctrl);
+                let ctrl1 = origin + self.parse_point()?;
+                let to = origin + self.parse_point()?;
+                self.push_cubic(ctrl0, ctrl1, to);
+                cubic_ctrl = Some(ctrl1);
+            }
+            b'Q' => {
+                let ctrl = origin + self.parse_point()?;
+                let to = origin + self.parse_point()?;
+                self.push_quadratic(ctrl, to);
+                quadratic_ctrl = Some(ctrl);
+            }
+            b'T' => {
+                let ctrl = self.reflect(self.last_quadratic_ctrl);
+                let to = origin + self.parse_point()?;
+                self.push_quadratic(ctrl, to);
+                quadratic_ctrl = Some(ctrl);
+            }
+            b'A' => {
+                let radii = self.parse_point()?;
+                let x_axis_rotation = self.parse_number()?;
+                let large_arc = self.parse_flag()?;
+                let sweep = self.parse_flag()?;
+                let to = origin + self.parse_point()?;
+                self.start_contour_if_necessary();
+                let direction = if sweep { ArcDirection::CW } else { ArcDirection::CCW };
+                self.contour.push_svg_arc(radii,
+                                          x_axis_rotation.to_radians(),
+                                          large_arc,
+                                          direction,
+                                          to);
+                self.current_point = to;
+            }
+            _ => unreachable!(),
+        }
+
+        self.last_cubic_ctrl = cubic_ctrl;
+        self.last_quadratic_ctrl = quadratic_ctrl;
+        Ok(())
+    }
+
+    fn push_cubic(&mut self, ctrl0: Vector2F, ctrl1: Vector2F, to: Vector2F) {
+        self.start_contour_if_necessary();
+        self.contour.push_cubic(ctrl0, ctrl1, to);
+        self.current_point = to;
+    }
+
+    fn push_quadratic(&mut self, ctrl: Vector2F, to: Vector2F) {
+        self.start_contour_if_necessary();
+        self.contour.push_quadratic(ctrl, to);
+        self.current_point = to;
+    }
+
+    // Reflects the previous control point about the current point, or returns the current point
+    // if the previous command wasn't a curve of the same kind.
+    fn reflect(&self, ctrl: Option<Vector2F>) -> Vector2F {
+        match ctrl {
+            None => self.current_point,
+            Some(ctrl) => self.current_point + (self.current_point - ctrl),
+        }
+    }
+
+    // After a close-path, drawing commands implicitly start a new subpath at the same point.
+    fn start_contour_if_necessary(&mut self) {
+        if self.contour.is_empty() {
+            self.contour.push_endpoint(self.current_point);
+        }
+    }
+
+    fn flush_contour(&mut self) {
+        if !self.contour.is_empty() {
+            self.outline.push_contour(mem::replace(&mut self.contour, Contour::new()));
+        }
+    }
+
+    fn parse_point(&mut self) -> Result<Vector2F, ParseError> {
+        let x = self.parse_number()?;
+        let y = self.parse_number()?;
+        Ok(vec2f(x, y))
+    }
+
+    fn parse_number(&mut self) -> Result<f32, ParseError> {
+        self.skip_comma_whitespace();
+        let start = self.position;
+
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.position += 1;
+        }
+        let integer_digits = self.skip_digits();
+        let mut fraction_digits = 0;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            fraction_digits = self.skip_digits();
+        }
+        if integer_digits == 0 && fraction_digits == 0 {
+            self.position = start;
+            return Err(self.error(ParseErrorKind::ExpectedNumber));
+        }
+
+        // Only consume the exponent if it's well-formed.
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.position = mantissa_end;
+            }
+        }
+
+        let string = std::str::from_utf8(&self.data[start..self.position]).unwrap();
+        match string.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.position = start;
+                Err(self.error(ParseErrorKind::ExpectedNumber))
+            }
+        }
+    }
+
+    fn parse_flag(&mut self) -> Result<bool, ParseError> {
+        self.skip_comma_whitespace();
+        match self.peek() {
+            Some(b'0') => {
+                self.position += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err(self.error(ParseErrorKind::ExpectedFlag)),
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.position;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') | Some(b'\x0c') =
+                self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comma_whitespace(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.position += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    // Looks past a separator for the start of a number, leaving it for `parse_number()`.
+    fn at_number(&mut self) -> bool {
+        let start = self.position;
+        self.skip_comma_whitespace();
+        let at_number =
+            matches!(self.peek(), Some(b'0'..=b'9') | Some(b'.') | Some(b'+') | Some(b'-'));
+        self.position = start;
+        at_number
+    }
+
+    #[inline]
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).cloned()
+    }
+
+    fn peek_char(&self) -> char {
+        let string = std::str::from_utf8(&self.data[self.position..]).unwrap_or("");
+        string.chars().next().unwrap_or('\u{fffd}')
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError { position: self.position, kind }
+    }
+}
+
+fn is_command(byte: u8) -> bool {
+    matches!(byte.to_ascii_uppercase(), b'M' | b'Z' | b'L' | b'H' | b'V' | b'C' | b'S' | b'Q' |
+                                         b'T' | b'A')
+}
+
+impl Display for ParseError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::MissingMoveTo => {
+                write!(formatter, "path data must begin with a move-to command")?
+            }
+            ParseErrorKind::ExpectedNumber => write!(formatter, "expected a number")?,
+            ParseErrorKind::ExpectedFlag => write!(formatter, "expected an arc flag")?,
+            ParseErrorKind::UnexpectedCharacter(character) => {
+                write!(formatter, "unexpected character {:?}", character)?
+            }
+        }
+        write!(formatter, " at offset {}", self.position)
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+    use crate::outline::{ContourIterFlags, Outline};
+    use crate::svg_path::{ParseError, ParseErrorKind};
+    use pathfinder_geometry::vector::vec2f;
+
+    #[test]
+    fn test_parse_absolute_and_relative() {
+        let outline = Outline::from_svg_path_data("M10,20 l5-5 h10 v10 H10 V20 z m1 1 L3 4").unwrap();
+        assert_eq!(outline.to_svg_path_data(),
+                   "M 10 20 L 15 15 L 25 15 L 25 25 L 10 25 L 10 20 z M 11 21 L 3 4");
+    }
+
+    #[test]
+    fn test_parse_implicit_commands_and_compact_numbers() {
+        let outline = Outline::from_svg_path_data("m0 0 1 1 .5.5-1e1-2E-1").unwrap();
+        assert_eq!(outline.to_svg_path_data(), "M 0 0 L 1 1 L 1.5 1.5 L -8.5 1.3");
+    }
+
+    #[test]
+    fn test_parse_smooth_curves() {
+        let outline = Outline::from_svg_path_data("M0 0 C0 10 10 10 10 0 S20 -10 20 0 \
+                                                   Q25 5 30 0 T40 0").unwrap();
+        assert_eq!(outline.to_svg_path_data(),
+                   "M 0 0 C 0 10 10 10 10 0 C 10 -10 20 -10 20 0 Q 25 5 30 0 Q 35 -5 40 0");
+
+        // Smooth commands not following a curve of the same kind use the current point.
+        let outline = Outline::from_svg_path_data("M0 0 Q5 5 10 0 S20 10 20 0").unwrap();
+        assert_eq!(outline.to_svg_path_data(), "M 0 0 Q 5 5 10 0 C 10 0 20 10 20 0");
+    }
+
+    #[test]
+    fn test_parse_arcs() {
+        // A semicircle of radius 10 centered on the origin, passing through (0, 10).
+        let outline = Outline::from_svg_path_data("M-10 0 A10 10 0 0 0 10 0").unwrap();
+        let bounds = outline.bounds();
+        assert!((bounds.max_y() - 10.0).abs() < 0.5 && bounds.min_y().abs() < 0.001);
+        for contour in outline.contours() {
+            for segment in contour.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT) {
+                for t_index in 0..9 {
+                    let point = segment.sample(t_index as f32 / 8.0);
+                    assert!((point.length() - 10.0).abs() < 0.01);
+                    assert!(point.y() >= -0.001);
+                }
+            }
+        }
+
+        // Radii too small to reach the endpoint are scaled up.
+        let outline = Outline::from_svg_path_data("M0 0 a1 1 0 0 1 20 0").unwrap();
+        assert_eq!(outline.contours()[0].last_position(), Some(vec2f(20.0, 0.0)));
+        assert!((outline.bounds().min_y() + 10.0).abs() < 0.01);
+
+        // Packed flags.
+        assert!(Outline::from_svg_path_data("M0 0a5 5 0 1110 0").is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Outline::from_svg_path_data("L 1 2").err(),
+                   Some(ParseError { position: 0, kind: ParseErrorKind::MissingMoveTo }));
+        assert_eq!(Outline::from_svg_path_data("M 1 2 L 3").err(),
+                   Some(ParseError { position: 9, kind: ParseErrorKind::ExpectedNumber }));
+        assert_eq!(Outline::from_svg_path_data("M 1 2 x").err(),
+                   Some(ParseError {
+                       position: 6,
+                       kind: ParseErrorKind::UnexpectedCharacter('x'),
+                   }));
+
+        // A separator can't come before the first argument of a command, or twice in a row.
+        assert_eq!(Outline::from_svg_path_data("M,10 10").err(),
+                   Some(ParseError {
+                       position: 1,
+                       kind: ParseErrorKind::UnexpectedCharacter(','),
+                   }));
+        assert_eq!(Outline::from_svg_path_data("M 1 2 L , 3 4").err(),
+                   Some(ParseError {
+                       position: 8,
+                       kind: ParseErrorKind::UnexpectedCharacter(','),
+                   }));
+        assert_eq!(Outline::from_svg_path_data("M 1 2,,3 4").err(),
+                   Some(ParseError {
+                       position: 6,
+                       kind: ParseErrorKind::UnexpectedCharacter(','),
+                   }));
+        assert_eq!(Outline::from_svg_path_data("M 1,,2").err(),
+                   Some(ParseError { position: 4, kind: ParseErrorKind::ExpectedNumber }));
+        assert_eq!(Outline::from_svg_path_data("M 1,2, 3,4 L5 6").unwrap().to_svg_path_data(),
+                   "M 1 2 L 3 4 L 5 6");
+
+        let outline = Outline::from_svg_path_data_lenient("M 1 2 L 3 4 L 5");
+        assert_eq!(outline.to_svg_path_data(), "M 1 2 L 3 4");
+    }
+
+    #[test]
+    fn test_serialize_with_precision() {
+        let outline = Outline::from_svg_path_data("M0.123456 -0.0001 L1 2.5").unwrap();
+        assert_eq!(outline.to_svg_path_data_with_precision(2), "M 0.12 0 L 1 2.5");
+        let outline = Outline::from_svg_path_data(&outline.to_svg_path_data()).unwrap();
+        assert_eq!(outline.to_svg_path_data(), "M 0.123456 -0.0001 L 1 2.5");
+    }
+}
@@ -219,6 +219,11 @@ impl<W> DemoApp<W> where W: Window {
         // Clear dirty flag.
         self.dirty = false;
 
+        // Pick up edits to shaders.
+        if !self.renderer.reload_changed_shaders(self.window.resource_loader()).is_empty() {
+            self.dirty = true;
+        }
+
         // Handle events.
         let ui_events = self.handle_events(events);
 
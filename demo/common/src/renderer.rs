@@ -14,9 +14,9 @@ use crate::camera::{Camera, Mode};
 use crate::window::{View, Window};
 use crate::{BackgroundColor, DemoApp, UIVisibility};
 use image::ColorType;
-use pathfinder_color::{ColorF, ColorU};
+use pathfinder_color::{self as color, ColorF, ColorU};
 use pathfinder_gpu::{ClearOps, DepthFunc, DepthState, Device, Primitive, RenderOptions};
-use pathfinder_gpu::{RenderState, RenderTarget, TextureData, TextureFormat, UniformData};
+use pathfinder_gpu::{RenderState, TextureFormat, UniformData};
 use pathfinder_geometry::rect::RectI;
 use pathfinder_geometry::transform3d::Transform4F;
 use pathfinder_geometry::vector::{Vector2I, Vector4F};
@@ -271,16 +271,13 @@ impl<W> DemoApp<W> where W: Window {
 
     pub fn take_raster_screenshot(&mut self, path: PathBuf) {
         let drawable_size = self.window_size.device_size();
-        let viewport = RectI::new(Vector2I::default(), drawable_size);
-        let texture_data_receiver =
-            self.renderer.device.read_pixels(&RenderTarget::Default, viewport);
-        let pixels = match self.renderer.device.recv_texture_data(&texture_data_receiver) {
-            TextureData::U8(pixels) => pixels,
-            _ => panic!("Unexpected pixel format for default framebuffer!"),
-        };
+        let dest_framebuffer = DestFramebuffer::full_window(drawable_size);
+        let old_dest_framebuffer = self.renderer.replace_dest_framebuffer(dest_framebuffer);
+        let image = self.renderer.read_back(RectI::new(Vector2I::default(), drawable_size));
+        self.renderer.replace_dest_framebuffer(old_dest_framebuffer);
         image::save_buffer(
             path,
-            &pixels,
+            color::color_slice_to_u8_slice(image.pixels()),
             drawable_size.x() as u32,
             drawable_size.y() as u32,
             ColorType::Rgba8,
@@ -213,7 +213,7 @@ impl WindowImpl {
 
                 event_pump = SDL_CONTEXT.with(|sdl_context| sdl_context.event_pump().unwrap());
 
-                let resource_loader = FilesystemResourceLoader::locate();
+                let resource_loader = FilesystemResourceLoader::locate().watching();
 
                 let open_svg_message_type = unsafe { sdl_event.register_event().unwrap() };
 
@@ -254,7 +254,7 @@ impl WindowImpl {
 
                 let event_pump = SDL_CONTEXT.with(|sdl_context| sdl_context.event_pump().unwrap());
 
-                let resource_loader = FilesystemResourceLoader::locate();
+                let resource_loader = FilesystemResourceLoader::locate().watching();
 
                 let open_svg_message_type = unsafe { sdl_event.register_event().unwrap() };
 
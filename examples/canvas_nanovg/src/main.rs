@@ -11,7 +11,6 @@
 use arrayvec::ArrayVec;
 use font_kit::handle::Handle;
 use font_kit::sources::mem::MemSource;
-use image;
 use pathfinder_canvas::{Canvas, CanvasFontContext, CanvasRenderingContext2D, LineJoin, Path2D};
 use pathfinder_canvas::{TextAlign, TextBaseline};
 use pathfinder_color::{ColorF, ColorU, rgbau, rgbf, rgbu};
@@ -1453,8 +1452,7 @@ struct DemoData {
 impl DemoData {
     fn load(resources: &dyn ResourceLoader) -> DemoData {
         let data = resources.slurp("textures/example-nanovg.png").unwrap();
-        let image = image::load_from_memory(&data).unwrap().to_rgba();
-        let image = Image::from_image_buffer(image);
+        let image = Image::decode(&data).unwrap();
         DemoData { image }
     }
 }
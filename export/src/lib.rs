@@ -58,7 +58,7 @@ fn export_svg<W: Write>(scene: &Scene, writer: &mut W) -> io::Result<()> {
         if !name.is_empty() {
             write!(writer, " id=\"{}\"", name)?;
         }
-        writeln!(writer, " fill=\"{:?}\" d=\"{:?}\" />", paint, outline)?;
+        writeln!(writer, " fill=\"{:?}\" d=\"{}\" />", paint, outline.to_svg_path_data())?;
     }
     writeln!(writer, "</svg>")?;
     Ok(())
@@ -10,7 +10,7 @@
 
 //! 2D axis-aligned rectangles, optimized with SIMD.
 
-use crate::vector::{IntoVector2F, Vector2F, Vector2I};
+use crate::vector::{IntoVector2F, Vector2D, Vector2F, Vector2I};
 use pathfinder_simd::default::{F32x4, I32x4};
 use std::ops::{Add, Mul, Sub};
 
@@ -188,6 +188,11 @@ impl RectF {
     pub fn to_i32(&self) -> RectI {
         RectI(self.0.to_i32x4())
     }
+
+    #[inline]
+    pub fn to_f64(&self) -> RectD {
+        RectD::from_points(self.origin().to_f64(), self.lower_right().to_f64())
+    }
 }
 
 impl Add<Vector2F> for RectF {
@@ -369,3 +374,151 @@ impl RectI {
         RectF(self.0.to_f32x4())
     }
 }
+
+/// A rectangle with 64-bit floating point coordinates, for world-scale data.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct RectD {
+    origin: Vector2D,
+    lower_right: Vector2D,
+}
+
+impl RectD {
+    #[inline]
+    pub fn new(origin: Vector2D, size: Vector2D) -> RectD {
+        RectD { origin, lower_right: origin + size }
+    }
+
+    #[inline]
+    pub fn from_points(origin: Vector2D, lower_right: Vector2D) -> RectD {
+        RectD { origin, lower_right }
+    }
+
+    // Accessors
+
+    #[inline]
+    pub fn origin(self) -> Vector2D {
+        self.origin
+    }
+
+    #[inline]
+    pub fn size(self) -> Vector2D {
+        self.lower_right - self.origin
+    }
+
+    #[inline]
+    pub fn width(self) -> f64 {
+        self.lower_right.x() - self.origin.x()
+    }
+
+    #[inline]
+    pub fn height(self) -> f64 {
+        self.lower_right.y() - self.origin.y()
+    }
+
+    #[inline]
+    pub fn upper_right(self) -> Vector2D {
+        Vector2D::new(self.lower_right.x(), self.origin.y())
+    }
+
+    #[inline]
+    pub fn lower_left(self) -> Vector2D {
+        Vector2D::new(self.origin.x(), self.lower_right.y())
+    }
+
+    #[inline]
+    pub fn lower_right(self) -> Vector2D {
+        self.lower_right
+    }
+
+    #[inline]
+    pub fn min_x(self) -> f64 {
+        self.origin.x()
+    }
+
+    #[inline]
+    pub fn min_y(self) -> f64 {
+        self.origin.y()
+    }
+
+    #[inline]
+    pub fn max_x(self) -> f64 {
+        self.lower_right.x()
+    }
+
+    #[inline]
+    pub fn max_y(self) -> f64 {
+        self.lower_right.y()
+    }
+
+    #[inline]
+    pub fn center(self) -> Vector2D {
+        self.origin + self.size() * 0.5
+    }
+
+    #[inline]
+    pub fn contains_point(self, point: Vector2D) -> bool {
+        self.origin.x() <= point.x() && point.x() <= self.lower_right.x() &&
+            self.origin.y() <= point.y() && point.y() <= self.lower_right.y()
+    }
+
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.origin == self.lower_right
+    }
+
+    #[inline]
+    pub fn union_point(self, point: Vector2D) -> RectD {
+        RectD::from_points(self.origin.min(point), self.lower_right.max(point))
+    }
+
+    #[inline]
+    pub fn union_rect(self, other: RectD) -> RectD {
+        RectD::from_points(self.origin.min(other.origin),
+                           self.lower_right.max(other.lower_right))
+    }
+
+    #[inline]
+    pub fn intersects(self, other: RectD) -> bool {
+        self.origin.x() < other.lower_right.x() && other.origin.x() < self.lower_right.x() &&
+            self.origin.y() < other.lower_right.y() && other.origin.y() < self.lower_right.y()
+    }
+
+    #[inline]
+    pub fn intersection(self, other: RectD) -> Option<RectD> {
+        if !self.intersects(other) {
+            None
+        } else {
+            Some(RectD::from_points(self.origin.max(other.origin),
+                                    self.lower_right.min(other.lower_right)))
+        }
+    }
+
+    #[inline]
+    pub fn dilate(self, amount: Vector2D) -> RectD {
+        RectD::from_points(self.origin - amount, self.lower_right + amount)
+    }
+
+    /// Converts this rectangle to single precision.
+    ///
+    /// To keep precision, subtract a nearby origin first.
+    #[inline]
+    pub fn to_f32(self) -> RectF {
+        RectF::from_points(self.origin.to_f32(), self.lower_right.to_f32())
+    }
+}
+
+impl Add<Vector2D> for RectD {
+    type Output = RectD;
+    #[inline]
+    fn add(self, other: Vector2D) -> RectD {
+        RectD::from_points(self.origin + other, self.lower_right + other)
+    }
+}
+
+impl Sub<Vector2D> for RectD {
+    type Output = RectD;
+    #[inline]
+    fn sub(self, other: Vector2D) -> RectD {
+        RectD::from_points(self.origin - other, self.lower_right - other)
+    }
+}
@@ -11,11 +11,12 @@
 //! 2D affine transforms.
 
 use crate::line_segment::LineSegment2F;
-use crate::rect::RectF;
+use crate::rect::{RectD, RectF};
 use crate::transform3d::Transform4F;
 use crate::unit_vector::UnitVector;
-use crate::vector::{IntoVector2F, Vector2F, vec2f};
+use crate::vector::{IntoVector2F, Vector2D, Vector2F, vec2d, vec2f};
 use pathfinder_simd::default::F32x4;
+use std::f32::consts::PI;
 use std::ops::{Mul, MulAssign, Sub};
 
 /// A 2x2 matrix, optimized with SIMD, in column-major order.
@@ -296,6 +297,110 @@ impl Transform2F {
         let vector_inv = -(matrix_inv * self.vector);
         Transform2F { matrix: matrix_inv, vector: vector_inv }
     }
+
+    /// Splits this transform into a translation, rotation, horizontal skew, and scale, applied in
+    /// the reverse of that order.
+    ///
+    /// Reflections are folded into the scale, choosing the axis that keeps the rotation angle
+    /// smallest, as CSS does.
+    pub fn decompose(&self) -> DecomposedTransform2F {
+        let (column_0, column_1) = (Vector2F(self.matrix.0.xy()), Vector2F(self.matrix.0.zw()));
+        let det = self.matrix.det();
+
+        let mut scale_x = column_0.length();
+        if det < 0.0 && self.m11() < self.m22() {
+            scale_x = -scale_x;
+        }
+
+        // The rotated x axis. If the x axis collapses, fall back to the perpendicular of the y
+        // axis, so that the skew is zero.
+        let x_axis = if scale_x != 0.0 {
+            column_0 / scale_x
+        } else if !column_1.is_zero() {
+            vec2f(column_1.y(), -column_1.x()) / column_1.length()
+        } else {
+            vec2f(1.0, 0.0)
+        };
+
+        let scale_y = x_axis.det(column_1);
+        let skew = if scale_y != 0.0 { x_axis.dot(column_1) / scale_y } else { 0.0 };
+
+        DecomposedTransform2F {
+            translation: self.vector,
+            rotation: f32::atan2(x_axis.y(), x_axis.x()),
+            skew,
+            scale: vec2f(scale_x, scale_y),
+        }
+    }
+
+    /// Interpolates between this transform and `other` by decomposing both, following the CSS
+    /// Transforms rules for 2D matrices. When `t` is 0.0, this transform is returned.
+    ///
+    /// Unlike interpolating the matrix entries, this never introduces shear or shrinking
+    /// partway through a rotation.
+    #[inline]
+    pub fn interpolate(&self, other: &Transform2F, t: f32) -> Transform2F {
+        self.decompose().lerp(&other.decompose(), t).to_transform()
+    }
+
+    #[inline]
+    pub fn to_f64(&self) -> Transform2D {
+        Transform2D::row_major(self.m11() as f64, self.m12() as f64, self.m13() as f64,
+                               self.m21() as f64, self.m22() as f64, self.m23() as f64)
+    }
+}
+
+/// The components of an affine transform, as returned by `Transform2F::decompose()`.
+///
+/// The transform is `translate(translation) rotate(rotation) skewX(atan(skew)) scale(scale)` in
+/// CSS notation: the scale is applied first and the translation last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecomposedTransform2F {
+    pub translation: Vector2F,
+    /// The rotation angle, in radians.
+    pub rotation: f32,
+    /// The horizontal shear factor: the amount added to x per unit of y.
+    pub skew: f32,
+    pub scale: Vector2F,
+}
+
+impl DecomposedTransform2F {
+    /// Recombines the components into a transform.
+    #[inline]
+    pub fn to_transform(&self) -> Transform2F {
+        let skew = Matrix2x2F::row_major(1.0, self.skew, 0.0, 1.0);
+        let matrix = Matrix2x2F::from_rotation(self.rotation) * skew *
+            Matrix2x2F::from_scale(self.scale);
+        Transform2F { matrix, vector: self.translation }
+    }
+
+    /// Interpolates the components of two decomposed transforms, rotating the short way around.
+    pub fn lerp(&self, other: &DecomposedTransform2F, t: f32) -> DecomposedTransform2F {
+        let (mut scale_a, mut rotation_a) = (self.scale, self.rotation);
+        let (scale_b, mut rotation_b) = (other.scale, other.rotation);
+
+        // A reflection in x interpolated against one in y would pass through a zero scale.
+        // Negating both scales is a half turn, so express the first transform that way instead.
+        if (scale_a.x() < 0.0 && scale_b.y() < 0.0) || (scale_a.y() < 0.0 && scale_b.x() < 0.0) {
+            scale_a = -scale_a;
+            rotation_a += if rotation_a < 0.0 { PI } else { -PI };
+        }
+
+        if f32::abs(rotation_a - rotation_b) > PI {
+            if rotation_a > rotation_b {
+                rotation_a -= 2.0 * PI;
+            } else {
+                rotation_b -= 2.0 * PI;
+            }
+        }
+
+        DecomposedTransform2F {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: rotation_a + (rotation_b - rotation_a) * t,
+            skew: self.skew + (other.skew - self.skew) * t,
+            scale: scale_a.lerp(scale_b, t),
+        }
+    }
 }
 
 impl Mul<Transform2F> for Transform2F {
@@ -343,3 +448,224 @@ impl MulAssign for Transform2F {
         *self = *self * other
     }
 }
+
+/// An affine transform with 64-bit floating point components, for world-scale data.
+///
+/// Compose view transforms in this type, then convert the result to a `Transform2F` once the
+/// large translations have cancelled out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform2D {
+    // Row-major: [m11, m12, m21, m22].
+    matrix: [f64; 4],
+    pub vector: Vector2D,
+}
+
+impl Default for Transform2D {
+    #[inline]
+    fn default() -> Transform2D {
+        Self::from_scale(vec2d(1.0, 1.0))
+    }
+}
+
+impl Transform2D {
+    #[inline]
+    pub fn from_scale(scale: Vector2D) -> Transform2D {
+        Transform2D::row_major(scale.x(), 0.0, 0.0, 0.0, scale.y(), 0.0)
+    }
+
+    #[inline]
+    pub fn from_rotation(theta: f64) -> Transform2D {
+        let (sin, cos) = theta.sin_cos();
+        Transform2D::row_major(cos, -sin, 0.0, sin, cos, 0.0)
+    }
+
+    #[inline]
+    pub fn from_translation(vector: Vector2D) -> Transform2D {
+        Transform2D { vector, ..Transform2D::default() }
+    }
+
+    #[inline]
+    pub fn row_major(m11: f64, m12: f64, m13: f64, m21: f64, m22: f64, m23: f64) -> Transform2D {
+        Transform2D { matrix: [m11, m12, m21, m22], vector: vec2d(m13, m23) }
+    }
+
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        *self == Transform2D::default()
+    }
+
+    #[inline]
+    pub fn m11(&self) -> f64 {
+        self.matrix[0]
+    }
+    #[inline]
+    pub fn m12(&self) -> f64 {
+        self.matrix[1]
+    }
+    #[inline]
+    pub fn m21(&self) -> f64 {
+        self.matrix[2]
+    }
+    #[inline]
+    pub fn m22(&self) -> f64 {
+        self.matrix[3]
+    }
+    #[inline]
+    pub fn m13(&self) -> f64 {
+        self.vector.x()
+    }
+    #[inline]
+    pub fn m23(&self) -> f64 {
+        self.vector.y()
+    }
+
+    #[inline]
+    pub fn det(&self) -> f64 {
+        self.m11() * self.m22() - self.m12() * self.m21()
+    }
+
+    #[inline]
+    pub fn translate(&self, vector: Vector2D) -> Transform2D {
+        Transform2D::from_translation(vector) * *self
+    }
+
+    #[inline]
+    pub fn rotate(&self, theta: f64) -> Transform2D {
+        Transform2D::from_rotation(theta) * *self
+    }
+
+    #[inline]
+    pub fn scale(&self, scale: Vector2D) -> Transform2D {
+        Transform2D::from_scale(scale) * *self
+    }
+
+    #[inline]
+    pub fn inverse(&self) -> Transform2D {
+        let det_recip = 1.0 / self.det();
+        let matrix = [
+            self.m22() * det_recip,
+            -self.m12() * det_recip,
+            -self.m21() * det_recip,
+            self.m11() * det_recip,
+        ];
+        let linear = Transform2D { matrix, vector: Vector2D::zero() };
+        Transform2D { matrix, vector: -(linear * self.vector) }
+    }
+
+    /// Converts this transform to single precision.
+    ///
+    /// The translation is rounded to `f32`, so this should be done after large translations have
+    /// been cancelled out, for example by `Transform2D::from_translation(origin)` for the origin
+    /// that outlines were rebased to.
+    #[inline]
+    pub fn to_f32(&self) -> Transform2F {
+        Transform2F::row_major(self.m11() as f32, self.m12() as f32, self.m13() as f32,
+                               self.m21() as f32, self.m22() as f32, self.m23() as f32)
+    }
+}
+
+impl Mul<Transform2D> for Transform2D {
+    type Output = Transform2D;
+    #[inline]
+    fn mul(self, other: Transform2D) -> Transform2D {
+        Transform2D::row_major(self.m11() * other.m11() + self.m12() * other.m21(),
+                               self.m11() * other.m12() + self.m12() * other.m22(),
+                               self.m11() * other.m13() + self.m12() * other.m23() + self.m13(),
+                               self.m21() * other.m11() + self.m22() * other.m21(),
+                               self.m21() * other.m12() + self.m22() * other.m22(),
+                               self.m21() * other.m13() + self.m22() * other.m23() + self.m23())
+    }
+}
+
+impl Mul<Vector2D> for Transform2D {
+    type Output = Vector2D;
+    #[inline]
+    fn mul(self, vector: Vector2D) -> Vector2D {
+        vec2d(self.m11() * vector.x() + self.m12() * vector.y() + self.m13(),
+              self.m21() * vector.x() + self.m22() * vector.y() + self.m23())
+    }
+}
+
+impl Mul<RectD> for Transform2D {
+    type Output = RectD;
+    #[inline]
+    fn mul(self, rect: RectD) -> RectD {
+        let (upper_left, upper_right) = (self * rect.origin(),     self * rect.upper_right());
+        let (lower_left, lower_right) = (self * rect.lower_left(), self * rect.lower_right());
+        let min_point = upper_left.min(upper_right).min(lower_left).min(lower_right);
+        let max_point = upper_left.max(upper_right).max(lower_left).max(lower_right);
+        RectD::from_points(min_point, max_point)
+    }
+}
+
+impl MulAssign for Transform2D {
+    #[inline]
+    fn mul_assign(&mut self, other: Transform2D) {
+        *self = *self * other
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transform2d::{Transform2D, Transform2F};
+    use crate::vector::{Vector2F, vec2d, vec2f};
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    fn assert_transforms_close(a: &Transform2F, b: &Transform2F) {
+        for &point in &[vec2f(0.0, 0.0), vec2f(1.0, 0.0), vec2f(0.0, 1.0)] {
+            assert!((*a * point - *b * point).length() < 0.0001, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_decompose_round_trip() {
+        let transforms = [
+            Transform2F::from_scale(vec2f(2.0, 3.0)).rotate(0.5).translate(vec2f(4.0, -1.0)),
+            Transform2F::row_major(1.0, 0.5, 3.0, 0.0, 1.0, 2.0),
+            Transform2F::from_scale(vec2f(-1.0, 1.0)),
+            Transform2F::from_scale(vec2f(1.0, -2.0)).rotate(2.0),
+            Transform2F::row_major(0.0, 1.0, 0.0, 0.0, 1.0, 0.0),
+        ];
+        for transform in &transforms {
+            assert_transforms_close(&transform.decompose().to_transform(), transform);
+        }
+
+        let decomposed = Transform2F::from_scale(vec2f(-1.0, 1.0)).decompose();
+        assert_eq!(decomposed.rotation, 0.0);
+        assert_eq!(decomposed.scale, vec2f(-1.0, 1.0));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        // Halfway through a quarter turn is an eighth turn, not a shrunken diagonal matrix.
+        let (a, b) = (Transform2F::default(), Transform2F::from_rotation(FRAC_PI_2));
+        assert_transforms_close(&a.interpolate(&b, 0.5), &Transform2F::from_rotation(FRAC_PI_4));
+        assert_transforms_close(&a.interpolate(&b, 0.0), &a);
+        assert_transforms_close(&a.interpolate(&b, 1.0), &b);
+
+        // Rotate the short way around.
+        let (a, b) = (Transform2F::from_rotation(PI - 0.1), Transform2F::from_rotation(-PI + 0.1));
+        assert_transforms_close(&a.interpolate(&b, 0.5), &Transform2F::from_rotation(PI));
+
+        let a = Transform2F::from_translation(vec2f(10.0, 0.0));
+        let b = Transform2F::from_scale(3.0).translate(vec2f(0.0, 10.0));
+        let halfway = a.interpolate(&b, 0.5);
+        assert_transforms_close(&halfway,
+                                &Transform2F::from_scale(2.0).translate(Vector2F::splat(5.0)));
+    }
+
+    #[test]
+    fn test_f64_rebase() {
+        // A view of a point far from the origin. Composing in f64 and converting only the
+        // rebased transform keeps the fractional part that f32 alone would lose.
+        let origin = vec2d(12_345_678.0, -8_765_432.0);
+        let view = Transform2D::from_translation(-origin - vec2d(0.25, 0.5)).scale(vec2d(4.0, 4.0));
+        let rebased = (view * Transform2D::from_translation(origin)).to_f32();
+        assert_eq!(rebased * vec2f(0.75, 1.0), vec2f(2.0, 2.0));
+
+        let point = vec2d(12_345_678.75, -8_765_431.0);
+        assert_eq!((view * point).to_f32(), vec2f(2.0, 2.0));
+        assert_eq!(view.inverse() * (view * point), point);
+        assert_eq!(Transform2F::from_scale(2.0).to_f64().to_f32(), Transform2F::from_scale(2.0));
+    }
+}
@@ -16,6 +16,9 @@ use crate::vector::{Vector2F, Vector2I, Vector3F, Vector4F};
 use pathfinder_simd::default::F32x4;
 use std::ops::{Add, Mul, MulAssign, Neg};
 
+// Below this, quaternions are treated as parallel and interpolated linearly.
+const SLERP_EPSILON: f32 = 0.0001;
+
 /// An transform, optimized with SIMD.
 ///
 /// In column-major order.
@@ -178,6 +181,9 @@ impl Transform4F {
     }
 
     /// Linearly interpolate between transforms
+    ///
+    /// This interpolates the matrix entries, which distorts rotations. See `interpolate()` for
+    /// an interpolation that respects them.
     pub fn lerp(&self, weight: f32, other: &Transform4F) -> Transform4F {
         let c0 = self.c0 * F32x4::splat(weight) + other.c0 * F32x4::splat(1.0 - weight);
         let c1 = self.c1 * F32x4::splat(weight) + other.c1 * F32x4::splat(1.0 - weight);
@@ -318,6 +324,189 @@ impl Transform4F {
     pub fn to_columns(&self) -> [F32x4; 4] {
         [self.c0, self.c1, self.c2, self.c3]
     }
+
+    /// Splits this transform into perspective, translation, rotation, skew, and scale, applied
+    /// in the reverse of that order, as described in the CSS Transforms Level 2 specification.
+    ///
+    /// Returns `None` if the transform is singular.
+    pub fn decompose(&self) -> Option<DecomposedTransform4F> {
+        let w = self.c3[3];
+        if w == 0.0 {
+            return None;
+        }
+        let normalize = F32x4::splat(1.0 / w);
+        let columns = [self.c0 * normalize, self.c1 * normalize, self.c2 * normalize];
+        let xyz = |column: F32x4| Vector3F::new(column[0], column[1], column[2]);
+        let (c0, c1, c2) = (xyz(columns[0]), xyz(columns[1]), xyz(columns[2]));
+        let translation = xyz(self.c3 * normalize);
+
+        let det = c0.dot(c1.cross(c2));
+        if det == 0.0 {
+            return None;
+        }
+
+        // Isolate perspective. The bottom row is `perspective * [A t; 0 1]`, where A is the
+        // upper 3x3 submatrix, so solve against the rows of A⁻¹.
+        let bottom_row = Vector4F::new(columns[0][3], columns[1][3], columns[2][3], 1.0);
+        let perspective = if bottom_row.x() == 0.0 && bottom_row.y() == 0.0 &&
+                bottom_row.z() == 0.0 {
+            Vector4F::new(0.0, 0.0, 0.0, 1.0)
+        } else {
+            let inverse_rows = [c1.cross(c2), c2.cross(c0), c0.cross(c1)];
+            let perspective = (inverse_rows[0].scale(bottom_row.x()) +
+                               inverse_rows[1].scale(bottom_row.y()) +
+                               inverse_rows[2].scale(bottom_row.z())).scale(1.0 / det);
+            Vector4F::new(perspective.x(),
+                          perspective.y(),
+                          perspective.z(),
+                          1.0 - perspective.dot(translation))
+        };
+
+        // Gram-Schmidt orthonormalize the columns of A to extract scale and skew.
+        let mut scale_x = c0.length();
+        let mut axis_x = c0.scale(1.0 / scale_x);
+        let mut skew_xy = axis_x.dot(c1);
+        let c1 = c1 - axis_x.scale(skew_xy);
+        let mut scale_y = c1.length();
+        let mut axis_y = c1.scale(1.0 / scale_y);
+        skew_xy /= scale_y;
+
+        let (mut skew_xz, mut skew_yz) = (axis_x.dot(c2), axis_y.dot(c2));
+        let c2 = c2 - axis_x.scale(skew_xz) - axis_y.scale(skew_yz);
+        let mut scale_z = c2.length();
+        let mut axis_z = c2.scale(1.0 / scale_z);
+        skew_xz /= scale_z;
+        skew_yz /= scale_z;
+
+        // Fold a reflection into the scale so that the remaining matrix is a rotation.
+        if det < 0.0 {
+            scale_x = -scale_x;
+            scale_y = -scale_y;
+            scale_z = -scale_z;
+            axis_x = -axis_x;
+            axis_y = -axis_y;
+            axis_z = -axis_z;
+        }
+
+        // Convert the rotation matrix to a quaternion, with the real part nonnegative. Solving
+        // for the largest component first keeps this stable near half turns.
+        let (m00, m10, m20) = (axis_x.x(), axis_x.y(), axis_x.z());
+        let (m01, m11, m21) = (axis_y.x(), axis_y.y(), axis_y.z());
+        let (m02, m12, m22) = (axis_z.x(), axis_z.y(), axis_z.z());
+        let trace = m00 + m11 + m22;
+        let mut rotation = if trace > 0.0 {
+            let s = 2.0 * f32::sqrt(1.0 + trace);
+            F32x4::new((m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s, 0.25 * s)
+        } else if m00 > m11 && m00 > m22 {
+            let s = 2.0 * f32::sqrt(1.0 + m00 - m11 - m22);
+            F32x4::new(0.25 * s, (m01 + m10) / s, (m02 + m20) / s, (m21 - m12) / s)
+        } else if m11 > m22 {
+            let s = 2.0 * f32::sqrt(1.0 + m11 - m00 - m22);
+            F32x4::new((m01 + m10) / s, 0.25 * s, (m12 + m21) / s, (m02 - m20) / s)
+        } else {
+            let s = 2.0 * f32::sqrt(1.0 + m22 - m00 - m11);
+            F32x4::new((m02 + m20) / s, (m12 + m21) / s, 0.25 * s, (m10 - m01) / s)
+        };
+        if rotation[3] < 0.0 {
+            rotation = -rotation;
+        }
+
+        Some(DecomposedTransform4F {
+            perspective,
+            translation,
+            rotation,
+            skew: Vector3F::new(skew_xy, skew_xz, skew_yz),
+            scale: Vector3F::new(scale_x, scale_y, scale_z),
+        })
+    }
+
+    /// Interpolates between this transform and `other` by decomposing both and spherically
+    /// interpolating the rotations. When `t` is 0.0, this transform is returned.
+    ///
+    /// As in CSS, if either transform is singular, this switches from one to the other halfway
+    /// through.
+    pub fn interpolate(&self, other: &Transform4F, t: f32) -> Transform4F {
+        match (self.decompose(), other.decompose()) {
+            (Some(this), Some(other)) => this.lerp(&other, t).to_transform(),
+            _ if t < 0.5 => *self,
+            _ => *other,
+        }
+    }
+}
+
+/// The components of a 3D transform, as returned by `Transform4F::decompose()`.
+///
+/// The transform is the product `perspective * translation * rotation * skew * scale`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecomposedTransform4F {
+    /// The bottom row of the perspective matrix.
+    pub perspective: Vector4F,
+    pub translation: Vector3F,
+    /// A unit quaternion, packed as in `Transform4F::from_rotation_quaternion()`.
+    pub rotation: F32x4,
+    /// The xy, xz, and yz shear factors.
+    pub skew: Vector3F,
+    pub scale: Vector3F,
+}
+
+impl DecomposedTransform4F {
+    /// Recombines the components into a transform.
+    pub fn to_transform(&self) -> Transform4F {
+        let perspective = Transform4F::row_major(1.0, 0.0, 0.0, 0.0,
+                                                 0.0, 1.0, 0.0, 0.0,
+                                                 0.0, 0.0, 1.0, 0.0,
+                                                 self.perspective.x(),
+                                                 self.perspective.y(),
+                                                 self.perspective.z(),
+                                                 self.perspective.w());
+        let (skew_xy, skew_xz, skew_yz) = (self.skew.x(), self.skew.y(), self.skew.z());
+        let skew = Transform4F::row_major(1.0, skew_xy, skew_xz, 0.0,
+                                          0.0, 1.0,     skew_yz, 0.0,
+                                          0.0, 0.0,     1.0,     0.0,
+                                          0.0, 0.0,     0.0,     1.0);
+        perspective *
+            Transform4F::from_translation(self.translation.to_4d()) *
+            Transform4F::from_rotation_quaternion(self.rotation) *
+            skew *
+            Transform4F::from_scale(self.scale.to_4d())
+    }
+
+    /// Interpolates the components of two decomposed transforms, spherically interpolating the
+    /// rotations along the shorter arc.
+    pub fn lerp(&self, other: &DecomposedTransform4F, t: f32) -> DecomposedTransform4F {
+        DecomposedTransform4F {
+            perspective: self.perspective.lerp(other.perspective, t),
+            translation: self.translation.lerp(other.translation, t),
+            rotation: slerp_quaternion(self.rotation, other.rotation, t),
+            skew: self.skew.lerp(other.skew, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+fn slerp_quaternion(a: F32x4, mut b: F32x4, t: f32) -> F32x4 {
+    let products = a * b;
+    let mut cos_theta = products[0] + products[1] + products[2] + products[3];
+
+    // `b` and `-b` are the same rotation; pick the one closer to `a`.
+    if cos_theta < 0.0 {
+        b = -b;
+        cos_theta = -cos_theta;
+    }
+
+    let (weight_a, weight_b) = if cos_theta > 1.0 - SLERP_EPSILON {
+        (1.0 - t, t)
+    } else {
+        let theta = f32::acos(cos_theta);
+        let sin_theta_recip = 1.0 / f32::sin(theta);
+        (f32::sin((1.0 - t) * theta) * sin_theta_recip, f32::sin(t * theta) * sin_theta_recip)
+    };
+
+    let result = a * F32x4::splat(weight_a) + b * F32x4::splat(weight_b);
+    let square_lengths = result * result;
+    let length = f32::sqrt(square_lengths[0] + square_lengths[1] + square_lengths[2] +
+                           square_lengths[3]);
+    result * F32x4::splat(1.0 / length)
 }
 
 impl Mul<Transform4F> for Transform4F {
@@ -427,6 +616,7 @@ impl Mul<RectF> for Perspective {
 mod test {
     use crate::vector::Vector4F;
     use crate::transform3d::Transform4F;
+    use std::f32::consts::FRAC_PI_2;
 
     #[test]
     fn test_post_mul() {
@@ -501,4 +691,39 @@ mod test {
         let p2 = m_inv * p1;
         assert!(p0.approx_eq(p2, 0.0001));
     }
+
+    #[test]
+    fn test_decompose_round_trip() {
+        let perspective = Transform4F::row_major(
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, -0.01, 1.0,
+        );
+        let skew = Transform4F::row_major(
+            1.0, 0.5, 0.0, 0.0, 0.0, 1.0, 0.25, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        );
+        let transforms = [
+            Transform4F::from_scale(Vector4F::new(2.0, 3.0, 4.0, 1.0))
+                .rotate(0.3, 0.7, -1.1)
+                .translate(Vector4F::new(5.0, -6.0, 7.0, 1.0)),
+            Transform4F::from_scale(Vector4F::new(-1.0, 1.0, 1.0, 1.0)).rotate(2.5, 0.0, 0.0),
+            perspective * Transform4F::from_rotation(0.0, FRAC_PI_2, 0.0) * skew,
+        ];
+        for transform in &transforms {
+            let recomposed = transform.decompose().unwrap().to_transform();
+            assert!(recomposed.approx_eq(transform, 0.0001), "{:?} != {:?}", recomposed, transform);
+        }
+
+        assert!(Transform4F::from_uniform_scale(0.0).decompose().is_none());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let a = Transform4F::default();
+        let b = Transform4F::from_rotation(FRAC_PI_2, 0.0, 0.0)
+            .translate(Vector4F::new(2.0, 0.0, 0.0, 1.0));
+        let expected = Transform4F::from_rotation(FRAC_PI_2 * 0.5, 0.0, 0.0)
+            .translate(Vector4F::new(1.0, 0.0, 0.0, 1.0));
+        assert!(a.interpolate(&b, 0.5).approx_eq(&expected, 0.0001));
+        assert!(a.interpolate(&b, 0.0).approx_eq(&a, 0.0001));
+        assert!(a.interpolate(&b, 1.0).approx_eq(&b, 0.0001));
+    }
 }
@@ -150,6 +150,11 @@ impl Vector2F {
         self + (other - self) * t
     }
 
+    #[inline]
+    pub fn to_f64(self) -> Vector2D {
+        Vector2D::new(self.x() as f64, self.y() as f64)
+    }
+
     #[inline]
     pub fn to_i32(self) -> Vector2I {
         Vector2I(self.0.to_i32x2())
@@ -435,6 +440,179 @@ impl Hash for Vector2I {
     }
 }
 
+/// 2D points with 64-bit floating point coordinates.
+///
+/// These are not SIMD-optimized. They exist to carry world-scale coordinates (e.g. from GIS or
+/// CAD data) until they can be rebased near the origin and converted to `Vector2F`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector2D {
+    x: f64,
+    y: f64,
+}
+
+impl Vector2D {
+    #[inline]
+    pub fn new(x: f64, y: f64) -> Vector2D {
+        Vector2D { x, y }
+    }
+
+    #[inline]
+    pub fn splat(value: f64) -> Vector2D {
+        Vector2D { x: value, y: value }
+    }
+
+    #[inline]
+    pub fn zero() -> Vector2D {
+        Vector2D::default()
+    }
+
+    #[inline]
+    pub fn x(self) -> f64 {
+        self.x
+    }
+
+    #[inline]
+    pub fn y(self) -> f64 {
+        self.y
+    }
+
+    #[inline]
+    pub fn set_x(&mut self, x: f64) {
+        self.x = x;
+    }
+
+    #[inline]
+    pub fn set_y(&mut self, y: f64) {
+        self.y = y;
+    }
+
+    #[inline]
+    pub fn min(self, other: Vector2D) -> Vector2D {
+        Vector2D::new(f64::min(self.x, other.x), f64::min(self.y, other.y))
+    }
+
+    #[inline]
+    pub fn max(self, other: Vector2D) -> Vector2D {
+        Vector2D::new(f64::max(self.x, other.x), f64::max(self.y, other.y))
+    }
+
+    #[inline]
+    pub fn det(self, other: Vector2D) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    #[inline]
+    pub fn dot(self, other: Vector2D) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    #[inline]
+    pub fn square_length(self) -> f64 {
+        self.dot(self)
+    }
+
+    #[inline]
+    pub fn length(self) -> f64 {
+        f64::sqrt(self.square_length())
+    }
+
+    #[inline]
+    pub fn normalize(self) -> Vector2D {
+        self * (1.0 / self.length())
+    }
+
+    #[inline]
+    pub fn yx(self) -> Vector2D {
+        Vector2D::new(self.y, self.x)
+    }
+
+    #[inline]
+    pub fn is_zero(self) -> bool {
+        self == Vector2D::zero()
+    }
+
+    #[inline]
+    pub fn lerp(self, other: Vector2D, t: f64) -> Vector2D {
+        self + (other - self) * t
+    }
+
+    /// Converts this vector to single precision.
+    ///
+    /// To keep precision, subtract a nearby origin first.
+    #[inline]
+    pub fn to_f32(self) -> Vector2F {
+        Vector2F::new(self.x as f32, self.y as f32)
+    }
+}
+
+/// A convenience alias for `Vector2D::new()`.
+#[inline]
+pub fn vec2d(x: f64, y: f64) -> Vector2D {
+    Vector2D::new(x, y)
+}
+
+impl Add<Vector2D> for Vector2D {
+    type Output = Vector2D;
+    #[inline]
+    fn add(self, other: Vector2D) -> Vector2D {
+        Vector2D::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign<Vector2D> for Vector2D {
+    #[inline]
+    fn add_assign(&mut self, other: Vector2D) {
+        *self = *self + other
+    }
+}
+
+impl Sub<Vector2D> for Vector2D {
+    type Output = Vector2D;
+    #[inline]
+    fn sub(self, other: Vector2D) -> Vector2D {
+        Vector2D::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign<Vector2D> for Vector2D {
+    #[inline]
+    fn sub_assign(&mut self, other: Vector2D) {
+        *self = *self - other
+    }
+}
+
+impl Mul<Vector2D> for Vector2D {
+    type Output = Vector2D;
+    #[inline]
+    fn mul(self, other: Vector2D) -> Vector2D {
+        Vector2D::new(self.x * other.x, self.y * other.y)
+    }
+}
+
+impl Mul<f64> for Vector2D {
+    type Output = Vector2D;
+    #[inline]
+    fn mul(self, other: f64) -> Vector2D {
+        Vector2D::new(self.x * other, self.y * other)
+    }
+}
+
+impl Div<f64> for Vector2D {
+    type Output = Vector2D;
+    #[inline]
+    fn div(self, other: f64) -> Vector2D {
+        Vector2D::new(self.x / other, self.y / other)
+    }
+}
+
+impl Neg for Vector2D {
+    type Output = Vector2D;
+    #[inline]
+    fn neg(self) -> Vector2D {
+        Vector2D::new(-self.x, -self.y)
+    }
+}
+
 /// 3D points.
 ///
 /// The w value in the SIMD vector is always 0.0.
@@ -508,6 +686,17 @@ impl Vector3F {
     pub fn scale(self, factor: f32) -> Vector3F {
         Vector3F(self.0 * F32x4::splat(factor))
     }
+
+    #[inline]
+    pub fn dot(self, other: Vector3F) -> f32 {
+        let xyz = self.0 * other.0;
+        xyz[0] + xyz[1] + xyz[2]
+    }
+
+    #[inline]
+    pub fn lerp(self, other: Vector3F, t: f32) -> Vector3F {
+        Vector3F(self.0 + (other.0 - self.0) * F32x4::splat(t))
+    }
 }
 
 impl Add<Vector3F> for Vector3F {
@@ -0,0 +1,558 @@
+// pathfinder/gl/src/headless.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! OpenGL contexts that need no window system, for rendering offscreen on servers and in CI.
+//!
+//! Two backends are available, each behind a Cargo feature because it links a system library:
+//!
+//! * `pf-egl` uses EGL on the `EGL_MESA_platform_surfaceless` platform, which works without an
+//!   X11 or Wayland display. Under Mesa this renders with llvmpipe when there is no GPU.
+//!
+//! * `pf-osmesa` uses OSMesa, Mesa's off-screen renderer, which renders into client memory.
+//!   It supports only desktop OpenGL.
+//!
+//! Either way the context comes with a default framebuffer of the requested size, so
+//! `DestFramebuffer::Default` and `Device::read_pixels()` work as they do with a window.
+
+use crate::{GLDevice, GLVersion};
+use pathfinder_geometry::vector::Vector2I;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::os::raw::c_void;
+
+/// An offscreen OpenGL context along with its default framebuffer.
+pub struct HeadlessContext {
+    context: PlatformContext,
+    version: GLVersion,
+    size: Vector2I,
+}
+
+enum PlatformContext {
+    #[cfg(feature = "pf-egl")]
+    Egl(egl::EglContext),
+    #[cfg(feature = "pf-osmesa")]
+    OSMesa(osmesa::OSMesaContext),
+}
+
+/// The library that provides a `HeadlessContext`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeadlessBackend {
+    /// EGL on the Mesa surfaceless platform.
+    #[cfg(feature = "pf-egl")]
+    Egl,
+    /// Mesa's off-screen renderer.
+    #[cfg(feature = "pf-osmesa")]
+    OSMesa,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeadlessError {
+    /// The platform lacks a required extension, named here.
+    MissingExtension(&'static str),
+    /// The backend can't create contexts of the requested version.
+    UnsupportedVersion,
+    /// No framebuffer configuration has the required color, depth and stencil bits.
+    NoSuitableConfig,
+    /// A call into the platform library failed. The name of the call and the platform's error
+    /// code are included.
+    PlatformError(&'static str, i32),
+}
+
+impl HeadlessContext {
+    /// Creates a context and makes it current, trying each compiled-in backend in turn.
+    ///
+    /// If every backend fails, the error from the last one is returned.
+    pub fn new(version: GLVersion, size: Vector2I) -> Result<HeadlessContext, HeadlessError> {
+        let mut result = Err(HeadlessError::UnsupportedVersion);
+        for &backend in HeadlessBackend::ALL {
+            result = HeadlessContext::with_backend(backend, version, size);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Creates a context with the given backend and makes it current.
+    pub fn with_backend(backend: HeadlessBackend, version: GLVersion, size: Vector2I)
+                        -> Result<HeadlessContext, HeadlessError> {
+        assert!(size.x() > 0 && size.y() > 0, "Headless framebuffer size must not be empty!");
+        let context = match backend {
+            #[cfg(feature = "pf-egl")]
+            HeadlessBackend::Egl => PlatformContext::Egl(egl::EglContext::new(version, size)?),
+            #[cfg(feature = "pf-osmesa")]
+            HeadlessBackend::OSMesa => {
+                PlatformContext::OSMesa(osmesa::OSMesaContext::new(version, size)?)
+            }
+        };
+        let context = HeadlessContext { context, version, size };
+        context.make_current()?;
+        Ok(context)
+    }
+
+    /// Makes this context current on the calling thread.
+    pub fn make_current(&self) -> Result<(), HeadlessError> {
+        match self.context {
+            #[cfg(feature = "pf-egl")]
+            PlatformContext::Egl(ref context) => context.make_current(),
+            #[cfg(feature = "pf-osmesa")]
+            PlatformContext::OSMesa(ref context) => context.make_current(self.size),
+        }
+    }
+
+    /// Returns the address of an OpenGL function, or null if it's unavailable.
+    pub fn get_proc_address(&self, name: &str) -> *const c_void {
+        match self.context {
+            #[cfg(feature = "pf-egl")]
+            PlatformContext::Egl(_) => egl::get_proc_address(name),
+            #[cfg(feature = "pf-osmesa")]
+            PlatformContext::OSMesa(_) => osmesa::get_proc_address(name),
+        }
+    }
+
+    /// Loads OpenGL functions from this context and creates a device that renders to it.
+    pub fn create_device(&self) -> GLDevice {
+        gl::load_with(|name| self.get_proc_address(name));
+        GLDevice::new(self.version, 0)
+    }
+
+    #[inline]
+    pub fn backend(&self) -> HeadlessBackend {
+        match self.context {
+            #[cfg(feature = "pf-egl")]
+            PlatformContext::Egl(_) => HeadlessBackend::Egl,
+            #[cfg(feature = "pf-osmesa")]
+            PlatformContext::OSMesa(_) => HeadlessBackend::OSMesa,
+        }
+    }
+
+    #[inline]
+    pub fn version(&self) -> GLVersion {
+        self.version
+    }
+
+    /// Returns the size of the default framebuffer.
+    #[inline]
+    pub fn size(&self) -> Vector2I {
+        self.size
+    }
+}
+
+impl HeadlessBackend {
+    /// The compiled-in backends, in the order `HeadlessContext::new()` tries them.
+    pub const ALL: &'static [HeadlessBackend] = &[
+        #[cfg(feature = "pf-egl")]
+        HeadlessBackend::Egl,
+        #[cfg(feature = "pf-osmesa")]
+        HeadlessBackend::OSMesa,
+    ];
+}
+
+impl Display for HeadlessError {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match *self {
+            HeadlessError::MissingExtension(name) => write!(formatter, "{} is unsupported", name),
+            HeadlessError::UnsupportedVersion => {
+                write!(formatter, "the OpenGL version is unsupported")
+            }
+            HeadlessError::NoSuitableConfig => write!(formatter, "no suitable config was found"),
+            HeadlessError::PlatformError(call, code) => {
+                write!(formatter, "{}() failed with error 0x{:x}", call, code)
+            }
+        }
+    }
+}
+
+impl Error for HeadlessError {}
+
+#[cfg(feature = "pf-egl")]
+mod egl {
+    use crate::GLVersion;
+    use super::HeadlessError;
+    use pathfinder_geometry::vector::Vector2I;
+    use std::ffi::{CStr, CString};
+    use std::mem;
+    use std::os::raw::{c_char, c_void};
+    use std::ptr;
+
+    type EGLBoolean = u32;
+    type EGLConfig = *mut c_void;
+    type EGLContext = *mut c_void;
+    type EGLDisplay = *mut c_void;
+    type EGLSurface = *mut c_void;
+    type EGLenum = u32;
+    type EGLint = i32;
+    type EGLGetPlatformDisplayEXTFn = unsafe extern "C" fn(platform: EGLenum,
+                                                           native_display: *mut c_void,
+                                                           attrib_list: *const EGLint)
+                                                           -> EGLDisplay;
+
+    const EGL_FALSE: EGLBoolean = 0;
+    const EGL_NONE: EGLint = 0x3038;
+    const EGL_ALPHA_SIZE: EGLint = 0x3021;
+    const EGL_BLUE_SIZE: EGLint = 0x3022;
+    const EGL_GREEN_SIZE: EGLint = 0x3023;
+    const EGL_RED_SIZE: EGLint = 0x3024;
+    const EGL_DEPTH_SIZE: EGLint = 0x3025;
+    const EGL_STENCIL_SIZE: EGLint = 0x3026;
+    const EGL_SURFACE_TYPE: EGLint = 0x3033;
+    const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+    const EGL_HEIGHT: EGLint = 0x3056;
+    const EGL_WIDTH: EGLint = 0x3057;
+    const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+    const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30fb;
+    const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30fd;
+    const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+    const EGL_PBUFFER_BIT: EGLint = 0x0001;
+    const EGL_OPENGL_BIT: EGLint = 0x0008;
+    const EGL_OPENGL_ES3_BIT: EGLint = 0x0040;
+    const EGL_EXTENSIONS: EGLint = 0x3055;
+    const EGL_OPENGL_ES_API: EGLenum = 0x30a0;
+    const EGL_OPENGL_API: EGLenum = 0x30a2;
+    const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31dd;
+
+    #[link(name = "EGL")]
+    extern "C" {
+        fn eglGetError() -> EGLint;
+        fn eglGetProcAddress(procname: *const c_char) -> *const c_void;
+        fn eglQueryString(display: EGLDisplay, name: EGLint) -> *const c_char;
+        fn eglInitialize(display: EGLDisplay, major: *mut EGLint, minor: *mut EGLint)
+                         -> EGLBoolean;
+        fn eglTerminate(display: EGLDisplay) -> EGLBoolean;
+        fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+        fn eglChooseConfig(display: EGLDisplay,
+                           attrib_list: *const EGLint,
+                           configs: *mut EGLConfig,
+                           config_size: EGLint,
+                           num_config: *mut EGLint)
+                           -> EGLBoolean;
+        fn eglCreatePbufferSurface(display: EGLDisplay,
+                                   config: EGLConfig,
+                                   attrib_list: *const EGLint)
+                                   -> EGLSurface;
+        fn eglDestroySurface(display: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+        fn eglCreateContext(display: EGLDisplay,
+                            config: EGLConfig,
+                            share_context: EGLContext,
+                            attrib_list: *const EGLint)
+                            -> EGLContext;
+        fn eglDestroyContext(display: EGLDisplay, context: EGLContext) -> EGLBoolean;
+        fn eglMakeCurrent(display: EGLDisplay,
+                          draw: EGLSurface,
+                          read: EGLSurface,
+                          context: EGLContext)
+                          -> EGLBoolean;
+    }
+
+    pub(crate) struct EglContext {
+        display: EGLDisplay,
+        surface: EGLSurface,
+        context: EGLContext,
+    }
+
+    impl EglContext {
+        pub(crate) fn new(version: GLVersion, size: Vector2I)
+                          -> Result<EglContext, HeadlessError> {
+            unsafe {
+                let display = get_surfaceless_display()?;
+                let mut context = EglContext {
+                    display,
+                    surface: ptr::null_mut(),
+                    context: ptr::null_mut(),
+                };
+                let (mut major, mut minor) = (0, 0);
+                if eglInitialize(display, &mut major, &mut minor) == EGL_FALSE {
+                    return Err(last_error("eglInitialize"));
+                }
+
+                let (api, renderable_type) = match version {
+                    GLVersion::GL3 => (EGL_OPENGL_API, EGL_OPENGL_BIT),
+                    GLVersion::GLES3 => (EGL_OPENGL_ES_API, EGL_OPENGL_ES3_BIT),
+                };
+                if eglBindAPI(api) == EGL_FALSE {
+                    return Err(HeadlessError::UnsupportedVersion);
+                }
+
+                let config_attributes = [
+                    EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+                    EGL_RENDERABLE_TYPE, renderable_type,
+                    EGL_RED_SIZE, 8,
+                    EGL_GREEN_SIZE, 8,
+                    EGL_BLUE_SIZE, 8,
+                    EGL_ALPHA_SIZE, 8,
+                    EGL_DEPTH_SIZE, 24,
+                    EGL_STENCIL_SIZE, 8,
+                    EGL_NONE,
+                ];
+                let (mut config, mut config_count) = (ptr::null_mut(), 0);
+                if eglChooseConfig(display,
+                                   config_attributes.as_ptr(),
+                                   &mut config,
+                                   1,
+                                   &mut config_count) == EGL_FALSE {
+                    return Err(last_error("eglChooseConfig"));
+                }
+                if config_count == 0 {
+                    return Err(HeadlessError::NoSuitableConfig);
+                }
+
+                let surface_attributes = [EGL_WIDTH, size.x(), EGL_HEIGHT, size.y(), EGL_NONE];
+                context.surface =
+                    eglCreatePbufferSurface(display, config, surface_attributes.as_ptr());
+                if context.surface.is_null() {
+                    return Err(last_error("eglCreatePbufferSurface"));
+                }
+
+                let context_attributes = match version {
+                    GLVersion::GL3 => [
+                        EGL_CONTEXT_MAJOR_VERSION, 3,
+                        EGL_CONTEXT_MINOR_VERSION, 3,
+                        EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                        EGL_NONE,
+                    ],
+                    GLVersion::GLES3 => [
+                        EGL_CONTEXT_MAJOR_VERSION, 3,
+                        EGL_CONTEXT_MINOR_VERSION, 0,
+                        EGL_NONE, EGL_NONE,
+                        EGL_NONE,
+                    ],
+                };
+                context.context = eglCreateContext(display,
+                                                   config,
+                                                   ptr::null_mut(),
+                                                   context_attributes.as_ptr());
+                if context.context.is_null() {
+                    return Err(last_error("eglCreateContext"));
+                }
+
+                Ok(context)
+            }
+        }
+
+        pub(crate) fn make_current(&self) -> Result<(), HeadlessError> {
+            unsafe {
+                if eglMakeCurrent(self.display, self.surface, self.surface, self.context) ==
+                        EGL_FALSE {
+                    return Err(last_error("eglMakeCurrent"));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    impl Drop for EglContext {
+        fn drop(&mut self) {
+            unsafe {
+                eglMakeCurrent(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+                if !self.context.is_null() {
+                    eglDestroyContext(self.display, self.context);
+                }
+                if !self.surface.is_null() {
+                    eglDestroySurface(self.display, self.surface);
+                }
+                eglTerminate(self.display);
+            }
+        }
+    }
+
+    pub(crate) fn get_proc_address(name: &str) -> *const c_void {
+        let name = CString::new(name).unwrap();
+        unsafe { eglGetProcAddress(name.as_ptr()) }
+    }
+
+    unsafe fn get_surfaceless_display() -> Result<EGLDisplay, HeadlessError> {
+        // Client extensions are queried on `EGL_NO_DISPLAY`.
+        let extensions = eglQueryString(ptr::null_mut(), EGL_EXTENSIONS);
+        if extensions.is_null() {
+            return Err(HeadlessError::MissingExtension("EGL_EXT_client_extensions"));
+        }
+        let extensions = CStr::from_ptr(extensions).to_string_lossy();
+        let has_extension = |name| extensions.split(' ').any(|extension| extension == name);
+        if !has_extension("EGL_EXT_platform_base") {
+            return Err(HeadlessError::MissingExtension("EGL_EXT_platform_base"));
+        }
+        if !has_extension("EGL_MESA_platform_surfaceless") {
+            return Err(HeadlessError::MissingExtension("EGL_MESA_platform_surfaceless"));
+        }
+
+        let get_platform_display = get_proc_address("eglGetPlatformDisplayEXT");
+        if get_platform_display.is_null() {
+            return Err(HeadlessError::MissingExtension("EGL_EXT_platform_base"));
+        }
+        let get_platform_display: EGLGetPlatformDisplayEXTFn =
+            mem::transmute(get_platform_display);
+        let display = get_platform_display(EGL_PLATFORM_SURFACELESS_MESA,
+                                           ptr::null_mut(),
+                                           [EGL_NONE].as_ptr());
+        if display.is_null() {
+            return Err(last_error("eglGetPlatformDisplayEXT"));
+        }
+        Ok(display)
+    }
+
+    fn last_error(call: &'static str) -> HeadlessError {
+        HeadlessError::PlatformError(call, unsafe { eglGetError() })
+    }
+}
+
+#[cfg(feature = "pf-osmesa")]
+mod osmesa {
+    use crate::GLVersion;
+    use super::HeadlessError;
+    use gl::types::{GLboolean, GLenum, GLsizei};
+    use pathfinder_geometry::vector::Vector2I;
+    use std::cell::UnsafeCell;
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::ptr;
+
+    type OSMesaContextHandle = *mut c_void;
+
+    const OSMESA_RGBA: c_int = gl::RGBA as c_int;
+    const OSMESA_FORMAT: c_int = 0x22;
+    const OSMESA_DEPTH_BITS: c_int = 0x30;
+    const OSMESA_STENCIL_BITS: c_int = 0x31;
+    const OSMESA_PROFILE: c_int = 0x33;
+    const OSMESA_CORE_PROFILE: c_int = 0x34;
+    const OSMESA_CONTEXT_MAJOR_VERSION: c_int = 0x36;
+    const OSMESA_CONTEXT_MINOR_VERSION: c_int = 0x37;
+
+    #[link(name = "OSMesa")]
+    extern "C" {
+        fn OSMesaCreateContextAttribs(attrib_list: *const c_int, sharelist: OSMesaContextHandle)
+                                      -> OSMesaContextHandle;
+        fn OSMesaDestroyContext(context: OSMesaContextHandle);
+        fn OSMesaMakeCurrent(context: OSMesaContextHandle,
+                             buffer: *mut c_void,
+                             ty: GLenum,
+                             width: GLsizei,
+                             height: GLsizei)
+                             -> GLboolean;
+        fn OSMesaGetProcAddress(func_name: *const c_char) -> *const c_void;
+    }
+
+    pub(crate) struct OSMesaContext {
+        context: OSMesaContextHandle,
+        // OSMesa renders straight into this memory, so it must outlive the context.
+        buffer: UnsafeCell<Vec<u8>>,
+    }
+
+    impl OSMesaContext {
+        pub(crate) fn new(version: GLVersion, size: Vector2I)
+                          -> Result<OSMesaContext, HeadlessError> {
+            match version {
+                GLVersion::GL3 => {}
+                GLVersion::GLES3 => return Err(HeadlessError::UnsupportedVersion),
+            }
+
+            let attributes = [
+                OSMESA_FORMAT, OSMESA_RGBA,
+                OSMESA_DEPTH_BITS, 24,
+                OSMESA_STENCIL_BITS, 8,
+                OSMESA_PROFILE, OSMESA_CORE_PROFILE,
+                OSMESA_CONTEXT_MAJOR_VERSION, 3,
+                OSMESA_CONTEXT_MINOR_VERSION, 3,
+                0,
+            ];
+            let context = unsafe {
+                OSMesaCreateContextAttribs(attributes.as_ptr(), ptr::null_mut())
+            };
+            if context.is_null() {
+                return Err(HeadlessError::PlatformError("OSMesaCreateContextAttribs", 0));
+            }
+
+            let buffer = vec![0; size.x() as usize * size.y() as usize * 4];
+            Ok(OSMesaContext { context, buffer: UnsafeCell::new(buffer) })
+        }
+
+        pub(crate) fn make_current(&self, size: Vector2I) -> Result<(), HeadlessError> {
+            unsafe {
+                let buffer = (*self.buffer.get()).as_mut_ptr() as *mut c_void;
+                if OSMesaMakeCurrent(self.context,
+                                     buffer,
+                                     gl::UNSIGNED_BYTE,
+                                     size.x(),
+                                     size.y()) == gl::FALSE {
+                    return Err(HeadlessError::PlatformError("OSMesaMakeCurrent", 0));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    impl Drop for OSMesaContext {
+        fn drop(&mut self) {
+            unsafe {
+                OSMesaDestroyContext(self.context);
+            }
+        }
+    }
+
+    pub(crate) fn get_proc_address(name: &str) -> *const c_void {
+        let name = CString::new(name).unwrap();
+        unsafe { OSMesaGetProcAddress(name.as_ptr()) }
+    }
+}
+
+#[cfg(all(test, feature = "pf-egl"))]
+mod test {
+    use crate::GLVersion;
+    use crate::headless::{HeadlessBackend, HeadlessContext};
+    use pathfinder_geometry::rect::RectI;
+    use pathfinder_geometry::vector::{Vector2I, vec2i};
+    use pathfinder_gpu::{Device, RenderTarget, TextureData};
+    use pathfinder_resources::ResourceLoader;
+    use std::io::Error as IOError;
+
+    #[test]
+    fn test_egl_clear_and_read_back() {
+        let context = HeadlessContext::with_backend(HeadlessBackend::Egl,
+                                                    GLVersion::GL3,
+                                                    vec2i(16, 8)).unwrap();
+        let device = context.create_device();
+        device.begin_commands();
+        unsafe {
+            gl::ClearColor(1.0, 0.0, 0.0, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+        let receiver = device.read_pixels(&RenderTarget::Default,
+                                          RectI::new(Vector2I::default(), vec2i(16, 8)));
+        device.end_commands();
+
+        match device.recv_texture_data(&receiver) {
+            TextureData::U8(pixels) => {
+                assert_eq!(pixels.len(), 16 * 8 * 4);
+                assert!(pixels.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+            }
+            _ => panic!("Expected RGBA8 pixels!"),
+        }
+    }
+
+    struct BrokenShaderLoader;
+
+    impl ResourceLoader for BrokenShaderLoader {
+        fn slurp(&self, path: &str) -> Result<Vec<u8>, IOError> {
+            assert!(path.starts_with("shaders/gl3/broken."));
+            Ok(b"#version {{version}}\nvoid main() { oops }\n".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_egl_shader_errors_are_returned() {
+        let context = HeadlessContext::with_backend(HeadlessBackend::Egl,
+                                                    GLVersion::GL3,
+                                                    vec2i(1, 1)).unwrap();
+        let device = context.create_device();
+        match device.try_create_program(&BrokenShaderLoader, "broken") {
+            Ok(_) => panic!("A broken shader compiled!"),
+            Err(error) => assert!(error.starts_with("shaders/gl3/broken.vs.glsl: ")),
+        }
+    }
+}
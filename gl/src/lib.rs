@@ -13,7 +13,8 @@
 #[macro_use]
 extern crate log;
 
-use gl::types::{GLboolean, GLchar, GLenum, GLfloat, GLint, GLsizei, GLsizeiptr, GLsync};
+use crate::state_cache::GLStateCache;
+use gl::types::{GLchar, GLenum, GLfloat, GLint, GLsizei, GLsizeiptr, GLsync};
 use gl::types::{GLuint, GLvoid};
 use half::f16;
 use pathfinder_geometry::rect::RectI;
@@ -25,15 +26,30 @@ use pathfinder_gpu::{TextureSamplingFlags, UniformData, VertexAttrClass};
 use pathfinder_gpu::{VertexAttrDescriptor, VertexAttrType};
 use pathfinder_resources::ResourceLoader;
 use pathfinder_simd::default::F32x4;
-use std::ffi::CString;
+use std::cell::{Cell, RefCell};
+use std::ffi::{CStr, CString};
 use std::mem;
 use std::ptr;
 use std::str;
 use std::time::Duration;
 
+#[cfg(any(feature = "pf-egl", feature = "pf-osmesa"))]
+pub mod headless;
+
+mod state_cache;
+
+// From `GL_EXT_texture_filter_anisotropic`, which the generated bindings don't include.
+const GL_TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84fe;
+const GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84ff;
+
 pub struct GLDevice {
     version: GLVersion,
     default_framebuffer: GLuint,
+    state_cache: RefCell<GLStateCache>,
+    verify_state_cache: bool,
+    // The maximum degree of anisotropic filtering, or 1.0 if it's unsupported. Queried lazily,
+    // since the device may be created before the context is current.
+    max_anisotropy: Cell<Option<GLfloat>>,
 }
 
 impl GLDevice {
@@ -42,6 +58,9 @@ impl GLDevice {
         GLDevice {
             version,
             default_framebuffer,
+            state_cache: RefCell::new(GLStateCache::default()),
+            verify_state_cache: false,
+            max_anisotropy: Cell::new(None),
         }
     }
 
@@ -49,14 +68,51 @@ impl GLDevice {
         self.default_framebuffer = framebuffer;
     }
 
-    fn set_render_state(&self, render_state: &RenderState<GLDevice>) {
-        self.bind_render_target(render_state.target);
+    /// Forgets all tracked GL state, so that the next draw sets everything again.
+    ///
+    /// The device skips GL calls that wouldn't change the state it last set. This is done
+    /// automatically in `begin_commands()`, but code that calls into GL itself between device
+    /// calls must call this afterward.
+    pub fn invalidate_state_cache(&self) {
+        *self.state_cache.borrow_mut() = GLStateCache::default();
+    }
 
+    /// Enables or disables checking the tracked GL state against `glGet*()` before every draw.
+    ///
+    /// A mismatch panics. This is a debugging aid for finding GL calls made behind the device's
+    /// back, and is very slow.
+    pub fn set_state_cache_verification(&mut self, enabled: bool) {
+        self.verify_state_cache = enabled;
+    }
+
+    fn max_anisotropy(&self) -> GLfloat {
+        if let Some(max_anisotropy) = self.max_anisotropy.get() {
+            return max_anisotropy;
+        }
+
+        let mut max_anisotropy = 1.0;
         unsafe {
-            let (origin, size) = (render_state.viewport.origin(), render_state.viewport.size());
-            gl::Viewport(origin.x(), origin.y(), size.x(), size.y());
+            let mut extension_count = 0;
+            gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut extension_count); ck();
+            for extension_index in 0..(extension_count as GLuint) {
+                let extension = gl::GetStringi(gl::EXTENSIONS, extension_index); ck();
+                let extension = CStr::from_ptr(extension as *const GLchar).to_bytes();
+                if extension == b"GL_EXT_texture_filter_anisotropic" ||
+                        extension == b"GL_ARB_texture_filter_anisotropic" {
+                    gl::GetFloatv(GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max_anisotropy); ck();
+                    break;
+                }
+            }
         }
 
+        self.max_anisotropy.set(Some(max_anisotropy));
+        max_anisotropy
+    }
+
+    fn set_render_state(&self, render_state: &RenderState<GLDevice>) {
+        self.bind_render_target(render_state.target);
+        self.state_cache.borrow_mut().set_viewport(render_state.viewport);
+
         if render_state.options.clear_ops.has_ops() {
             self.clear(&render_state.options.clear_ops);
         }
@@ -69,61 +125,58 @@ impl GLDevice {
 
         render_state.uniforms.iter().for_each(|(uniform, data)| self.set_uniform(uniform, data));
         self.set_render_options(&render_state.options);
+
+        if self.verify_state_cache {
+            self.state_cache.borrow().verify(render_state.textures.len());
+        }
     }
 
     fn set_render_options(&self, render_options: &RenderOptions) {
-        unsafe {
-            // Set blend.
-            match render_options.blend {
-                None => {
-                    gl::Disable(gl::BLEND); ck();
-                }
-                Some(ref blend) => {
-                    gl::BlendFuncSeparate(blend.src_rgb_factor.to_gl_blend_factor(),
-                                          blend.dest_rgb_factor.to_gl_blend_factor(),
-                                          blend.src_alpha_factor.to_gl_blend_factor(),
-                                          blend.dest_alpha_factor.to_gl_blend_factor()); ck();
-                    gl::BlendEquation(blend.op.to_gl_blend_op()); ck();
-                    gl::Enable(gl::BLEND); ck();
-                }
+        let mut state_cache = self.state_cache.borrow_mut();
+
+        // Set blend.
+        match render_options.blend {
+            None => state_cache.set_blend(false),
+            Some(ref blend) => {
+                state_cache.set_blend_func([blend.src_rgb_factor.to_gl_blend_factor(),
+                                            blend.dest_rgb_factor.to_gl_blend_factor(),
+                                            blend.src_alpha_factor.to_gl_blend_factor(),
+                                            blend.dest_alpha_factor.to_gl_blend_factor()]);
+                state_cache.set_blend_equation(blend.op.to_gl_blend_op());
+                state_cache.set_blend(true);
             }
+        }
 
-            // Set depth.
-            match render_options.depth {
-                None => {
-                    gl::Disable(gl::DEPTH_TEST); ck();
-                }
-                Some(ref state) => {
-                    gl::DepthFunc(state.func.to_gl_depth_func()); ck();
-                    gl::DepthMask(state.write as GLboolean); ck();
-                    gl::Enable(gl::DEPTH_TEST); ck();
-                }
+        // Set depth.
+        match render_options.depth {
+            None => state_cache.set_depth_test(false),
+            Some(ref state) => {
+                state_cache.set_depth_func(state.func.to_gl_depth_func());
+                state_cache.set_depth_mask(state.write);
+                state_cache.set_depth_test(true);
             }
+        }
 
-            // Set stencil.
-            match render_options.stencil {
-                None => {
-                    gl::Disable(gl::STENCIL_TEST); ck();
-                }
-                Some(ref state) => {
-                    gl::StencilFunc(state.func.to_gl_stencil_func(),
-                                    state.reference as GLint,
-                                    state.mask); ck();
-                    let (pass_action, write_mask) = if state.write {
-                        (gl::REPLACE, state.mask)
-                    } else {
-                        (gl::KEEP, 0)
-                    };
-                    gl::StencilOp(gl::KEEP, gl::KEEP, pass_action); ck();
-                    gl::StencilMask(write_mask);
-                    gl::Enable(gl::STENCIL_TEST); ck();
-                }
+        // Set stencil.
+        match render_options.stencil {
+            None => state_cache.set_stencil_test(false),
+            Some(ref state) => {
+                state_cache.set_stencil_func(state.func.to_gl_stencil_func(),
+                                             state.reference as GLint,
+                                             state.mask);
+                let (pass_action, write_mask) = if state.write {
+                    (gl::REPLACE, state.mask)
+                } else {
+                    (gl::KEEP, 0)
+                };
+                state_cache.set_stencil_pass_op(pass_action);
+                state_cache.set_stencil_mask(write_mask);
+                state_cache.set_stencil_test(true);
             }
-
-            // Set color mask.
-            let color_mask = render_options.color_mask as GLboolean;
-            gl::ColorMask(color_mask, color_mask, color_mask, color_mask); ck();
         }
+
+        // Set color mask.
+        state_cache.set_color_mask(render_options.color_mask);
     }
 
     fn set_uniform(&self, uniform: &GLUniform, data: &UniformData) {
@@ -184,33 +237,10 @@ impl GLDevice {
         }
     }
 
+    // Everything else stays bound after a draw, and the state cache skips it next time if it
+    // doesn't change. `end_commands()` restores the defaults at the end of the frame.
     fn reset_render_state(&self, render_state: &RenderState<GLDevice>) {
-        self.reset_render_options(&render_state.options);
-        for texture_unit in 0..(render_state.textures.len() as u32) {
-            self.unbind_texture(texture_unit);
-        }
         render_state.uniforms.iter().for_each(|(uniform, data)| self.unset_uniform(uniform, data));
-        self.unuse_program();
-        self.unbind_vertex_array();
-    }
-
-    fn reset_render_options(&self, render_options: &RenderOptions) {
-        unsafe {
-            if render_options.blend.is_some() {
-                gl::Disable(gl::BLEND); ck();
-            }
-
-            if render_options.depth.is_some() {
-                gl::Disable(gl::DEPTH_TEST); ck();
-            }
-
-            if render_options.stencil.is_some() {
-                gl::StencilMask(!0); ck();
-                gl::Disable(gl::STENCIL_TEST); ck();
-            }
-
-            gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE); ck();
-        }
     }
 }
 
@@ -230,6 +260,7 @@ impl Device for GLDevice {
         let mut texture = GLTexture { gl_texture: 0, size, format };
         unsafe {
             gl::GenTextures(1, &mut texture.gl_texture); ck();
+            self.state_cache.borrow_mut().forget_texture(texture.gl_texture);
             self.bind_texture(&texture, 0);
             gl::TexImage2D(gl::TEXTURE_2D,
                            0,
@@ -246,12 +277,19 @@ impl Device for GLDevice {
         texture
     }
 
+    #[inline]
+    fn create_mipmapped_texture(&self, format: TextureFormat, size: Vector2I) -> GLTexture {
+        // GL allocates the other levels in `glGenerateMipmap()`.
+        self.create_texture(format, size)
+    }
+
     fn create_texture_from_data(&self, format: TextureFormat, size: Vector2I, data: TextureDataRef)
                                 -> GLTexture {
         let data_ptr = data.check_and_extract_data_ptr(size, format);
         let mut texture = GLTexture { gl_texture: 0, size, format: TextureFormat::R8 };
         unsafe {
             gl::GenTextures(1, &mut texture.gl_texture); ck();
+            self.state_cache.borrow_mut().forget_texture(texture.gl_texture);
             self.bind_texture(&texture, 0);
             gl::TexImage2D(gl::TEXTURE_2D,
                            0,
@@ -269,41 +307,12 @@ impl Device for GLDevice {
     }
 
     fn create_shader_from_source(&self, name: &str, source: &[u8], kind: ShaderKind) -> GLShader {
-        // FIXME(pcwalton): Do this once and cache it.
-        let glsl_version_spec = self.version.to_glsl_version_spec();
-
-        let mut output = vec![];
-        self.preprocess(&mut output, source, glsl_version_spec);
-        let source = output;
-
-        let gl_shader_kind = match kind {
-            ShaderKind::Vertex => gl::VERTEX_SHADER,
-            ShaderKind::Fragment => gl::FRAGMENT_SHADER,
-        };
-
-        unsafe {
-            let gl_shader = gl::CreateShader(gl_shader_kind); ck();
-            gl::ShaderSource(gl_shader,
-                             1,
-                             [source.as_ptr() as *const GLchar].as_ptr(),
-                             [source.len() as GLint].as_ptr()); ck();
-            gl::CompileShader(gl_shader); ck();
-
-            let mut compile_status = 0;
-            gl::GetShaderiv(gl_shader, gl::COMPILE_STATUS, &mut compile_status); ck();
-            if compile_status != gl::TRUE as GLint {
-                let mut info_log_length = 0;
-                gl::GetShaderiv(gl_shader, gl::INFO_LOG_LENGTH, &mut info_log_length); ck();
-                let mut info_log = vec![0; info_log_length as usize];
-                gl::GetShaderInfoLog(gl_shader,
-                                     info_log.len() as GLint,
-                                     ptr::null_mut(),
-                                     info_log.as_mut_ptr() as *mut GLchar); ck();
-                error!("Shader info log:\n{}", String::from_utf8_lossy(&info_log));
+        match self.compile_shader(source, kind) {
+            Ok(shader) => shader,
+            Err(info_log) => {
+                error!("Shader info log:\n{}", info_log);
                 panic!("{:?} shader '{}' compilation failed", kind, name);
             }
-
-            GLShader { gl_shader }
         }
     }
 
@@ -313,29 +322,29 @@ impl Device for GLDevice {
                                    vertex_shader: GLShader,
                                    fragment_shader: GLShader)
                                    -> GLProgram {
-        let gl_program;
-        unsafe {
-            gl_program = gl::CreateProgram(); ck();
-            gl::AttachShader(gl_program, vertex_shader.gl_shader); ck();
-            gl::AttachShader(gl_program, fragment_shader.gl_shader); ck();
-            gl::LinkProgram(gl_program); ck();
-
-            let mut link_status = 0;
-            gl::GetProgramiv(gl_program, gl::LINK_STATUS, &mut link_status); ck();
-            if link_status != gl::TRUE as GLint {
-                let mut info_log_length = 0;
-                gl::GetProgramiv(gl_program, gl::INFO_LOG_LENGTH, &mut info_log_length); ck();
-                let mut info_log = vec![0; info_log_length as usize];
-                gl::GetProgramInfoLog(gl_program,
-                                      info_log.len() as GLint,
-                                      ptr::null_mut(),
-                                      info_log.as_mut_ptr() as *mut GLchar); ck();
-                eprintln!("Program info log:\n{}", String::from_utf8_lossy(&info_log));
+        match self.link_program(vertex_shader, fragment_shader) {
+            Ok(program) => program,
+            Err(info_log) => {
+                eprintln!("Program info log:\n{}", info_log);
                 panic!("Program '{}' linking failed", name);
             }
         }
+    }
 
-        GLProgram { gl_program, vertex_shader, fragment_shader }
+    fn try_create_program(&self, resources: &dyn ResourceLoader, name: &str)
+                          -> Result<GLProgram, String> {
+        let mut shaders = vec![];
+        for &kind in &[ShaderKind::Vertex, ShaderKind::Fragment] {
+            let path = shader_path(name, kind);
+            let source = resources.slurp(&path).map_err(|err| format!("{}: {}", path, err))?;
+            let shader = self.compile_shader(&source, kind)
+                             .map_err(|info_log| format!("{}: {}", path, info_log))?;
+            shaders.push(shader);
+        }
+        let fragment_shader = shaders.pop().unwrap();
+        let vertex_shader = shaders.pop().unwrap();
+        self.link_program(vertex_shader, fragment_shader)
+            .map_err(|info_log| format!("Program '{}': {}", name, info_log))
     }
 
     #[inline]
@@ -343,6 +352,7 @@ impl Device for GLDevice {
         unsafe {
             let mut array = GLVertexArray { gl_vertex_array: 0 };
             gl::GenVertexArrays(1, &mut array.gl_vertex_array); ck();
+            self.state_cache.borrow_mut().forget_vertex_array(array.gl_vertex_array);
             array
         }
     }
@@ -411,8 +421,10 @@ impl Device for GLDevice {
         let mut gl_framebuffer = 0;
         unsafe {
             gl::GenFramebuffers(1, &mut gl_framebuffer); ck();
-            gl::BindFramebuffer(gl::FRAMEBUFFER, gl_framebuffer); ck();
-            self.bind_texture(&texture, 0);
+            let mut state_cache = self.state_cache.borrow_mut();
+            state_cache.forget_framebuffer(gl_framebuffer);
+            state_cache.bind_framebuffer(gl_framebuffer);
+            state_cache.bind_texture(texture.gl_texture, 0);
             gl::FramebufferTexture2D(gl::FRAMEBUFFER,
                                      gl::COLOR_ATTACHMENT0,
                                      gl::TEXTURE_2D,
@@ -447,6 +459,13 @@ impl Device for GLDevice {
         };
         let len = (len * mem::size_of::<T>()) as GLsizeiptr;
         let usage = mode.to_gl_usage();
+
+        // The element array binding is part of the vertex array state, so make sure that we don't
+        // clobber the index buffer of whatever vertex array the last draw left bound.
+        if target == gl::ELEMENT_ARRAY_BUFFER {
+            self.unbind_vertex_array();
+        }
+
         unsafe {
             gl::BindBuffer(target, buffer.gl_buffer); ck();
             gl::BufferData(target, len, ptr, usage); ck();
@@ -468,6 +487,7 @@ impl Device for GLDevice {
         unsafe {
             gl::DeleteFramebuffers(1, &mut framebuffer.gl_framebuffer); ck();
         }
+        self.state_cache.borrow_mut().forget_framebuffer(framebuffer.gl_framebuffer);
         mem::forget(framebuffer);
         texture
     }
@@ -487,10 +507,12 @@ impl Device for GLDevice {
         unsafe {
             gl::TexParameteri(gl::TEXTURE_2D,
                               gl::TEXTURE_MIN_FILTER,
-                              if flags.contains(TextureSamplingFlags::NEAREST_MIN) {
-                                  gl::NEAREST as GLint
-                              } else {
-                                  gl::LINEAR as GLint
+                              match (flags.contains(TextureSamplingFlags::NEAREST_MIN),
+                                     flags.contains(TextureSamplingFlags::MIPMAP_MIN)) {
+                                  (true, false) => gl::NEAREST as GLint,
+                                  (false, false) => gl::LINEAR as GLint,
+                                  (true, true) => gl::NEAREST_MIPMAP_LINEAR as GLint,
+                                  (false, true) => gl::LINEAR_MIPMAP_LINEAR as GLint,
                               }); ck();
             gl::TexParameteri(gl::TEXTURE_2D,
                               gl::TEXTURE_MAG_FILTER,
@@ -514,6 +536,18 @@ impl Device for GLDevice {
                                   gl::CLAMP_TO_EDGE as GLint
                               }); ck();
         }
+
+        let max_anisotropy = self.max_anisotropy();
+        if max_anisotropy > 1.0 {
+            let anisotropy = if flags.contains(TextureSamplingFlags::ANISOTROPIC) {
+                max_anisotropy
+            } else {
+                1.0
+            };
+            unsafe {
+                gl::TexParameterf(gl::TEXTURE_2D, GL_TEXTURE_MAX_ANISOTROPY_EXT, anisotropy); ck();
+            }
+        }
     }
 
     fn upload_to_texture(&self, texture: &Self::Texture, rect: RectI, data: TextureDataRef) {
@@ -552,6 +586,13 @@ impl Device for GLDevice {
         self.set_texture_sampling_mode(texture, TextureSamplingFlags::empty());
     }
 
+    fn generate_mipmaps(&self, texture: &Self::Texture) {
+        self.bind_texture(texture, 0);
+        unsafe {
+            gl::GenerateMipmap(gl::TEXTURE_2D); ck();
+        }
+    }
+
     fn read_pixels(&self, render_target: &RenderTarget<GLDevice>, viewport: RectI)
                    -> GLTextureDataReceiver {
         let (origin, size) = (viewport.origin(), viewport.size());
@@ -585,9 +626,14 @@ impl Device for GLDevice {
     fn begin_commands(&self) {
         // TODO(pcwalton): Add some checks in debug mode to make sure render commands are bracketed
         // by these?
+
+        // The application may have changed GL state since the last frame.
+        self.invalidate_state_cache();
     }
 
     fn end_commands(&self) {
+        // Don't leave our state behind for the application.
+        self.state_cache.borrow_mut().restore_defaults();
         unsafe { gl::Flush(); }
     }
 
@@ -709,11 +755,7 @@ impl Device for GLDevice {
         name: &str,
         kind: ShaderKind,
     ) -> Self::Shader {
-        let suffix = match kind {
-            ShaderKind::Vertex => 'v',
-            ShaderKind::Fragment => 'f',
-        };
-        let path = format!("shaders/gl3/{}.{}s.glsl", name, suffix);
+        let path = shader_path(name, kind);
         self.create_shader_from_source(name, &resources.slurp(&path).unwrap(), kind)
     }
 }
@@ -727,52 +769,93 @@ impl GLDevice {
     }
 
     fn bind_vertex_array(&self, vertex_array: &GLVertexArray) {
-        unsafe {
-            gl::BindVertexArray(vertex_array.gl_vertex_array); ck();
-        }
+        self.state_cache.borrow_mut().bind_vertex_array(vertex_array.gl_vertex_array);
     }
 
     fn unbind_vertex_array(&self) {
-        unsafe {
-            gl::BindVertexArray(0); ck();
-        }
+        self.state_cache.borrow_mut().bind_vertex_array(0);
     }
 
     fn bind_texture(&self, texture: &GLTexture, unit: u32) {
-        unsafe {
-            gl::ActiveTexture(gl::TEXTURE0 + unit); ck();
-            gl::BindTexture(gl::TEXTURE_2D, texture.gl_texture); ck();
-        }
+        self.state_cache.borrow_mut().bind_texture(texture.gl_texture, unit);
     }
 
-    fn unbind_texture(&self, unit: u32) {
-        unsafe {
-            gl::ActiveTexture(gl::TEXTURE0 + unit); ck();
-            gl::BindTexture(gl::TEXTURE_2D, 0); ck();
-        }
+    fn use_program(&self, program: &GLProgram) {
+        self.state_cache.borrow_mut().use_program(program.gl_program);
     }
 
-    fn use_program(&self, program: &GLProgram) {
-        unsafe {
-            gl::UseProgram(program.gl_program); ck();
-        }
+    fn bind_default_framebuffer(&self) {
+        self.state_cache.borrow_mut().bind_framebuffer(self.default_framebuffer);
     }
 
-    fn unuse_program(&self) {
-        unsafe {
-            gl::UseProgram(0); ck();
-        }
+    fn bind_framebuffer(&self, framebuffer: &GLFramebuffer) {
+        self.state_cache.borrow_mut().bind_framebuffer(framebuffer.gl_framebuffer);
     }
 
-    fn bind_default_framebuffer(&self) {
+    // Returns the info log on failure.
+    fn compile_shader(&self, source: &[u8], kind: ShaderKind) -> Result<GLShader, String> {
+        // FIXME(pcwalton): Do this once and cache it.
+        let glsl_version_spec = self.version.to_glsl_version_spec();
+
+        let mut output = vec![];
+        self.preprocess(&mut output, source, glsl_version_spec);
+        let source = output;
+
+        let gl_shader_kind = match kind {
+            ShaderKind::Vertex => gl::VERTEX_SHADER,
+            ShaderKind::Fragment => gl::FRAGMENT_SHADER,
+        };
+
         unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, self.default_framebuffer); ck();
+            let shader = GLShader { gl_shader: gl::CreateShader(gl_shader_kind) }; ck();
+            gl::ShaderSource(shader.gl_shader,
+                             1,
+                             [source.as_ptr() as *const GLchar].as_ptr(),
+                             [source.len() as GLint].as_ptr()); ck();
+            gl::CompileShader(shader.gl_shader); ck();
+
+            let mut compile_status = 0;
+            gl::GetShaderiv(shader.gl_shader, gl::COMPILE_STATUS, &mut compile_status); ck();
+            if compile_status != gl::TRUE as GLint {
+                let mut info_log_length = 0;
+                gl::GetShaderiv(shader.gl_shader, gl::INFO_LOG_LENGTH, &mut info_log_length); ck();
+                let mut info_log = vec![0; info_log_length as usize];
+                gl::GetShaderInfoLog(shader.gl_shader,
+                                     info_log.len() as GLint,
+                                     ptr::null_mut(),
+                                     info_log.as_mut_ptr() as *mut GLchar); ck();
+                return Err(info_log_to_string(info_log));
+            }
+
+            Ok(shader)
         }
     }
 
-    fn bind_framebuffer(&self, framebuffer: &GLFramebuffer) {
+    // Returns the info log on failure.
+    fn link_program(&self, vertex_shader: GLShader, fragment_shader: GLShader)
+                    -> Result<GLProgram, String> {
         unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.gl_framebuffer); ck();
+            let gl_program = gl::CreateProgram(); ck();
+            self.state_cache.borrow_mut().forget_program(gl_program);
+            let program = GLProgram { gl_program, vertex_shader, fragment_shader };
+            gl::AttachShader(gl_program, program.vertex_shader.gl_shader); ck();
+            gl::AttachShader(gl_program, program.fragment_shader.gl_shader); ck();
+            gl::LinkProgram(gl_program); ck();
+
+            let mut link_status = 0;
+            gl::GetProgramiv(gl_program, gl::LINK_STATUS, &mut link_status); ck();
+            if link_status != gl::TRUE as GLint {
+                let mut info_log_length = 0;
+                gl::GetProgramiv(gl_program, gl::INFO_LOG_LENGTH, &mut info_log_length); ck();
+                let mut info_log = vec![0; info_log_length as usize];
+                gl::GetProgramInfoLog(gl_program,
+                                      info_log.len() as GLint,
+                                      ptr::null_mut(),
+                                      info_log.as_mut_ptr() as *mut GLchar); ck();
+                return Err(info_log_to_string(info_log));
+            }
+
+            Ok(program)
         }
     }
 
@@ -801,18 +884,19 @@ impl GLDevice {
     fn clear(&self, ops: &ClearOps) {
         unsafe {
             let mut flags = 0;
+            let mut state_cache = self.state_cache.borrow_mut();
             if let Some(color) = ops.color {
-                gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE); ck();
+                state_cache.set_color_mask(true);
                 gl::ClearColor(color.r(), color.g(), color.b(), color.a()); ck();
                 flags |= gl::COLOR_BUFFER_BIT;
             }
             if let Some(depth) = ops.depth {
-                gl::DepthMask(gl::TRUE); ck();
+                state_cache.set_depth_mask(true);
                 gl::ClearDepthf(depth as _); ck(); // FIXME(pcwalton): GLES
                 flags |= gl::DEPTH_BUFFER_BIT;
             }
             if let Some(stencil) = ops.stencil {
-                gl::StencilMask(!0); ck();
+                state_cache.set_stencil_mask(!0);
                 gl::ClearStencil(stencil as GLint); ck();
                 flags |= gl::STENCIL_BUFFER_BIT;
             }
@@ -1184,6 +1268,20 @@ impl Drop for GLTextureDataReceiver {
     }
 }
 
+fn shader_path(name: &str, kind: ShaderKind) -> String {
+    let suffix = match kind {
+        ShaderKind::Vertex => 'v',
+        ShaderKind::Fragment => 'f',
+    };
+    format!("shaders/gl3/{}.{}s.glsl", name, suffix)
+}
+
+fn info_log_to_string(mut info_log: Vec<u8>) -> String {
+    // Drop the NUL terminator.
+    info_log.pop();
+    String::from_utf8_lossy(&info_log).into_owned()
+}
+
 /// The version/dialect of OpenGL we should render with.
 #[derive(Clone, Copy)]
 #[repr(u32)]
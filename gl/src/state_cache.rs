@@ -0,0 +1,470 @@
+// pathfinder/gl/src/state_cache.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tracks OpenGL state to skip calls that wouldn't change anything.
+//!
+//! Every field is `None` until the device sets it, so nothing is assumed about state left behind
+//! by other code. Objects are identified by their GL names, which the driver may hand out again
+//! after deletion; callers must `forget_*()` a name when it's generated so that a stale entry
+//! can't match the new object.
+
+use crate::ck;
+use gl::types::{GLboolean, GLenum, GLint, GLuint};
+use pathfinder_geometry::rect::RectI;
+use pathfinder_geometry::vector::vec2i;
+use std::fmt::Debug;
+
+#[derive(Clone, Default)]
+pub(crate) struct GLStateCache {
+    framebuffer: Option<GLuint>,
+    viewport: Option<RectI>,
+    program: Option<GLuint>,
+    vertex_array: Option<GLuint>,
+    active_texture_unit: Option<u32>,
+    textures: Vec<Option<GLuint>>,
+    blend: Option<bool>,
+    blend_func: Option<[GLenum; 4]>,
+    blend_equation: Option<GLenum>,
+    depth_test: Option<bool>,
+    depth_func: Option<GLenum>,
+    depth_mask: Option<bool>,
+    stencil_test: Option<bool>,
+    stencil_func: Option<(GLenum, GLint, GLuint)>,
+    stencil_pass_op: Option<GLenum>,
+    stencil_mask: Option<GLuint>,
+    color_mask: Option<bool>,
+}
+
+impl GLStateCache {
+    pub(crate) fn bind_framebuffer(&mut self, framebuffer: GLuint) {
+        if self.framebuffer != Some(framebuffer) {
+            unsafe {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer); ck();
+            }
+            self.framebuffer = Some(framebuffer);
+        }
+    }
+
+    pub(crate) fn set_viewport(&mut self, viewport: RectI) {
+        if self.viewport != Some(viewport) {
+            let (origin, size) = (viewport.origin(), viewport.size());
+            unsafe {
+                gl::Viewport(origin.x(), origin.y(), size.x(), size.y()); ck();
+            }
+            self.viewport = Some(viewport);
+        }
+    }
+
+    pub(crate) fn use_program(&mut self, program: GLuint) {
+        if self.program != Some(program) {
+            unsafe {
+                gl::UseProgram(program); ck();
+            }
+            self.program = Some(program);
+        }
+    }
+
+    pub(crate) fn bind_vertex_array(&mut self, vertex_array: GLuint) {
+        if self.vertex_array != Some(vertex_array) {
+            unsafe {
+                gl::BindVertexArray(vertex_array); ck();
+            }
+            self.vertex_array = Some(vertex_array);
+        }
+    }
+
+    pub(crate) fn bind_texture(&mut self, texture: GLuint, unit: u32) {
+        let unit_index = unit as usize;
+        if self.textures.len() <= unit_index {
+            self.textures.resize(unit_index + 1, None);
+        }
+        if self.textures[unit_index] == Some(texture) {
+            return;
+        }
+
+        if self.active_texture_unit != Some(unit) {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0 + unit); ck();
+            }
+            self.active_texture_unit = Some(unit);
+        }
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture); ck();
+        }
+        self.textures[unit_index] = Some(texture);
+    }
+
+    pub(crate) fn set_blend(&mut self, enabled: bool) {
+        set_capability(&mut self.blend, gl::BLEND, enabled);
+    }
+
+    pub(crate) fn set_blend_func(&mut self, func: [GLenum; 4]) {
+        if self.blend_func != Some(func) {
+            unsafe {
+                gl::BlendFuncSeparate(func[0], func[1], func[2], func[3]); ck();
+            }
+            self.blend_func = Some(func);
+        }
+    }
+
+    pub(crate) fn set_blend_equation(&mut self, equation: GLenum) {
+        if self.blend_equation != Some(equation) {
+            unsafe {
+                gl::BlendEquation(equation); ck();
+            }
+            self.blend_equation = Some(equation);
+        }
+    }
+
+    pub(crate) fn set_depth_test(&mut self, enabled: bool) {
+        set_capability(&mut self.depth_test, gl::DEPTH_TEST, enabled);
+    }
+
+    pub(crate) fn set_depth_func(&mut self, func: GLenum) {
+        if self.depth_func != Some(func) {
+            unsafe {
+                gl::DepthFunc(func); ck();
+            }
+            self.depth_func = Some(func);
+        }
+    }
+
+    pub(crate) fn set_depth_mask(&mut self, write: bool) {
+        if self.depth_mask != Some(write) {
+            unsafe {
+                gl::DepthMask(write as GLboolean); ck();
+            }
+            self.depth_mask = Some(write);
+        }
+    }
+
+    pub(crate) fn set_stencil_test(&mut self, enabled: bool) {
+        set_capability(&mut self.stencil_test, gl::STENCIL_TEST, enabled);
+    }
+
+    pub(crate) fn set_stencil_func(&mut self, func: GLenum, reference: GLint, mask: GLuint) {
+        if self.stencil_func != Some((func, reference, mask)) {
+            unsafe {
+                gl::StencilFunc(func, reference, mask); ck();
+            }
+            self.stencil_func = Some((func, reference, mask));
+        }
+    }
+
+    pub(crate) fn set_stencil_pass_op(&mut self, pass_op: GLenum) {
+        if self.stencil_pass_op != Some(pass_op) {
+            unsafe {
+                gl::StencilOp(gl::KEEP, gl::KEEP, pass_op); ck();
+            }
+            self.stencil_pass_op = Some(pass_op);
+        }
+    }
+
+    pub(crate) fn set_stencil_mask(&mut self, mask: GLuint) {
+        if self.stencil_mask != Some(mask) {
+            unsafe {
+                gl::StencilMask(mask); ck();
+            }
+            self.stencil_mask = Some(mask);
+        }
+    }
+
+    pub(crate) fn set_color_mask(&mut self, write: bool) {
+        if self.color_mask != Some(write) {
+            let mask = write as GLboolean;
+            unsafe {
+                gl::ColorMask(mask, mask, mask, mask); ck();
+            }
+            self.color_mask = Some(write);
+        }
+    }
+
+    /// Returns the blend, depth, stencil and color mask state, the program, the vertex array and
+    /// the texture bindings to the OpenGL defaults, so that other code sharing the context doesn't
+    /// inherit them.
+    pub(crate) fn restore_defaults(&mut self) {
+        for unit in 0..(self.textures.len() as u32) {
+            self.bind_texture(0, unit);
+        }
+        if self.active_texture_unit != Some(0) {
+            unsafe {
+                gl::ActiveTexture(gl::TEXTURE0); ck();
+            }
+            self.active_texture_unit = Some(0);
+        }
+        self.use_program(0);
+        self.bind_vertex_array(0);
+
+        self.set_blend(false);
+        self.set_blend_func([gl::ONE, gl::ZERO, gl::ONE, gl::ZERO]);
+        self.set_blend_equation(gl::FUNC_ADD);
+        self.set_depth_test(false);
+        self.set_depth_func(gl::LESS);
+        self.set_depth_mask(true);
+        self.set_stencil_test(false);
+        self.set_stencil_func(gl::ALWAYS, 0, !0);
+        self.set_stencil_pass_op(gl::KEEP);
+        self.set_stencil_mask(!0);
+        self.set_color_mask(true);
+    }
+
+    pub(crate) fn forget_framebuffer(&mut self, framebuffer: GLuint) {
+        forget(&mut self.framebuffer, framebuffer);
+    }
+
+    pub(crate) fn forget_program(&mut self, program: GLuint) {
+        forget(&mut self.program, program);
+    }
+
+    pub(crate) fn forget_vertex_array(&mut self, vertex_array: GLuint) {
+        forget(&mut self.vertex_array, vertex_array);
+    }
+
+    pub(crate) fn forget_texture(&mut self, texture: GLuint) {
+        for binding in &mut self.textures {
+            forget(binding, texture);
+        }
+    }
+
+    /// Panics if any known state, including the bindings of the first `texture_unit_count`
+    /// texture units, differs from what the driver reports.
+    ///
+    /// This is slow, because every query stalls the pipeline.
+    pub(crate) fn verify(&self, texture_unit_count: usize) {
+        unsafe {
+            check("framebuffer binding",
+                  self.framebuffer,
+                  get_integer(gl::DRAW_FRAMEBUFFER_BINDING) as GLuint);
+            let mut viewport = [0; 4];
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()); ck();
+            check("viewport",
+                  self.viewport,
+                  RectI::new(vec2i(viewport[0], viewport[1]), vec2i(viewport[2], viewport[3])));
+            check("program", self.program, get_integer(gl::CURRENT_PROGRAM) as GLuint);
+            check("vertex array binding",
+                  self.vertex_array,
+                  get_integer(gl::VERTEX_ARRAY_BINDING) as GLuint);
+
+            let active_texture = get_integer(gl::ACTIVE_TEXTURE) as GLenum;
+            check("active texture unit", self.active_texture_unit, active_texture - gl::TEXTURE0);
+            for (unit, &texture) in self.textures.iter().enumerate().take(texture_unit_count) {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as GLuint); ck();
+                check("texture binding", texture, get_integer(gl::TEXTURE_BINDING_2D) as GLuint);
+            }
+            gl::ActiveTexture(active_texture); ck();
+
+            check("blend", self.blend, gl::IsEnabled(gl::BLEND) != gl::FALSE);
+            check("blend function",
+                  self.blend_func,
+                  [
+                      get_integer(gl::BLEND_SRC_RGB) as GLenum,
+                      get_integer(gl::BLEND_DST_RGB) as GLenum,
+                      get_integer(gl::BLEND_SRC_ALPHA) as GLenum,
+                      get_integer(gl::BLEND_DST_ALPHA) as GLenum,
+                  ]);
+            check("blend equation",
+                  self.blend_equation,
+                  get_integer(gl::BLEND_EQUATION_RGB) as GLenum);
+
+            check("depth test", self.depth_test, gl::IsEnabled(gl::DEPTH_TEST) != gl::FALSE);
+            check("depth function", self.depth_func, get_integer(gl::DEPTH_FUNC) as GLenum);
+            check("depth mask", self.depth_mask, get_boolean(gl::DEPTH_WRITEMASK));
+
+            // Stencil masks are compared in the 8 bits that the default framebuffer has, since
+            // drivers may truncate or clamp the rest when returning them as signed integers.
+            check("stencil test",
+                  self.stencil_test,
+                  gl::IsEnabled(gl::STENCIL_TEST) != gl::FALSE);
+            check("stencil function",
+                  self.stencil_func.map(|(func, reference, mask)| (func, reference, mask & 0xff)),
+                  (get_integer(gl::STENCIL_FUNC) as GLenum,
+                   get_integer(gl::STENCIL_REF),
+                   get_integer(gl::STENCIL_VALUE_MASK) as GLuint & 0xff));
+            check("stencil pass operation",
+                  self.stencil_pass_op,
+                  get_integer(gl::STENCIL_PASS_DEPTH_PASS) as GLenum);
+            check("stencil mask",
+                  self.stencil_mask.map(|mask| mask & 0xff),
+                  get_integer(gl::STENCIL_WRITEMASK) as GLuint & 0xff);
+
+            let mut color_mask = [gl::FALSE; 4];
+            gl::GetBooleanv(gl::COLOR_WRITEMASK, color_mask.as_mut_ptr()); ck();
+            check("color mask", self.color_mask, color_mask[0] != gl::FALSE);
+        }
+    }
+}
+
+fn set_capability(cached: &mut Option<bool>, capability: GLenum, enabled: bool) {
+    if *cached != Some(enabled) {
+        unsafe {
+            if enabled {
+                gl::Enable(capability); ck();
+            } else {
+                gl::Disable(capability); ck();
+            }
+        }
+        *cached = Some(enabled);
+    }
+}
+
+fn forget(cached: &mut Option<GLuint>, name: GLuint) {
+    if *cached == Some(name) {
+        *cached = None;
+    }
+}
+
+fn check<T>(what: &str, cached: Option<T>, actual: T) where T: PartialEq + Debug {
+    if let Some(cached) = cached {
+        assert!(cached == actual,
+                "GL state cache is stale: {} is {:?}, but the cache says {:?}",
+                what,
+                actual,
+                cached);
+    }
+}
+
+unsafe fn get_integer(name: GLenum) -> GLint {
+    let mut value = 0;
+    gl::GetIntegerv(name, &mut value); ck();
+    value
+}
+
+unsafe fn get_boolean(name: GLenum) -> bool {
+    let mut value = gl::FALSE;
+    gl::GetBooleanv(name, &mut value); ck();
+    value != gl::FALSE
+}
+
+#[cfg(all(test, feature = "pf-egl"))]
+mod test {
+    use crate::{GLDevice, GLVersion};
+    use crate::headless::{HeadlessBackend, HeadlessContext};
+    use pathfinder_geometry::rect::RectI;
+    use pathfinder_geometry::vector::{Vector2I, vec2i};
+    use pathfinder_gpu::{BlendState, BufferData, BufferTarget, BufferUploadMode, Device};
+    use pathfinder_gpu::{Primitive, RenderOptions, RenderState, RenderTarget, ShaderKind};
+    use pathfinder_gpu::{TextureData, UniformData, VertexAttrClass, VertexAttrDescriptor};
+    use pathfinder_gpu::VertexAttrType;
+    use pathfinder_simd::default::F32x4;
+
+    static VERTEX_SHADER: &str = "#version {{version}}
+in vec2 aPosition;
+void main() {
+    gl_Position = vec4(aPosition, 0.0, 1.0);
+}
+";
+
+    static FRAGMENT_SHADER: &str = "#version {{version}}
+precision highp float;
+uniform vec4 uColor;
+out vec4 oFragColor;
+void main() {
+    oFragColor = uColor;
+}
+";
+
+    static VERTICES: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+    static INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+    fn draw_quads(mut device: GLDevice, disturb: fn()) -> Vec<u8> {
+        device.set_state_cache_verification(true);
+
+        let vertex_shader =
+            device.create_shader_from_source("test", VERTEX_SHADER.as_bytes(), ShaderKind::Vertex);
+        let fragment_shader = device.create_shader_from_source("test",
+                                                               FRAGMENT_SHADER.as_bytes(),
+                                                               ShaderKind::Fragment);
+        let program = device.create_program_from_shaders(&NoResources,
+                                                          "test",
+                                                          vertex_shader,
+                                                          fragment_shader);
+        let color_uniform = device.get_uniform(&program, "Color");
+        let position_attr = device.get_vertex_attr(&program, "Position").unwrap();
+
+        let vertex_array = device.create_vertex_array();
+        let (vertex_buffer, index_buffer) = (device.create_buffer(), device.create_buffer());
+        device.allocate_buffer(&vertex_buffer,
+                               BufferData::Memory(&VERTICES),
+                               BufferTarget::Vertex,
+                               BufferUploadMode::Static);
+        device.allocate_buffer(&index_buffer,
+                               BufferData::Memory(&INDICES),
+                               BufferTarget::Index,
+                               BufferUploadMode::Static);
+        device.bind_buffer(&vertex_array, &vertex_buffer, BufferTarget::Vertex);
+        device.bind_buffer(&vertex_array, &index_buffer, BufferTarget::Index);
+        device.configure_vertex_attr(&vertex_array, &position_attr, &VertexAttrDescriptor {
+            size: 2,
+            class: VertexAttrClass::Float,
+            attr_type: VertexAttrType::F32,
+            stride: 8,
+            offset: 0,
+            divisor: 0,
+            buffer_index: 0,
+        });
+
+        let viewport = RectI::new(Vector2I::default(), vec2i(4, 4));
+        let draw = |color: F32x4, blend: Option<BlendState>| {
+            device.draw_elements(6, &RenderState {
+                target: &RenderTarget::Default,
+                program: &program,
+                vertex_array: &vertex_array,
+                primitive: Primitive::Triangles,
+                uniforms: &[(&color_uniform, UniformData::Vec4(color))],
+                textures: &[],
+                viewport,
+                options: RenderOptions { blend, ..RenderOptions::default() },
+            });
+        };
+
+        device.begin_commands();
+        draw(F32x4::new(0.0, 0.0, 1.0, 1.0), None);
+        disturb();
+        draw(F32x4::new(1.0, 0.0, 0.0, 0.0), Some(BlendState::default()));
+        draw(F32x4::new(0.0, 1.0, 0.0, 0.0), Some(BlendState::default()));
+        let receiver = device.read_pixels(&RenderTarget::Default, viewport);
+        device.end_commands();
+
+        match device.recv_texture_data(&receiver) {
+            TextureData::U8(pixels) => pixels,
+            _ => panic!("Expected RGBA8 pixels!"),
+        }
+    }
+
+    struct NoResources;
+
+    impl pathfinder_resources::ResourceLoader for NoResources {
+        fn slurp(&self, _: &str) -> Result<Vec<u8>, std::io::Error> {
+            unreachable!()
+        }
+    }
+
+    fn create_context() -> HeadlessContext {
+        HeadlessContext::with_backend(HeadlessBackend::Egl, GLVersion::GL3, vec2i(4, 4)).unwrap()
+    }
+
+    #[test]
+    fn test_cached_state_renders_correctly() {
+        let context = create_context();
+        let pixels = draw_quads(context.create_device(), || {});
+        // The second and third draws blend additively over the first, since `RenderOptions`
+        // changed; the third repeats the second's state, so its calls were skipped.
+        assert!(pixels.chunks(4).all(|pixel| pixel == [255, 255, 255, 255]));
+    }
+
+    #[test]
+    #[should_panic(expected = "GL state cache is stale: color mask")]
+    fn test_verification_catches_external_changes() {
+        let context = create_context();
+        draw_quads(context.create_device(), || unsafe {
+            gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+        });
+    }
+}
@@ -24,6 +24,8 @@ use pathfinder_simd::default::{F32x2, F32x4, I32x2};
 use std::os::raw::c_void;
 use std::time::Duration;
 
+pub mod recording;
+
 pub trait Device: Sized {
     type Buffer;
     type Framebuffer;
@@ -37,6 +39,9 @@ pub trait Device: Sized {
     type VertexAttr;
 
     fn create_texture(&self, format: TextureFormat, size: Vector2I) -> Self::Texture;
+    /// Creates a texture with room for a full chain of mipmap levels, which
+    /// `generate_mipmaps()` fills in from the base level.
+    fn create_mipmapped_texture(&self, format: TextureFormat, size: Vector2I) -> Self::Texture;
     fn create_texture_from_data(&self, format: TextureFormat, size: Vector2I, data: TextureDataRef)
                                 -> Self::Texture;
     fn create_shader(&self, resources: &dyn ResourceLoader, name: &str, kind: ShaderKind)
@@ -76,6 +81,9 @@ pub trait Device: Sized {
     fn texture_size(&self, texture: &Self::Texture) -> Vector2I;
     fn set_texture_sampling_mode(&self, texture: &Self::Texture, flags: TextureSamplingFlags);
     fn upload_to_texture(&self, texture: &Self::Texture, rect: RectI, data: TextureDataRef);
+    /// Regenerates all mipmap levels of a texture created with `create_mipmapped_texture()` from
+    /// its base level.
+    fn generate_mipmaps(&self, texture: &Self::Texture);
     fn read_pixels(&self, target: &RenderTarget<Self>, viewport: RectI)
                    -> Self::TextureDataReceiver;
     fn begin_commands(&self);
@@ -119,6 +127,15 @@ pub trait Device: Sized {
     fn create_program(&self, resources: &dyn ResourceLoader, name: &str) -> Self::Program {
         self.create_program_from_shader_names(resources, name, name, name)
     }
+
+    /// Like `create_program()`, but returns errors in loading, compiling or linking the shaders
+    /// instead of panicking, for reloading shaders while they're being edited.
+    ///
+    /// The default implementation still panics on errors.
+    fn try_create_program(&self, resources: &dyn ResourceLoader, name: &str)
+                          -> Result<Self::Program, String> {
+        Ok(self.create_program(resources, name))
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -163,7 +180,7 @@ pub enum ShaderKind {
     Fragment,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum UniformData {
     Float(f32),
     IVec2(I32x2),
@@ -177,7 +194,7 @@ pub enum UniformData {
     TextureUnit(u32),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum Primitive {
     Triangles,
     Lines,
@@ -405,6 +422,10 @@ bitflags! {
         const REPEAT_V    = 0x02;
         const NEAREST_MIN = 0x04;
         const NEAREST_MAG = 0x08;
+        /// Blends between mipmap levels when minifying. Only valid for mipmapped textures.
+        const MIPMAP_MIN  = 0x10;
+        /// Samples anisotropically when minifying, where the device supports it.
+        const ANISOTROPIC = 0x20;
     }
 }
 
@@ -0,0 +1,1077 @@
+// pathfinder/gpu/src/recording.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A device that records calls instead of rendering.
+//!
+//! `RecordingDevice` needs no GPU, so code written against `Device` can be tested with plain
+//! `cargo test`. Every call is appended to a log of `Command`s that tests can inspect, and
+//! misuse that a real driver would silently accept or crash on (drawing with a destroyed
+//! framebuffer, setting a uniform from another program, uploading data that doesn't fit a
+//! texture, and so on) panics at the offending call. A log can later be replayed on a real
+//! device with `Replayer`.
+
+use crate::{BufferData, BufferTarget, BufferUploadMode, Device, Primitive, RenderOptions};
+use crate::{RenderState, RenderTarget, ShaderKind, TextureData, TextureDataRef, TextureFormat};
+use crate::{TextureSamplingFlags, UniformData, VertexAttrDescriptor};
+use half::f16;
+use pathfinder_geometry::rect::RectI;
+use pathfinder_geometry::vector::Vector2I;
+use pathfinder_resources::ResourceLoader;
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::mem::{self, Discriminant};
+use std::slice;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BufferId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FramebufferId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ProgramId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShaderId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimerQueryId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UniformId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexArrayId(pub u32);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexAttrId(pub u32);
+
+/// A framebuffer created by a `RecordingDevice`.
+#[derive(Debug)]
+pub struct RecordedFramebuffer {
+    pub id: FramebufferId,
+    pub texture: TextureId,
+}
+
+/// One call made on a `RecordingDevice`, with all data copied out.
+#[derive(Clone, Debug)]
+pub enum Command {
+    CreateTexture { texture: TextureId, format: TextureFormat, size: Vector2I },
+    CreateMipmappedTexture { texture: TextureId, format: TextureFormat, size: Vector2I },
+    CreateTextureFromData {
+        texture: TextureId,
+        format: TextureFormat,
+        size: Vector2I,
+        data: TextureData,
+    },
+    CreateShader { shader: ShaderId, name: String, kind: ShaderKind },
+    CreateShaderFromSource { shader: ShaderId, name: String, source: Vec<u8>, kind: ShaderKind },
+    CreateVertexArray { vertex_array: VertexArrayId },
+    CreateProgram {
+        program: ProgramId,
+        name: String,
+        vertex_shader: ShaderId,
+        fragment_shader: ShaderId,
+    },
+    GetVertexAttr { program: ProgramId, name: String, attr: VertexAttrId },
+    GetUniform { program: ProgramId, name: String, uniform: UniformId },
+    BindBuffer { vertex_array: VertexArrayId, buffer: BufferId, target: BufferTarget },
+    ConfigureVertexAttr {
+        vertex_array: VertexArrayId,
+        attr: VertexAttrId,
+        descriptor: VertexAttrDescriptor,
+    },
+    CreateFramebuffer { framebuffer: FramebufferId, texture: TextureId },
+    CreateBuffer { buffer: BufferId },
+    AllocateBuffer {
+        buffer: BufferId,
+        data: RecordedBufferData,
+        target: BufferTarget,
+        mode: BufferUploadMode,
+    },
+    DestroyFramebuffer { framebuffer: FramebufferId },
+    SetTextureSamplingMode { texture: TextureId, flags: TextureSamplingFlags },
+    UploadToTexture { texture: TextureId, rect: RectI, data: TextureData },
+    GenerateMipmaps { texture: TextureId },
+    ReadPixels { target: RecordedRenderTarget, viewport: RectI },
+    BeginCommands,
+    EndCommands,
+    Draw { call: DrawCall, index_count: u32, render_state: RecordedRenderState },
+    CreateTimerQuery { query: TimerQueryId },
+    BeginTimerQuery { query: TimerQueryId },
+    EndTimerQuery { query: TimerQueryId },
+}
+
+/// The contents of a buffer upload, as bytes.
+#[derive(Clone, Debug)]
+pub enum RecordedBufferData {
+    Uninitialized(usize),
+    Memory(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawCall {
+    Arrays,
+    Elements,
+    ElementsInstanced { instance_count: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordedRenderTarget {
+    Default,
+    Framebuffer(FramebufferId),
+}
+
+/// A `RenderState` with handles replaced by IDs.
+#[derive(Clone, Debug)]
+pub struct RecordedRenderState {
+    pub target: RecordedRenderTarget,
+    pub program: ProgramId,
+    pub vertex_array: VertexArrayId,
+    pub primitive: Primitive,
+    pub uniforms: Vec<(UniformId, UniformData)>,
+    pub textures: Vec<TextureId>,
+    pub viewport: RectI,
+    pub options: RenderOptions,
+}
+
+/// A `Device` that records and validates calls without rendering anything.
+///
+/// Pixels read back from it are always zero, and timer queries always report zero time.
+pub struct RecordingDevice {
+    default_framebuffer_size: Vector2I,
+    state: RefCell<RecordingState>,
+}
+
+#[derive(Default)]
+struct RecordingState {
+    commands: Vec<Command>,
+    textures: Vec<TextureInfo>,
+    framebuffers: Vec<FramebufferInfo>,
+    shaders: Vec<ShaderKind>,
+    programs: Vec<String>,
+    uniforms: Vec<UniformInfo>,
+    vertex_arrays: Vec<VertexArrayInfo>,
+    vertex_attrs: Vec<ProgramId>,
+    buffers: Vec<Option<usize>>,
+    timer_queries: Vec<TimerQueryState>,
+    in_commands: bool,
+}
+
+struct TextureInfo {
+    format: TextureFormat,
+    size: Vector2I,
+    mipmapped: bool,
+}
+
+struct FramebufferInfo {
+    texture: TextureId,
+    alive: bool,
+}
+
+struct UniformInfo {
+    program: ProgramId,
+    name: String,
+    kind: Option<Discriminant<UniformData>>,
+}
+
+#[derive(Default)]
+struct VertexArrayInfo {
+    vertex_buffer: Option<BufferId>,
+    index_buffer: Option<BufferId>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TimerQueryState {
+    Idle,
+    Running,
+    Ended,
+}
+
+impl RecordingDevice {
+    /// Creates a device whose default framebuffer has the given size.
+    pub fn new(default_framebuffer_size: Vector2I) -> RecordingDevice {
+        RecordingDevice { default_framebuffer_size, state: RefCell::new(RecordingState::default()) }
+    }
+
+    /// Returns every call recorded so far, oldest first.
+    #[inline]
+    pub fn commands(&self) -> Ref<'_, [Command]> {
+        Ref::map(self.state.borrow(), |state| &state.commands[..])
+    }
+
+    /// Returns the draw calls recorded so far, oldest first.
+    pub fn draw_calls(&self) -> Vec<(DrawCall, u32, RecordedRenderState)> {
+        self.commands().iter().filter_map(|command| {
+            match *command {
+                Command::Draw { call, index_count, ref render_state } => {
+                    Some((call, index_count, render_state.clone()))
+                }
+                _ => None,
+            }
+        }).collect()
+    }
+
+    /// Returns the name passed to `create_program` for `program`.
+    pub fn program_name(&self, program: ProgramId) -> String {
+        self.state.borrow().programs[program.0 as usize].clone()
+    }
+
+    fn record(&self, command: Command) {
+        self.state.borrow_mut().commands.push(command);
+    }
+
+    fn render_target_size(&self, state: &RecordingState, target: &RecordedRenderTarget)
+                          -> Vector2I {
+        match *target {
+            RecordedRenderTarget::Default => self.default_framebuffer_size,
+            RecordedRenderTarget::Framebuffer(framebuffer) => {
+                let framebuffer = &state.framebuffers[framebuffer.0 as usize];
+                assert!(framebuffer.alive, "Render target is a destroyed framebuffer!");
+                state.textures[framebuffer.texture.0 as usize].size
+            }
+        }
+    }
+
+    fn draw(&self, call: DrawCall, index_count: u32, render_state: &RenderState<Self>) {
+        let render_state = RecordedRenderState::from_render_state(render_state);
+        {
+            let state = self.state.borrow();
+            validate_draw(self, &state, call, index_count, &render_state);
+        }
+
+        let mut state = self.state.borrow_mut();
+        for &(uniform, data) in &render_state.uniforms {
+            state.uniforms[uniform.0 as usize].kind = Some(mem::discriminant(&data));
+        }
+        state.commands.push(Command::Draw { call, index_count, render_state });
+    }
+}
+
+fn validate_draw(device: &RecordingDevice,
+                 state: &RecordingState,
+                 call: DrawCall,
+                 index_count: u32,
+                 render_state: &RecordedRenderState) {
+    let target_size = device.render_target_size(state, &render_state.target);
+    assert!(rect_fits_within(render_state.viewport, target_size),
+            "Viewport {:?} exceeds render target bounds {:?}!",
+            render_state.viewport,
+            target_size);
+
+    for &(uniform, data) in &render_state.uniforms {
+        let info = &state.uniforms[uniform.0 as usize];
+        assert_eq!(info.program,
+                   render_state.program,
+                   "Uniform `{}` belongs to program `{}`, not `{}`!",
+                   info.name,
+                   state.programs[info.program.0 as usize],
+                   state.programs[render_state.program.0 as usize]);
+        if let Some(kind) = info.kind {
+            assert!(kind == mem::discriminant(&data),
+                    "Uniform `{}` was set with a different type before!",
+                    info.name);
+        }
+        if let UniformData::TextureUnit(unit) = data {
+            assert!((unit as usize) < render_state.textures.len(),
+                    "Uniform `{}` refers to texture unit {}, but only {} textures are bound!",
+                    info.name,
+                    unit,
+                    render_state.textures.len());
+        }
+    }
+
+    if let RecordedRenderTarget::Framebuffer(framebuffer) = render_state.target {
+        let target_texture = state.framebuffers[framebuffer.0 as usize].texture;
+        assert!(!render_state.textures.contains(&target_texture),
+                "A framebuffer's texture is bound while rendering to it!");
+    }
+
+    let vertex_array = &state.vertex_arrays[render_state.vertex_array.0 as usize];
+    assert!(vertex_array.vertex_buffer.is_some(), "Drawing without a vertex buffer!");
+    match call {
+        DrawCall::Arrays => {}
+        DrawCall::Elements | DrawCall::ElementsInstanced { .. } => {
+            let index_buffer = vertex_array.index_buffer.expect("Drawing without an index buffer!");
+            let index_buffer_size = state.buffers[index_buffer.0 as usize]
+                                         .expect("Drawing with an unallocated index buffer!");
+            assert!(index_count as usize * mem::size_of::<u32>() <= index_buffer_size,
+                    "Drawing {} indices, but the index buffer only holds {} bytes!",
+                    index_count,
+                    index_buffer_size);
+        }
+    }
+}
+
+fn validate_texture_data(format: TextureFormat, size: Vector2I, data: &TextureDataRef) {
+    let length = match (format, *data) {
+        (TextureFormat::R8, TextureDataRef::U8(data)) |
+        (TextureFormat::RGBA8, TextureDataRef::U8(data)) => data.len(),
+        (TextureFormat::R16F, TextureDataRef::F16(data)) |
+        (TextureFormat::RGBA16F, TextureDataRef::F16(data)) => data.len(),
+        (TextureFormat::RGBA32F, TextureDataRef::F32(data)) => data.len(),
+        _ => panic!("Texture data doesn't match texture format {:?}!", format),
+    };
+    let expected_length = size.x() as usize * size.y() as usize * format.channels();
+    assert!(length >= expected_length,
+            "Texture data has {} values, but {:?} needs {}!",
+            length,
+            size,
+            expected_length);
+}
+
+fn rect_fits_within(rect: RectI, size: Vector2I) -> bool {
+    rect.min_x() >= 0 && rect.min_y() >= 0 && rect.max_x() <= size.x() && rect.max_y() <= size.y()
+}
+
+fn texture_data_to_owned(data: TextureDataRef) -> TextureData {
+    match data {
+        TextureDataRef::U8(data) => TextureData::U8(data.to_vec()),
+        TextureDataRef::F16(data) => TextureData::F16(data.to_vec()),
+        TextureDataRef::F32(data) => TextureData::F32(data.to_vec()),
+    }
+}
+
+fn texture_data_as_ref(data: &TextureData) -> TextureDataRef<'_> {
+    match *data {
+        TextureData::U8(ref data) => TextureDataRef::U8(data),
+        TextureData::F16(ref data) => TextureDataRef::F16(data),
+        TextureData::F32(ref data) => TextureDataRef::F32(data),
+        TextureData::U16(_) => panic!("Can't upload `u16` texture data!"),
+    }
+}
+
+impl RecordedRenderState {
+    fn from_render_state(render_state: &RenderState<RecordingDevice>) -> RecordedRenderState {
+        RecordedRenderState {
+            target: match *render_state.target {
+                RenderTarget::Default => RecordedRenderTarget::Default,
+                RenderTarget::Framebuffer(framebuffer) => {
+                    RecordedRenderTarget::Framebuffer(framebuffer.id)
+                }
+            },
+            program: *render_state.program,
+            vertex_array: *render_state.vertex_array,
+            primitive: render_state.primitive,
+            uniforms: render_state.uniforms
+                                  .iter()
+                                  .map(|&(&uniform, data)| (uniform, data))
+                                  .collect(),
+            textures: render_state.textures.iter().map(|&&texture| texture).collect(),
+            viewport: render_state.viewport,
+            options: render_state.options.clone(),
+        }
+    }
+}
+
+impl Device for RecordingDevice {
+    type Buffer = BufferId;
+    type Framebuffer = RecordedFramebuffer;
+    type Program = ProgramId;
+    type Shader = ShaderId;
+    type Texture = TextureId;
+    type TextureDataReceiver = TextureData;
+    type TimerQuery = TimerQueryId;
+    type Uniform = UniformId;
+    type VertexArray = VertexArrayId;
+    type VertexAttr = VertexAttrId;
+
+    fn create_texture(&self, format: TextureFormat, size: Vector2I) -> TextureId {
+        assert!(size.x() > 0 && size.y() > 0, "Texture size {:?} is empty!", size);
+        let mut state = self.state.borrow_mut();
+        let texture = TextureId(state.textures.len() as u32);
+        state.textures.push(TextureInfo { format, size, mipmapped: false });
+        state.commands.push(Command::CreateTexture { texture, format, size });
+        texture
+    }
+
+    fn create_mipmapped_texture(&self, format: TextureFormat, size: Vector2I) -> TextureId {
+        let texture = self.create_texture(format, size);
+        let mut state = self.state.borrow_mut();
+        state.commands.pop();
+        state.textures[texture.0 as usize].mipmapped = true;
+        state.commands.push(Command::CreateMipmappedTexture { texture, format, size });
+        texture
+    }
+
+    fn create_texture_from_data(&self, format: TextureFormat, size: Vector2I, data: TextureDataRef)
+                                -> TextureId {
+        validate_texture_data(format, size, &data);
+        let texture = self.create_texture(format, size);
+        let mut state = self.state.borrow_mut();
+        state.commands.pop();
+        let data = texture_data_to_owned(data);
+        state.commands.push(Command::CreateTextureFromData { texture, format, size, data });
+        texture
+    }
+
+    fn create_shader(&self, _: &dyn ResourceLoader, name: &str, kind: ShaderKind) -> ShaderId {
+        let mut state = self.state.borrow_mut();
+        let shader = ShaderId(state.shaders.len() as u32);
+        state.shaders.push(kind);
+        state.commands.push(Command::CreateShader { shader, name: name.to_owned(), kind });
+        shader
+    }
+
+    fn create_shader_from_source(&self, name: &str, source: &[u8], kind: ShaderKind)
+                                 -> ShaderId {
+        let mut state = self.state.borrow_mut();
+        let shader = ShaderId(state.shaders.len() as u32);
+        state.shaders.push(kind);
+        state.commands.push(Command::CreateShaderFromSource {
+            shader,
+            name: name.to_owned(),
+            source: source.to_vec(),
+            kind,
+        });
+        shader
+    }
+
+    fn create_vertex_array(&self) -> VertexArrayId {
+        let mut state = self.state.borrow_mut();
+        let vertex_array = VertexArrayId(state.vertex_arrays.len() as u32);
+        state.vertex_arrays.push(VertexArrayInfo::default());
+        state.commands.push(Command::CreateVertexArray { vertex_array });
+        vertex_array
+    }
+
+    fn create_program_from_shaders(&self,
+                                   _: &dyn ResourceLoader,
+                                   name: &str,
+                                   vertex_shader: ShaderId,
+                                   fragment_shader: ShaderId)
+                                   -> ProgramId {
+        let mut state = self.state.borrow_mut();
+        assert_eq!(state.shaders[vertex_shader.0 as usize],
+                   ShaderKind::Vertex,
+                   "Program `{}` has a fragment shader in the vertex stage!",
+                   name);
+        assert_eq!(state.shaders[fragment_shader.0 as usize],
+                   ShaderKind::Fragment,
+                   "Program `{}` has a vertex shader in the fragment stage!",
+                   name);
+        let program = ProgramId(state.programs.len() as u32);
+        state.programs.push(name.to_owned());
+        state.commands.push(Command::CreateProgram {
+            program,
+            name: name.to_owned(),
+            vertex_shader,
+            fragment_shader,
+        });
+        program
+    }
+
+    fn get_vertex_attr(&self, program: &ProgramId, name: &str) -> Option<VertexAttrId> {
+        let mut state = self.state.borrow_mut();
+        let attr = VertexAttrId(state.vertex_attrs.len() as u32);
+        state.vertex_attrs.push(*program);
+        state.commands.push(Command::GetVertexAttr {
+            program: *program,
+            name: name.to_owned(),
+            attr,
+        });
+        Some(attr)
+    }
+
+    fn get_uniform(&self, program: &ProgramId, name: &str) -> UniformId {
+        let mut state = self.state.borrow_mut();
+        let uniform = UniformId(state.uniforms.len() as u32);
+        state.uniforms.push(UniformInfo { program: *program, name: name.to_owned(), kind: None });
+        state.commands.push(Command::GetUniform {
+            program: *program,
+            name: name.to_owned(),
+            uniform,
+        });
+        uniform
+    }
+
+    fn bind_buffer(&self, vertex_array: &VertexArrayId, buffer: &BufferId, target: BufferTarget) {
+        let mut state = self.state.borrow_mut();
+        let vertex_array_info = &mut state.vertex_arrays[vertex_array.0 as usize];
+        match target {
+            BufferTarget::Vertex => vertex_array_info.vertex_buffer = Some(*buffer),
+            BufferTarget::Index => vertex_array_info.index_buffer = Some(*buffer),
+        }
+        state.commands.push(Command::BindBuffer {
+            vertex_array: *vertex_array,
+            buffer: *buffer,
+            target,
+        });
+    }
+
+    fn configure_vertex_attr(&self,
+                             vertex_array: &VertexArrayId,
+                             attr: &VertexAttrId,
+                             descriptor: &VertexAttrDescriptor) {
+        let mut state = self.state.borrow_mut();
+        assert!(state.vertex_arrays[vertex_array.0 as usize].vertex_buffer.is_some(),
+                "Configuring a vertex attribute with no vertex buffer bound!");
+        assert!((1..=4).contains(&descriptor.size),
+                "Vertex attributes must have 1 to 4 components, not {}!",
+                descriptor.size);
+        state.commands.push(Command::ConfigureVertexAttr {
+            vertex_array: *vertex_array,
+            attr: *attr,
+            descriptor: *descriptor,
+        });
+    }
+
+    fn create_framebuffer(&self, texture: TextureId) -> RecordedFramebuffer {
+        let mut state = self.state.borrow_mut();
+        let id = FramebufferId(state.framebuffers.len() as u32);
+        state.framebuffers.push(FramebufferInfo { texture, alive: true });
+        state.commands.push(Command::CreateFramebuffer { framebuffer: id, texture });
+        RecordedFramebuffer { id, texture }
+    }
+
+    fn create_buffer(&self) -> BufferId {
+        let mut state = self.state.borrow_mut();
+        let buffer = BufferId(state.buffers.len() as u32);
+        state.buffers.push(None);
+        state.commands.push(Command::CreateBuffer { buffer });
+        buffer
+    }
+
+    fn allocate_buffer<T>(&self,
+                          buffer: &BufferId,
+                          data: BufferData<T>,
+                          target: BufferTarget,
+                          mode: BufferUploadMode) {
+        let data = match data {
+            BufferData::Uninitialized(length) => {
+                RecordedBufferData::Uninitialized(length * mem::size_of::<T>())
+            }
+            BufferData::Memory(data) => unsafe {
+                let byte_length = mem::size_of_val(data);
+                let bytes = slice::from_raw_parts(data.as_ptr() as *const u8, byte_length);
+                RecordedBufferData::Memory(bytes.to_vec())
+            },
+        };
+
+        let mut state = self.state.borrow_mut();
+        state.buffers[buffer.0 as usize] = Some(match data {
+            RecordedBufferData::Uninitialized(byte_length) => byte_length,
+            RecordedBufferData::Memory(ref bytes) => bytes.len(),
+        });
+        state.commands.push(Command::AllocateBuffer { buffer: *buffer, data, target, mode });
+    }
+
+    #[inline]
+    fn framebuffer_texture<'f>(&self, framebuffer: &'f RecordedFramebuffer) -> &'f TextureId {
+        &framebuffer.texture
+    }
+
+    fn destroy_framebuffer(&self, framebuffer: RecordedFramebuffer) -> TextureId {
+        let mut state = self.state.borrow_mut();
+        let info = &mut state.framebuffers[framebuffer.id.0 as usize];
+        assert!(info.alive, "Framebuffer destroyed twice!");
+        info.alive = false;
+        state.commands.push(Command::DestroyFramebuffer { framebuffer: framebuffer.id });
+        framebuffer.texture
+    }
+
+    fn texture_format(&self, texture: &TextureId) -> TextureFormat {
+        self.state.borrow().textures[texture.0 as usize].format
+    }
+
+    fn texture_size(&self, texture: &TextureId) -> Vector2I {
+        self.state.borrow().textures[texture.0 as usize].size
+    }
+
+    fn set_texture_sampling_mode(&self, texture: &TextureId, flags: TextureSamplingFlags) {
+        assert!(!flags.contains(TextureSamplingFlags::MIPMAP_MIN) ||
+                self.state.borrow().textures[texture.0 as usize].mipmapped,
+                "Texture {:?} samples mipmaps but wasn't created with any!",
+                texture);
+        self.record(Command::SetTextureSamplingMode { texture: *texture, flags });
+    }
+
+    fn upload_to_texture(&self, texture: &TextureId, rect: RectI, data: TextureDataRef) {
+        {
+            let state = self.state.borrow();
+            let info = &state.textures[texture.0 as usize];
+            assert!(rect_fits_within(rect, info.size),
+                    "Upload rect {:?} exceeds texture size {:?}!",
+                    rect,
+                    info.size);
+            validate_texture_data(info.format, rect.size(), &data);
+        }
+        self.record(Command::UploadToTexture {
+            texture: *texture,
+            rect,
+            data: texture_data_to_owned(data),
+        });
+    }
+
+    fn generate_mipmaps(&self, texture: &TextureId) {
+        assert!(self.state.borrow().textures[texture.0 as usize].mipmapped,
+                "Texture {:?} wasn't created with mipmap levels!",
+                texture);
+        self.record(Command::GenerateMipmaps { texture: *texture });
+    }
+
+    fn read_pixels(&self, target: &RenderTarget<Self>, viewport: RectI) -> TextureData {
+        let target = match *target {
+            RenderTarget::Default => RecordedRenderTarget::Default,
+            RenderTarget::Framebuffer(framebuffer) => {
+                RecordedRenderTarget::Framebuffer(framebuffer.id)
+            }
+        };
+
+        let format = {
+            let state = self.state.borrow();
+            let target_size = self.render_target_size(&state, &target);
+            assert!(rect_fits_within(viewport, target_size),
+                    "Read rect {:?} exceeds render target bounds {:?}!",
+                    viewport,
+                    target_size);
+            match target {
+                RecordedRenderTarget::Default => TextureFormat::RGBA8,
+                RecordedRenderTarget::Framebuffer(framebuffer) => {
+                    let texture = state.framebuffers[framebuffer.0 as usize].texture;
+                    state.textures[texture.0 as usize].format
+                }
+            }
+        };
+
+        self.record(Command::ReadPixels { target, viewport });
+
+        let length = viewport.size().x() as usize * viewport.size().y() as usize *
+            format.channels();
+        match format {
+            TextureFormat::R8 | TextureFormat::RGBA8 => TextureData::U8(vec![0; length]),
+            TextureFormat::R16F | TextureFormat::RGBA16F => {
+                TextureData::F16(vec![f16::default(); length])
+            }
+            TextureFormat::RGBA32F => TextureData::F32(vec![0.0; length]),
+        }
+    }
+
+    fn begin_commands(&self) {
+        let mut state = self.state.borrow_mut();
+        assert!(!state.in_commands, "`begin_commands()` called twice!");
+        state.in_commands = true;
+        state.commands.push(Command::BeginCommands);
+    }
+
+    fn end_commands(&self) {
+        let mut state = self.state.borrow_mut();
+        assert!(state.in_commands, "`end_commands()` called without `begin_commands()`!");
+        state.in_commands = false;
+        state.commands.push(Command::EndCommands);
+    }
+
+    fn draw_arrays(&self, index_count: u32, render_state: &RenderState<Self>) {
+        self.draw(DrawCall::Arrays, index_count, render_state);
+    }
+
+    fn draw_elements(&self, index_count: u32, render_state: &RenderState<Self>) {
+        self.draw(DrawCall::Elements, index_count, render_state);
+    }
+
+    fn draw_elements_instanced(&self,
+                               index_count: u32,
+                               instance_count: u32,
+                               render_state: &RenderState<Self>) {
+        self.draw(DrawCall::ElementsInstanced { instance_count }, index_count, render_state);
+    }
+
+    fn create_timer_query(&self) -> TimerQueryId {
+        let mut state = self.state.borrow_mut();
+        let query = TimerQueryId(state.timer_queries.len() as u32);
+        state.timer_queries.push(TimerQueryState::Idle);
+        state.commands.push(Command::CreateTimerQuery { query });
+        query
+    }
+
+    fn begin_timer_query(&self, query: &TimerQueryId) {
+        let mut state = self.state.borrow_mut();
+        assert!(state.timer_queries.iter().all(|&query| query != TimerQueryState::Running),
+                "Timer queries can't overlap!");
+        state.timer_queries[query.0 as usize] = TimerQueryState::Running;
+        state.commands.push(Command::BeginTimerQuery { query: *query });
+    }
+
+    fn end_timer_query(&self, query: &TimerQueryId) {
+        let mut state = self.state.borrow_mut();
+        assert!(state.timer_queries[query.0 as usize] == TimerQueryState::Running,
+                "Ending a timer query that wasn't begun!");
+        state.timer_queries[query.0 as usize] = TimerQueryState::Ended;
+        state.commands.push(Command::EndTimerQuery { query: *query });
+    }
+
+    fn try_recv_timer_query(&self, query: &TimerQueryId) -> Option<Duration> {
+        Some(self.recv_timer_query(query))
+    }
+
+    fn recv_timer_query(&self, query: &TimerQueryId) -> Duration {
+        assert!(self.state.borrow().timer_queries[query.0 as usize] == TimerQueryState::Ended,
+                "Receiving a timer query that hasn't ended!");
+        Duration::default()
+    }
+
+    fn try_recv_texture_data(&self, receiver: &TextureData) -> Option<TextureData> {
+        Some(receiver.clone())
+    }
+
+    fn recv_texture_data(&self, receiver: &TextureData) -> TextureData {
+        receiver.clone()
+    }
+}
+
+/// Replays recorded commands on another device.
+///
+/// Objects are created on first use and kept across calls to `replay()`, so a recording can be
+/// replayed in pieces, e.g. frame by frame.
+pub struct Replayer<'a, D> where D: Device {
+    device: &'a D,
+    textures: HashMap<TextureId, D::Texture>,
+    framebuffers: HashMap<FramebufferId, D::Framebuffer>,
+    framebuffer_textures: HashMap<TextureId, FramebufferId>,
+    shaders: HashMap<ShaderId, D::Shader>,
+    programs: HashMap<ProgramId, D::Program>,
+    uniforms: HashMap<UniformId, D::Uniform>,
+    vertex_arrays: HashMap<VertexArrayId, D::VertexArray>,
+    vertex_attrs: HashMap<VertexAttrId, D::VertexAttr>,
+    buffers: HashMap<BufferId, D::Buffer>,
+    timer_queries: HashMap<TimerQueryId, D::TimerQuery>,
+}
+
+impl<'a, D> Replayer<'a, D> where D: Device {
+    pub fn new(device: &'a D) -> Replayer<'a, D> {
+        Replayer {
+            device,
+            textures: HashMap::new(),
+            framebuffers: HashMap::new(),
+            framebuffer_textures: HashMap::new(),
+            shaders: HashMap::new(),
+            programs: HashMap::new(),
+            uniforms: HashMap::new(),
+            vertex_arrays: HashMap::new(),
+            vertex_attrs: HashMap::new(),
+            buffers: HashMap::new(),
+            timer_queries: HashMap::new(),
+        }
+    }
+
+    /// Replays `commands` in order. Shaders are loaded from `resources` by name.
+    pub fn replay(&mut self, commands: &[Command], resources: &dyn ResourceLoader) {
+        for command in commands {
+            self.replay_command(command, resources);
+        }
+    }
+
+    fn replay_command(&mut self, command: &Command, resources: &dyn ResourceLoader) {
+        let device = self.device;
+        match *command {
+            Command::CreateTexture { texture, format, size } => {
+                self.textures.insert(texture, device.create_texture(format, size));
+            }
+            Command::CreateMipmappedTexture { texture, format, size } => {
+                self.textures.insert(texture, device.create_mipmapped_texture(format, size));
+            }
+            Command::CreateTextureFromData { texture, format, size, ref data } => {
+                let data = texture_data_as_ref(data);
+                self.textures.insert(texture, device.create_texture_from_data(format, size, data));
+            }
+            Command::CreateShader { shader, ref name, kind } => {
+                self.shaders.insert(shader, device.create_shader(resources, name, kind));
+            }
+            Command::CreateShaderFromSource { shader, ref name, ref source, kind } => {
+                let new_shader = device.create_shader_from_source(name, source, kind);
+                self.shaders.insert(shader, new_shader);
+            }
+            Command::CreateVertexArray { vertex_array } => {
+                self.vertex_arrays.insert(vertex_array, device.create_vertex_array());
+            }
+            Command::CreateProgram { program, ref name, vertex_shader, fragment_shader } => {
+                let vertex_shader = self.shaders.remove(&vertex_shader).unwrap();
+                let fragment_shader = self.shaders.remove(&fragment_shader).unwrap();
+                let new_program = device.create_program_from_shaders(resources,
+                                                                     name,
+                                                                     vertex_shader,
+                                                                     fragment_shader);
+                self.programs.insert(program, new_program);
+            }
+            Command::GetVertexAttr { program, ref name, attr } => {
+                if let Some(new_attr) = device.get_vertex_attr(&self.programs[&program], name) {
+                    self.vertex_attrs.insert(attr, new_attr);
+                }
+            }
+            Command::GetUniform { program, ref name, uniform } => {
+                let new_uniform = device.get_uniform(&self.programs[&program], name);
+                self.uniforms.insert(uniform, new_uniform);
+            }
+            Command::BindBuffer { vertex_array, buffer, target } => {
+                device.bind_buffer(&self.vertex_arrays[&vertex_array],
+                                   &self.buffers[&buffer],
+                                   target);
+            }
+            Command::ConfigureVertexAttr { vertex_array, attr, ref descriptor } => {
+                // Attributes that the shader compiler optimized out have nothing to configure.
+                if let Some(attr) = self.vertex_attrs.get(&attr) {
+                    device.configure_vertex_attr(&self.vertex_arrays[&vertex_array],
+                                                 attr,
+                                                 descriptor);
+                }
+            }
+            Command::CreateFramebuffer { framebuffer, texture } => {
+                let new_texture = self.textures.remove(&texture).unwrap();
+                self.framebuffers.insert(framebuffer, device.create_framebuffer(new_texture));
+                self.framebuffer_textures.insert(texture, framebuffer);
+            }
+            Command::CreateBuffer { buffer } => {
+                self.buffers.insert(buffer, device.create_buffer());
+            }
+            Command::AllocateBuffer { buffer, ref data, target, mode } => {
+                let data = match *data {
+                    RecordedBufferData::Uninitialized(length) => BufferData::Uninitialized(length),
+                    RecordedBufferData::Memory(ref bytes) => BufferData::Memory(&bytes[..]),
+                };
+                device.allocate_buffer::<u8>(&self.buffers[&buffer], data, target, mode);
+            }
+            Command::DestroyFramebuffer { framebuffer } => {
+                let framebuffer_object = self.framebuffers.remove(&framebuffer).unwrap();
+                let texture = device.destroy_framebuffer(framebuffer_object);
+                let texture_id = *self.framebuffer_textures
+                                      .iter()
+                                      .find(|&(_, &owner)| owner == framebuffer)
+                                      .unwrap()
+                                      .0;
+                self.framebuffer_textures.remove(&texture_id);
+                self.textures.insert(texture_id, texture);
+            }
+            Command::SetTextureSamplingMode { texture, flags } => {
+                device.set_texture_sampling_mode(self.texture(texture), flags);
+            }
+            Command::UploadToTexture { texture, rect, ref data } => {
+                device.upload_to_texture(self.texture(texture), rect, texture_data_as_ref(data));
+            }
+            Command::GenerateMipmaps { texture } => {
+                device.generate_mipmaps(self.texture(texture));
+            }
+            Command::ReadPixels { target, viewport } => {
+                let target = self.render_target(target);
+                let receiver = device.read_pixels(&target, viewport);
+                device.recv_texture_data(&receiver);
+            }
+            Command::BeginCommands => device.begin_commands(),
+            Command::EndCommands => device.end_commands(),
+            Command::Draw { call, index_count, ref render_state } => {
+                self.replay_draw(call, index_count, render_state);
+            }
+            Command::CreateTimerQuery { query } => {
+                self.timer_queries.insert(query, device.create_timer_query());
+            }
+            Command::BeginTimerQuery { query } => {
+                device.begin_timer_query(&self.timer_queries[&query]);
+            }
+            Command::EndTimerQuery { query } => {
+                device.end_timer_query(&self.timer_queries[&query]);
+            }
+        }
+    }
+
+    fn replay_draw(&self, call: DrawCall, index_count: u32, render_state: &RecordedRenderState) {
+        let target = self.render_target(render_state.target);
+        let uniforms: Vec<_> = render_state.uniforms.iter().map(|&(uniform, data)| {
+            (&self.uniforms[&uniform], data)
+        }).collect();
+        let textures: Vec<_> =
+            render_state.textures.iter().map(|&texture| self.texture(texture)).collect();
+        let render_state = RenderState {
+            target: &target,
+            program: &self.programs[&render_state.program],
+            vertex_array: &self.vertex_arrays[&render_state.vertex_array],
+            primitive: render_state.primitive,
+            uniforms: &uniforms,
+            textures: &textures,
+            viewport: render_state.viewport,
+            options: render_state.options.clone(),
+        };
+
+        match call {
+            DrawCall::Arrays => self.device.draw_arrays(index_count, &render_state),
+            DrawCall::Elements => self.device.draw_elements(index_count, &render_state),
+            DrawCall::ElementsInstanced { instance_count } => {
+                self.device.draw_elements_instanced(index_count, instance_count, &render_state)
+            }
+        }
+    }
+
+    fn texture(&self, texture: TextureId) -> &D::Texture {
+        match self.framebuffer_textures.get(&texture) {
+            Some(framebuffer) => self.device.framebuffer_texture(&self.framebuffers[framebuffer]),
+            None => &self.textures[&texture],
+        }
+    }
+
+    fn render_target(&self, target: RecordedRenderTarget) -> RenderTarget<'_, D> {
+        match target {
+            RecordedRenderTarget::Default => RenderTarget::Default,
+            RecordedRenderTarget::Framebuffer(framebuffer) => {
+                RenderTarget::Framebuffer(&self.framebuffers[&framebuffer])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::recording::{Command, DrawCall, RecordedRenderTarget, RecordingDevice, Replayer};
+    use crate::{BufferData, BufferTarget, BufferUploadMode, Device, Primitive, RenderOptions};
+    use crate::{RenderState, RenderTarget, ShaderKind, TextureDataRef, TextureFormat};
+    use crate::UniformData;
+    use pathfinder_geometry::rect::RectI;
+    use pathfinder_geometry::vector::{Vector2I, vec2i};
+    use pathfinder_resources::ResourceLoader;
+    use std::io::Error as IOError;
+
+    struct NoResources;
+
+    impl ResourceLoader for NoResources {
+        fn slurp(&self, _: &str) -> Result<Vec<u8>, IOError> {
+            unreachable!()
+        }
+    }
+
+    struct Scene {
+        program: <RecordingDevice as Device>::Program,
+        vertex_array: <RecordingDevice as Device>::VertexArray,
+        color_uniform: <RecordingDevice as Device>::Uniform,
+        texture_uniform: <RecordingDevice as Device>::Uniform,
+        texture: <RecordingDevice as Device>::Texture,
+    }
+
+    fn create_scene(device: &RecordingDevice) -> Scene {
+        let program = device.create_program(&NoResources, "test");
+        let vertex_array = device.create_vertex_array();
+        let buffer = device.create_buffer();
+        device.allocate_buffer(&buffer,
+                               BufferData::Memory(&[0u32, 1, 2, 3, 4, 5]),
+                               BufferTarget::Index,
+                               BufferUploadMode::Static);
+        device.bind_buffer(&vertex_array, &buffer, BufferTarget::Vertex);
+        device.bind_buffer(&vertex_array, &buffer, BufferTarget::Index);
+        let color_uniform = device.get_uniform(&program, "Color");
+        let texture_uniform = device.get_uniform(&program, "Texture");
+        let texture = device.create_texture_from_data(TextureFormat::R8,
+                                                      vec2i(2, 2),
+                                                      TextureDataRef::U8(&[0; 4]));
+        Scene { program, vertex_array, color_uniform, texture_uniform, texture }
+    }
+
+    fn draw(device: &RecordingDevice,
+            scene: &Scene,
+            target: &RenderTarget<RecordingDevice>,
+            index_count: u32,
+            uniforms: &[(&<RecordingDevice as Device>::Uniform, UniformData)]) {
+        device.draw_elements(index_count, &RenderState {
+            target,
+            program: &scene.program,
+            vertex_array: &scene.vertex_array,
+            primitive: Primitive::Triangles,
+            uniforms,
+            textures: &[&scene.texture],
+            viewport: RectI::new(Vector2I::default(), vec2i(64, 64)),
+            options: RenderOptions::default(),
+        });
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        let scene = create_scene(&device);
+        let framebuffer_texture = device.create_texture(TextureFormat::RGBA8, vec2i(64, 64));
+        let framebuffer = device.create_framebuffer(framebuffer_texture);
+
+        device.begin_commands();
+        draw(&device,
+             &scene,
+             &RenderTarget::Framebuffer(&framebuffer),
+             6,
+             &[(&scene.color_uniform, UniformData::Float(1.0)),
+               (&scene.texture_uniform, UniformData::TextureUnit(0))]);
+        device.end_commands();
+
+        let draw_calls = device.draw_calls();
+        assert_eq!(draw_calls.len(), 1);
+        let (call, index_count, ref render_state) = draw_calls[0];
+        assert_eq!((call, index_count), (DrawCall::Elements, 6));
+        assert_eq!(render_state.target, RecordedRenderTarget::Framebuffer(framebuffer.id));
+        assert_eq!(device.program_name(render_state.program), "test");
+        assert_eq!(render_state.textures, vec![scene.texture]);
+
+        let pixels = device.read_pixels(&RenderTarget::Framebuffer(&framebuffer),
+                                        RectI::new(vec2i(8, 8), vec2i(4, 4)));
+        match device.recv_texture_data(&pixels) {
+            crate::TextureData::U8(pixels) => assert_eq!(pixels, vec![0; 64]),
+            _ => panic!("Expected RGBA8 pixels!"),
+        }
+
+        // Replaying onto another recording device reproduces the same calls.
+        let commands: Vec<Command> = device.commands().to_vec();
+        let target_device = RecordingDevice::new(vec2i(64, 64));
+        Replayer::new(&target_device).replay(&commands, &NoResources);
+        assert_eq!(format!("{:?}", &*target_device.commands()), format!("{:?}", commands));
+        assert_eq!(target_device.draw_calls().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "different type")]
+    fn test_uniform_type_mismatch() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        let scene = create_scene(&device);
+        draw(&device, &scene, &RenderTarget::Default, 3,
+             &[(&scene.color_uniform, UniformData::Float(1.0))]);
+        draw(&device, &scene, &RenderTarget::Default, 3,
+             &[(&scene.color_uniform, UniformData::Int(1))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "index buffer only holds")]
+    fn test_index_buffer_overrun() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        let scene = create_scene(&device);
+        draw(&device, &scene, &RenderTarget::Default, 7, &[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds texture size")]
+    fn test_upload_out_of_bounds() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        let texture = device.create_texture(TextureFormat::RGBA8, vec2i(4, 4));
+        device.upload_to_texture(&texture,
+                                 RectI::new(vec2i(2, 2), vec2i(4, 4)),
+                                 TextureDataRef::U8(&[0; 64]));
+    }
+
+    #[test]
+    #[should_panic(expected = "wasn't created with mipmap levels")]
+    fn test_generate_mipmaps_without_levels() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        let texture = device.create_texture(TextureFormat::RGBA8, vec2i(4, 4));
+        device.generate_mipmaps(&texture);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match texture format")]
+    fn test_texture_format_mismatch() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        device.create_texture_from_data(TextureFormat::RGBA32F,
+                                        vec2i(1, 1),
+                                        TextureDataRef::U8(&[0; 16]));
+    }
+
+    #[test]
+    #[should_panic(expected = "fragment shader in the vertex stage")]
+    fn test_shader_kind_mismatch() {
+        let device = RecordingDevice::new(vec2i(64, 64));
+        let vertex_shader = device.create_shader(&NoResources, "test", ShaderKind::Vertex);
+        let fragment_shader = device.create_shader(&NoResources, "test", ShaderKind::Fragment);
+        device.create_program_from_shaders(&NoResources, "test", fragment_shader, vertex_shader);
+    }
+}
@@ -31,7 +31,8 @@ use metal::{MTLArgument, MTLArgumentEncoder, MTLBlendFactor, MTLBlendOperation,
 use metal::{MTLColorWriteMask, MTLCompareFunction, MTLDataType, MTLDevice, MTLFunctionType};
 use metal::{MTLIndexType, MTLLoadAction, MTLOrigin, MTLPixelFormat, MTLPrimitiveType, MTLRegion};
 use metal::{MTLRenderPipelineReflection, MTLRenderPipelineState, MTLResourceOptions};
-use metal::{MTLResourceUsage, MTLSamplerAddressMode, MTLSamplerMinMagFilter, MTLSize};
+use metal::{MTLResourceUsage, MTLSamplerAddressMode, MTLSamplerMinMagFilter};
+use metal::{MTLSamplerMipFilter, MTLSize};
 use metal::{MTLStencilOperation, MTLStorageMode, MTLStoreAction, MTLTextureType, MTLTextureUsage};
 use metal::{MTLVertexFormat, MTLVertexStepFunction, MTLViewport, RenderCommandEncoder};
 use metal::{RenderCommandEncoderRef, RenderPassDescriptor, RenderPassDescriptorRef};
@@ -90,7 +91,7 @@ impl MetalDevice {
         let drawable = layer.next_drawable().unwrap().retain();
         let command_queue = device.new_command_queue();
 
-        let samplers = (0..16).map(|sampling_flags_value| {
+        let samplers = (0..64).map(|sampling_flags_value| {
             let sampling_flags = TextureSamplingFlags::from_bits(sampling_flags_value).unwrap();
             let sampler_descriptor = SamplerDescriptor::new();
             sampler_descriptor.set_support_argument_buffers(true);
@@ -107,6 +108,15 @@ impl MetalDevice {
                 } else {
                     MTLSamplerMinMagFilter::Linear
                 });
+            sampler_descriptor.set_mip_filter(
+                if sampling_flags.contains(TextureSamplingFlags::MIPMAP_MIN) {
+                    MTLSamplerMipFilter::Linear
+                } else {
+                    MTLSamplerMipFilter::NotMipmapped
+                });
+            if sampling_flags.contains(TextureSamplingFlags::ANISOTROPIC) {
+                sampler_descriptor.set_max_anisotropy(16);
+            }
             sampler_descriptor.set_address_mode_s(
                 if sampling_flags.contains(TextureSamplingFlags::REPEAT_U) {
                     MTLSamplerAddressMode::Repeat
@@ -238,26 +248,14 @@ impl Device for MetalDevice {
     type VertexArray = MetalVertexArray;
     type VertexAttr = VertexAttribute;
 
-    // TODO: Add texture usage hint.
+    #[inline]
     fn create_texture(&self, format: TextureFormat, size: Vector2I) -> MetalTexture {
-        let descriptor = TextureDescriptor::new();
-        descriptor.set_texture_type(MTLTextureType::D2);
-        match format {
-            TextureFormat::R8 => descriptor.set_pixel_format(MTLPixelFormat::R8Unorm),
-            TextureFormat::R16F => descriptor.set_pixel_format(MTLPixelFormat::R16Float),
-            TextureFormat::RGBA8 => descriptor.set_pixel_format(MTLPixelFormat::RGBA8Unorm),
-            TextureFormat::RGBA16F => descriptor.set_pixel_format(MTLPixelFormat::RGBA16Float),
-            TextureFormat::RGBA32F => descriptor.set_pixel_format(MTLPixelFormat::RGBA32Float),
-        }
-        descriptor.set_width(size.x() as u64);
-        descriptor.set_height(size.y() as u64);
-        descriptor.set_storage_mode(MTLStorageMode::Managed);
-        descriptor.set_usage(MTLTextureUsage::Unknown);
-        MetalTexture {
-            texture: self.device.new_texture(&descriptor),
-            sampling_flags: Cell::new(TextureSamplingFlags::empty()),
-            dirty: Cell::new(false),
-        }
+        self.create_texture_with_mipmap_levels(format, size, 1)
+    }
+
+    fn create_mipmapped_texture(&self, format: TextureFormat, size: Vector2I) -> MetalTexture {
+        let mipmap_level_count = 32 - (size.x().max(size.y()) as u32).leading_zeros();
+        self.create_texture_with_mipmap_levels(format, size, mipmap_level_count as u64)
     }
 
     fn create_texture_from_data(&self, format: TextureFormat, size: Vector2I, data: TextureDataRef)
@@ -267,14 +265,12 @@ impl Device for MetalDevice {
         texture
     }
 
-    fn create_shader_from_source(&self, _: &str, source: &[u8], _: ShaderKind) -> MetalShader {
-        let source = String::from_utf8(source.to_vec()).expect("Source wasn't valid UTF-8!");
-
-        let compile_options = CompileOptions::new();
-        let library = self.device.new_library_with_source(&source, &compile_options).unwrap();
-        let function = library.get_function("main0", None).unwrap();
-
-        MetalShader { library, function, uniforms: RefCell::new(ShaderUniforms::Unknown) }
+    fn create_shader_from_source(&self, name: &str, source: &[u8], kind: ShaderKind)
+                                 -> MetalShader {
+        match self.compile_shader(source) {
+            Ok(shader) => shader,
+            Err(log) => panic!("{:?} shader '{}' compilation failed: {}", kind, name, log),
+        }
     }
 
     fn create_vertex_array(&self) -> MetalVertexArray {
@@ -308,6 +304,20 @@ impl Device for MetalDevice {
         MetalProgram { vertex: vertex_shader, fragment: fragment_shader }
     }
 
+    fn try_create_program(&self, resources: &dyn ResourceLoader, name: &str)
+                          -> Result<MetalProgram, String> {
+        let mut shaders = vec![];
+        for &kind in &[ShaderKind::Vertex, ShaderKind::Fragment] {
+            let path = shader_path(name, kind);
+            let source = resources.slurp(&path).map_err(|err| format!("{}: {}", path, err))?;
+            let shader = self.compile_shader(&source).map_err(|log| format!("{}: {}", path, log))?;
+            shaders.push(shader);
+        }
+        let fragment = shaders.pop().unwrap();
+        let vertex = shaders.pop().unwrap();
+        Ok(MetalProgram { vertex, fragment })
+    }
+
     fn get_vertex_attr(&self, program: &MetalProgram, name: &str) -> Option<VertexAttribute> {
         // TODO(pcwalton): Cache the function?
         let attributes = program.vertex.function.real_vertex_attributes();
@@ -516,6 +526,14 @@ impl Device for MetalDevice {
         texture.dirty.set(true);
     }
 
+    fn generate_mipmaps(&self, texture: &MetalTexture) {
+        let command_buffers = self.command_buffers.borrow();
+        let command_buffer = command_buffers.last().unwrap();
+        let encoder = command_buffer.new_blit_command_encoder();
+        encoder.generate_mipmaps(&texture.texture);
+        encoder.end_encoding();
+    }
+
     fn read_pixels(&self, target: &RenderTarget<MetalDevice>, viewport: RectI)
                    -> MetalTextureDataReceiver {
         let texture = self.render_target_color_texture(target);
@@ -677,16 +695,23 @@ impl Device for MetalDevice {
         name: &str,
         kind: ShaderKind,
     ) -> Self::Shader {
-        let suffix = match kind {
-            ShaderKind::Vertex => 'v',
-            ShaderKind::Fragment => 'f',
-        };
-        let path = format!("shaders/metal/{}.{}s.metal", name, suffix);
+        let path = shader_path(name, kind);
         self.create_shader_from_source(name, &resources.slurp(&path).unwrap(), kind)
     }
 }
 
 impl MetalDevice {
+    fn compile_shader(&self, source: &[u8]) -> Result<MetalShader, String> {
+        let source = String::from_utf8(source.to_vec())
+                            .map_err(|_| "Source wasn't valid UTF-8!".to_owned())?;
+
+        let compile_options = CompileOptions::new();
+        let library = self.device.new_library_with_source(&source, &compile_options)?;
+        let function = library.get_function("main0", None)?;
+
+        Ok(MetalShader { library, function, uniforms: RefCell::new(ShaderUniforms::Unknown) })
+    }
+
     fn get_uniform_index(&self, shader: &MetalShader, name: &str) -> Option<MetalUniformIndex> {
         let uniforms = shader.uniforms.borrow();
         let struct_type = match *uniforms {
@@ -1184,6 +1209,33 @@ impl MetalDevice {
         })
     }
 
+    // TODO: Add texture usage hint.
+    fn create_texture_with_mipmap_levels(&self,
+                                        format: TextureFormat,
+                                        size: Vector2I,
+                                        mipmap_level_count: u64)
+                                        -> MetalTexture {
+        let descriptor = TextureDescriptor::new();
+        descriptor.set_texture_type(MTLTextureType::D2);
+        match format {
+            TextureFormat::R8 => descriptor.set_pixel_format(MTLPixelFormat::R8Unorm),
+            TextureFormat::R16F => descriptor.set_pixel_format(MTLPixelFormat::R16Float),
+            TextureFormat::RGBA8 => descriptor.set_pixel_format(MTLPixelFormat::RGBA8Unorm),
+            TextureFormat::RGBA16F => descriptor.set_pixel_format(MTLPixelFormat::RGBA16Float),
+            TextureFormat::RGBA32F => descriptor.set_pixel_format(MTLPixelFormat::RGBA32Float),
+        }
+        descriptor.set_width(size.x() as u64);
+        descriptor.set_height(size.y() as u64);
+        descriptor.set_mipmap_level_count(mipmap_level_count);
+        descriptor.set_storage_mode(MTLStorageMode::Managed);
+        descriptor.set_usage(MTLTextureUsage::Unknown);
+        MetalTexture {
+            texture: self.device.new_texture(&descriptor),
+            sampling_flags: Cell::new(TextureSamplingFlags::empty()),
+            dirty: Cell::new(false),
+        }
+    }
+
     fn synchronize_texture(&self, texture: &Texture, block: RcBlock<(*mut Object,), ()>) {
         unsafe {
             let command_buffers = self.command_buffers.borrow();
@@ -1216,6 +1268,14 @@ impl DeviceExtra for metal::Device {
     }
 }
 
+fn shader_path(name: &str, kind: ShaderKind) -> String {
+    let suffix = match kind {
+        ShaderKind::Vertex => 'v',
+        ShaderKind::Fragment => 'f',
+    };
+    format!("shaders/metal/{}.{}s.metal", name, suffix)
+}
+
 // Conversion helpers
 
 trait BlendFactorExt {
@@ -25,6 +25,7 @@ pub struct TextureAllocator {
 pub struct TexturePage {
     allocator: TexturePageAllocator,
     is_new: bool,
+    mipmapped: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -53,6 +54,8 @@ enum TreeNode {
 pub enum AllocationMode {
     Atlas,
     OwnPage,
+    // A separate page with a mipmap chain generated from its contents once they're uploaded.
+    OwnMipmappedPage,
 }
 
 impl TextureAllocator {
@@ -63,10 +66,11 @@ impl TextureAllocator {
 
     pub fn allocate(&mut self, requested_size: Vector2I, mode: AllocationMode) -> TextureLocation {
         // If requested, or if the image is too big, use a separate page.
-        if mode == AllocationMode::OwnPage ||
+        if mode != AllocationMode::Atlas ||
                 requested_size.x() > ATLAS_TEXTURE_LENGTH as i32 ||
                 requested_size.y() > ATLAS_TEXTURE_LENGTH as i32 {
-            return self.allocate_image(requested_size);
+            let mipmapped = mode == AllocationMode::OwnMipmappedPage;
+            return self.allocate_image_page(requested_size, mipmapped);
         }
 
         // Try to add to each atlas.
@@ -87,16 +91,24 @@ impl TextureAllocator {
         let rect = allocator.allocate(requested_size).expect("Allocation failed!");
         self.pages.push(TexturePage {
             is_new: true,
+            mipmapped: false,
             allocator: TexturePageAllocator::Atlas(allocator),
         });
         TextureLocation { page, rect }
     }
 
+    #[inline]
     pub fn allocate_image(&mut self, requested_size: Vector2I) -> TextureLocation {
+        self.allocate_image_page(requested_size, false)
+    }
+
+    fn allocate_image_page(&mut self, requested_size: Vector2I, mipmapped: bool)
+                           -> TextureLocation {
         let page = TexturePageId(self.pages.len() as u32);
         let rect = RectI::new(Vector2I::default(), requested_size);
         self.pages.push(TexturePage {
             is_new: true,
+            mipmapped,
             allocator: TexturePageAllocator::Image { size: rect.size() },
         });
         TextureLocation { page, rect }
@@ -117,6 +129,10 @@ impl TextureAllocator {
         self.pages[page_id.0 as usize].is_new
     }
 
+    pub fn page_is_mipmapped(&self, page_id: TexturePageId) -> bool {
+        self.pages[page_id.0 as usize].mipmapped
+    }
+
     pub fn mark_page_as_allocated(&mut self, page_id: TexturePageId) {
         self.pages[page_id.0 as usize].is_new = false;
     }
@@ -290,7 +306,7 @@ mod test {
     use quickcheck;
     use std::u32;
 
-    use super::TextureAtlasAllocator;
+    use super::{AllocationMode, TextureAllocator, TextureAtlasAllocator};
 
     #[test]
     fn test_allocation_and_freeing() {
@@ -323,4 +339,18 @@ mod test {
             true
         }
     }
+
+    #[test]
+    fn test_mipmapped_pages() {
+        let mut allocator = TextureAllocator::new();
+        let atlas = allocator.allocate(vec2i(16, 16), AllocationMode::Atlas);
+        let image = allocator.allocate(vec2i(16, 16), AllocationMode::OwnPage);
+        let mipmapped = allocator.allocate(vec2i(16, 16), AllocationMode::OwnMipmappedPage);
+        let render_target = allocator.allocate_image(vec2i(16, 16));
+        assert!(!allocator.page_is_mipmapped(atlas.page));
+        assert!(!allocator.page_is_mipmapped(image.page));
+        assert!(allocator.page_is_mipmapped(mipmapped.page));
+        assert!(!allocator.page_is_mipmapped(render_target.page));
+        assert_eq!(allocator.page_count(), 4);
+    }
 }
@@ -31,6 +31,10 @@ use pathfinder_geometry::util;
 use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2f, vec2i};
 use pathfinder_gpu::TextureSamplingFlags;
 use pathfinder_simd::default::{F32x4, I32x4};
+#[cfg(any(feature = "pf-no-simd", not(any(target_arch = "x86", target_arch = "x86_64"))))]
+use pathfinder_simd::default::{F32x8, I32x8};
+#[cfg(all(not(feature = "pf-no-simd"), any(target_arch = "x86", target_arch = "x86_64")))]
+use pathfinder_simd::dispatch::FeatureLevel;
 use std::sync::atomic::AtomicUsize;
 use instant::Instant;
 use std::u32;
@@ -634,6 +638,7 @@ impl ObjectBuilder {
         self.built_path.tiles.rect
     }
 
+    #[inline(always)]
     fn add_fill(
         &mut self,
         scene_builder: &SceneBuilder,
@@ -647,48 +652,52 @@ impl ObjectBuilder {
             return;
         };
 
-        debug_assert_eq!(TILE_WIDTH, TILE_HEIGHT);
-
-        // Compute the upper left corner of the tile.
-        let tile_size = F32x4::splat(TILE_WIDTH as f32);
-        let tile_upper_left = tile_coords.to_f32().0.to_f32x4().xyxy() * tile_size;
+        if let Some((px, subpx)) = pack_fill(segment, tile_coords) {
+            self.push_fill(scene_builder, px, subpx, tile_coords);
+        }
+    }
 
-        // Convert to 4.8 fixed point.
-        let segment = (segment.0 - tile_upper_left) * F32x4::splat(256.0);
-        let (min, max) = (F32x4::default(), F32x4::splat((TILE_WIDTH * 256 - 1) as f32));
-        let segment = segment.clamp(min, max).to_i32x4();
-        let (from_x, from_y, to_x, to_y) = (segment[0], segment[1], segment[2], segment[3]);
+    #[inline(always)]
+    fn add_fill_pair(
+        &mut self,
+        scene_builder: &SceneBuilder,
+        segments: [LineSegment2F; 2],
+        tile_coords: [Vector2I; 2],
+    ) {
+        debug!("add_fill_pair({:?} ({:?}))", segments, tile_coords);
 
-        // Cull degenerate fills.
-        if from_x == to_x {
-            debug!("... culling!");
-            return;
+        let fills = pack_fill_pair(segments, tile_coords);
+        for (&fill, &tile_coords) in fills.iter().zip(tile_coords.iter()) {
+            // Ensure this fill is in bounds. If not, cull it.
+            if self.tile_coords_to_local_index(tile_coords).is_none() {
+                continue;
+            }
+            if let Some((px, subpx)) = fill {
+                self.push_fill(scene_builder, px, subpx, tile_coords);
+            }
         }
+    }
 
+    #[inline(always)]
+    fn push_fill(
+        &mut self,
+        scene_builder: &SceneBuilder,
+        px: LineSegmentU4,
+        subpx: LineSegmentU8,
+        tile_coords: Vector2I,
+    ) {
         // Allocate a global tile if necessary.
         let alpha_tile_id = self.get_or_allocate_alpha_tile_index(scene_builder, tile_coords);
 
-        // Pack whole pixels.
-        let px = (segment & I32x4::splat(0xf00)).to_u32x4();
-        let px = (px >> 8).to_i32x4() | (px >> 4).to_i32x4().yxwz();
-
         // Pack instance data.
         debug!("... OK, pushing");
         self.fills.push(FillBatchEntry {
             page: alpha_tile_id.page(),
-            fill: Fill {
-                px: LineSegmentU4 { from: px[0] as u8, to: px[2] as u8 },
-                subpx: LineSegmentU8 {
-                    from_x: from_x as u8,
-                    from_y: from_y as u8,
-                    to_x:   to_x   as u8,
-                    to_y:   to_y   as u8,
-                },
-                alpha_tile_index: alpha_tile_id.tile(),
-            },
+            fill: Fill { px, subpx, alpha_tile_index: alpha_tile_id.tile() },
         });
     }
 
+    #[inline(always)]
     fn get_or_allocate_alpha_tile_index(
         &mut self,
         scene_builder: &SceneBuilder,
@@ -705,6 +714,7 @@ impl ObjectBuilder {
         alpha_tile_id
     }
 
+    #[inline(always)]
     pub(crate) fn add_active_fill(
         &mut self,
         scene_builder: &SceneBuilder,
@@ -741,6 +751,7 @@ impl ObjectBuilder {
         }
     }
 
+    #[inline(always)]
     pub(crate) fn generate_fill_primitives_for_line(
         &mut self,
         scene_builder: &SceneBuilder,
@@ -773,6 +784,7 @@ impl ObjectBuilder {
             self.tile_rect()
         );
 
+        let mut pending_fill = None;
         for subsegment_tile_x in segment_tile_left..segment_tile_right {
             let (mut fill_from, mut fill_to) = (segment.from(), segment.to());
             let subsegment_tile_right =
@@ -791,7 +803,20 @@ impl ObjectBuilder {
 
             let fill_segment = LineSegment2F::new(fill_from, fill_to);
             let fill_tile_coords = vec2i(subsegment_tile_x, tile_y);
-            self.add_fill(scene_builder, fill_segment, fill_tile_coords);
+
+            // Convert fills two at a time so that both fit in one eight-wide vector.
+            match pending_fill.take() {
+                None => pending_fill = Some((fill_segment, fill_tile_coords)),
+                Some((pending_segment, pending_tile_coords)) => {
+                    self.add_fill_pair(scene_builder,
+                                       [pending_segment, fill_segment],
+                                       [pending_tile_coords, fill_tile_coords]);
+                }
+            }
+        }
+
+        if let Some((pending_segment, pending_tile_coords)) = pending_fill {
+            self.add_fill(scene_builder, pending_segment, pending_tile_coords);
         }
     }
 
@@ -806,6 +831,120 @@ impl ObjectBuilder {
     }
 }
 
+/// Converts a fill to 4.8 fixed point relative to its tile, culling it if it's degenerate.
+fn pack_fill(segment: LineSegment2F, tile_coords: Vector2I)
+             -> Option<(LineSegmentU4, LineSegmentU8)> {
+    debug_assert_eq!(TILE_WIDTH, TILE_HEIGHT);
+
+    // Compute the upper left corner of the tile.
+    let tile_size = F32x4::splat(TILE_WIDTH as f32);
+    let tile_upper_left = tile_coords.to_f32().0.to_f32x4().xyxy() * tile_size;
+
+    // Convert to 4.8 fixed point.
+    let segment = (segment.0 - tile_upper_left) * F32x4::splat(256.0);
+    let (min, max) = (F32x4::default(), F32x4::splat((TILE_WIDTH * 256 - 1) as f32));
+    let segment = segment.clamp(min, max).to_i32x4();
+
+    // Pack whole pixels.
+    let px = (segment & I32x4::splat(0xf00)).to_u32x4();
+    let px = (px >> 8).to_i32x4() | (px >> 4).to_i32x4().yxwz();
+
+    unpack_fill(segment, px)
+}
+
+/// Like `pack_fill`, but converts two fills at once.
+#[cfg(all(not(feature = "pf-no-simd"), any(target_arch = "x86", target_arch = "x86_64")))]
+fn pack_fill_pair(segments: [LineSegment2F; 2], tile_coords: [Vector2I; 2])
+                  -> [Option<(LineSegmentU4, LineSegmentU8)>; 2] {
+    if FeatureLevel::current() >= FeatureLevel::Avx2 {
+        unsafe { pack_fill_pair_avx2(segments, tile_coords) }
+    } else {
+        [pack_fill(segments[0], tile_coords[0]), pack_fill(segments[1], tile_coords[1])]
+    }
+}
+
+/// Like `pack_fill`, but converts two fills at once.
+#[cfg(any(feature = "pf-no-simd", not(any(target_arch = "x86", target_arch = "x86_64"))))]
+#[inline]
+fn pack_fill_pair(segments: [LineSegment2F; 2], tile_coords: [Vector2I; 2])
+                  -> [Option<(LineSegmentU4, LineSegmentU8)>; 2] {
+    pack_fill_pair_wide(segments, tile_coords)
+}
+
+// The AVX2 types are only safe to use once runtime detection has found AVX2, and they only inline
+// into functions compiled with it enabled. They take the x86 four-wide types, which aren't the
+// default ones without SIMD.
+#[cfg(all(not(feature = "pf-no-simd"), any(target_arch = "x86", target_arch = "x86_64")))]
+#[target_feature(enable = "avx2")]
+unsafe fn pack_fill_pair_avx2(segments: [LineSegment2F; 2], tile_coords: [Vector2I; 2])
+                              -> [Option<(LineSegmentU4, LineSegmentU8)>; 2] {
+    use pathfinder_simd::x86::avx2::{F32x8, I32x8};
+
+    debug_assert_eq!(TILE_WIDTH, TILE_HEIGHT);
+
+    // Compute the upper left corners of the tiles.
+    let tile_size = F32x8::splat(TILE_WIDTH as f32);
+    let tile_coords = I32x8::from_halves(tile_coords[0].0.concat_xy_xy(tile_coords[0].0),
+                                         tile_coords[1].0.concat_xy_xy(tile_coords[1].0));
+    let tile_upper_left = tile_coords.to_f32x8().mul(tile_size);
+
+    // Convert to 4.8 fixed point.
+    let segments = F32x8::from_halves(segments[0].0, segments[1].0);
+    let segments = segments.sub(tile_upper_left).mul(F32x8::splat(256.0));
+    let (min, max) = (F32x8::zero(), F32x8::splat((TILE_WIDTH * 256 - 1) as f32));
+    let segments = segments.clamp(min, max).to_i32x8();
+
+    // Pack whole pixels.
+    let px = segments.and(I32x8::splat(0xf00)).to_u32x8();
+    let px = px.shr(8).to_i32x8().or(px.shr(4).to_i32x8().yxwz());
+
+    [unpack_fill(segments.lo(), px.lo()), unpack_fill(segments.hi(), px.hi())]
+}
+
+#[cfg(any(feature = "pf-no-simd", not(any(target_arch = "x86", target_arch = "x86_64"))))]
+#[inline(always)]
+fn pack_fill_pair_wide(segments: [LineSegment2F; 2], tile_coords: [Vector2I; 2])
+                       -> [Option<(LineSegmentU4, LineSegmentU8)>; 2] {
+    debug_assert_eq!(TILE_WIDTH, TILE_HEIGHT);
+
+    // Compute the upper left corners of the tiles.
+    let tile_size = F32x8::splat(TILE_WIDTH as f32);
+    let tile_coords = I32x8::from_halves(tile_coords[0].0.concat_xy_xy(tile_coords[0].0),
+                                         tile_coords[1].0.concat_xy_xy(tile_coords[1].0));
+    let tile_upper_left = tile_coords.to_f32x8() * tile_size;
+
+    // Convert to 4.8 fixed point.
+    let segments = F32x8::from_halves(segments[0].0, segments[1].0);
+    let segments = (segments - tile_upper_left) * F32x8::splat(256.0);
+    let (min, max) = (F32x8::default(), F32x8::splat((TILE_WIDTH * 256 - 1) as f32));
+    let segments = segments.clamp(min, max).to_i32x8();
+
+    // Pack whole pixels.
+    let px = (segments & I32x8::splat(0xf00)).to_u32x8();
+    let px = (px >> 8).to_i32x8() | (px >> 4).to_i32x8().yxwz();
+
+    [unpack_fill(segments.lo(), px.lo()), unpack_fill(segments.hi(), px.hi())]
+}
+
+#[inline]
+fn unpack_fill(segment: I32x4, px: I32x4) -> Option<(LineSegmentU4, LineSegmentU8)> {
+    let (from_x, from_y, to_x, to_y) = (segment[0], segment[1], segment[2], segment[3]);
+
+    // Cull degenerate fills.
+    if from_x == to_x {
+        debug!("... culling!");
+        return None;
+    }
+
+    Some((LineSegmentU4 { from: px[0] as u8, to: px[2] as u8 },
+          LineSegmentU8 {
+              from_x: from_x as u8,
+              from_y: from_y as u8,
+              to_x:   to_x   as u8,
+              to_y:   to_y   as u8,
+          }))
+}
+
 impl<'a> PackedTile<'a> {
     pub(crate) fn add_to(&self,
                          tiles: &mut Vec<BuiltTile>,
@@ -912,3 +1051,48 @@ fn calculate_mask_uv(tile_index: u16) -> Vector2I {
     let mask_v = tile_index as i32 / MASK_TILES_ACROSS as i32;
     vec2i(mask_u, mask_v)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{pack_fill, pack_fill_pair};
+    use pathfinder_geometry::line_segment::{LineSegment2F, LineSegmentU4, LineSegmentU8};
+    use pathfinder_geometry::vector::{Vector2I, vec2f, vec2i};
+
+    type PackedFill = Option<(LineSegmentU4, LineSegmentU8)>;
+
+    fn check_pack_fill_pair<F>(pack_fill_pair: F)
+                               where F: Fn([LineSegment2F; 2], [Vector2I; 2]) -> [PackedFill; 2] {
+        let fills = [
+            (LineSegment2F::new(vec2f(17.25, 3.5), vec2f(31.75, 15.0)), vec2i(1, 0)),
+            (LineSegment2F::new(vec2f(32.0, 40.0), vec2f(32.0, 47.0)), vec2i(2, 2)),
+            (LineSegment2F::new(vec2f(-3.0, 90.5), vec2f(12.125, 81.0)), vec2i(0, 5)),
+            (LineSegment2F::new(vec2f(100.0, 7.0), vec2f(66.5, 0.0)), vec2i(4, 0)),
+        ];
+        for &(segment_a, tile_a) in &fills {
+            for &(segment_b, tile_b) in &fills {
+                let pair = pack_fill_pair([segment_a, segment_b], [tile_a, tile_b]);
+                let single = [pack_fill(segment_a, tile_a), pack_fill(segment_b, tile_b)];
+                assert_eq!(format!("{:?}", pair), format!("{:?}", single));
+            }
+        }
+        assert!(pack_fill(fills[1].0, fills[1].1).is_none());
+    }
+
+    #[test]
+    fn test_pack_fill_pair_matches_pack_fill() {
+        check_pack_fill_pair(pack_fill_pair);
+    }
+
+    // This calls the AVX2 version directly rather than restricting the feature level, which is
+    // global and would change what the other tests run.
+    #[cfg(all(not(feature = "pf-no-simd"), any(target_arch = "x86", target_arch = "x86_64")))]
+    #[test]
+    fn test_pack_fill_pair_avx2_matches_pack_fill() {
+        use pathfinder_simd::dispatch::FeatureLevel;
+        if FeatureLevel::detect() >= FeatureLevel::Avx2 {
+            check_pack_fill_pair(|segments, tile_coords| unsafe {
+                super::pack_fill_pair_avx2(segments, tile_coords)
+            });
+        }
+    }
+}
@@ -24,8 +24,9 @@ use fxhash::FxHashMap;
 use half::f16;
 use pathfinder_color::{self as color, ColorF, ColorU};
 use pathfinder_content::effects::{BlendMode, BlurDirection, DefringingKernel};
-use pathfinder_content::effects::{Filter, PatternFilter};
+use pathfinder_content::effects::{ColorMatrix, Filter, PatternFilter};
 use pathfinder_content::fill::FillRule;
+use pathfinder_content::pattern::Image;
 use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::RectI;
@@ -35,13 +36,14 @@ use pathfinder_geometry::vector::{Vector2F, Vector2I, Vector4F, vec2f, vec2i};
 use pathfinder_gpu::{BlendFactor, BlendOp, BlendState, BufferData, BufferTarget, BufferUploadMode};
 use pathfinder_gpu::{ClearOps, DepthFunc, DepthState, Device, Primitive, RenderOptions};
 use pathfinder_gpu::{RenderState, RenderTarget, StencilFunc, StencilState, TextureDataRef};
-use pathfinder_gpu::{TextureFormat, UniformData};
+use pathfinder_gpu::{TextureData, TextureFormat, UniformData};
 use pathfinder_resources::ResourceLoader;
 use pathfinder_simd::default::{F32x2, F32x4, I32x2};
 use std::collections::VecDeque;
 use std::f32;
 use std::mem;
 use std::ops::{Add, Div};
+use std::sync::Arc;
 use std::time::Duration;
 use std::u32;
 
@@ -74,6 +76,9 @@ const COMBINER_CTRL_COLOR_COMBINE_DEST_IN: i32 =    0x2;
 const COMBINER_CTRL_FILTER_RADIAL_GRADIENT: i32 =   0x1;
 const COMBINER_CTRL_FILTER_TEXT: i32 =              0x2;
 const COMBINER_CTRL_FILTER_BLUR: i32 =              0x3;
+const COMBINER_CTRL_FILTER_COLOR_MATRIX: i32 =      0x4;
+
+const COMBINER_CTRL_COLOR_SAMPLE_BICUBIC: i32 =     0x1;
 
 const COMBINER_CTRL_COMPOSITE_NORMAL: i32 =         0x0;
 const COMBINER_CTRL_COMPOSITE_MULTIPLY: i32 =       0x1;
@@ -94,8 +99,9 @@ const COMBINER_CTRL_COMPOSITE_LUMINOSITY: i32 =     0xf;
 
 const COMBINER_CTRL_MASK_0_SHIFT: i32 =             0;
 const COMBINER_CTRL_COLOR_FILTER_SHIFT: i32 =       4;
-const COMBINER_CTRL_COLOR_COMBINE_SHIFT: i32 =      6;
-const COMBINER_CTRL_COMPOSITE_SHIFT: i32 =          8;
+const COMBINER_CTRL_COLOR_COMBINE_SHIFT: i32 =      7;
+const COMBINER_CTRL_COMPOSITE_SHIFT: i32 =          9;
+const COMBINER_CTRL_COLOR_SAMPLE_SHIFT: i32 =       13;
 
 pub struct Renderer<D>
 where
@@ -300,6 +306,97 @@ where
         }
     }
 
+    /// Recompiles the programs whose shaders `resources` reports as changed.
+    ///
+    /// This is for editing shaders while the application runs, with a loader such as
+    /// `FilesystemResourceLoader::watching()`, and should be called between scenes. A program
+    /// that fails to load, compile or link is logged and keeps its previous version. Returns the
+    /// name of each program that was reloaded along with the outcome.
+    pub fn reload_changed_shaders(&mut self, resources: &dyn ResourceLoader)
+                                  -> Vec<(String, Result<(), String>)> {
+        let mut program_names: Vec<_> = resources.poll_changes()
+                                                 .iter()
+                                                 .filter_map(|path| shader_program_name(path))
+                                                 .filter(|name| RELOADABLE_PROGRAMS.contains(name))
+                                                 .map(|name| name.to_owned())
+                                                 .collect();
+        program_names.sort();
+        program_names.dedup();
+
+        program_names.into_iter().map(|program_name| {
+            let result = self.reload_program(resources, &program_name);
+            match result {
+                Ok(()) => info!("Reloaded shader program `{}`", program_name),
+                Err(ref error) => {
+                    error!("Failed to reload shader program `{}`: {}", program_name, error)
+                }
+            }
+            (program_name, result)
+        }).collect()
+    }
+
+    fn reload_program(&mut self, resources: &dyn ResourceLoader, program_name: &str)
+                      -> Result<(), String> {
+        let device = &self.device;
+        let program = device.try_create_program(resources, program_name)?;
+
+        // Vertex attribute locations may have changed, so rebuild the vertex arrays too.
+        match program_name {
+            "blit" => {
+                self.blit_program = BlitProgram::from_program(device, program);
+                self.blit_vertex_array = BlitVertexArray::new(device,
+                                                              &self.blit_program,
+                                                              &self.quad_vertex_positions_buffer,
+                                                              &self.quad_vertex_indices_buffer);
+            }
+            "fill" => {
+                self.fill_program = FillProgram::from_program(device, program);
+                self.fill_vertex_array = FillVertexArray::new(device,
+                                                              &self.fill_program,
+                                                              &self.quad_vertex_positions_buffer,
+                                                              &self.quad_vertex_indices_buffer);
+            }
+            "tile" => {
+                self.tile_program = TileProgram::from_program(device, program);
+                self.tile_vertex_array = TileVertexArray::new(device,
+                                                              &self.tile_program,
+                                                              &self.tile_vertex_buffer,
+                                                              &self.quad_vertex_positions_buffer,
+                                                              &self.quad_vertex_indices_buffer);
+            }
+            "tile_copy" => {
+                self.tile_copy_program = CopyTileProgram::from_program(device, program);
+                self.tile_copy_vertex_array =
+                    CopyTileVertexArray::new(device,
+                                             &self.tile_copy_program,
+                                             &self.tile_vertex_buffer,
+                                             &self.quads_vertex_indices_buffer);
+            }
+            "tile_clip" => {
+                self.tile_clip_program = ClipTileProgram::from_program(device, program);
+                self.tile_clip_vertex_array =
+                    ClipTileVertexArray::new(device,
+                                             &self.tile_clip_program,
+                                             &self.quad_vertex_positions_buffer,
+                                             &self.quad_vertex_indices_buffer);
+            }
+            "stencil" => {
+                self.stencil_program = StencilProgram::from_program(program);
+                self.stencil_vertex_array = StencilVertexArray::new(device, &self.stencil_program);
+            }
+            "reproject" => {
+                self.reprojection_program = ReprojectionProgram::from_program(device, program);
+                self.reprojection_vertex_array =
+                    ReprojectionVertexArray::new(device,
+                                                 &self.reprojection_program,
+                                                 &self.quad_vertex_positions_buffer,
+                                                 &self.quad_vertex_indices_buffer);
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
     pub fn begin_scene(&mut self) {
         self.framebuffer_flags = FramebufferFlags::empty();
         for alpha_tile_page in self.alpha_tile_pages.values_mut() {
@@ -426,6 +523,49 @@ where
         mem::replace(&mut self.dest_framebuffer, new_dest_framebuffer)
     }
 
+    /// Reads back a rectangle of the destination framebuffer as a non-premultiplied RGBA image,
+    /// blocking until the GPU has finished rendering.
+    ///
+    /// `rect` is in pixels relative to the top left of the destination viewport, regardless of
+    /// which way up the device stores its framebuffers.
+    pub fn read_back(&self, rect: RectI) -> Image {
+        let read_back = self.begin_read_back(rect);
+        self.finish_read_back(&read_back)
+    }
+
+    /// Starts reading back a rectangle of the destination framebuffer without waiting for the
+    /// GPU. Use `try_finish_read_back()` or `finish_read_back()` to get the pixels.
+    pub fn begin_read_back(&self, rect: RectI) -> PendingReadBack<D> {
+        // Devices disagree on whether framebuffer rows start at the top or the bottom, so we
+        // can't translate `rect` into device coordinates. They all return rows top-down,
+        // though, so read the whole viewport and crop afterward.
+        let viewport = self.main_viewport();
+        assert!(rect.min_x() >= 0 && rect.min_y() >= 0 &&
+                rect.max_x() <= viewport.width() && rect.max_y() <= viewport.height(),
+                "Read back rect {:?} exceeds the destination viewport {:?}!",
+                rect,
+                viewport);
+
+        let render_target = match self.dest_framebuffer {
+            DestFramebuffer::Default { .. } => RenderTarget::Default,
+            DestFramebuffer::Other(ref framebuffer) => RenderTarget::Framebuffer(framebuffer),
+        };
+        let receiver = self.device.read_pixels(&render_target, viewport);
+        PendingReadBack { receiver, viewport_size: viewport.size(), rect }
+    }
+
+    /// Returns the pixels of a read back if the GPU has finished rendering them.
+    pub fn try_finish_read_back(&self, read_back: &PendingReadBack<D>) -> Option<Image> {
+        let texture_data = self.device.try_recv_texture_data(&read_back.receiver)?;
+        Some(read_back.to_image(texture_data))
+    }
+
+    /// Returns the pixels of a read back, waiting for the GPU if necessary.
+    pub fn finish_read_back(&self, read_back: &PendingReadBack<D>) -> Image {
+        let texture_data = self.device.recv_texture_data(&read_back.receiver);
+        read_back.to_image(texture_data)
+    }
+
     #[inline]
     pub fn set_options(&mut self, new_options: RendererOptions) {
         self.options = new_options
@@ -465,21 +605,27 @@ where
             self.texture_pages.push(None);
         }
 
-        // Clear out any existing texture.
+        // Clear out any existing texture. Mipmapped textures aren't cached, since they can't be
+        // reused for ordinary pages.
         if let Some(old_texture_page) = self.texture_pages[page_index].take() {
             let old_texture = self.device.destroy_framebuffer(old_texture_page.framebuffer);
-            self.texture_cache.release_texture(old_texture);
+            if !old_texture_page.mipmapped {
+                self.texture_cache.release_texture(old_texture);
+            }
         }
 
         // Allocate texture.
         let texture_size = descriptor.size;
-        let texture = self.texture_cache.create_texture(&mut self.device,
-                                                        TextureFormat::RGBA8,
-                                                        texture_size);
+        let texture = if descriptor.mipmapped {
+            self.device.create_mipmapped_texture(TextureFormat::RGBA8, texture_size)
+        } else {
+            self.texture_cache.create_texture(&mut self.device, TextureFormat::RGBA8, texture_size)
+        };
         let framebuffer = self.device.create_framebuffer(texture);
         self.texture_pages[page_index] = Some(TexturePage {
             framebuffer,
             must_preserve_contents: false,
+            mipmapped: descriptor.mipmapped,
         });
     }
 
@@ -490,6 +636,9 @@ where
         let texture = self.device.framebuffer_texture(&texture_page.framebuffer);
         let texels = color::color_slice_to_u8_slice(texels);
         self.device.upload_to_texture(texture, location.rect, TextureDataRef::U8(texels));
+        if texture_page.mipmapped {
+            self.device.generate_mipmaps(texture);
+        }
         texture_page.must_preserve_contents = true;
     }
 
@@ -791,6 +940,9 @@ where
 
             ctrl |= color_texture.composite_op.to_combine_mode() <<
                 COMBINER_CTRL_COLOR_COMBINE_SHIFT;
+            if color_texture.bicubic {
+                ctrl |= COMBINER_CTRL_COLOR_SAMPLE_BICUBIC << COMBINER_CTRL_COLOR_SAMPLE_SHIFT;
+            }
         }
 
         ctrl |= blend_mode.to_composite_ctrl() << COMBINER_CTRL_COMPOSITE_SHIFT;
@@ -819,6 +971,10 @@ where
                 ctrl |= COMBINER_CTRL_FILTER_BLUR << COMBINER_CTRL_COLOR_FILTER_SHIFT;
                 self.set_uniforms_for_blur_filter(&mut uniforms, direction, sigma);
             }
+            Filter::PatternFilter(PatternFilter::ColorMatrix(matrix)) => {
+                ctrl |= COMBINER_CTRL_FILTER_COLOR_MATRIX << COMBINER_CTRL_COLOR_FILTER_SHIFT;
+                self.set_uniforms_for_color_matrix_filter(&mut uniforms, matrix);
+            }
         }
 
         uniforms.push((&self.tile_program.ctrl_uniform, UniformData::Int(ctrl)));
@@ -1062,6 +1218,16 @@ where
         ]);
     }
 
+    fn set_uniforms_for_color_matrix_filter<'a>(&'a self,
+                                                uniforms: &mut Vec<(&'a D::Uniform, UniformData)>,
+                                                matrix: ColorMatrix) {
+        uniforms.extend_from_slice(&[
+            (&self.tile_program.filter_params_0_uniform, UniformData::Vec4(matrix.0[0])),
+            (&self.tile_program.filter_params_1_uniform, UniformData::Vec4(matrix.0[1])),
+            (&self.tile_program.filter_params_2_uniform, UniformData::Vec4(matrix.0[2])),
+        ]);
+    }
+
     fn blit_intermediate_dest_framebuffer_if_necessary(&mut self) {
         if !self.flags.contains(RendererFlags::INTERMEDIATE_DEST_FRAMEBUFFER_NEEDED) {
             return;
@@ -1228,6 +1394,81 @@ struct PendingTimer<D> where D: Device {
     gpu_timer_query: D::TimerQuery,
 }
 
+/// A read back of rendered pixels that the GPU may not have finished yet.
+pub struct PendingReadBack<D> where D: Device {
+    receiver: D::TextureDataReceiver,
+    viewport_size: Vector2I,
+    rect: RectI,
+}
+
+impl<D> PendingReadBack<D> where D: Device {
+    /// The rectangle being read, relative to the top left of the destination viewport.
+    #[inline]
+    pub fn rect(&self) -> RectI {
+        self.rect
+    }
+
+    fn to_image(&self, texture_data: TextureData) -> Image {
+        let pixel_count = self.viewport_size.x() as usize * self.viewport_size.y() as usize;
+        let viewport_pixels = match texture_data {
+            TextureData::U8(data) => unpremultiply_u8_pixels(&data, data.len() / pixel_count),
+            TextureData::F16(data) => {
+                let data: Vec<f32> = data.into_iter().map(f32::from).collect();
+                unpremultiply_f32_pixels(&data, data.len() / pixel_count)
+            }
+            TextureData::F32(data) => unpremultiply_f32_pixels(&data, data.len() / pixel_count),
+            TextureData::U16(_) => panic!("Unexpected pixel format for the dest framebuffer!"),
+        };
+
+        let (rect, stride) = (self.rect, self.viewport_size.x() as usize);
+        let mut pixels = Vec::with_capacity(rect.width() as usize * rect.height() as usize);
+        for y in rect.min_y()..rect.max_y() {
+            let row_start = y as usize * stride;
+            pixels.extend_from_slice(&viewport_pixels[(row_start + rect.min_x() as usize)..
+                                                      (row_start + rect.max_x() as usize)]);
+        }
+        Image::new(rect.size(), Arc::new(pixels))
+    }
+}
+
+fn unpremultiply_u8_pixels(data: &[u8], channels: usize) -> Vec<ColorU> {
+    data.chunks(channels).map(|pixel| {
+        match *pixel {
+            [r, g, b, a] => {
+                if a == 0 {
+                    return ColorU::transparent_black();
+                }
+                let unpremultiply = |c: u8| {
+                    ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8
+                };
+                ColorU::new(unpremultiply(r), unpremultiply(g), unpremultiply(b), a)
+            }
+            [r, ..] => ColorU::new(r, r, r, 255),
+            [] => unreachable!(),
+        }
+    }).collect()
+}
+
+fn unpremultiply_f32_pixels(data: &[f32], channels: usize) -> Vec<ColorU> {
+    data.chunks(channels).map(|pixel| {
+        match *pixel {
+            [r, g, b, a] => {
+                if a <= 0.0 {
+                    return ColorU::transparent_black();
+                }
+                let (r, g, b) = (r.max(0.0), g.max(0.0), b.max(0.0));
+                let a = a.min(1.0);
+                ColorF::new((r / a).min(1.0), (g / a).min(1.0), (b / a).min(1.0), a).to_u8()
+            }
+            [r, ..] => {
+                let r = r.clamp(0.0, 1.0);
+                ColorF::new(r, r, r, 1.0).to_u8()
+            }
+            [] => unreachable!(),
+        }
+    }).collect()
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct RenderTime {
     pub gpu_time: Duration,
@@ -1297,6 +1538,7 @@ impl<D> TextureCache<D> where D: Device {
 struct TexturePage<D> where D: Device {
     framebuffer: D::Framebuffer,
     must_preserve_contents: bool,
+    mipmapped: bool,
 }
 
 struct RenderTargetInfo {
@@ -1547,3 +1789,194 @@ impl ToCombineMode for PaintCompositeOp {
         }
     }
 }
+
+// The programs that `Renderer::reload_changed_shaders()` can replace.
+static RELOADABLE_PROGRAMS: [&str; 7] =
+    ["blit", "fill", "reproject", "stencil", "tile", "tile_clip", "tile_copy"];
+
+// Maps e.g. `shaders/gl3/tile.fs.glsl` to `tile`.
+fn shader_program_name(path: &str) -> Option<&str> {
+    if !path.starts_with("shaders/") {
+        return None;
+    }
+    let file_name = path.rsplit('/').next().unwrap();
+    file_name.split('.').next()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gpu::options::{DestFramebuffer, RendererOptions};
+    use crate::concurrent::executor::SequentialExecutor;
+    use crate::gpu::renderer::{PendingReadBack, Renderer};
+    use crate::gpu_data::{Fill, FillBatchEntry, RenderCommand};
+    use crate::options::BuildOptions;
+    use crate::paint::Paint;
+    use crate::scene::{DrawPath, Scene};
+    use half::f16;
+    use pathfinder_color::ColorU;
+    use pathfinder_content::outline::Outline;
+    use pathfinder_geometry::rect::{RectF, RectI};
+    use pathfinder_geometry::vector::{vec2f, vec2i};
+    use pathfinder_gpu::TextureData;
+    use pathfinder_gpu::recording::{Command, DrawCall, RecordedRenderTarget, RecordingDevice};
+    use pathfinder_resources::ResourceLoader;
+    use std::fs;
+    use std::io::Error as IOError;
+
+    // A 1×1 grayscale PNG, standing in for the lookup tables and debug UI textures.
+    static BLANK_PNG: [u8; 67] = [
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48,
+        0x44, 0x52, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00,
+        0x00, 0x3a, 0x7e, 0x9b, 0x55, 0x00, 0x00, 0x00, 0x0a, 0x49, 0x44, 0x41, 0x54, 0x78,
+        0x9c, 0x63, 0x60, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x48, 0xaf, 0xa4, 0x71, 0x00,
+        0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+    ];
+
+    #[derive(Default)]
+    struct TestResourceLoader {
+        changed_paths: Vec<String>,
+    }
+
+    impl ResourceLoader for TestResourceLoader {
+        fn slurp(&self, virtual_path: &str) -> Result<Vec<u8>, IOError> {
+            if virtual_path.starts_with("textures/") {
+                return Ok(BLANK_PNG.to_vec());
+            }
+            fs::read(format!("{}/../resources/{}", env!("CARGO_MANIFEST_DIR"), virtual_path))
+        }
+
+        fn poll_changes(&self) -> Vec<String> {
+            self.changed_paths.clone()
+        }
+    }
+
+    fn create_renderer() -> Renderer<RecordingDevice> {
+        let device = RecordingDevice::new(vec2i(640, 480));
+        Renderer::new(device,
+                      &TestResourceLoader::default(),
+                      DestFramebuffer::full_window(vec2i(640, 480)),
+                      RendererOptions::default())
+    }
+
+    #[test]
+    fn test_fills_are_drawn_in_one_instanced_batch() {
+        let mut renderer = create_renderer();
+        let fill = FillBatchEntry { fill: Fill::default(), page: 0 };
+        renderer.begin_scene();
+        renderer.render_command(&RenderCommand::AddFills(vec![fill; 3]));
+        renderer.render_command(&RenderCommand::FlushFills);
+        renderer.end_scene();
+
+        let device = renderer.device;
+        let draw_calls = device.draw_calls();
+        assert_eq!(draw_calls.len(), 1);
+        let (call, index_count, ref render_state) = draw_calls[0];
+        assert_eq!(call, DrawCall::ElementsInstanced { instance_count: 3 });
+        assert_eq!(index_count, 6);
+        assert_eq!(device.program_name(render_state.program), "fill");
+        match render_state.target {
+            RecordedRenderTarget::Framebuffer(_) => {}
+            RecordedRenderTarget::Default => panic!("Fills should go to a mask framebuffer!"),
+        }
+        assert!(matches!(device.commands().last(), Some(Command::EndCommands)));
+    }
+
+    #[test]
+    fn test_reload_changed_shaders() {
+        let mut renderer = create_renderer();
+        let resources = TestResourceLoader {
+            changed_paths: vec![
+                "shaders/gl3/fill.vs.glsl".to_owned(),
+                "shaders/gl3/fill.fs.glsl".to_owned(),
+                "shaders/gl3/debug_solid.fs.glsl".to_owned(),
+                "svg/Ghostscript_Tiger.svg".to_owned(),
+            ],
+        };
+        let reloads = renderer.reload_changed_shaders(&resources);
+        assert_eq!(reloads, vec![("fill".to_owned(), Ok(()))]);
+
+        // Fills are now drawn with the new program.
+        renderer.begin_scene();
+        renderer.render_command(&RenderCommand::AddFills(vec![FillBatchEntry::default()]));
+        renderer.render_command(&RenderCommand::FlushFills);
+        renderer.end_scene();
+
+        let device = &renderer.device;
+        let fill_programs: Vec<_> = device.commands().iter().filter_map(|command| {
+            match *command {
+                Command::CreateProgram { program, ref name, .. } if name == "fill" => Some(program),
+                _ => None,
+            }
+        }).collect();
+        assert_eq!(fill_programs.len(), 2);
+        assert_eq!(device.draw_calls()[0].2.program, fill_programs[1]);
+    }
+
+    #[test]
+    fn test_scene_build_and_render() {
+        let mut renderer = create_renderer();
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(vec2f(0.0, 0.0), vec2f(640.0, 480.0)));
+        let paint = scene.push_paint(&Paint::black());
+        let outline = Outline::from_rect(RectF::new(vec2f(10.5, 10.5), vec2f(100.0, 100.0)));
+        scene.push_path(DrawPath::new(outline, paint));
+        scene.build_and_render(&mut renderer, BuildOptions::default(), &SequentialExecutor);
+
+        let device = &renderer.device;
+        let programs: Vec<_> = device.draw_calls().iter().map(|(_, _, render_state)| {
+            device.program_name(render_state.program).to_owned()
+        }).collect();
+        assert!(programs.contains(&"fill".to_owned()));
+        assert!(programs.contains(&"tile".to_owned()));
+    }
+
+    #[test]
+    fn test_read_back_reads_dest_viewport() {
+        let mut renderer = create_renderer();
+        let viewport = RectI::new(vec2i(100, 50), vec2i(320, 240));
+        renderer.replace_dest_framebuffer(DestFramebuffer::Default {
+            viewport,
+            window_size: vec2i(640, 480),
+        });
+
+        let rect = RectI::new(vec2i(10, 20), vec2i(30, 40));
+        let image = renderer.read_back(rect);
+        assert_eq!(image.size(), rect.size());
+        assert!(image.pixels().iter().all(|pixel| pixel.is_fully_transparent()));
+        match renderer.device.commands().last() {
+            Some(&Command::ReadPixels { target: RecordedRenderTarget::Default, viewport: read }) => {
+                assert_eq!(read, viewport)
+            }
+            command => panic!("Expected a read of the default framebuffer, got {:?}!", command),
+        };
+    }
+
+    #[test]
+    fn test_read_back_crops_and_unpremultiplies() {
+        let read_back: PendingReadBack<RecordingDevice> = PendingReadBack {
+            receiver: TextureData::U8(vec![]),
+            viewport_size: vec2i(2, 2),
+            rect: RectI::new(vec2i(1, 0), vec2i(1, 2)),
+        };
+
+        let image = read_back.to_image(TextureData::U8(vec![
+            255, 0, 0, 255,     64, 32, 0, 128,
+            0, 0, 0, 0,         10, 20, 30, 0,
+        ]));
+        assert_eq!(**image.pixels(),
+                   [ColorU::new(128, 64, 0, 128), ColorU::transparent_black()]);
+
+        let half = |value: f32| f16::from_f32(value);
+        let image = read_back.to_image(TextureData::F16(vec![
+            half(0.0), half(0.0), half(0.0), half(0.0),
+            half(0.25), half(0.5), half(2.0), half(0.5),
+            half(0.0), half(0.0), half(0.0), half(0.0),
+            half(1.0), half(1.0), half(1.0), half(1.0),
+        ]));
+        assert_eq!(**image.pixels(),
+                   [ColorU::new(128, 255, 255, 128), ColorU::white()]);
+
+        let image = read_back.to_image(TextureData::U8(vec![0, 7, 0, 9]));
+        assert_eq!(**image.pixels(), [ColorU::new(7, 7, 7, 255), ColorU::new(9, 9, 9, 255)]);
+    }
+}
@@ -330,7 +330,10 @@ pub struct BlitProgram<D> where D: Device {
 
 impl<D> BlitProgram<D> where D: Device {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> BlitProgram<D> {
-        let program = device.create_program(resources, "blit");
+        BlitProgram::from_program(device, device.create_program(resources, "blit"))
+    }
+
+    pub fn from_program(device: &D, program: D::Program) -> BlitProgram<D> {
         let src_uniform = device.get_uniform(&program, "Src");
         BlitProgram { program, src_uniform }
     }
@@ -351,7 +354,10 @@ where
     D: Device,
 {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> FillProgram<D> {
-        let program = device.create_program(resources, "fill");
+        FillProgram::from_program(device, device.create_program(resources, "fill"))
+    }
+
+    pub fn from_program(device: &D, program: D::Program) -> FillProgram<D> {
         let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
         let tile_size_uniform = device.get_uniform(&program, "TileSize");
         let area_lut_uniform = device.get_uniform(&program, "AreaLUT");
@@ -385,7 +391,10 @@ pub struct TileProgram<D> where D: Device {
 
 impl<D> TileProgram<D> where D: Device {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> TileProgram<D> {
-        let program = device.create_program(resources, "tile");
+        TileProgram::from_program(device, device.create_program(resources, "tile"))
+    }
+
+    pub fn from_program(device: &D, program: D::Program) -> TileProgram<D> {
         let transform_uniform = device.get_uniform(&program, "Transform");
         let tile_size_uniform = device.get_uniform(&program, "TileSize");
         let texture_metadata_uniform = device.get_uniform(&program, "TextureMetadata");
@@ -432,7 +441,10 @@ pub struct CopyTileProgram<D> where D: Device {
 
 impl<D> CopyTileProgram<D> where D: Device {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> CopyTileProgram<D> {
-        let program = device.create_program(resources, "tile_copy");
+        CopyTileProgram::from_program(device, device.create_program(resources, "tile_copy"))
+    }
+
+    pub fn from_program(device: &D, program: D::Program) -> CopyTileProgram<D> {
         let transform_uniform = device.get_uniform(&program, "Transform");
         let tile_size_uniform = device.get_uniform(&program, "TileSize");
         let framebuffer_size_uniform = device.get_uniform(&program, "FramebufferSize");
@@ -454,7 +466,10 @@ pub struct ClipTileProgram<D> where D: Device {
 
 impl<D> ClipTileProgram<D> where D: Device {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> ClipTileProgram<D> {
-        let program = device.create_program(resources, "tile_clip");
+        ClipTileProgram::from_program(device, device.create_program(resources, "tile_clip"))
+    }
+
+    pub fn from_program(device: &D, program: D::Program) -> ClipTileProgram<D> {
         let src_uniform = device.get_uniform(&program, "Src");
         ClipTileProgram { program, src_uniform }
     }
@@ -472,7 +487,10 @@ where
     D: Device,
 {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> StencilProgram<D> {
-        let program = device.create_program(resources, "stencil");
+        StencilProgram::from_program(device.create_program(resources, "stencil"))
+    }
+
+    pub fn from_program(program: D::Program) -> StencilProgram<D> {
         StencilProgram { program }
     }
 }
@@ -527,7 +545,10 @@ where
     D: Device,
 {
     pub fn new(device: &D, resources: &dyn ResourceLoader) -> ReprojectionProgram<D> {
-        let program = device.create_program(resources, "reproject");
+        ReprojectionProgram::from_program(device, device.create_program(resources, "reproject"))
+    }
+
+    pub fn from_program(device: &D, program: D::Program) -> ReprojectionProgram<D> {
         let old_transform_uniform = device.get_uniform(&program, "OldTransform");
         let new_transform_uniform = device.get_uniform(&program, "NewTransform");
         let texture_uniform = device.get_uniform(&program, "Texture");
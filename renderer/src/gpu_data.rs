@@ -90,6 +90,8 @@ pub struct TexturePageId(pub u32);
 #[derive(Clone, Copy, Debug)]
 pub struct TexturePageDescriptor {
     pub size: Vector2I,
+    /// Whether the page gets a mipmap chain, generated when texels are uploaded to it.
+    pub mipmapped: bool,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
@@ -112,6 +114,7 @@ pub struct TileBatch {
 pub struct TileBatchTexture {
     pub page: TexturePageId,
     pub sampling_flags: TextureSamplingFlags,
+    pub bicubic: bool,
     pub composite_op: PaintCompositeOp,
 }
 
@@ -16,7 +16,7 @@ use hashbrown::HashMap;
 use pathfinder_color::ColorU;
 use pathfinder_content::effects::{Filter, PatternFilter};
 use pathfinder_content::gradient::{Gradient, GradientGeometry};
-use pathfinder_content::pattern::{Pattern, PatternSource};
+use pathfinder_content::pattern::{Pattern, PatternSampling, PatternSource};
 use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::{RectF, RectI};
@@ -299,6 +299,8 @@ pub struct PaintColorTextureMetadata {
     pub transform: Transform2F,
     /// The sampling mode for the texture.
     pub sampling_flags: TextureSamplingFlags,
+    /// Whether the texture is upscaled with a bicubic filter in the shader.
+    pub bicubic: bool,
     /// The filter to be applied to this paint.
     pub filter: PaintFilter,
     /// How the color texture is to be composited over the base color.
@@ -373,6 +375,7 @@ impl Palette {
                             location,
                             page_scale: allocator.page_scale(location.page),
                             sampling_flags: TextureSamplingFlags::empty(),
+                            bicubic: false,
                             filter: match gradient.geometry {
                                 GradientGeometry::Linear(_) => PaintFilter::None,
                                 GradientGeometry::Radial { line, radii, .. } => {
@@ -393,7 +396,14 @@ impl Palette {
                             PatternSource::Image(ref image) => {
                                 // TODO(pcwalton): We should be able to use tile cleverness to
                                 // repeat inside the atlas in some cases.
-                                let allocation_mode = AllocationMode::OwnPage;
+                                let allocation_mode = match pattern.sampling() {
+                                    PatternSampling::Trilinear | PatternSampling::Bicubic => {
+                                        AllocationMode::OwnMipmappedPage
+                                    }
+                                    PatternSampling::Nearest | PatternSampling::Bilinear => {
+                                        AllocationMode::OwnPage
+                                    }
+                                };
                                 location = allocator.allocate(image.size(), allocation_mode);
                                 image_texel_info.push(ImageTexelInfo {
                                     location,
@@ -409,9 +419,19 @@ impl Palette {
                         if pattern.repeat_y() {
                             sampling_flags.insert(TextureSamplingFlags::REPEAT_V);
                         }
-                        if !pattern.smoothing_enabled() {
-                            sampling_flags.insert(TextureSamplingFlags::NEAREST_MIN |
-                                                  TextureSamplingFlags::NEAREST_MAG);
+                        match pattern.sampling() {
+                            PatternSampling::Nearest => {
+                                sampling_flags.insert(TextureSamplingFlags::NEAREST_MIN |
+                                                      TextureSamplingFlags::NEAREST_MAG);
+                            }
+                            PatternSampling::Bilinear | PatternSampling::Trilinear => {}
+                            PatternSampling::Bicubic => {
+                                sampling_flags.insert(TextureSamplingFlags::ANISOTROPIC);
+                            }
+                        }
+                        // Render targets have no mipmaps, so they fall back to bilinear.
+                        if allocator.page_is_mipmapped(location.page) {
+                            sampling_flags.insert(TextureSamplingFlags::MIPMAP_MIN);
                         }
 
                         let filter = match pattern.filter() {
@@ -423,6 +443,7 @@ impl Palette {
                             location,
                             page_scale: allocator.page_scale(location.page),
                             sampling_flags,
+                            bicubic: pattern.sampling() == PatternSampling::Bicubic,
                             filter,
                             transform: Transform2F::default(),
                             composite_op: overlay.composite_op(),
@@ -506,7 +527,10 @@ impl Palette {
         for page_index in 0..self.allocator.page_count() {
             let page_id = TexturePageId(page_index);
             let page_size = self.allocator.page_size(page_id);
-            let descriptor = TexturePageDescriptor { size: page_size };
+            let descriptor = TexturePageDescriptor {
+                size: page_size,
+                mipmapped: self.allocator.page_is_mipmapped(page_id),
+            };
             texture_page_descriptors.push(descriptor);
 
             if self.allocator.page_is_new(page_id) {
@@ -564,13 +588,17 @@ impl Palette {
                         PaintContents::Pattern(ref pattern) => {
                             match pattern.source() {
                                 PatternSource::RenderTarget { id: old_render_target_id, size } => {
+                                    // Render targets of other scenes keep their IDs.
+                                    let new_render_target_id =
+                                        render_target_mapping.get(old_render_target_id)
+                                                             .unwrap_or(old_render_target_id);
                                     let mut new_pattern =
-                                        Pattern::from_render_target(*old_render_target_id, *size);
+                                        Pattern::from_render_target(*new_render_target_id, *size);
                                     new_pattern.set_filter(pattern.filter());
                                     new_pattern.apply_transform(pattern.transform());
                                     new_pattern.set_repeat_x(pattern.repeat_x());
                                     new_pattern.set_repeat_y(pattern.repeat_y());
-                                    new_pattern.set_smoothing_enabled(pattern.smoothing_enabled());
+                                    new_pattern.set_sampling(pattern.sampling());
                                     self.push_paint(&Paint::from_pattern(new_pattern))
                                 }
                                 _ => self.push_paint(old_paint),
@@ -696,6 +724,7 @@ impl PaintColorTextureMetadata {
         TileBatchTexture {
             page: self.location.page,
             sampling_flags: self.sampling_flags,
+            bicubic: self.bicubic,
             composite_op: self.composite_op,
         }
     }
@@ -12,6 +12,7 @@
 
 use crate::builder::SceneBuilder;
 use crate::concurrent::executor::Executor;
+use crate::gpu::renderer::Renderer;
 use crate::options::{BuildOptions, PreparedBuildOptions};
 use crate::options::{PreparedRenderTransform, RenderCommandListener};
 use crate::paint::{MergedPaletteInfo, Paint, PaintId, PaintInfo, Palette};
@@ -22,7 +23,10 @@ use pathfinder_content::render_target::RenderTargetId;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::{Vector2I, vec2f};
+use pathfinder_gpu::Device;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
 static NEXT_SCENE_ID: AtomicUsize = AtomicUsize::new(0);
 
@@ -94,29 +98,67 @@ impl Scene {
         self.display_list.push(DisplayItem::PopRenderTarget);
     }
 
+    #[inline]
     pub fn append_scene(&mut self, scene: Scene) {
+        self.append_scene_with_transform(scene, Transform2F::default())
+    }
+
+    /// Appends `scene`, transforming what it draws into the current render target by
+    /// `transform`. What it draws into its own render targets isn't transformed, since those have
+    /// coordinates of their own.
+    pub fn append_scene_with_transform(&mut self, scene: Scene, transform: Transform2F) {
+        let transformed_paths = if transform.is_identity() {
+            vec![false; scene.paths.len()]
+        } else {
+            scene.paths_outside_render_targets()
+        };
+
         let MergedPaletteInfo {
             render_target_mapping,
             paint_mapping,
         } = self.palette.append_palette(scene.palette);
 
-        // Merge clip paths.
+        // Merge clip paths. Transformed paths get transformed copies of theirs.
         let mut clip_path_mapping = Vec::with_capacity(scene.clip_paths.len());
-        for clip_path in scene.clip_paths {
+        for clip_path in &scene.clip_paths {
             clip_path_mapping.push(self.clip_paths.len());
-            self.clip_paths.push(clip_path);
+            self.clip_paths.push(clip_path.clone());
         }
+        let mut transformed_clip_path_mapping = HashMap::new();
 
-        // Merge draw paths.
+        // Merge draw paths, likewise transforming the paints of transformed paths.
+        let mut transformed_paint_mapping = HashMap::new();
         let mut draw_path_mapping = Vec::with_capacity(scene.paths.len());
-        for draw_path in scene.paths {
+        for (draw_path, transformed) in scene.paths.into_iter().zip(transformed_paths) {
+            let mut outline = draw_path.outline;
+            let mut paint = paint_mapping[&draw_path.paint];
+            let mut clip_path = draw_path.clip_path.map(|clip_path_id| {
+                ClipPathId(clip_path_mapping[clip_path_id.0 as usize] as u32)
+            });
+            if transformed {
+                outline.transform(&transform);
+                let palette = &mut self.palette;
+                paint = *transformed_paint_mapping.entry(paint).or_insert_with(|| {
+                    let mut transformed_paint = palette.paints[paint.0 as usize].clone();
+                    transformed_paint.apply_transform(&transform);
+                    palette.push_paint(&transformed_paint)
+                });
+                let clip_paths = &mut self.clip_paths;
+                clip_path = clip_path.map(|clip_path_id| {
+                    *transformed_clip_path_mapping.entry(clip_path_id.0).or_insert_with(|| {
+                        let mut transformed_clip_path = clip_paths[clip_path_id.0 as usize].clone();
+                        transformed_clip_path.outline.transform(&transform);
+                        clip_paths.push(transformed_clip_path);
+                        ClipPathId(clip_paths.len() as u32 - 1)
+                    })
+                });
+            }
+
             draw_path_mapping.push(self.paths.len() as u32);
             self.paths.push(DrawPath {
-                outline: draw_path.outline,
-                paint: paint_mapping[&draw_path.paint],
-                clip_path: draw_path.clip_path.map(|clip_path_id| {
-                    ClipPathId(clip_path_mapping[clip_path_id.0 as usize] as u32)
-                }),
+                outline,
+                paint,
+                clip_path,
                 fill_rule: draw_path.fill_rule,
                 blend_mode: draw_path.blend_mode,
                 name: draw_path.name,
@@ -145,6 +187,26 @@ impl Scene {
         }
     }
 
+    // Returns whether each path is drawn outside of the render targets this scene pushes.
+    fn paths_outside_render_targets(&self) -> Vec<bool> {
+        let mut outside = vec![false; self.paths.len()];
+        let mut render_target_depth = 0;
+        for display_item in &self.display_list {
+            match *display_item {
+                DisplayItem::PushRenderTarget(_) => render_target_depth += 1,
+                DisplayItem::PopRenderTarget => {
+                    render_target_depth = u32::saturating_sub(render_target_depth, 1)
+                }
+                DisplayItem::DrawPaths { start_index, end_index } => {
+                    for path_index in start_index..end_index {
+                        outside[path_index as usize] = render_target_depth == 0;
+                    }
+                }
+            }
+        }
+        outside
+    }
+
     #[inline]
     pub fn build_paint_info(&mut self, render_transform: Transform2F) -> PaintInfo {
         self.palette.build_paint_info(render_transform)
@@ -165,6 +227,20 @@ impl Scene {
         self.bounds
     }
 
+    /// Returns the bounds of the paths drawn outside of the render targets this scene pushes, or
+    /// `None` if there are none. Unlike `bounds()`, this leaves out clip paths and what's drawn
+    /// into render targets, which has coordinates of its own.
+    pub fn bounds_outside_render_targets(&self) -> Option<RectF> {
+        let mut bounds: Option<RectF> = None;
+        for (path, outside) in self.paths.iter().zip(self.paths_outside_render_targets()) {
+            if outside && !path.outline.contours().is_empty() {
+                let path_bounds = path.outline.bounds();
+                bounds = Some(bounds.map_or(path_bounds, |bounds| bounds.union_rect(path_bounds)));
+            }
+        }
+        bounds
+    }
+
     #[inline]
     pub fn set_bounds(&mut self, new_bounds: RectF) {
         self.bounds = new_bounds;
@@ -253,6 +329,27 @@ impl Scene {
         SceneBuilder::new(self, &prepared_options, listener).build(executor)
     }
 
+    /// Builds the scene on this thread and sends the resulting commands to `renderer`.
+    ///
+    /// Unlike `SceneProxy::build_and_render()`, rendering doesn't start until building has
+    /// finished.
+    pub fn build_and_render<D, E>(&mut self,
+                                  renderer: &mut Renderer<D>,
+                                  options: BuildOptions,
+                                  executor: &E)
+                                  where D: Device, E: Executor {
+        let commands = Arc::new(Mutex::new(vec![]));
+        let listener_commands = commands.clone();
+        let listener = Box::new(move |command| listener_commands.lock().unwrap().push(command));
+        self.build(options, listener, executor);
+
+        renderer.begin_scene();
+        for command in commands.lock().unwrap().drain(..) {
+            renderer.render_command(&command);
+        }
+        renderer.end_scene();
+    }
+
     pub fn paths<'a>(&'a self) -> PathIter {
         PathIter {
             scene: self,
@@ -19,6 +19,7 @@ use pathfinder_content::sorted_vector::SortedVector;
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_geometry::rect::{RectF, RectI};
 use pathfinder_geometry::vector::{Vector2F, Vector2I, vec2f, vec2i};
+use pathfinder_simd::dispatch::FeatureLevel;
 use std::cmp::Ordering;
 use std::mem;
 
@@ -81,6 +82,32 @@ impl<'a, 'b> Tiler<'a, 'b> {
     }
 
     pub(crate) fn generate_tiles(&mut self) {
+        // This loop dominates scene building, so it's compiled once per feature level and the
+        // best copy for this CPU is picked at runtime. Everything it calls while tiling is
+        // `#[inline(always)]` so that the copies reach the callees too.
+        match FeatureLevel::current() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            FeatureLevel::Avx2 => unsafe { self.generate_tiles_avx2() },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            FeatureLevel::Sse41 => unsafe { self.generate_tiles_sse41() },
+            _ => self.generate_tiles_for_feature_level(),
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx2")]
+    unsafe fn generate_tiles_avx2(&mut self) {
+        self.generate_tiles_for_feature_level()
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn generate_tiles_sse41(&mut self) {
+        self.generate_tiles_for_feature_level()
+    }
+
+    #[inline(always)]
+    fn generate_tiles_for_feature_level(&mut self) {
         // Initialize the point queue.
         self.init_point_queue();
 
@@ -101,6 +128,7 @@ impl<'a, 'b> Tiler<'a, 'b> {
         debug!("{:#?}", self.object_builder.built_path);
     }
 
+    #[inline(always)]
     fn generate_strip(&mut self, strip_origin_y: i32) {
         // Process old active edges.
         self.process_old_active_edges(strip_origin_y);
@@ -120,6 +148,7 @@ impl<'a, 'b> Tiler<'a, 'b> {
         }
     }
 
+    #[inline(always)]
     fn pack_and_cull(&mut self) {
         let draw_tiling_path_info = match self.path_info {
             TilingPathInfo::Clip => return,
@@ -173,6 +202,7 @@ impl<'a, 'b> Tiler<'a, 'b> {
         }
     }
 
+    #[inline(always)]
     fn process_old_active_edges(&mut self, tile_y: i32) {
         let mut current_tile_x = self.object_builder.tile_rect().min_x();
         let mut current_subtile_x = 0.0;
@@ -286,6 +316,7 @@ impl<'a, 'b> Tiler<'a, 'b> {
         }
     }
 
+    #[inline(always)]
     fn add_new_active_edge(&mut self, tile_y: i32) {
         let outline = &self.outline;
         let point_index = self.point_queue.pop().unwrap().point_index;
@@ -353,6 +384,7 @@ impl<'a, 'b> Tiler<'a, 'b> {
         }
     }
 
+    #[inline(always)]
     fn init_point_queue(&mut self) {
         // Find MIN points.
         self.point_queue.clear();
@@ -519,6 +551,7 @@ pub fn round_rect_out_to_tile_bounds(rect: RectF) -> RectI {
     (rect * vec2f(1.0 / TILE_WIDTH as f32, 1.0 / TILE_HEIGHT as f32)).round_out().to_i32()
 }
 
+#[inline(always)]
 fn process_active_segment(
     contour: &Contour,
     from_endpoint_index: u32,
@@ -563,6 +596,7 @@ struct ActiveEdge {
 }
 
 impl ActiveEdge {
+    #[inline(always)]
     fn from_segment(segment: &Segment) -> ActiveEdge {
         let crossing = if segment.baseline.from_y() < segment.baseline.to_y() {
             segment.baseline.from()
@@ -572,10 +606,12 @@ impl ActiveEdge {
         ActiveEdge::from_segment_and_crossing(segment, crossing)
     }
 
+    #[inline(always)]
     fn from_segment_and_crossing(segment: &Segment, crossing: Vector2F) -> ActiveEdge {
         ActiveEdge { segment: *segment, crossing }
     }
 
+    #[inline(always)]
     fn process(&mut self,
                builder: &SceneBuilder,
                object_builder: &mut ObjectBuilder,
@@ -656,6 +692,7 @@ impl ActiveEdge {
         }
     }
 
+    #[inline(always)]
     fn process_line_segment(
         &mut self,
         line_segment: LineSegment2F,
@@ -73,6 +73,11 @@ precision highp sampler2D;
 
 
 
+
+
+
+
+
 
 
 
@@ -98,7 +103,50 @@ out vec4 oFragColor;
 
 
 
-vec4 sampleColor(sampler2D colorTexture, vec2 colorTexCoord){
+
+
+vec4 sampleColorBicubic(sampler2D colorTexture, vec2 colorTexCoord, vec2 colorTextureSize){
+    vec2 texCoordDX = dFdx(colorTexCoord), texCoordDY = dFdy(colorTexCoord);
+    vec2 footprint = max(abs(texCoordDX), abs(texCoordDY))* colorTextureSize;
+    if(max(footprint . x, footprint . y)>= 1.0)
+        return textureGrad(colorTexture, colorTexCoord, texCoordDX, texCoordDY);
+
+
+
+    vec2 texelCoord = colorTexCoord * colorTextureSize;
+    vec2 texelCenter = floor(texelCoord - 0.5)+ 0.5;
+    vec2 t = texelCoord - texelCenter;
+    vec2 w0 = t *(- 0.5 + t *(1.0 - 0.5 * t));
+    vec2 w1 = 1.0 + t * t *(- 2.5 + 1.5 * t);
+    vec2 w2 = t *(0.5 + t *(2.0 - 1.5 * t));
+    vec2 w3 = t * t *(- 0.5 + 0.5 * t);
+    vec2 w12 = w1 + w2;
+
+    vec2 texCoord0 =(texelCenter - 1.0)/ colorTextureSize;
+    vec2 texCoord12 =(texelCenter + w2 / w12)/ colorTextureSize;
+    vec2 texCoord3 =(texelCenter + 2.0)/ colorTextureSize;
+
+    vec4 color =
+       (textureLod(colorTexture, vec2(texCoord0 . x, texCoord0 . y), 0.0)* w0 . x +
+         textureLod(colorTexture, vec2(texCoord12 . x, texCoord0 . y), 0.0)* w12 . x +
+         textureLod(colorTexture, vec2(texCoord3 . x, texCoord0 . y), 0.0)* w3 . x)* w0 . y +
+       (textureLod(colorTexture, vec2(texCoord0 . x, texCoord12 . y), 0.0)* w0 . x +
+         textureLod(colorTexture, vec2(texCoord12 . x, texCoord12 . y), 0.0)* w12 . x +
+         textureLod(colorTexture, vec2(texCoord3 . x, texCoord12 . y), 0.0)* w3 . x)* w12 . y +
+       (textureLod(colorTexture, vec2(texCoord0 . x, texCoord3 . y), 0.0)* w0 . x +
+         textureLod(colorTexture, vec2(texCoord12 . x, texCoord3 . y), 0.0)* w12 . x +
+         textureLod(colorTexture, vec2(texCoord3 . x, texCoord3 . y), 0.0)* w3 . x)* w3 . y;
+
+
+    return clamp(color, 0.0, 1.0);
+}
+
+vec4 sampleColor(sampler2D colorTexture,
+                 vec2 colorTexCoord,
+                 vec2 colorTextureSize,
+                 int colorSample){
+    if(colorSample == 0x1)
+        return sampleColorBicubic(colorTexture, colorTexCoord, colorTextureSize);
     return texture(colorTexture, colorTexCoord);
 }
 
@@ -369,11 +417,41 @@ vec4 filterBlur(vec2 colorTexCoord,
     }
 
 
-    return color / gaussSum;
+
+    color /= gaussSum;
+    if(color . a > 0.0)
+        color . rgb /= color . a;
+    return color;
+}
+
+
+
+
+
+
+vec4 filterColorMatrix(vec2 colorTexCoord,
+                       sampler2D colorTexture,
+                       vec2 colorTextureSize,
+                       vec4 filterParams0,
+                       vec4 filterParams1,
+                       vec4 filterParams2,
+                       int colorSample){
+
+
+    vec4 color = sampleColor(colorTexture, colorTexCoord, colorTextureSize, colorSample);
+    if(color . a > 0.0)
+        color . rgb /= color . a;
+
+    vec4 rgb1 = vec4(color . rgb, 1.0);
+    vec3 rgb = vec3(dot(filterParams0, rgb1), dot(filterParams1, rgb1), dot(filterParams2, rgb1));
+    return vec4(clamp(rgb, 0.0, 1.0), color . a);
 }
 
-vec4 filterNone(vec2 colorTexCoord, sampler2D colorTexture){
-    return sampleColor(colorTexture, colorTexCoord);
+vec4 filterNone(vec2 colorTexCoord,
+                sampler2D colorTexture,
+                vec2 colorTextureSize,
+                int colorSample){
+    return sampleColor(colorTexture, colorTexCoord, colorTextureSize, colorSample);
 }
 
 vec4 filterColor(vec2 colorTexCoord,
@@ -385,7 +463,8 @@ vec4 filterColor(vec2 colorTexCoord,
                  vec4 filterParams0,
                  vec4 filterParams1,
                  vec4 filterParams2,
-                 int colorFilter){
+                 int colorFilter,
+                 int colorSample){
     switch(colorFilter){
     case 0x1 :
         return filterRadialGradient(colorTexCoord,
@@ -409,8 +488,16 @@ vec4 filterColor(vec2 colorTexCoord,
                           filterParams0,
                           filterParams1,
                           filterParams2);
+    case 0x4 :
+        return filterColorMatrix(colorTexCoord,
+                                 colorTexture,
+                                 colorTextureSize,
+                                 filterParams0,
+                                 filterParams1,
+                                 filterParams2,
+                                 colorSample);
     }
-    return filterNone(colorTexCoord, colorTexture);
+    return filterNone(colorTexCoord, colorTexture, colorTextureSize, colorSample);
 }
 
 
@@ -564,10 +651,12 @@ void calculateColor(int ctrl){
 
 
     vec4 color = vBaseColor;
-    int color0Combine =(ctrl >> 6)&
+    int color0Combine =(ctrl >> 7)&
                                        0x3;
     if(color0Combine != 0){
-        int color0Filter =(ctrl >> 4)& 0x3;
+        int color0Filter =(ctrl >> 4)& 0x7;
+        int color0Sample =(ctrl >> 13)&
+                                         0x1;
         vec4 color0 = filterColor(vColorTexCoord0,
                                   uColorTexture0,
                                   uGammaLUT,
@@ -577,7 +666,8 @@ void calculateColor(int ctrl){
                                   uFilterParams0,
                                   uFilterParams1,
                                   uFilterParams2,
-                                  color0Filter);
+                                  color0Filter,
+                                  color0Sample);
         color = combineColor0(color, color0, color0Combine);
     }
 
@@ -585,7 +675,7 @@ void calculateColor(int ctrl){
     color . a *= maskAlpha;
 
 
-    int compositeOp =(ctrl >> 8)& 0xf;
+    int compositeOp =(ctrl >> 9)& 0xf;
     color = composite(color, uDestTexture, uFramebufferSize, gl_FragCoord . xy, compositeOp);
 
 
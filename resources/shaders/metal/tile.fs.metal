@@ -120,7 +120,13 @@ float4 filterBlur(thread const float2& colorTexCoord, thread const texture2d<flo
         float2 _658 = gaussCoeff.xy * gaussCoeff.yz;
         gaussCoeff = float3(_658.x, _658.y, gaussCoeff.z);
     }
-    return color / float4(gaussSum);
+    color /= float4(gaussSum);
+    if (color.w > 0.0)
+    {
+        float3 _633 = color.xyz / float3(color.w);
+        color = float4(_633.x, _633.y, _633.z, color.w);
+    }
+    return color;
 }
 
 float filterTextSample1Tap(thread const float& offset, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTexCoord)
@@ -239,18 +245,66 @@ float4 filterText(thread const float2& colorTexCoord, thread const texture2d<flo
     return float4(mix(bgColor, fgColor, alpha), 1.0);
 }
 
-float4 sampleColor(thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTexCoord)
+float4 sampleColorBicubic(thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTexCoord, thread const float2& colorTextureSize)
+{
+    float2 texCoordDX = dfdx(colorTexCoord);
+    float2 texCoordDY = dfdy(colorTexCoord);
+    float2 footprint = fast::max(abs(texCoordDX), abs(texCoordDY)) * colorTextureSize;
+    if (fast::max(footprint.x, footprint.y) >= 1.0)
+    {
+        return colorTexture.sample(colorTextureSmplr, colorTexCoord, gradient2d(texCoordDX, texCoordDY));
+    }
+    float2 texelCoord = colorTexCoord * colorTextureSize;
+    float2 texelCenter = floor(texelCoord - float2(0.5)) + float2(0.5);
+    float2 t = texelCoord - texelCenter;
+    float2 w0 = t * (float2(-0.5) + (t * (float2(1.0) - (t * 0.5))));
+    float2 w1 = float2(1.0) + ((t * t) * (float2(-2.5) + (t * 1.5)));
+    float2 w2 = t * (float2(0.5) + (t * (float2(2.0) - (t * 1.5))));
+    float2 w3 = (t * t) * (float2(-0.5) + (t * 0.5));
+    float2 w12 = w1 + w2;
+    float2 texCoord0 = (texelCenter - float2(1.0)) / colorTextureSize;
+    float2 texCoord12 = (texelCenter + (w2 / w12)) / colorTextureSize;
+    float2 texCoord3 = (texelCenter + float2(2.0)) / colorTextureSize;
+    float4 color = (((((colorTexture.sample(colorTextureSmplr, float2(texCoord0.x, texCoord0.y), level(0.0)) * w0.x) + (colorTexture.sample(colorTextureSmplr, float2(texCoord12.x, texCoord0.y), level(0.0)) * w12.x)) + (colorTexture.sample(colorTextureSmplr, float2(texCoord3.x, texCoord0.y), level(0.0)) * w3.x)) * w0.y) + ((((colorTexture.sample(colorTextureSmplr, float2(texCoord0.x, texCoord12.y), level(0.0)) * w0.x) + (colorTexture.sample(colorTextureSmplr, float2(texCoord12.x, texCoord12.y), level(0.0)) * w12.x)) + (colorTexture.sample(colorTextureSmplr, float2(texCoord3.x, texCoord12.y), level(0.0)) * w3.x)) * w12.y)) + ((((colorTexture.sample(colorTextureSmplr, float2(texCoord0.x, texCoord3.y), level(0.0)) * w0.x) + (colorTexture.sample(colorTextureSmplr, float2(texCoord12.x, texCoord3.y), level(0.0)) * w12.x)) + (colorTexture.sample(colorTextureSmplr, float2(texCoord3.x, texCoord3.y), level(0.0)) * w3.x)) * w3.y);
+    return fast::clamp(color, float4(0.0), float4(1.0));
+}
+
+float4 sampleColor(thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTexCoord, thread const float2& colorTextureSize, thread const int& colorSample)
 {
+    if (colorSample == 1)
+    {
+        float2 param = colorTexCoord;
+        float2 param_1 = colorTextureSize;
+        return sampleColorBicubic(colorTexture, colorTextureSmplr, param, param_1);
+    }
     return colorTexture.sample(colorTextureSmplr, colorTexCoord);
 }
 
-float4 filterNone(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr)
+float4 filterColorMatrix(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTextureSize, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const int& colorSample)
 {
     float2 param = colorTexCoord;
-    return sampleColor(colorTexture, colorTextureSmplr, param);
+    float2 param_1 = colorTextureSize;
+    int param_2 = colorSample;
+    float4 color = sampleColor(colorTexture, colorTextureSmplr, param, param_1, param_2);
+    if (color.w > 0.0)
+    {
+        float3 _675 = color.xyz / float3(color.w);
+        color = float4(_675.x, _675.y, _675.z, color.w);
+    }
+    float4 rgb1 = float4(color.xyz, 1.0);
+    float3 rgb = float3(dot(filterParams0, rgb1), dot(filterParams1, rgb1), dot(filterParams2, rgb1));
+    return float4(fast::clamp(rgb, float3(0.0), float3(1.0)), color.w);
+}
+
+float4 filterNone(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const float2& colorTextureSize, thread const int& colorSample)
+{
+    float2 param = colorTexCoord;
+    float2 param_1 = colorTextureSize;
+    int param_2 = colorSample;
+    return sampleColor(colorTexture, colorTextureSmplr, param, param_1, param_2);
 }
 
-float4 filterColor(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const texture2d<float> gammaLUT, thread const sampler gammaLUTSmplr, thread const float2& colorTextureSize, thread const float2& fragCoord, thread const float2& framebufferSize, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const int& colorFilter)
+float4 filterColor(thread const float2& colorTexCoord, thread const texture2d<float> colorTexture, thread const sampler colorTextureSmplr, thread const texture2d<float> gammaLUT, thread const sampler gammaLUTSmplr, thread const float2& colorTextureSize, thread const float2& fragCoord, thread const float2& framebufferSize, thread const float4& filterParams0, thread const float4& filterParams1, thread const float4& filterParams2, thread const int& colorFilter, thread const int& colorSample)
 {
     switch (colorFilter)
     {
@@ -281,9 +335,21 @@ float4 filterColor(thread const float2& colorTexCoord, thread const texture2d<fl
             float4 param_14 = filterParams2;
             return filterText(param_10, colorTexture, colorTextureSmplr, gammaLUT, gammaLUTSmplr, param_11, param_12, param_13, param_14);
         }
+        case 4:
+        {
+            float2 param_15 = colorTexCoord;
+            float2 param_16 = colorTextureSize;
+            float4 param_17 = filterParams0;
+            float4 param_18 = filterParams1;
+            float4 param_19 = filterParams2;
+            int param_20 = colorSample;
+            return filterColorMatrix(param_15, colorTexture, colorTextureSmplr, param_16, param_17, param_18, param_19, param_20);
+        }
     }
-    float2 param_15 = colorTexCoord;
-    return filterNone(param_15, colorTexture, colorTextureSmplr);
+    float2 param_21 = colorTexCoord;
+    float2 param_22 = colorTextureSize;
+    int param_23 = colorSample;
+    return filterNone(param_21, colorTexture, colorTextureSmplr, param_22, param_23);
 }
 
 float4 combineColor0(thread const float4& destColor, thread const float4& srcColor, thread const int& op)
@@ -554,10 +620,11 @@ void calculateColor(thread const int& ctrl, thread texture2d<float> uMaskTexture
     int param_2 = maskCtrl0;
     maskAlpha = sampleMask(param, uMaskTexture0, uMaskTexture0Smplr, param_1, param_2);
     float4 color = vBaseColor;
-    int color0Combine = (ctrl >> 6) & 3;
+    int color0Combine = (ctrl >> 7) & 3;
     if (color0Combine != 0)
     {
-        int color0Filter = (ctrl >> 4) & 3;
+        int color0Filter = (ctrl >> 4) & 7;
+        int color0Sample = (ctrl >> 13) & 1;
         float2 param_3 = vColorTexCoord0;
         float2 param_4 = uColorTexture0Size;
         float2 param_5 = gl_FragCoord.xy;
@@ -566,19 +633,20 @@ void calculateColor(thread const int& ctrl, thread texture2d<float> uMaskTexture
         float4 param_8 = uFilterParams1;
         float4 param_9 = uFilterParams2;
         int param_10 = color0Filter;
-        float4 color0 = filterColor(param_3, uColorTexture0, uColorTexture0Smplr, uGammaLUT, uGammaLUTSmplr, param_4, param_5, param_6, param_7, param_8, param_9, param_10);
-        float4 param_11 = color;
-        float4 param_12 = color0;
-        int param_13 = color0Combine;
-        color = combineColor0(param_11, param_12, param_13);
+        int param_11 = color0Sample;
+        float4 color0 = filterColor(param_3, uColorTexture0, uColorTexture0Smplr, uGammaLUT, uGammaLUTSmplr, param_4, param_5, param_6, param_7, param_8, param_9, param_10, param_11);
+        float4 param_12 = color;
+        float4 param_13 = color0;
+        int param_14 = color0Combine;
+        color = combineColor0(param_12, param_13, param_14);
     }
     color.w *= maskAlpha;
-    int compositeOp = (ctrl >> 8) & 15;
-    float4 param_14 = color;
-    float2 param_15 = uFramebufferSize;
-    float2 param_16 = gl_FragCoord.xy;
-    int param_17 = compositeOp;
-    color = composite(param_14, uDestTexture, uDestTextureSmplr, param_15, param_16, param_17);
+    int compositeOp = (ctrl >> 9) & 15;
+    float4 param_15 = color;
+    float2 param_16 = uFramebufferSize;
+    float2 param_17 = gl_FragCoord.xy;
+    int param_18 = compositeOp;
+    color = composite(param_15, uDestTexture, uDestTextureSmplr, param_16, param_17, param_18);
     float3 _1324 = color.xyz * color.w;
     color = float4(_1324.x, _1324.y, _1324.z, color.w);
     oFragColor = color;
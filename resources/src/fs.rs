@@ -11,13 +11,18 @@
 //! Reads resources from the filesystem.
 
 use crate::ResourceLoader;
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Error as IOError, Read};
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 pub struct FilesystemResourceLoader {
     pub directory: PathBuf,
+    // The modification times of loaded files, if watching.
+    modification_times: Option<Mutex<HashMap<String, SystemTime>>>,
 }
 
 impl FilesystemResourceLoader {
@@ -33,9 +38,7 @@ impl FilesystemResourceLoader {
                 shaders_directory.push("shaders");
                 textures_directory.push("textures");
                 if shaders_directory.is_dir() && textures_directory.is_dir() {
-                    return FilesystemResourceLoader {
-                        directory: resources_directory,
-                    };
+                    return FilesystemResourceLoader::new(resources_directory);
                 }
             }
 
@@ -46,17 +49,101 @@ impl FilesystemResourceLoader {
 
         panic!("No suitable `resources/` directory found!");
     }
-}
 
-impl ResourceLoader for FilesystemResourceLoader {
-    fn slurp(&self, virtual_path: &str) -> Result<Vec<u8>, IOError> {
+    #[inline]
+    pub fn new(directory: PathBuf) -> FilesystemResourceLoader {
+        FilesystemResourceLoader { directory, modification_times: None }
+    }
+
+    /// Makes this loader watch the files it loads from now on, so that `poll_changes()` reports
+    /// them when they're modified on disk.
+    ///
+    /// This is meant for editing shaders while an application is running. Each poll checks every
+    /// loaded file, so it's best done once per frame at most.
+    pub fn watching(mut self) -> FilesystemResourceLoader {
+        self.modification_times = Some(Mutex::new(HashMap::new()));
+        self
+    }
+
+    fn path_of(&self, virtual_path: &str) -> PathBuf {
         let mut path = self.directory.clone();
         virtual_path
             .split('/')
             .for_each(|segment| path.push(segment));
+        path
+    }
+}
+
+impl ResourceLoader for FilesystemResourceLoader {
+    fn slurp(&self, virtual_path: &str) -> Result<Vec<u8>, IOError> {
+        let path = self.path_of(virtual_path);
+        let mut file = File::open(&path)?;
+        if let Some(ref modification_times) = self.modification_times {
+            let modification_time = file.metadata()?.modified()?;
+            modification_times.lock()
+                              .unwrap()
+                              .insert(virtual_path.to_owned(), modification_time);
+        }
 
         let mut data = vec![];
-        File::open(&path)?.read_to_end(&mut data)?;
+        file.read_to_end(&mut data)?;
         Ok(data)
     }
+
+    fn poll_changes(&self) -> Vec<String> {
+        let mut modification_times = match self.modification_times {
+            None => return vec![],
+            Some(ref modification_times) => modification_times.lock().unwrap(),
+        };
+
+        let mut changed_paths = vec![];
+        for (virtual_path, last_modification_time) in modification_times.iter_mut() {
+            // Files that can't be read right now, e.g. because an editor is replacing them, are
+            // checked again on the next poll.
+            let path = self.path_of(virtual_path);
+            if let Ok(modification_time) = fs::metadata(&path).and_then(|meta| meta.modified()) {
+                if modification_time != *last_modification_time {
+                    *last_modification_time = modification_time;
+                    changed_paths.push(virtual_path.clone());
+                }
+            }
+        }
+        changed_paths
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ResourceLoader;
+    use crate::fs::FilesystemResourceLoader;
+    use std::env;
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_poll_changes() {
+        let directory = env::temp_dir().join(format!("pathfinder-fs-test-{}", std::process::id()));
+        fs::create_dir_all(directory.join("shaders")).unwrap();
+        fs::write(directory.join("shaders/test.glsl"), b"void main() {}").unwrap();
+        fs::write(directory.join("shaders/other.glsl"), b"void main() {}").unwrap();
+
+        let loader = FilesystemResourceLoader::new(directory.clone()).watching();
+        assert_eq!(loader.slurp("shaders/test.glsl").unwrap(), b"void main() {}");
+        assert!(loader.poll_changes().is_empty());
+
+        // Set the time explicitly, since file system timestamps may be coarse.
+        let file = File::options().write(true).open(directory.join("shaders/test.glsl")).unwrap();
+        file.set_len(0).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        drop(file);
+        let file = File::options().write(true).open(directory.join("shaders/other.glsl")).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        drop(file);
+
+        // Only files that were loaded are watched, and each change is reported once.
+        assert_eq!(loader.poll_changes(), vec!["shaders/test.glsl".to_owned()]);
+        assert!(loader.poll_changes().is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
 }
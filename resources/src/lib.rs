@@ -26,4 +26,12 @@ pub trait ResourceLoader {
     /// This is deliberately not a `Path`, because these are virtual paths
     /// that do not necessarily correspond to real paths on a filesystem.
     fn slurp(&self, path: &str) -> Result<Vec<u8>, IOError>;
+
+    /// Returns the paths of previously-loaded resources that have changed since they were last
+    /// loaded, if this loader watches for changes. Each change is reported only once.
+    ///
+    /// The default implementation never reports any changes.
+    fn poll_changes(&self) -> Vec<String> {
+        vec![]
+    }
 }
@@ -49,10 +49,14 @@ precision highp sampler2D;
 #define COMBINER_CTRL_COLOR_COMBINE_SRC_IN      0x1
 #define COMBINER_CTRL_COLOR_COMBINE_DEST_IN     0x2
 
-#define COMBINER_CTRL_FILTER_MASK               0x3
+#define COMBINER_CTRL_FILTER_MASK               0x7
 #define COMBINER_CTRL_FILTER_RADIAL_GRADIENT    0x1
 #define COMBINER_CTRL_FILTER_TEXT               0x2
 #define COMBINER_CTRL_FILTER_BLUR               0x3
+#define COMBINER_CTRL_FILTER_COLOR_MATRIX       0x4
+
+#define COMBINER_CTRL_COLOR_SAMPLE_MASK         0x1
+#define COMBINER_CTRL_COLOR_SAMPLE_BICUBIC      0x1
 
 #define COMBINER_CTRL_COMPOSITE_MASK            0xf
 #define COMBINER_CTRL_COMPOSITE_NORMAL          0x0
@@ -74,8 +78,9 @@ precision highp sampler2D;
 
 #define COMBINER_CTRL_MASK_0_SHIFT              0
 #define COMBINER_CTRL_COLOR_FILTER_SHIFT        4
-#define COMBINER_CTRL_COLOR_COMBINE_SHIFT       6
-#define COMBINER_CTRL_COMPOSITE_SHIFT           8
+#define COMBINER_CTRL_COLOR_COMBINE_SHIFT       7
+#define COMBINER_CTRL_COMPOSITE_SHIFT           9
+#define COMBINER_CTRL_COLOR_SAMPLE_SHIFT        13
 
 uniform sampler2D uColorTexture0;
 uniform sampler2D uMaskTexture0;
@@ -96,7 +101,50 @@ out vec4 oFragColor;
 
 // Color sampling
 
-vec4 sampleColor(sampler2D colorTexture, vec2 colorTexCoord) {
+// Upscales with a Catmull-Rom filter, built from nine bilinear taps. When downscaling, this uses
+// the texture's own filtering instead, which may be trilinear or anisotropic.
+vec4 sampleColorBicubic(sampler2D colorTexture, vec2 colorTexCoord, vec2 colorTextureSize) {
+    vec2 texCoordDX = dFdx(colorTexCoord), texCoordDY = dFdy(colorTexCoord);
+    vec2 footprint = max(abs(texCoordDX), abs(texCoordDY)) * colorTextureSize;
+    if (max(footprint.x, footprint.y) >= 1.0)
+        return textureGrad(colorTexture, colorTexCoord, texCoordDX, texCoordDY);
+
+    // Weights for the texels at offsets -1, 0, 1, and 2 from the one at or below the sample
+    // point. The middle two are merged into one bilinear tap between them.
+    vec2 texelCoord = colorTexCoord * colorTextureSize;
+    vec2 texelCenter = floor(texelCoord - 0.5) + 0.5;
+    vec2 t = texelCoord - texelCenter;
+    vec2 w0 = t * (-0.5 + t * (1.0 - 0.5 * t));
+    vec2 w1 = 1.0 + t * t * (-2.5 + 1.5 * t);
+    vec2 w2 = t * (0.5 + t * (2.0 - 1.5 * t));
+    vec2 w3 = t * t * (-0.5 + 0.5 * t);
+    vec2 w12 = w1 + w2;
+
+    vec2 texCoord0 = (texelCenter - 1.0) / colorTextureSize;
+    vec2 texCoord12 = (texelCenter + w2 / w12) / colorTextureSize;
+    vec2 texCoord3 = (texelCenter + 2.0) / colorTextureSize;
+
+    vec4 color =
+        (textureLod(colorTexture, vec2(texCoord0.x, texCoord0.y), 0.0) * w0.x +
+         textureLod(colorTexture, vec2(texCoord12.x, texCoord0.y), 0.0) * w12.x +
+         textureLod(colorTexture, vec2(texCoord3.x, texCoord0.y), 0.0) * w3.x) * w0.y +
+        (textureLod(colorTexture, vec2(texCoord0.x, texCoord12.y), 0.0) * w0.x +
+         textureLod(colorTexture, vec2(texCoord12.x, texCoord12.y), 0.0) * w12.x +
+         textureLod(colorTexture, vec2(texCoord3.x, texCoord12.y), 0.0) * w3.x) * w12.y +
+        (textureLod(colorTexture, vec2(texCoord0.x, texCoord3.y), 0.0) * w0.x +
+         textureLod(colorTexture, vec2(texCoord12.x, texCoord3.y), 0.0) * w12.x +
+         textureLod(colorTexture, vec2(texCoord3.x, texCoord3.y), 0.0) * w3.x) * w3.y;
+
+    // Catmull-Rom overshoots around sharp edges.
+    return clamp(color, 0.0, 1.0);
+}
+
+vec4 sampleColor(sampler2D colorTexture,
+                 vec2 colorTexCoord,
+                 vec2 colorTextureSize,
+                 int colorSample) {
+    if (colorSample == COMBINER_CTRL_COLOR_SAMPLE_BICUBIC)
+        return sampleColorBicubic(colorTexture, colorTexCoord, colorTextureSize);
     return texture(colorTexture, colorTexCoord);
 }
 
@@ -366,12 +414,42 @@ vec4 filterBlur(vec2 colorTexCoord,
         gaussCoeff.xy *= gaussCoeff.yz;
     }
 
-    // Finish.
-    return color / gaussSum;
+    // Finish. The source is a render target, so the sum is premultiplied, but we return
+    // unpremultiplied color.
+    color /= gaussSum;
+    if (color.a > 0.0)
+        color.rgb /= color.a;
+    return color;
 }
 
-vec4 filterNone(vec2 colorTexCoord, sampler2D colorTexture) {
-    return sampleColor(colorTexture, colorTexCoord);
+//                | x             y             z             w
+//  --------------+----------------------------------------------------
+//  filterParams0 | matrix[0].r   matrix[0].g   matrix[0].b   offset.r
+//  filterParams1 | matrix[1].r   matrix[1].g   matrix[1].b   offset.g
+//  filterParams2 | matrix[2].r   matrix[2].g   matrix[2].b   offset.b
+vec4 filterColorMatrix(vec2 colorTexCoord,
+                       sampler2D colorTexture,
+                       vec2 colorTextureSize,
+                       vec4 filterParams0,
+                       vec4 filterParams1,
+                       vec4 filterParams2,
+                       int colorSample) {
+    // The source is a render target, so it's premultiplied. The matrix applies to
+    // unpremultiplied color, which is also what we return.
+    vec4 color = sampleColor(colorTexture, colorTexCoord, colorTextureSize, colorSample);
+    if (color.a > 0.0)
+        color.rgb /= color.a;
+
+    vec4 rgb1 = vec4(color.rgb, 1.0);
+    vec3 rgb = vec3(dot(filterParams0, rgb1), dot(filterParams1, rgb1), dot(filterParams2, rgb1));
+    return vec4(clamp(rgb, 0.0, 1.0), color.a);
+}
+
+vec4 filterNone(vec2 colorTexCoord,
+                sampler2D colorTexture,
+                vec2 colorTextureSize,
+                int colorSample) {
+    return sampleColor(colorTexture, colorTexCoord, colorTextureSize, colorSample);
 }
 
 vec4 filterColor(vec2 colorTexCoord,
@@ -383,7 +461,8 @@ vec4 filterColor(vec2 colorTexCoord,
                  vec4 filterParams0,
                  vec4 filterParams1,
                  vec4 filterParams2,
-                 int colorFilter) {
+                 int colorFilter,
+                 int colorSample) {
     switch (colorFilter) {
     case COMBINER_CTRL_FILTER_RADIAL_GRADIENT:
         return filterRadialGradient(colorTexCoord,
@@ -407,8 +486,16 @@ vec4 filterColor(vec2 colorTexCoord,
                           filterParams0,
                           filterParams1,
                           filterParams2);
+    case COMBINER_CTRL_FILTER_COLOR_MATRIX:
+        return filterColorMatrix(colorTexCoord,
+                                 colorTexture,
+                                 colorTextureSize,
+                                 filterParams0,
+                                 filterParams1,
+                                 filterParams2,
+                                 colorSample);
     }
-    return filterNone(colorTexCoord, colorTexture);
+    return filterNone(colorTexCoord, colorTexture, colorTextureSize, colorSample);
 }
 
 // Compositing
@@ -566,6 +653,8 @@ void calculateColor(int ctrl) {
         COMBINER_CTRL_COLOR_COMBINE_MASK;
     if (color0Combine != 0) {
         int color0Filter = (ctrl >> COMBINER_CTRL_COLOR_FILTER_SHIFT) & COMBINER_CTRL_FILTER_MASK;
+        int color0Sample = (ctrl >> COMBINER_CTRL_COLOR_SAMPLE_SHIFT) &
+            COMBINER_CTRL_COLOR_SAMPLE_MASK;
         vec4 color0 = filterColor(vColorTexCoord0,
                                   uColorTexture0,
                                   uGammaLUT,
@@ -575,7 +664,8 @@ void calculateColor(int ctrl) {
                                   uFilterParams0,
                                   uFilterParams1,
                                   uFilterParams2,
-                                  color0Filter);
+                                  color0Filter,
+                                  color0Sample);
         color = combineColor0(color, color0, color0Combine);
     }
 
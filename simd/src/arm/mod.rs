@@ -15,6 +15,10 @@ use std::fmt::{self, Debug, Formatter};
 use std::mem;
 use std::ops::{Add, BitAnd, BitOr, Div, Index, IndexMut, Mul, Shr, Sub};
 
+pub use self::pair::{F32x8, I32x8, U32x8};
+
+#[path = "../pair.rs"]
+mod pair;
 mod swizzle_f32x4;
 mod swizzle_i32x4;
 
@@ -574,6 +578,11 @@ impl I32x4 {
         unsafe { U32x4(simd_eq(self.0, other.0)) }
     }
 
+    #[inline]
+    pub fn packed_gt(self, other: I32x4) -> U32x4 {
+        unsafe { U32x4(simd_gt(self.0, other.0)) }
+    }
+
     #[inline]
     pub fn packed_le(self, other: I32x4) -> U32x4 {
         unsafe { U32x4(simd_le(self.0, other.0)) }
@@ -630,6 +639,14 @@ impl I32x4 {
     pub fn to_f32x4(self) -> F32x4 {
         unsafe { F32x4(simd_cast(self.0)) }
     }
+
+    /// Converts these packed signed integers to unsigned integers.
+    ///
+    /// Overflowing values will wrap around.
+    #[inline]
+    pub fn to_u32x4(self) -> U32x4 {
+        unsafe { U32x4(mem::transmute(self.0)) }
+    }
 }
 
 impl Default for I32x4 {
@@ -760,6 +777,19 @@ impl Index<usize> for U32x2 {
 pub struct U32x4(pub uint32x4_t);
 
 impl U32x4 {
+    #[inline]
+    pub fn new(a: u32, b: u32, c: u32, d: u32) -> U32x4 {
+        unsafe { U32x4(mem::transmute([a, b, c, d])) }
+    }
+
+    /// Converts these packed unsigned integers to signed integers.
+    ///
+    /// Overflowing values will wrap around.
+    #[inline]
+    pub fn to_i32x4(self) -> I32x4 {
+        unsafe { I32x4(mem::transmute(self.0)) }
+    }
+
     /// Returns true if all four booleans in this vector are true.
     ///
     /// The result is *undefined* if all four values in this vector are not booleans. A boolean is
@@ -791,6 +821,14 @@ impl Index<usize> for U32x4 {
     }
 }
 
+impl Shr<u32> for U32x4 {
+    type Output = U32x4;
+    #[inline]
+    fn shr(self, amount: u32) -> U32x4 {
+        unsafe { U32x4(simd_shr(self.0, U32x4::new(amount, amount, amount, amount).0)) }
+    }
+}
+
 // Intrinsics
 
 extern "platform-intrinsic" {
@@ -0,0 +1,76 @@
+// pathfinder/simd/src/dispatch.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Runtime selection of CPU feature levels.
+//!
+//! The backend is chosen at compile time, so a binary built for baseline x86-64 only runs SSE2
+//! code even on CPUs with AVX2. Hot loops can instead be compiled once per feature level, using
+//! `#[target_feature]` wrappers around an `#[inline(always)]` body, and pick the best copy with
+//! `FeatureLevel::current()`. Detection runs once; afterward it's a single relaxed load.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const UNDETECTED: usize = !0;
+
+static CURRENT_FEATURE_LEVEL: AtomicUsize = AtomicUsize::new(UNDETECTED);
+
+/// A set of CPU features that code can be specialized for, in increasing order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FeatureLevel {
+    /// Only the features enabled at compile time.
+    Baseline = 0,
+    /// SSE4.1 on x86.
+    Sse41 = 1,
+    /// AVX2 on x86, which implies AVX and SSE4.1.
+    Avx2 = 2,
+}
+
+impl FeatureLevel {
+    /// Returns the feature level to use on this CPU.
+    #[inline]
+    pub fn current() -> FeatureLevel {
+        match CURRENT_FEATURE_LEVEL.load(Ordering::Relaxed) {
+            0 => FeatureLevel::Baseline,
+            1 => FeatureLevel::Sse41,
+            2 => FeatureLevel::Avx2,
+            _ => {
+                let level = FeatureLevel::detect();
+                CURRENT_FEATURE_LEVEL.store(level as usize, Ordering::Relaxed);
+                level
+            }
+        }
+    }
+
+    /// Caps the level that `current()` returns, e.g. to benchmark or test the slower paths.
+    ///
+    /// The result never exceeds what the CPU supports, so this can't enable missing features.
+    pub fn restrict_to(max_level: FeatureLevel) {
+        let level = FeatureLevel::detect().min(max_level);
+        CURRENT_FEATURE_LEVEL.store(level as usize, Ordering::Relaxed);
+    }
+
+    /// Returns the highest feature level that this CPU supports, ignoring any restriction.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn detect() -> FeatureLevel {
+        if is_x86_feature_detected!("avx2") {
+            FeatureLevel::Avx2
+        } else if is_x86_feature_detected!("sse4.1") {
+            FeatureLevel::Sse41
+        } else {
+            FeatureLevel::Baseline
+        }
+    }
+
+    /// Returns the highest feature level that this CPU supports, ignoring any restriction.
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    pub fn detect() -> FeatureLevel {
+        FeatureLevel::Baseline
+    }
+}
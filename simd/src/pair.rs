@@ -0,0 +1,467 @@
+// pathfinder/simd/src/pair.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Eight-wide vectors built from pairs of four-wide ones.
+//!
+//! Every backend includes this file as a submodule for its default eight-wide types, so `super`
+//! refers to the backend. On x86 these are the safe fallback; see `x86::avx2` for the AVX2 types.
+
+use super::{F32x4, I32x4, U32x4};
+use std::cmp::PartialEq;
+use std::fmt::{self, Debug, Formatter};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Index, IndexMut, Mul, Not, Shr, Sub};
+
+// Eight 32-bit floats
+
+#[derive(Clone, Copy, Default)]
+pub struct F32x8(pub F32x4, pub F32x4);
+
+impl F32x8 {
+    // Constructors
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32, g: f32, h: f32) -> F32x8 {
+        F32x8(F32x4::new(a, b, c, d), F32x4::new(e, f, g, h))
+    }
+
+    #[inline]
+    pub fn splat(x: f32) -> F32x8 {
+        F32x8(F32x4::splat(x), F32x4::splat(x))
+    }
+
+    #[inline]
+    pub fn from_halves(lo: F32x4, hi: F32x4) -> F32x8 {
+        F32x8(lo, hi)
+    }
+
+    // Basic operations
+
+    #[inline]
+    pub fn min(self, other: F32x8) -> F32x8 {
+        F32x8(self.0.min(other.0), self.1.min(other.1))
+    }
+
+    #[inline]
+    pub fn max(self, other: F32x8) -> F32x8 {
+        F32x8(self.0.max(other.0), self.1.max(other.1))
+    }
+
+    #[inline]
+    pub fn clamp(self, min: F32x8, max: F32x8) -> F32x8 {
+        self.max(min).min(max)
+    }
+
+    #[inline]
+    pub fn abs(self) -> F32x8 {
+        F32x8(self.0.abs(), self.1.abs())
+    }
+
+    #[inline]
+    pub fn floor(self) -> F32x8 {
+        F32x8(self.0.floor(), self.1.floor())
+    }
+
+    #[inline]
+    pub fn ceil(self) -> F32x8 {
+        F32x8(self.0.ceil(), self.1.ceil())
+    }
+
+    // Packed comparisons
+
+    #[inline]
+    pub fn packed_eq(self, other: F32x8) -> U32x8 {
+        U32x8(self.0.packed_eq(other.0), self.1.packed_eq(other.1))
+    }
+
+    #[inline]
+    pub fn packed_gt(self, other: F32x8) -> U32x8 {
+        U32x8(self.0.packed_gt(other.0), self.1.packed_gt(other.1))
+    }
+
+    #[inline]
+    pub fn packed_lt(self, other: F32x8) -> U32x8 {
+        U32x8(self.0.packed_lt(other.0), self.1.packed_lt(other.1))
+    }
+
+    #[inline]
+    pub fn packed_le(self, other: F32x8) -> U32x8 {
+        U32x8(self.0.packed_le(other.0), self.1.packed_le(other.1))
+    }
+
+    // Conversions
+
+    /// Converts these packed floats to integers via rounding.
+    #[inline]
+    pub fn to_i32x8(self) -> I32x8 {
+        I32x8(self.0.to_i32x4(), self.1.to_i32x4())
+    }
+
+    // Extraction
+
+    #[inline]
+    pub fn lo(self) -> F32x4 {
+        self.0
+    }
+
+    #[inline]
+    pub fn hi(self) -> F32x4 {
+        self.1
+    }
+}
+
+impl Index<usize> for F32x8 {
+    type Output = f32;
+    #[inline]
+    fn index(&self, index: usize) -> &f32 {
+        if index < 4 { &self.0[index] } else { &self.1[index - 4] }
+    }
+}
+
+impl IndexMut<usize> for F32x8 {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        if index < 4 { &mut self.0[index] } else { &mut self.1[index - 4] }
+    }
+}
+
+impl Debug for F32x8 {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<{}, {}, {}, {}, {}, {}, {}, {}>",
+               self[0], self[1], self[2], self[3], self[4], self[5], self[6], self[7])
+    }
+}
+
+impl PartialEq for F32x8 {
+    #[inline]
+    fn eq(&self, other: &F32x8) -> bool {
+        self.packed_eq(*other).all_true()
+    }
+}
+
+impl Add<F32x8> for F32x8 {
+    type Output = F32x8;
+    #[inline]
+    fn add(self, other: F32x8) -> F32x8 {
+        F32x8(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl Div<F32x8> for F32x8 {
+    type Output = F32x8;
+    #[inline]
+    fn div(self, other: F32x8) -> F32x8 {
+        F32x8(self.0 / other.0, self.1 / other.1)
+    }
+}
+
+impl Mul<F32x8> for F32x8 {
+    type Output = F32x8;
+    #[inline]
+    fn mul(self, other: F32x8) -> F32x8 {
+        F32x8(self.0 * other.0, self.1 * other.1)
+    }
+}
+
+impl Sub<F32x8> for F32x8 {
+    type Output = F32x8;
+    #[inline]
+    fn sub(self, other: F32x8) -> F32x8 {
+        F32x8(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+// Eight 32-bit signed integers
+
+#[derive(Clone, Copy, Default)]
+pub struct I32x8(pub I32x4, pub I32x4);
+
+impl I32x8 {
+    // Constructors
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub fn new(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, g: i32, h: i32) -> I32x8 {
+        I32x8(I32x4::new(a, b, c, d), I32x4::new(e, f, g, h))
+    }
+
+    #[inline]
+    pub fn splat(x: i32) -> I32x8 {
+        I32x8(I32x4::splat(x), I32x4::splat(x))
+    }
+
+    #[inline]
+    pub fn from_halves(lo: I32x4, hi: I32x4) -> I32x8 {
+        I32x8(lo, hi)
+    }
+
+    // Extraction
+
+    #[inline]
+    pub fn lo(self) -> I32x4 {
+        self.0
+    }
+
+    #[inline]
+    pub fn hi(self) -> I32x4 {
+        self.1
+    }
+
+    // Swizzle
+
+    /// Swaps adjacent pairs of lanes, like `I32x4::yxwz()` applied to each half.
+    #[inline]
+    pub fn yxwz(self) -> I32x8 {
+        I32x8(self.0.yxwz(), self.1.yxwz())
+    }
+
+    // Conversions
+
+    /// Converts these packed integers to floats.
+    #[inline]
+    pub fn to_f32x8(self) -> F32x8 {
+        F32x8(self.0.to_f32x4(), self.1.to_f32x4())
+    }
+
+    /// Converts these packed signed integers to unsigned integers.
+    ///
+    /// Overflowing values will wrap around.
+    #[inline]
+    pub fn to_u32x8(self) -> U32x8 {
+        U32x8(self.0.to_u32x4(), self.1.to_u32x4())
+    }
+
+    // Basic operations
+
+    #[inline]
+    pub fn max(self, other: I32x8) -> I32x8 {
+        I32x8(self.0.max(other.0), self.1.max(other.1))
+    }
+
+    #[inline]
+    pub fn min(self, other: I32x8) -> I32x8 {
+        I32x8(self.0.min(other.0), self.1.min(other.1))
+    }
+
+    // Packed comparisons
+
+    #[inline]
+    pub fn packed_eq(self, other: I32x8) -> U32x8 {
+        U32x8(self.0.packed_eq(other.0), self.1.packed_eq(other.1))
+    }
+
+    #[inline]
+    pub fn packed_gt(self, other: I32x8) -> U32x8 {
+        U32x8(self.0.packed_gt(other.0), self.1.packed_gt(other.1))
+    }
+
+    #[inline]
+    pub fn packed_lt(self, other: I32x8) -> U32x8 {
+        U32x8(self.0.packed_lt(other.0), self.1.packed_lt(other.1))
+    }
+
+    #[inline]
+    pub fn packed_le(self, other: I32x8) -> U32x8 {
+        U32x8(self.0.packed_le(other.0), self.1.packed_le(other.1))
+    }
+}
+
+impl Index<usize> for I32x8 {
+    type Output = i32;
+    #[inline]
+    fn index(&self, index: usize) -> &i32 {
+        if index < 4 { &self.0[index] } else { &self.1[index - 4] }
+    }
+}
+
+impl IndexMut<usize> for I32x8 {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut i32 {
+        if index < 4 { &mut self.0[index] } else { &mut self.1[index - 4] }
+    }
+}
+
+impl Add<I32x8> for I32x8 {
+    type Output = I32x8;
+    #[inline]
+    fn add(self, other: I32x8) -> I32x8 {
+        I32x8(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl Sub<I32x8> for I32x8 {
+    type Output = I32x8;
+    #[inline]
+    fn sub(self, other: I32x8) -> I32x8 {
+        I32x8(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+impl Mul<I32x8> for I32x8 {
+    type Output = I32x8;
+    #[inline]
+    fn mul(self, other: I32x8) -> I32x8 {
+        I32x8(self.0 * other.0, self.1 * other.1)
+    }
+}
+
+impl BitAnd<I32x8> for I32x8 {
+    type Output = I32x8;
+    #[inline]
+    fn bitand(self, other: I32x8) -> I32x8 {
+        I32x8(self.0 & other.0, self.1 & other.1)
+    }
+}
+
+impl BitOr<I32x8> for I32x8 {
+    type Output = I32x8;
+    #[inline]
+    fn bitor(self, other: I32x8) -> I32x8 {
+        I32x8(self.0 | other.0, self.1 | other.1)
+    }
+}
+
+impl Debug for I32x8 {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<{}, {}, {}, {}, {}, {}, {}, {}>",
+               self[0], self[1], self[2], self[3], self[4], self[5], self[6], self[7])
+    }
+}
+
+impl PartialEq for I32x8 {
+    #[inline]
+    fn eq(&self, other: &I32x8) -> bool {
+        self.packed_eq(*other).all_true()
+    }
+}
+
+// Eight 32-bit unsigned integers
+
+#[derive(Clone, Copy)]
+pub struct U32x8(pub U32x4, pub U32x4);
+
+impl U32x8 {
+    // Constructors
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub fn new(a: u32, b: u32, c: u32, d: u32, e: u32, f: u32, g: u32, h: u32) -> U32x8 {
+        U32x8(U32x4::new(a, b, c, d), U32x4::new(e, f, g, h))
+    }
+
+    #[inline]
+    pub fn splat(x: u32) -> U32x8 {
+        U32x8::new(x, x, x, x, x, x, x, x)
+    }
+
+    #[inline]
+    pub fn from_halves(lo: U32x4, hi: U32x4) -> U32x8 {
+        U32x8(lo, hi)
+    }
+
+    // Conversions
+
+    /// Converts these packed unsigned integers to signed integers.
+    ///
+    /// Overflowing values will wrap around.
+    #[inline]
+    pub fn to_i32x8(self) -> I32x8 {
+        I32x8(self.0.to_i32x4(), self.1.to_i32x4())
+    }
+
+    // Basic operations
+
+    /// Returns true if all eight booleans in this vector are true.
+    ///
+    /// The result is *undefined* if all eight values in this vector are not booleans. A boolean
+    /// is a value with all bits set or all bits clear (i.e. !0 or 0).
+    #[inline]
+    pub fn all_true(self) -> bool {
+        self.0.all_true() && self.1.all_true()
+    }
+
+    /// Returns true if all eight booleans in this vector are false.
+    ///
+    /// The result is *undefined* if all eight values in this vector are not booleans. A boolean
+    /// is a value with all bits set or all bits clear (i.e. !0 or 0).
+    #[inline]
+    pub fn all_false(self) -> bool {
+        self.0.all_false() && self.1.all_false()
+    }
+
+    // Extraction
+
+    #[inline]
+    pub fn lo(self) -> U32x4 {
+        self.0
+    }
+
+    #[inline]
+    pub fn hi(self) -> U32x4 {
+        self.1
+    }
+
+    // Packed comparisons
+
+    #[inline]
+    pub fn packed_eq(self, other: U32x8) -> U32x8 {
+        self.to_i32x8().packed_eq(other.to_i32x8())
+    }
+}
+
+impl Debug for U32x8 {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<{}, {}, {}, {}, {}, {}, {}, {}>",
+               self[0], self[1], self[2], self[3], self[4], self[5], self[6], self[7])
+    }
+}
+
+impl Index<usize> for U32x8 {
+    type Output = u32;
+    #[inline]
+    fn index(&self, index: usize) -> &u32 {
+        if index < 4 { &self.0[index] } else { &self.1[index - 4] }
+    }
+}
+
+impl PartialEq for U32x8 {
+    #[inline]
+    fn eq(&self, other: &U32x8) -> bool {
+        self.packed_eq(*other).all_true()
+    }
+}
+
+impl Not for U32x8 {
+    type Output = U32x8;
+    #[inline]
+    fn not(self) -> U32x8 {
+        self ^ U32x8::splat(!0)
+    }
+}
+
+impl BitXor<U32x8> for U32x8 {
+    type Output = U32x8;
+    #[inline]
+    fn bitxor(self, other: U32x8) -> U32x8 {
+        U32x8::new(self[0] ^ other[0], self[1] ^ other[1], self[2] ^ other[2], self[3] ^ other[3],
+                   self[4] ^ other[4], self[5] ^ other[5], self[6] ^ other[6], self[7] ^ other[7])
+    }
+}
+
+impl Shr<u32> for U32x8 {
+    type Output = U32x8;
+    #[inline]
+    fn shr(self, amount: u32) -> U32x8 {
+        U32x8(self.0 >> amount, self.1 >> amount)
+    }
+}
@@ -12,6 +12,10 @@ use std::f32;
 use std::fmt::{self, Debug, Formatter};
 use std::ops::{Add, BitAnd, BitOr, Div, Index, IndexMut, Mul, Shr, Sub};
 
+pub use self::pair::{F32x8, I32x8, U32x8};
+
+#[path = "../pair.rs"]
+mod pair;
 mod swizzle_f32x4;
 mod swizzle_i32x4;
 
@@ -8,8 +8,10 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::default::{F32x4, I32x4, U32x4};
+use crate::default::{F32x4, F32x8, I32x4, I32x8, U32x4, U32x8};
+use crate::dispatch::FeatureLevel;
 use crate::scalar::F32x4 as F32x4S;
+use crate::scalar::F32x8 as F32x8S;
 
 // F32x4
 
@@ -37,7 +39,9 @@ fn test_f32x4_accessors_and_mutators() {
 fn test_f32x4_basic_ops() {
     let a = F32x4::new(1.0, 3.0, 5.0, 7.0);
     let b = F32x4::new(2.0, 2.0, 6.0, 6.0);
-    assert_eq!(a.approx_recip(), F32x4::new(0.99975586, 0.33325195, 0.19995117, 0.14282227));
+    // `approx_recip` precision varies by backend; x86 guarantees 1.5 × 2⁻¹² relative error.
+    let recip_error = (a.approx_recip() * a - F32x4::splat(1.0)).abs();
+    assert!(recip_error.packed_lt(F32x4::splat(0.0004)).all_true());
     assert_eq!(a.min(b), F32x4::new(1.0, 2.0, 5.0, 6.0));
     assert_eq!(a.max(b), F32x4::new(2.0, 3.0, 6.0, 7.0));
     let c = F32x4::new(-1.0, 1.3, -20.0, 3.6);
@@ -667,6 +671,128 @@ fn test_i32x4_swizzles() {
     assert_eq!(a.zwww(), I32x4::new(3, 4, 4, 4));
 }
 
+// F32x8
+
+#[test]
+fn test_f32x8_basic_ops() {
+    let a = F32x8::new(1.0, 3.0, 5.0, 7.0, -1.0, 1.25, -20.0, 3.75);
+    let b = F32x8::new(2.0, 2.0, 6.0, 6.0, 0.5, 0.5, 10.0, 3.0);
+    assert_eq!((a[0], a[3], a[4], a[7]), (1.0, 7.0, -1.0, 3.75));
+    assert_eq!(a.lo(), F32x4::new(1.0, 3.0, 5.0, 7.0));
+    assert_eq!(a.hi(), F32x4::new(-1.0, 1.25, -20.0, 3.75));
+    assert_eq!(F32x8::from_halves(a.lo(), a.hi()), a);
+    assert_eq!(a.min(b), F32x8::new(1.0, 2.0, 5.0, 6.0, -1.0, 0.5, -20.0, 3.0));
+    assert_eq!(a.max(b), F32x8::new(2.0, 3.0, 6.0, 7.0, 0.5, 1.25, 10.0, 3.75));
+    assert_eq!(a.abs().hi(), F32x4::new(1.0, 1.25, 20.0, 3.75));
+    assert_eq!(a.floor().hi(), F32x4::new(-1.0, 1.0, -20.0, 3.0));
+    assert_eq!(a.ceil().hi(), F32x4::new(-1.0, 2.0, -20.0, 4.0));
+    assert_eq!(a.clamp(F32x8::splat(0.0), F32x8::splat(4.0)),
+               F32x8::new(1.0, 3.0, 4.0, 4.0, 0.0, 1.25, 0.0, 3.75));
+    assert_eq!(a + b, F32x8::new(3.0, 5.0, 11.0, 13.0, -0.5, 1.75, -10.0, 6.75));
+    assert_eq!(a - b, F32x8::new(-1.0, 1.0, -1.0, 1.0, -1.5, 0.75, -30.0, 0.75));
+    assert_eq!(a * b, F32x8::new(2.0, 6.0, 30.0, 42.0, -0.5, 0.625, -200.0, 11.25));
+    assert_eq!(a.packed_gt(b), U32x8::new(0, !0, 0, !0, 0, !0, 0, !0));
+    assert_eq!(a.packed_le(b), U32x8::new(!0, 0, !0, 0, !0, 0, !0, 0));
+    assert_eq!(a.to_i32x8(), I32x8::new(1, 3, 5, 7, -1, 1, -20, 4));
+    assert_eq!("<1, 3, 5, 7, -1, 1.25, -20, 3.75>", format!("{:?}", a));
+}
+
+#[test]
+fn test_i32x8_basic_ops() {
+    let a = I32x8::new(0x123, 0x456, 0x789, 0xabc, -5, 6, -7, 8);
+    let b = I32x8::splat(0xf00);
+    assert_eq!(a.lo(), I32x4::new(0x123, 0x456, 0x789, 0xabc));
+    assert_eq!(I32x8::from_halves(a.lo(), a.hi()), a);
+    assert_eq!(a & b, I32x8::new(0x100, 0x400, 0x700, 0xa00, 0xf00, 0, 0xf00, 0));
+    assert_eq!(a | I32x8::splat(1), I32x8::new(0x123, 0x457, 0x789, 0xabd, -5, 7, -7, 9));
+    assert_eq!(a.yxwz(), I32x8::new(0x456, 0x123, 0xabc, 0x789, 6, -5, 8, -7));
+    assert_eq!((a & b).to_u32x8() >> 8,
+               U32x8::new(0x1, 0x4, 0x7, 0xa, 0xf, 0, 0xf, 0));
+    assert_eq!(a.min(I32x8::splat(0)), I32x8::new(0, 0, 0, 0, -5, 0, -7, 0));
+    assert_eq!(a.packed_gt(I32x8::splat(0)).hi(), U32x4::new(0, !0, 0, !0));
+    assert_eq!(a.to_f32x8().hi(), F32x4::new(-5.0, 6.0, -7.0, 8.0));
+}
+
+// AVX2 F32x8
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[test]
+fn test_avx2_matches_default() {
+    // Baseline x86-64 lacks AVX2, so the AVX2 types can only be exercised where it's detected.
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    unsafe { check_avx2_matches_default() }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn check_avx2_matches_default() {
+    use crate::x86::avx2::{F32x8 as F32x8A, I32x8 as I32x8A, U32x8 as U32x8A};
+
+    let a = F32x8A::new(1.0, 3.0, 5.0, 7.0, -1.0, 1.25, -20.0, 3.75);
+    let b = F32x8A::splat(2.0);
+    let (a4, b4) = (F32x8::new(1.0, 3.0, 5.0, 7.0, -1.0, 1.25, -20.0, 3.75), F32x8::splat(2.0));
+    assert_eq!((a[0], a[3], a[4], a[7]), (1.0, 7.0, -1.0, 3.75));
+    assert_eq!((a.lo(), a.hi()), (a4.lo(), a4.hi()));
+    assert_eq!(F32x8A::from_halves(a.lo(), a.hi()).hi(), a4.hi());
+    assert_eq!(a.min(b).lo(), a4.min(b4).lo());
+    assert_eq!(a.max(b).hi(), a4.max(b4).hi());
+    assert_eq!(a.abs().hi(), a4.abs().hi());
+    assert_eq!(a.floor().hi(), a4.floor().hi());
+    assert_eq!(a.ceil().hi(), a4.ceil().hi());
+    assert_eq!(a.clamp(F32x8A::zero(), b).hi(), a4.clamp(F32x8::default(), b4).hi());
+    assert_eq!(a.add(b).hi(), (a4 + b4).hi());
+    assert_eq!(a.sub(b).hi(), (a4 - b4).hi());
+    assert_eq!(a.mul(b).hi(), (a4 * b4).hi());
+    assert_eq!(a.div(b).hi(), (a4 / b4).hi());
+    assert_eq!(a.packed_gt(b).hi(), a4.packed_gt(b4).hi());
+    assert_eq!(a.packed_le(b).lo(), a4.packed_le(b4).lo());
+    assert_eq!(a.to_i32x8().hi(), a4.to_i32x8().hi());
+    assert_eq!(format!("{:?}", a), format!("{:?}", a4));
+
+    let c = I32x8A::new(0x123, 0x456, 0x789, 0xabc, -5, 6, -7, 8);
+    let c4 = I32x8::new(0x123, 0x456, 0x789, 0xabc, -5, 6, -7, 8);
+    let mask = I32x8A::splat(0xf00);
+    assert_eq!(c.and(mask).hi(), (c4 & I32x8::splat(0xf00)).hi());
+    assert_eq!(c.or(I32x8A::splat(1)).lo(), (c4 | I32x8::splat(1)).lo());
+    assert_eq!(c.yxwz().hi(), c4.yxwz().hi());
+    assert_eq!(c.min(I32x8A::zero()).hi(), c4.min(I32x8::default()).hi());
+    assert_eq!(c.packed_gt(I32x8A::zero()).hi(), c4.packed_gt(I32x8::default()).hi());
+    assert_eq!(c.to_f32x8().hi(), c4.to_f32x8().hi());
+    assert_eq!(c.and(mask).to_u32x8().shr(8).hi(),
+               ((c4 & I32x8::splat(0xf00)).to_u32x8() >> 8).hi());
+    assert!(U32x8A::splat(!0).all_true() && U32x8A::splat(0).all_false());
+    assert!(U32x8A::new(!0, !0, !0, !0, 0, 0, 0, 0).not().hi().all_true());
+    assert_eq!(U32x8A::from_halves(U32x4::splat(1), U32x4::splat(2)).hi(), U32x4::splat(2));
+}
+
+// Scalar F32x8
+
+#[test]
+fn test_f32x8s_matches_four_wide() {
+    let a = F32x8S::new(4.0, -1.0, 6.0, -32.0, 1.5, 2.5, -0.5, 3.6);
+    let b = F32x8S::splat(0.5);
+    let (a_lo, a_hi) = (a.lo(), a.hi());
+    let b4 = F32x4S::splat(0.5);
+    assert_eq!(((a + b).lo(), (a + b).hi()), (a_lo + b4, a_hi + b4));
+    assert_eq!(((a * b).lo(), (a * b).hi()), (a_lo * b4, a_hi * b4));
+    assert_eq!(a.to_i32x8().hi(), a_hi.to_i32x4());
+    assert_eq!(a.packed_gt(b).hi(), a_hi.packed_gt(b4));
+}
+
+// Feature level dispatch
+
+#[test]
+fn test_feature_level_restriction() {
+    let detected = FeatureLevel::detect();
+    assert!(FeatureLevel::current() <= detected);
+    FeatureLevel::restrict_to(FeatureLevel::Baseline);
+    assert_eq!(FeatureLevel::current(), FeatureLevel::Baseline);
+    FeatureLevel::restrict_to(FeatureLevel::Avx2);
+    assert_eq!(FeatureLevel::current(), detected);
+}
+
 // Scalar F32x4
 
 #[test]
@@ -689,3 +815,90 @@ fn test_f32x4s_basic_ops() {
     assert_eq!(c.ceil(), F32x4S::new(-1.0, 2.0, -20.0, 4.0));
     assert_eq!(c.to_i32x4().to_f32x4(), F32x4S::new(-1.0, 1.0, -20.0, 4.0));
 }
+
+// Cross-checks of the default backend against the scalar one
+
+const CROSS_CHECK_VALUES: [f32; 12] =
+    [0.0, -0.0, 1.0, -1.0, 0.5, -2.5, 3.6, -7.25, 100.0, 1.0e-3, -4096.0, 1234.5];
+
+fn cross_check_f32x4s() -> impl Iterator<Item = (F32x4, F32x4S)> {
+    let values = &CROSS_CHECK_VALUES;
+    (0..values.len()).map(move |i| {
+        let lanes = [values[i],
+                     values[(i + 5) % values.len()],
+                     values[(i + 7) % values.len()],
+                     values[(i + 11) % values.len()]];
+        (F32x4::new(lanes[0], lanes[1], lanes[2], lanes[3]),
+         F32x4S::new(lanes[0], lanes[1], lanes[2], lanes[3]))
+    })
+}
+
+fn assert_f32x4_matches(a: F32x4, b: F32x4S) {
+    assert_eq!((a[0], a[1], a[2], a[3]), (b[0], b[1], b[2], b[3]));
+}
+
+#[test]
+fn test_f32x4_matches_scalar() {
+    for (a, a_s) in cross_check_f32x4s() {
+        for (b, b_s) in cross_check_f32x4s() {
+            assert_f32x4_matches(a + b, a_s + b_s);
+            assert_f32x4_matches(a - b, a_s - b_s);
+            assert_f32x4_matches(a * b, a_s * b_s);
+            assert_f32x4_matches(a.min(b), a_s.min(b_s));
+            assert_f32x4_matches(a.max(b), a_s.max(b_s));
+            assert_f32x4_matches(a.concat_xy_xy(b), a_s.concat_xy_xy(b_s));
+            assert_f32x4_matches(a.concat_xy_zw(b), a_s.concat_xy_zw(b_s));
+            assert_f32x4_matches(a.concat_zw_zw(b), a_s.concat_zw_zw(b_s));
+            assert_f32x4_matches(a.concat_wz_yx(b), a_s.concat_wz_yx(b_s));
+            for (mask, mask_s) in [(a.packed_eq(b), a_s.packed_eq(b_s)),
+                                   (a.packed_gt(b), a_s.packed_gt(b_s)),
+                                   (a.packed_lt(b), a_s.packed_lt(b_s)),
+                                   (a.packed_le(b), a_s.packed_le(b_s))].iter() {
+                assert_eq!((mask[0], mask[1], mask[2], mask[3]),
+                           (mask_s[0], mask_s[1], mask_s[2], mask_s[3]));
+            }
+        }
+        assert_f32x4_matches(a.abs(), a_s.abs());
+        assert_f32x4_matches(a.floor(), a_s.floor());
+        assert_f32x4_matches(a.ceil(), a_s.ceil());
+        assert_f32x4_matches(a.abs().sqrt(), a_s.abs().sqrt());
+        assert_f32x4_matches(a.wzyx(), a_s.wzyx());
+        assert_f32x4_matches(a.yxwz(), a_s.yxwz());
+        // Backends may round ties differently, so keep conversions away from them.
+        let (c, c_s) = (a + F32x4::splat(0.25), a_s + F32x4S::splat(0.25));
+        let (i, i_s) = (c.to_i32x4(), c_s.to_i32x4());
+        assert_eq!((i[0], i[1], i[2], i[3]), (i_s[0], i_s[1], i_s[2], i_s[3]));
+    }
+}
+
+#[test]
+fn test_i32x4_matches_scalar() {
+    for (a, a_s) in cross_check_f32x4s() {
+        let (a, a_s) = ((a * F32x4::splat(8.0)).to_i32x4(), (a_s * F32x4S::splat(8.0)).to_i32x4());
+        for (b, b_s) in cross_check_f32x4s() {
+            let (b, b_s) = ((b + F32x4::splat(0.25)).to_i32x4(),
+                            (b_s + F32x4S::splat(0.25)).to_i32x4());
+            for (c, c_s) in [(a + b, a_s + b_s),
+                             (a - b, a_s - b_s),
+                             (a * b, a_s * b_s),
+                             (a & b, a_s & b_s),
+                             (a | b, a_s | b_s),
+                             (a.min(b), a_s.min(b_s)),
+                             (a.max(b), a_s.max(b_s)),
+                             (a.concat_xy_xy(b), a_s.concat_xy_xy(b_s)),
+                             (a.concat_zw_zw(b), a_s.concat_zw_zw(b_s))].iter() {
+                assert_eq!((c[0], c[1], c[2], c[3]), (c_s[0], c_s[1], c_s[2], c_s[3]));
+            }
+            for (mask, mask_s) in [(a.packed_eq(b), a_s.packed_eq(b_s)),
+                                   (a.packed_gt(b), a_s.packed_gt(b_s)),
+                                   (a.packed_lt(b), a_s.packed_lt(b_s)),
+                                   (a.packed_le(b), a_s.packed_le(b_s))].iter() {
+                assert_eq!((mask[0], mask[1], mask[2], mask[3]),
+                           (mask_s[0], mask_s[1], mask_s[2], mask_s[3]));
+            }
+        }
+        assert_f32x4_matches(a.to_f32x4(), a_s.to_f32x4());
+        let (s, s_s) = (a.to_u32x4() >> 3, a_s.to_u32x4() >> 3);
+        assert_eq!((s[0], s[1], s[2], s[3]), (s_s[0], s_s[1], s_s[2], s_s[3]));
+    }
+}
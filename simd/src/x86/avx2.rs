@@ -0,0 +1,660 @@
+// pathfinder/simd/src/x86/avx2.rs
+//
+// Copyright © 2020 The Pathfinder Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Eight-wide vectors using AVX2.
+//!
+//! Unlike the four-wide types, these require a CPU feature that baseline x86-64 lacks, so every
+//! operation is an `unsafe fn`. Callers must check `FeatureLevel::current()` (or
+//! `is_x86_feature_detected!("avx2")`) first, and should call them from a function marked
+//! `#[target_feature(enable = "avx2")]` so that the intrinsics are inlined. Safe code should use
+//! `pathfinder_simd::default::F32x8` and friends, which are built from pairs of four-wide vectors
+//! on x86.
+
+use crate::x86::{F32x4, I32x4, U32x4};
+use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::ops::{Index, IndexMut};
+
+#[cfg(target_pointer_width = "32")]
+use std::arch::x86::{__m256, __m256i};
+#[cfg(target_pointer_width = "32")]
+use std::arch::x86;
+#[cfg(target_pointer_width = "64")]
+use std::arch::x86_64::{__m256, __m256i};
+#[cfg(target_pointer_width = "64")]
+use std::arch::x86_64 as x86;
+
+// Eight 32-bit floats
+
+#[derive(Clone, Copy)]
+pub struct F32x8(pub __m256);
+
+impl F32x8 {
+    // Constructors
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32, g: f32, h: f32) -> F32x8 {
+        F32x8(x86::_mm256_setr_ps(a, b, c, d, e, f, g, h))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn splat(x: f32) -> F32x8 {
+        F32x8(x86::_mm256_set1_ps(x))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn zero() -> F32x8 {
+        F32x8(x86::_mm256_setzero_ps())
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn from_halves(lo: F32x4, hi: F32x4) -> F32x8 {
+        F32x8(x86::_mm256_set_m128(hi.0, lo.0))
+    }
+
+    // Basic operations
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn min(self, other: F32x8) -> F32x8 {
+        F32x8(x86::_mm256_min_ps(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn max(self, other: F32x8) -> F32x8 {
+        F32x8(x86::_mm256_max_ps(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn clamp(self, min: F32x8, max: F32x8) -> F32x8 {
+        self.max(min).min(max)
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn abs(self) -> F32x8 {
+        F32x8(x86::_mm256_andnot_ps(x86::_mm256_set1_ps(-0.0), self.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn floor(self) -> F32x8 {
+        F32x8(x86::_mm256_floor_ps(self.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn ceil(self) -> F32x8 {
+        F32x8(x86::_mm256_ceil_ps(self.0))
+    }
+
+    // Arithmetic
+    //
+    // These are methods rather than operator impls, because trait methods can't be `unsafe`.
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add(self, other: F32x8) -> F32x8 {
+        F32x8(x86::_mm256_add_ps(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub(self, other: F32x8) -> F32x8 {
+        F32x8(x86::_mm256_sub_ps(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn mul(self, other: F32x8) -> F32x8 {
+        F32x8(x86::_mm256_mul_ps(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn div(self, other: F32x8) -> F32x8 {
+        F32x8(x86::_mm256_div_ps(self.0, other.0))
+    }
+
+    // Packed comparisons
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_eq(self, other: F32x8) -> U32x8 {
+        U32x8(x86::_mm256_castps_si256(x86::_mm256_cmp_ps(self.0, other.0, x86::_CMP_EQ_OQ)))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_gt(self, other: F32x8) -> U32x8 {
+        U32x8(x86::_mm256_castps_si256(x86::_mm256_cmp_ps(self.0, other.0, x86::_CMP_GT_OQ)))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_lt(self, other: F32x8) -> U32x8 {
+        other.packed_gt(self)
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_le(self, other: F32x8) -> U32x8 {
+        self.packed_gt(other).not()
+    }
+
+    // Conversions
+
+    /// Converts these packed floats to integers via rounding.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn to_i32x8(self) -> I32x8 {
+        I32x8(x86::_mm256_cvtps_epi32(self.0))
+    }
+
+    // Extraction
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn lo(self) -> F32x4 {
+        F32x4(x86::_mm256_castps256_ps128(self.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn hi(self) -> F32x4 {
+        F32x4(x86::_mm256_extractf128_ps(self.0, 1))
+    }
+}
+
+impl Index<usize> for F32x8 {
+    type Output = f32;
+    #[inline]
+    fn index(&self, index: usize) -> &f32 {
+        unsafe { &mem::transmute::<&__m256, &[f32; 8]>(&self.0)[index] }
+    }
+}
+
+impl IndexMut<usize> for F32x8 {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut f32 {
+        unsafe { &mut mem::transmute::<&mut __m256, &mut [f32; 8]>(&mut self.0)[index] }
+    }
+}
+
+impl Debug for F32x8 {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<{}, {}, {}, {}, {}, {}, {}, {}>",
+               self[0], self[1], self[2], self[3], self[4], self[5], self[6], self[7])
+    }
+}
+
+// Eight 32-bit signed integers
+
+#[derive(Clone, Copy)]
+pub struct I32x8(pub __m256i);
+
+impl I32x8 {
+    // Constructors
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn new(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, g: i32, h: i32) -> I32x8 {
+        I32x8(x86::_mm256_setr_epi32(a, b, c, d, e, f, g, h))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn splat(x: i32) -> I32x8 {
+        I32x8(x86::_mm256_set1_epi32(x))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn zero() -> I32x8 {
+        I32x8(x86::_mm256_setzero_si256())
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn from_halves(lo: I32x4, hi: I32x4) -> I32x8 {
+        I32x8(x86::_mm256_set_m128i(hi.0, lo.0))
+    }
+
+    // Extraction
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn lo(self) -> I32x4 {
+        I32x4(x86::_mm256_castsi256_si128(self.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn hi(self) -> I32x4 {
+        I32x4(x86::_mm256_extracti128_si256(self.0, 1))
+    }
+
+    // Swizzle
+
+    /// Swaps adjacent pairs of lanes, like `I32x4::yxwz()` applied to each half.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn yxwz(self) -> I32x8 {
+        I32x8(x86::_mm256_shuffle_epi32(self.0, 0b1011_0001))
+    }
+
+    // Conversions
+
+    /// Converts these packed integers to floats.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn to_f32x8(self) -> F32x8 {
+        F32x8(x86::_mm256_cvtepi32_ps(self.0))
+    }
+
+    /// Converts these packed signed integers to unsigned integers.
+    ///
+    /// Overflowing values will wrap around.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn to_u32x8(self) -> U32x8 {
+        U32x8(self.0)
+    }
+
+    // Basic operations
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn max(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_max_epi32(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn min(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_min_epi32(self.0, other.0))
+    }
+
+    // Arithmetic and bitwise operations
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_add_epi32(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_sub_epi32(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn mul(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_mullo_epi32(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn and(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_and_si256(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn or(self, other: I32x8) -> I32x8 {
+        I32x8(x86::_mm256_or_si256(self.0, other.0))
+    }
+
+    // Packed comparisons
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_eq(self, other: I32x8) -> U32x8 {
+        U32x8(x86::_mm256_cmpeq_epi32(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_gt(self, other: I32x8) -> U32x8 {
+        U32x8(x86::_mm256_cmpgt_epi32(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_lt(self, other: I32x8) -> U32x8 {
+        other.packed_gt(self)
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_le(self, other: I32x8) -> U32x8 {
+        self.packed_gt(other).not()
+    }
+}
+
+impl Index<usize> for I32x8 {
+    type Output = i32;
+    #[inline]
+    fn index(&self, index: usize) -> &i32 {
+        unsafe { &mem::transmute::<&__m256i, &[i32; 8]>(&self.0)[index] }
+    }
+}
+
+impl IndexMut<usize> for I32x8 {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut i32 {
+        unsafe { &mut mem::transmute::<&mut __m256i, &mut [i32; 8]>(&mut self.0)[index] }
+    }
+}
+
+impl Debug for I32x8 {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<{}, {}, {}, {}, {}, {}, {}, {}>",
+               self[0], self[1], self[2], self[3], self[4], self[5], self[6], self[7])
+    }
+}
+
+// Eight 32-bit unsigned integers
+
+#[derive(Clone, Copy)]
+pub struct U32x8(pub __m256i);
+
+impl U32x8 {
+    // Constructors
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn new(a: u32, b: u32, c: u32, d: u32, e: u32, f: u32, g: u32, h: u32) -> U32x8 {
+        I32x8::new(a as i32, b as i32, c as i32, d as i32,
+                   e as i32, f as i32, g as i32, h as i32).to_u32x8()
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn splat(x: u32) -> U32x8 {
+        I32x8::splat(x as i32).to_u32x8()
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn from_halves(lo: U32x4, hi: U32x4) -> U32x8 {
+        U32x8(x86::_mm256_set_m128i(hi.0, lo.0))
+    }
+
+    // Conversions
+
+    /// Converts these packed unsigned integers to signed integers.
+    ///
+    /// Overflowing values will wrap around.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn to_i32x8(self) -> I32x8 {
+        I32x8(self.0)
+    }
+
+    // Basic operations
+
+    /// Returns true if all eight booleans in this vector are true.
+    ///
+    /// The result is *undefined* if all eight values in this vector are not booleans. A boolean
+    /// is a value with all bits set or all bits clear (i.e. !0 or 0).
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn all_true(self) -> bool {
+        x86::_mm256_movemask_ps(x86::_mm256_castsi256_ps(self.0)) == 0xff
+    }
+
+    /// Returns true if all eight booleans in this vector are false.
+    ///
+    /// The result is *undefined* if all eight values in this vector are not booleans. A boolean
+    /// is a value with all bits set or all bits clear (i.e. !0 or 0).
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn all_false(self) -> bool {
+        x86::_mm256_testz_si256(self.0, self.0) != 0
+    }
+
+    // Bitwise operations
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn not(self) -> U32x8 {
+        self.xor(U32x8::splat(!0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn xor(self, other: U32x8) -> U32x8 {
+        U32x8(x86::_mm256_xor_si256(self.0, other.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn shr(self, amount: u32) -> U32x8 {
+        U32x8(x86::_mm256_srl_epi32(self.0, U32x4::new(amount, 0, 0, 0).0))
+    }
+
+    // Extraction
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn lo(self) -> U32x4 {
+        U32x4(x86::_mm256_castsi256_si128(self.0))
+    }
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn hi(self) -> U32x4 {
+        U32x4(x86::_mm256_extracti128_si256(self.0, 1))
+    }
+
+    // Packed comparisons
+
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn packed_eq(self, other: U32x8) -> U32x8 {
+        U32x8(x86::_mm256_cmpeq_epi32(self.0, other.0))
+    }
+}
+
+impl Index<usize> for U32x8 {
+    type Output = u32;
+    #[inline]
+    fn index(&self, index: usize) -> &u32 {
+        unsafe { &mem::transmute::<&__m256i, &[u32; 8]>(&self.0)[index] }
+    }
+}
+
+impl Debug for U32x8 {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<{}, {}, {}, {}, {}, {}, {}, {}>",
+               self[0], self[1], self[2], self[3], self[4], self[5], self[6], self[7])
+    }
+}
@@ -22,6 +22,11 @@ use std::arch::x86_64::{__m128, __m128i};
 #[cfg(target_pointer_width = "64")]
 use std::arch::x86_64 as x86;
 
+pub use self::pair::{F32x8, I32x8, U32x8};
+
+pub mod avx2;
+#[path = "../pair.rs"]
+mod pair;
 mod swizzle_f32x4;
 mod swizzle_i32x4;
 
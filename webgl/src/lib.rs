@@ -28,14 +28,29 @@ use wasm_bindgen::{JsCast, JsValue};
 use web_sys::WebGl2RenderingContext as WebGl;
 use js_sys::{Uint8Array, Uint16Array, Float32Array, Object};
 
+// From `EXT_texture_filter_anisotropic`, which `web_sys` doesn't define.
+const TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84fe;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84ff;
+
 pub struct WebGlDevice {
     context: web_sys::WebGl2RenderingContext,
+    // The maximum degree of anisotropic filtering, or 1.0 if it's unsupported.
+    max_anisotropy: f32,
 }
 
 impl WebGlDevice {
     pub fn new(context: web_sys::WebGl2RenderingContext) -> Self {
         context.get_extension("EXT_color_buffer_float").unwrap();
-        WebGlDevice { context }
+        let max_anisotropy = match context.get_extension("EXT_texture_filter_anisotropic") {
+            Ok(Some(_)) => {
+                context.get_parameter(MAX_TEXTURE_MAX_ANISOTROPY_EXT)
+                       .ok()
+                       .and_then(|value| value.as_f64())
+                       .unwrap_or(1.0) as f32
+            }
+            _ => 1.0,
+        };
+        WebGlDevice { context, max_anisotropy }
     }
 
     // Error checking
@@ -367,6 +382,75 @@ impl WebGlDevice {
         */
         output
     }
+
+    fn compile_shader(&self, source: &[u8], kind: ShaderKind) -> Result<WebGlShader, String> {
+        let glsl_version_spec = "300 es";
+
+        let source = self.preprocess(source, glsl_version_spec);
+
+        let gl_shader_kind = match kind {
+            ShaderKind::Vertex => WebGl::VERTEX_SHADER,
+            ShaderKind::Fragment => WebGl::FRAGMENT_SHADER,
+        };
+
+        let gl_shader = self
+            .context
+            .create_shader(gl_shader_kind)
+            .expect("could not create shader");
+        self.context.shader_source(&gl_shader, &source);
+        self.context.compile_shader(&gl_shader);
+        let compile_status = self
+            .context
+            .get_shader_parameter(&gl_shader, WebGl::COMPILE_STATUS);
+        if !compile_status.as_bool().unwrap_or(false) {
+            let info_log = self.context.get_shader_info_log(&gl_shader).unwrap_or_default();
+            self.context.delete_shader(Some(&gl_shader));
+            return Err(info_log);
+        }
+
+        Ok(WebGlShader { gl_shader })
+    }
+
+    fn link_program(
+        &self,
+        vertex_shader: WebGlShader,
+        fragment_shader: WebGlShader,
+    ) -> Result<WebGlProgram, String> {
+        let gl_program = self
+            .context
+            .create_program()
+            .expect("unable to create program object");
+        self.context
+            .attach_shader(&gl_program, &vertex_shader.gl_shader);
+        self.context
+            .attach_shader(&gl_program, &fragment_shader.gl_shader);
+        self.context.link_program(&gl_program);
+        let program = WebGlProgram {
+            context: self.context.clone(),
+            gl_program,
+        };
+        if !self
+            .context
+            .get_program_parameter(&program.gl_program, WebGl::LINK_STATUS)
+            .as_bool()
+            .unwrap_or(false)
+        {
+            return Err(self
+                .context
+                .get_program_info_log(&program.gl_program)
+                .unwrap_or_default());
+        }
+
+        Ok(program)
+    }
+}
+
+fn shader_path(name: &str, kind: ShaderKind) -> String {
+    let suffix = match kind {
+        ShaderKind::Vertex => 'v',
+        ShaderKind::Fragment => 'f',
+    };
+    format!("shaders/gl3/{}.{}s.glsl", name, suffix)
 }
 
 fn slice_to_u8<T>(slice: &[T]) -> &[u8] {
@@ -449,6 +533,12 @@ impl Device for WebGlDevice {
         texture
     }
 
+    #[inline]
+    fn create_mipmapped_texture(&self, format: TextureFormat, size: Vector2I) -> WebGlTexture {
+        // WebGL allocates the other levels in `generateMipmap()`.
+        self.create_texture(format, size)
+    }
+
     fn create_texture_from_data(
         &self,
         format: TextureFormat,
@@ -497,32 +587,13 @@ impl Device for WebGlDevice {
         source: &[u8],
         kind: ShaderKind,
     ) -> WebGlShader {
-        let glsl_version_spec = "300 es";
-
-        let source = self.preprocess(source, glsl_version_spec);
-
-        let gl_shader_kind = match kind {
-            ShaderKind::Vertex => WebGl::VERTEX_SHADER,
-            ShaderKind::Fragment => WebGl::FRAGMENT_SHADER,
-        };
-
-        let gl_shader = self
-            .context
-            .create_shader(gl_shader_kind)
-            .expect("could not create shader");
-        self.context.shader_source(&gl_shader, &source);
-        self.context.compile_shader(&gl_shader);
-        let compile_status = self
-            .context
-            .get_shader_parameter(&gl_shader, WebGl::COMPILE_STATUS);
-        if !compile_status.as_bool().unwrap_or(false) {
-            if let Some(info_log) = self.context.get_shader_info_log(&gl_shader) {
+        match self.compile_shader(source, kind) {
+            Ok(shader) => shader,
+            Err(info_log) => {
                 info!("Shader info log:\n{}", info_log);
+                panic!("{:?} shader '{}' compilation failed", kind, name);
             }
-            panic!("{:?} shader '{}' compilation failed", kind, name);
         }
-
-        WebGlShader { gl_shader }
     }
 
     fn create_program_from_shaders(
@@ -532,31 +603,35 @@ impl Device for WebGlDevice {
         vertex_shader: WebGlShader,
         fragment_shader: WebGlShader,
     ) -> WebGlProgram {
-        let gl_program = self
-            .context
-            .create_program()
-            .expect("unable to create program object");
-        self.context
-            .attach_shader(&gl_program, &vertex_shader.gl_shader);
-        self.context
-            .attach_shader(&gl_program, &fragment_shader.gl_shader);
-        self.context.link_program(&gl_program);
-        if !self
-            .context
-            .get_program_parameter(&gl_program, WebGl::LINK_STATUS)
-            .as_bool()
-            .unwrap_or(false)
-        {
-            if let Some(info_log) = self.context.get_program_info_log(&gl_program) {
+        match self.link_program(vertex_shader, fragment_shader) {
+            Ok(program) => program,
+            Err(info_log) => {
                 info!("Program info log for {}:\n{}", name, info_log);
+                panic!("Program {:?} linking failed", name);
             }
-            panic!("Program {:?} linking failed", name);
         }
+    }
 
-        WebGlProgram {
-            context: self.context.clone(),
-            gl_program,
+    fn try_create_program(
+        &self,
+        resources: &dyn ResourceLoader,
+        name: &str,
+    ) -> Result<WebGlProgram, String> {
+        let mut shaders = vec![];
+        for &kind in &[ShaderKind::Vertex, ShaderKind::Fragment] {
+            let path = shader_path(name, kind);
+            let source = resources
+                .slurp(&path)
+                .map_err(|err| format!("{}: {}", path, err))?;
+            let shader = self
+                .compile_shader(&source, kind)
+                .map_err(|info_log| format!("{}: {}", path, info_log))?;
+            shaders.push(shader);
         }
+        let fragment_shader = shaders.pop().unwrap();
+        let vertex_shader = shaders.pop().unwrap();
+        self.link_program(vertex_shader, fragment_shader)
+            .map_err(|info_log| format!("Program '{}': {}", name, info_log))
     }
 
     #[inline]
@@ -720,10 +795,12 @@ impl Device for WebGlDevice {
         self.context
             .tex_parameteri(WebGl::TEXTURE_2D,
                             WebGl::TEXTURE_MIN_FILTER,
-                            if flags.contains(TextureSamplingFlags::NEAREST_MIN) {
-                                WebGl::NEAREST as i32
-                            } else {
-                                WebGl::LINEAR as i32
+                            match (flags.contains(TextureSamplingFlags::NEAREST_MIN),
+                                   flags.contains(TextureSamplingFlags::MIPMAP_MIN)) {
+                                (true, false) => WebGl::NEAREST as i32,
+                                (false, false) => WebGl::LINEAR as i32,
+                                (true, true) => WebGl::NEAREST_MIPMAP_LINEAR as i32,
+                                (false, true) => WebGl::LINEAR_MIPMAP_LINEAR as i32,
                             });
         self.context
             .tex_parameteri(WebGl::TEXTURE_2D,
@@ -749,6 +826,14 @@ impl Device for WebGlDevice {
                             } else {
                                 WebGl::CLAMP_TO_EDGE as i32
                             });
+        if self.max_anisotropy > 1.0 {
+            let anisotropy = if flags.contains(TextureSamplingFlags::ANISOTROPIC) {
+                self.max_anisotropy
+            } else {
+                1.0
+            };
+            self.context.tex_parameterf(WebGl::TEXTURE_2D, TEXTURE_MAX_ANISOTROPY_EXT, anisotropy);
+        }
     }
 
     fn upload_to_texture(&self, texture: &WebGlTexture, rect: RectI, data_ref: TextureDataRef) {
@@ -794,6 +879,11 @@ impl Device for WebGlDevice {
         self.set_texture_sampling_mode(&texture, TextureSamplingFlags::empty());
     }
 
+    fn generate_mipmaps(&self, texture: &WebGlTexture) {
+        self.bind_texture(texture, 0);
+        self.context.generate_mipmap(WebGl::TEXTURE_2D);
+    }
+
     fn read_pixels(&self, _render_target: &RenderTarget<WebGlDevice>, _viewport: RectI) -> () {
         panic!("read_pixels is not supported");
     }
@@ -897,11 +987,7 @@ impl Device for WebGlDevice {
         name: &str,
         kind: ShaderKind,
     ) -> Self::Shader {
-        let suffix = match kind {
-            ShaderKind::Vertex => 'v',
-            ShaderKind::Fragment => 'f',
-        };
-        let path = format!("shaders/gl3/{}.{}s.glsl", name, suffix);
+        let path = shader_path(name, kind);
         self.create_shader_from_source(name, &resources.slurp(&path).unwrap(), kind)
     }
 }